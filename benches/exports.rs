@@ -0,0 +1,123 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use object::{
+	read::pe::{ImageNtHeaders, ImageOptionalHeader},
+	LittleEndian,
+};
+use objparse::{hash::ExportHasher, hash::Fnv1a, section, ParseOptions, PeHeaders};
+use std::{ffi::CStr, hint::black_box};
+
+/// DLL to benchmark against, `OBJPARSE_BENCH_DLL` or kernel32 on Windows.
+fn dll_path() -> Option<String> {
+	std::env::var("OBJPARSE_BENCH_DLL")
+		.ok()
+		.or_else(|| cfg!(windows).then(|| r"C:\Windows\System32\kernel32.dll".into()))
+}
+
+/// Maps the DLL the way the loader would, without applying relocations or resolving imports.
+fn map_image() -> Option<*mut u8> {
+	let Some(path) = dll_path() else {
+		eprintln!("set OBJPARSE_BENCH_DLL to a DLL to run the benchmarks");
+		return None;
+	};
+	let file: &'static [u8] = Box::leak(std::fs::read(path).ok()?.into_boxed_slice());
+	let headers = PeHeaders::parse_file(file, ParseOptions::new()).ok()?;
+	let optional_header = headers.nt_header.optional_header();
+	let mut image = vec![0u8; optional_header.size_of_image() as usize];
+	let size_of_headers = optional_header.size_of_headers() as usize;
+	image[..size_of_headers].copy_from_slice(&file[..size_of_headers]);
+	for section in headers.section_headers {
+		let va = section.virtual_address.get(LittleEndian) as usize;
+		let offset = section.pointer_to_raw_data.get(LittleEndian) as usize;
+		let len = (section::section_file_size(section) as usize)
+			.min(file.len().saturating_sub(offset))
+			.min(image.len().saturating_sub(va));
+		image[va..va + len].copy_from_slice(&file[offset..offset + len]);
+	}
+	Some(Box::leak(image.into_boxed_slice()).as_mut_ptr())
+}
+
+/// Names spread over the sorted name table, so the lookups are not all best or worst case.
+unsafe fn sample_names(headers: &PeHeaders, image_base: *mut u8) -> Vec<&'static CStr> {
+	let export_table = headers.export_table().unwrap();
+	let names = &export_table.name_table;
+	(1..=8)
+		.map(|i| names[names.len() * i / 8 - 1])
+		.map(|rva| unsafe { CStr::from_ptr(image_base.add(rva.get(LittleEndian) as _).cast()) })
+		.collect()
+}
+
+fn bench(c: &mut Criterion) {
+	let Some(image_base) = map_image() else {
+		return;
+	};
+	let headers = unsafe { PeHeaders::parse(image_base) }.unwrap();
+	let export_table = headers.export_table().unwrap();
+	let names = unsafe { sample_names(&headers, image_base) };
+	let hashes: Vec<u32> = names
+		.iter()
+		.map(|name| Fnv1a::hash(name.to_bytes().iter().copied()))
+		.collect();
+
+	c.bench_function("parse", |b| {
+		b.iter(|| unsafe { PeHeaders::parse(black_box(image_base)) }.unwrap())
+	});
+	c.bench_function("export_table", |b| {
+		b.iter(|| unsafe { headers.export_table_mem(black_box(image_base)) }.unwrap())
+	});
+	c.bench_function("export_by_name_linear", |b| {
+		b.iter(|| {
+			for name in &names {
+				let found = unsafe { export_table.iter_string_addr(image_base) }
+					.find(|(export, _)| export == name);
+				black_box(found.unwrap());
+			}
+		})
+	});
+	c.bench_function("export_by_name", |b| {
+		b.iter(|| {
+			for name in &names {
+				black_box(
+					unsafe { export_table.find_by_name(&headers, image_base, name.to_bytes()) }
+						.unwrap(),
+				);
+			}
+		})
+	});
+	c.bench_function("export_by_hash", |b| {
+		b.iter(|| {
+			for &hash in &hashes {
+				black_box(unsafe { export_table.find_by_hash::<Fnv1a>(image_base, hash) }.unwrap());
+			}
+		})
+	});
+	#[cfg(feature = "std")]
+	{
+		let index = unsafe { objparse::export_index::ExportIndex::build(export_table, image_base) };
+		c.bench_function("export_index", |b| {
+			b.iter(|| {
+				for name in &names {
+					black_box(index.rva(name.to_bytes()).unwrap());
+				}
+			})
+		});
+	}
+	c.bench_function("import_walk", |b| {
+		b.iter(|| {
+			let import_table = unsafe { headers.import_table_mem(image_base) };
+			let Ok(import_table) = import_table else {
+				return 0;
+			};
+			let mut count = 0;
+			for descriptor in import_table.import_descriptors {
+				for thunk in unsafe { headers.import_thunks(descriptor, image_base) }.unwrap() {
+					black_box(thunk.unwrap());
+					count += 1;
+				}
+			}
+			count
+		})
+	});
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+#ifndef OBJPARSE_H
+#define OBJPARSE_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define OBJPARSE_OK 0
+#define OBJPARSE_ERROR_NULL_POINTER (-1)
+#define OBJPARSE_ERROR_NOT_FOUND (-2)
+/* The export forwards to another module, `*address` receives its "module.name" string. */
+#define OBJPARSE_FORWARDED (-3)
+
+/* Parser errors, `objparse::error::Error::code` + 1. Codes are only ever appended. */
+typedef enum ObjparseError {
+    OBJPARSE_ERROR_PE_HEADERS = 1,
+    OBJPARSE_ERROR_EXPORT_TABLE = 2,
+    OBJPARSE_ERROR_IMPORT_TABLE = 3,
+    OBJPARSE_ERROR_DEBUG_TABLE = 4,
+    OBJPARSE_ERROR_TLS_TABLE = 5,
+    OBJPARSE_ERROR_RESOURCE_TABLE = 6,
+    OBJPARSE_ERROR_DELAY_IMPORT_TABLE = 7,
+    OBJPARSE_ERROR_IMPORT_RESOLUTION = 8,
+    OBJPARSE_ERROR_CLR_HEADER = 9,
+    OBJPARSE_ERROR_CLR_METADATA = 10,
+    OBJPARSE_ERROR_RVA_OVERFLOW = 11,
+    OBJPARSE_ERROR_RVA_OUTSIDE_SECTIONS = 12,
+    OBJPARSE_ERROR_INVALID_MEMORY = 13,
+    OBJPARSE_ERROR_SECTION_NAME = 14,
+    OBJPARSE_ERROR_UTF16 = 15,
+    OBJPARSE_ERROR_MODULE_NOT_FOUND = 16,
+    OBJPARSE_ERROR_REMOTE_PROCESS = 17,
+    OBJPARSE_ERROR_MINIDUMP = 18,
+    OBJPARSE_ERROR_TE_HEADER = 19,
+    OBJPARSE_ERROR_CHPE_METADATA = 20,
+    OBJPARSE_ERROR_BUNDLE = 21,
+    OBJPARSE_ERROR_IO = 22,
+    OBJPARSE_ERROR_NO_HEADER_SPACE = 23,
+    OBJPARSE_ERROR_RELOCATION_TABLE = 24,
+    OBJPARSE_ERROR_AUTHENTICODE = 25,
+    OBJPARSE_ERROR_COFF = 26,
+    OBJPARSE_ERROR_ARCHIVE = 27,
+    OBJPARSE_ERROR_IMPORT_OBJECT = 28,
+    OBJPARSE_ERROR_LIMIT_EXCEEDED = 29,
+    OBJPARSE_ERROR_ARCH_MISMATCH = 30,
+    OBJPARSE_ERROR_RVA_OUT_OF_BOUNDS = 31,
+    OBJPARSE_ERROR_OUT_OF_REGION = 32,
+} ObjparseError;
+
+typedef struct ObjparseImage ObjparseImage;
+
+/* `name` is NULL for imports by ordinal, `ordinal` holds the hint for imports by name.
+ * Return false to stop the enumeration. */
+typedef bool (*ObjparseImportCallback)(void *context, const char *dll, const char *name,
+                                       uint16_t ordinal, void *iat_slot);
+
+/* `image_base` has to stay mapped until `objparse_free`. */
+int32_t objparse_parse(const void *image_base, ObjparseImage **image);
+void objparse_free(ObjparseImage *image);
+
+/* Forwarded exports return OBJPARSE_FORWARDED with `*address` set to the forwarder string. */
+int32_t objparse_find_export(const ObjparseImage *image, const char *name, void **address);
+/* `ordinal` is biased by the export directory's Base, as in import thunks. */
+int32_t objparse_find_export_by_ordinal(const ObjparseImage *image, uint32_t ordinal,
+                                        void **address);
+
+int32_t objparse_enum_imports(const ObjparseImage *image, ObjparseImportCallback callback,
+                              void *context);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,172 @@
+use crate::{
+	nt::{NtHeaders, TlsDirectory},
+	section, PeHeaders,
+};
+use alloc::vec::Vec;
+use object::{pe::IMAGE_DIRECTORY_ENTRY_IMPORT, read::pe::ImageOptionalHeader, LittleEndian};
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+	Info,
+	Low,
+	Medium,
+	High,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Finding {
+	/// The entry point lies in the last section, typical of packers and appended stubs.
+	EntryPointInLastSection,
+	/// The entry point is non-zero but not inside any section.
+	EntryPointOutsideSections,
+	/// The TLS directory has callbacks, which run before the entry point.
+	TlsCallbacks,
+	/// Index of a section that is both writable and executable.
+	RwxSection(usize),
+	/// No import directory or no import descriptors.
+	NoImports,
+	/// `CheckSum` is zero, normal for most executables but required for drivers.
+	ChecksumZero,
+	/// `TimeDateStamp` is past the time of analysis, or a reproducible build's hash.
+	FutureTimestamp(u32),
+	/// A non-zero timestamp elsewhere in the image differs from the file header's, left behind
+	/// when the header was patched or a timestamp forged.
+	TimestampMismatch(TimestampSource, u32),
+}
+
+/// Where a timestamp other than the file header's was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampSource {
+	/// Index into the debug directory.
+	DebugDirectory(usize),
+	ExportDirectory,
+}
+
+impl Finding {
+	pub fn severity(&self) -> Severity {
+		match self {
+			Finding::EntryPointInLastSection => Severity::Medium,
+			Finding::EntryPointOutsideSections => Severity::High,
+			Finding::TlsCallbacks => Severity::Low,
+			Finding::RwxSection(_) => Severity::High,
+			Finding::NoImports => Severity::Medium,
+			Finding::ChecksumZero => Severity::Info,
+			Finding::FutureTimestamp(_) => Severity::Low,
+			Finding::TimestampMismatch(..) => Severity::Low,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+	pub findings: Vec<Finding>,
+}
+
+impl Report {
+	/// Highest severity of all findings, `None` for a clean image.
+	pub fn verdict(&self) -> Option<Severity> {
+		self.findings.iter().map(Finding::severity).max()
+	}
+
+	pub fn iter_at_least(&self, severity: Severity) -> impl Iterator<Item = &Finding> {
+		self.findings
+			.iter()
+			.filter(move |finding| finding.severity() >= severity)
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Runs the anomaly checks on the image mapped at `image_base`, comparing the timestamp
+	/// against the current time.
+	#[cfg(feature = "std")]
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn analyze(&self, image_base: *const u8) -> Report {
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |now| now.as_secs().min(u32::MAX as u64) as u32);
+		unsafe { self.analyze_at(image_base, now) }
+	}
+
+	/// [`PeHeaders::analyze`] with `now` in seconds since the Unix epoch.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn analyze_at(&self, image_base: *const u8, now: u32) -> Report {
+		let file_header = self.nt_header.file_header();
+		let optional_header = self.nt_header.optional_header();
+		let mut findings = Vec::new();
+
+		let entry_point = optional_header.address_of_entry_point();
+		match self.section_for_rva(entry_point) {
+			Some(entry_section)
+				if self
+					.section_headers
+					.last()
+					.is_some_and(|last| core::ptr::eq(last, entry_section)) =>
+			{
+				findings.push(Finding::EntryPointInLastSection)
+			}
+			Some(_) => {}
+			None if entry_point != 0 => findings.push(Finding::EntryPointOutsideSections),
+			None => {}
+		}
+
+		if let Ok(Some(tls_dir)) = unsafe { self.tls_table_mem(image_base) } {
+			let va = tls_dir.tls_dir.address_of_call_backs();
+			if let Some(rva) = self.va_to_rva(image_base, va) {
+				let loaded_base = va - rva as u64;
+				if unsafe { tls_dir.callback_addresses_with(self, image_base, loaded_base) }
+					.next()
+					.is_some()
+				{
+					findings.push(Finding::TlsCallbacks);
+				}
+			}
+		}
+
+		for (index, section) in self.section_headers.iter().enumerate() {
+			if section::section_is_executable(section) && section::section_is_writable(section) {
+				findings.push(Finding::RwxSection(index));
+			}
+		}
+
+		let has_imports = self.data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT).is_some()
+			&& unsafe { self.import_table_mem(image_base) }
+				.is_ok_and(|import_table| !import_table.import_descriptors.is_empty());
+		if !has_imports {
+			findings.push(Finding::NoImports);
+		}
+
+		if optional_header.check_sum() == 0 {
+			findings.push(Finding::ChecksumZero);
+		}
+		let timestamp = file_header.time_date_stamp.get(LittleEndian);
+		if timestamp > now {
+			findings.push(Finding::FutureTimestamp(timestamp));
+		}
+		let mut check_timestamp = |source, other: u32| {
+			if other != 0 && other != timestamp {
+				findings.push(Finding::TimestampMismatch(source, other));
+			}
+		};
+		if let Ok(debug_table) = unsafe { self.debug_table_mem(image_base) } {
+			for (index, descriptor) in debug_table.debug_descriptors.iter().enumerate() {
+				check_timestamp(
+					TimestampSource::DebugDirectory(index),
+					descriptor.time_date_stamp.get(LittleEndian),
+				);
+			}
+		}
+		if let Ok(export_table) = unsafe { self.export_table_mem(image_base) } {
+			check_timestamp(
+				TimestampSource::ExportDirectory,
+				export_table
+					.export_directory
+					.time_date_stamp
+					.get(LittleEndian),
+			);
+		}
+
+		Report { findings }
+	}
+}
@@ -0,0 +1,102 @@
+#[cfg(feature = "pe32")]
+use crate::PeHeaders32;
+#[cfg(feature = "pe64")]
+use crate::PeHeaders64;
+use crate::{
+	error::{Error, Result},
+	info::PeInfo,
+	HeadersOnly, ParseOptions,
+};
+use core::mem::size_of;
+use object::{
+	pe::{
+		ImageNtHeaders32, IMAGE_FILE_MACHINE_ARM64, IMAGE_NT_OPTIONAL_HDR32_MAGIC,
+		IMAGE_NT_OPTIONAL_HDR64_MAGIC,
+	},
+	LittleEndian,
+};
+
+/// Headers of an image of whichever layout it declares, independent of the host. The `pe32`
+/// and `pe64` features select the layouts that are parsed, ARM64 images additionally need
+/// `arm64`; anything else fails with [`Error::PeHeaders`].
+pub enum AnyPeHeaders {
+	#[cfg(feature = "pe32")]
+	Pe32(PeHeaders32),
+	#[cfg(feature = "pe64")]
+	Pe64(PeHeaders64),
+}
+
+impl AnyPeHeaders {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse(address: *const u8, options: ParseOptions) -> Result<Self> {
+		match unsafe { supported_magic(address, &options)? } {
+			#[cfg(feature = "pe32")]
+			IMAGE_NT_OPTIONAL_HDR32_MAGIC => {
+				unsafe { PeHeaders32::parse_nt(address, options) }.map(Self::Pe32)
+			}
+			#[cfg(feature = "pe64")]
+			IMAGE_NT_OPTIONAL_HDR64_MAGIC => {
+				unsafe { PeHeaders64::parse_nt(address, options) }.map(Self::Pe64)
+			}
+			_ => Err(Error::PeHeaders),
+		}
+	}
+
+	/// Raw file contents, see [`crate::PeHeaders::parse_file_nt`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse_file(data: &'static [u8], options: ParseOptions) -> Result<Self> {
+		// The probe has to stay inside `data` like the full parse does.
+		let max_nt_offset = data
+			.len()
+			.checked_sub(size_of::<ImageNtHeaders32>())
+			.ok_or(Error::PeHeaders)?;
+		let probe_options = options.max_nt_offset(options.max_nt_offset.min(max_nt_offset));
+		match unsafe { supported_magic(data.as_ptr(), &probe_options)? } {
+			#[cfg(feature = "pe32")]
+			IMAGE_NT_OPTIONAL_HDR32_MAGIC => PeHeaders32::parse_file_nt(data, options).map(Self::Pe32),
+			#[cfg(feature = "pe64")]
+			IMAGE_NT_OPTIONAL_HDR64_MAGIC => PeHeaders64::parse_file_nt(data, options).map(Self::Pe64),
+			_ => Err(Error::PeHeaders),
+		}
+	}
+
+	pub fn is_64(&self) -> bool {
+		match self {
+			#[cfg(feature = "pe32")]
+			Self::Pe32(_) => false,
+			#[cfg(feature = "pe64")]
+			Self::Pe64(_) => true,
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn info(&self) -> PeInfo {
+		match self {
+			#[cfg(feature = "pe32")]
+			Self::Pe32(headers) => headers.info(),
+			#[cfg(feature = "pe64")]
+			Self::Pe64(headers) => headers.info(),
+		}
+	}
+}
+
+/// The optional header magic if its layout and the machine are enabled. Parsing the NT headers
+/// as 32-bit is enough to read both, they only differ past the magic.
+pub(crate) unsafe fn supported_magic(address: *const u8, options: &ParseOptions) -> Result<u16> {
+	let headers = unsafe { HeadersOnly::<ImageNtHeaders32>::parse_any_magic(address, options)? };
+	let magic = headers.nt_header.optional_header.magic.get(LittleEndian);
+	let machine = headers.nt_header.file_header.machine.get(LittleEndian);
+	let supported = match magic {
+		IMAGE_NT_OPTIONAL_HDR32_MAGIC => cfg!(feature = "pe32"),
+		IMAGE_NT_OPTIONAL_HDR64_MAGIC if machine == IMAGE_FILE_MACHINE_ARM64 => {
+			cfg!(feature = "arm64")
+		}
+		IMAGE_NT_OPTIONAL_HDR64_MAGIC => cfg!(feature = "pe64"),
+		_ => false,
+	};
+	if !supported {
+		trace_event!(magic, machine, "image layout not enabled");
+		return Err(Error::PeHeaders);
+	}
+	Ok(magic)
+}
@@ -0,0 +1,295 @@
+use crate::{
+	coff::CoffFile,
+	error::{Error, Result},
+};
+use core::{ffi::CStr, mem::size_of, str};
+use object::{
+	archive::{Header, MAGIC, TERMINATOR},
+	pe::{
+		ImportObjectHeader, IMAGE_FILE_MACHINE_I386, IMAGE_FILE_MACHINE_UNKNOWN,
+		IMPORT_OBJECT_CODE, IMPORT_OBJECT_CONST, IMPORT_OBJECT_DATA, IMPORT_OBJECT_HDR_SIG2,
+		IMPORT_OBJECT_NAME, IMPORT_OBJECT_NAME_EXPORTAS, IMPORT_OBJECT_NAME_NO_PREFIX,
+		IMPORT_OBJECT_NAME_UNDECORATE, IMPORT_OBJECT_ORDINAL,
+	},
+	pod, LittleEndian,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ImportType {
+	/// A function, with both `__imp_` and thunk symbols.
+	Code,
+	Data,
+	Const,
+	Unknown(u16),
+}
+
+/// A short import member of an import library, which the linker expands into the import
+/// descriptor, thunks and IAT entries of one imported symbol.
+#[derive(Clone, Copy, Debug)]
+pub struct ShortImport {
+	pub header: &'static ImportObjectHeader,
+	/// Public symbol the member defines.
+	pub symbol: &'static CStr,
+	pub dll: &'static CStr,
+	/// Name for `IMPORT_OBJECT_NAME_EXPORTAS`, following the dll name.
+	pub export_name: Option<&'static CStr>,
+}
+
+impl ShortImport {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(data: &'static [u8]) -> Result<Self> {
+		let (header, rest) =
+			pod::from_bytes::<ImportObjectHeader>(data).map_err(|_| Error::ImportObject)?;
+		// Anonymous objects share the signature with a version of at least 1.
+		if header.sig1.get(LittleEndian) != IMAGE_FILE_MACHINE_UNKNOWN
+			|| header.sig2.get(LittleEndian) != IMPORT_OBJECT_HDR_SIG2
+			|| header.version.get(LittleEndian) != 0
+		{
+			return Err(Error::ImportObject);
+		}
+		let strings = rest
+			.get(..header.size_of_data.get(LittleEndian) as usize)
+			.ok_or(Error::ImportObject)?;
+		let symbol = CStr::from_bytes_until_nul(strings).map_err(|_| Error::ImportObject)?;
+		let strings = &strings[symbol.to_bytes_with_nul().len()..];
+		let dll = CStr::from_bytes_until_nul(strings).map_err(|_| Error::ImportObject)?;
+		let strings = &strings[dll.to_bytes_with_nul().len()..];
+		let export_name = CStr::from_bytes_until_nul(strings).ok();
+		Ok(Self {
+			header,
+			symbol,
+			dll,
+			export_name,
+		})
+	}
+
+	pub fn machine(&self) -> u16 {
+		self.header.machine.get(LittleEndian)
+	}
+
+	pub fn import_type(&self) -> ImportType {
+		match self.header.name_type.get(LittleEndian) & 0x3 {
+			IMPORT_OBJECT_CODE => ImportType::Code,
+			IMPORT_OBJECT_DATA => ImportType::Data,
+			IMPORT_OBJECT_CONST => ImportType::Const,
+			typ => ImportType::Unknown(typ),
+		}
+	}
+
+	/// One of the `IMPORT_OBJECT_*` name types.
+	pub fn name_type(&self) -> u16 {
+		(self.header.name_type.get(LittleEndian) >> 2) & 0x7
+	}
+
+	/// Ordinal to import by, `None` for imports by name.
+	pub fn ordinal(&self) -> Option<u16> {
+		(self.name_type() == IMPORT_OBJECT_ORDINAL)
+			.then(|| self.header.ordinal_or_hint.get(LittleEndian))
+	}
+
+	/// Hint into the dll's export name table, `None` for imports by ordinal.
+	pub fn hint(&self) -> Option<u16> {
+		(self.name_type() != IMPORT_OBJECT_ORDINAL)
+			.then(|| self.header.ordinal_or_hint.get(LittleEndian))
+	}
+
+	/// Name looked up in the dll's exports, derived from the symbol according to the name type.
+	/// `None` for imports by ordinal and unknown name types.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn import_name(&self) -> Option<&'static [u8]> {
+		let symbol = self.symbol.to_bytes();
+		let strip_prefix = |name: &'static [u8]| match name.first() {
+			Some(b'?' | b'@') => &name[1..],
+			// Only x86 decorates C names with an underscore.
+			Some(b'_') if self.machine() == IMAGE_FILE_MACHINE_I386 => &name[1..],
+			_ => name,
+		};
+		match self.name_type() {
+			IMPORT_OBJECT_NAME => Some(symbol),
+			IMPORT_OBJECT_NAME_NO_PREFIX => Some(strip_prefix(symbol)),
+			IMPORT_OBJECT_NAME_UNDECORATE => {
+				let name = strip_prefix(symbol);
+				let len = name
+					.iter()
+					.position(|&byte| byte == b'@')
+					.unwrap_or(name.len());
+				Some(&name[..len])
+			}
+			IMPORT_OBJECT_NAME_EXPORTAS => self.export_name.map(CStr::to_bytes),
+			_ => None,
+		}
+	}
+}
+
+/// A member of an archive, with its name resolved through the long names member.
+#[derive(Clone, Copy, Debug)]
+pub struct ArchiveMember {
+	pub header: &'static Header,
+	/// Offset of the header from the start of the archive, as stored in the linker members.
+	pub offset: usize,
+	pub name: &'static [u8],
+	pub data: &'static [u8],
+}
+
+impl ArchiveMember {
+	/// Linker members, the long names member and other members the linker reserves.
+	pub fn is_special(&self) -> bool {
+		self.name.starts_with(b"/") || self.name.starts_with(b"__.SYMDEF")
+	}
+
+	/// Short import members share the signature of anonymous objects, with version 0.
+	pub fn is_short_import(&self) -> bool {
+		self.data.starts_with(&[0, 0, 0xff, 0xff, 0, 0])
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn coff(&self) -> Result<CoffFile> {
+		CoffFile::parse(self.data)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn short_import(&self) -> Result<ShortImport> {
+		ShortImport::parse(self.data)
+	}
+}
+
+/// A `!<arch>` archive, the format of static and import libraries. Thin archives are not
+/// supported since their members are not stored inline.
+pub struct Archive {
+	pub data: &'static [u8],
+	/// Data of the `//` member, empty if there is none.
+	pub long_names: &'static [u8],
+}
+
+impl Archive {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(data: &'static [u8]) -> Result<Self> {
+		if !data.starts_with(&MAGIC) {
+			return Err(Error::Archive);
+		}
+		let mut archive = Self {
+			data,
+			long_names: &[],
+		};
+		// The long names member follows the linker members, so their names never need it.
+		for member in archive.members() {
+			let member = member?;
+			if member.name == b"//" {
+				archive.long_names = member.data;
+				break;
+			}
+			if !member.is_special() {
+				break;
+			}
+		}
+		Ok(archive)
+	}
+
+	/// Members in file order, including the special ones, stopping after the first malformed
+	/// one.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn members(&self) -> ArchiveMembers<'_> {
+		ArchiveMembers {
+			archive: self,
+			offset: MAGIC.len(),
+			failed: false,
+		}
+	}
+
+	/// Members that are not [special](ArchiveMember::is_special).
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn objects(&self) -> impl Iterator<Item = Result<ArchiveMember>> + '_ {
+		self.members()
+			.filter(|member| member.as_ref().map_or(true, |member| !member.is_special()))
+	}
+
+	fn long_name(&self, offset: usize) -> Option<&'static [u8]> {
+		let name = self.long_names.get(offset..)?;
+		// MSVC terminates names with a null, GNU with "/\n".
+		let len = name
+			.iter()
+			.position(|&byte| byte == 0 || byte == b'\n')
+			.unwrap_or(name.len());
+		let name = &name[..len];
+		Some(name.strip_suffix(b"/").unwrap_or(name))
+	}
+
+	fn member_at(&self, offset: usize) -> Option<(ArchiveMember, usize)> {
+		let (header, _) = pod::from_bytes::<Header>(self.data.get(offset..)?).ok()?;
+		if header.terminator != TERMINATOR {
+			return None;
+		}
+		let size = parse_decimal(&header.size)?;
+		let data_offset = offset + size_of::<Header>();
+		let data_end = data_offset.checked_add(size)?;
+		let mut data = self.data.get(data_offset..data_end)?;
+
+		let raw_name = trim_spaces(&header.name);
+		let name = if raw_name == b"/" || raw_name == b"//" {
+			raw_name
+		} else if let Some(len) = raw_name.strip_prefix(b"#1/") {
+			// BSD stores long names at the start of the data.
+			let len = parse_decimal(len)?;
+			let name = data.get(..len)?;
+			data = &data[len..];
+			let len = name.iter().position(|&byte| byte == 0).unwrap_or(len);
+			&name[..len]
+		} else if let Some(long_offset) = raw_name
+			.strip_prefix(b"/")
+			.filter(|digits| digits.first().is_some_and(u8::is_ascii_digit))
+		{
+			self.long_name(parse_decimal(long_offset)?)?
+		} else if raw_name.starts_with(b"/") {
+			raw_name
+		} else {
+			raw_name.strip_suffix(b"/").unwrap_or(raw_name)
+		};
+
+		let member = ArchiveMember {
+			header,
+			offset,
+			name,
+			data,
+		};
+		// Members start on an even offset.
+		Some((member, data_end + (data_end & 1)))
+	}
+}
+
+pub struct ArchiveMembers<'a> {
+	archive: &'a Archive,
+	offset: usize,
+	failed: bool,
+}
+
+impl Iterator for ArchiveMembers<'_> {
+	type Item = Result<ArchiveMember>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.failed || self.offset >= self.archive.data.len() {
+			return None;
+		}
+		match self.archive.member_at(self.offset) {
+			Some((member, next)) => {
+				self.offset = next;
+				Some(Ok(member))
+			}
+			None => {
+				self.failed = true;
+				Some(Err(Error::Archive))
+			}
+		}
+	}
+}
+
+fn trim_spaces(field: &[u8]) -> &[u8] {
+	let len = field
+		.iter()
+		.rposition(|&byte| byte != b' ')
+		.map_or(0, |last| last + 1);
+	&field[..len]
+}
+
+fn parse_decimal(field: &[u8]) -> Option<usize> {
+	str::from_utf8(trim_spaces(field)).ok()?.parse().ok()
+}
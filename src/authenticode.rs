@@ -0,0 +1,426 @@
+use crate::{
+	check_range,
+	error::{Error, Result},
+	nt::NtHeaders,
+	Layout, PeHeaders,
+};
+use core::{mem::size_of, slice};
+use object::{pe::IMAGE_DIRECTORY_ENTRY_SECURITY, LittleEndian};
+
+pub const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xA0;
+const TAG_CONTEXT_1: u8 = 0xA1;
+
+/// 1.2.840.113549.1.7.2
+pub const OID_SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+/// 1.2.840.113549.1.9.5
+pub const OID_SIGNING_TIME: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x05];
+/// 1.2.840.113549.1.9.6
+pub const OID_COUNTER_SIGNATURE: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x06];
+/// 1.2.840.113549.1.9.16.1.4
+pub const OID_TST_INFO: &[u8] = &[
+	0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+/// 1.3.6.1.4.1.311.2.4.1, `szOID_NESTED_SIGNATURE`.
+pub const OID_NESTED_SIGNATURE: &[u8] =
+	&[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x04, 0x01];
+/// 1.3.6.1.4.1.311.3.3.1, `szOID_RFC3161_counterSign`.
+pub const OID_RFC3161_COUNTER_SIGNATURE: &[u8] =
+	&[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x03, 0x03, 0x01];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+	Md5,
+	Sha1,
+	Sha256,
+	Sha384,
+	Sha512,
+	/// Encoded object identifier of any other algorithm.
+	Other(&'static [u8]),
+}
+
+impl DigestAlgorithm {
+	pub fn from_oid(oid: &'static [u8]) -> Self {
+		const NIST_HASH: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02];
+		match oid {
+			[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x02, 0x05] => Self::Md5,
+			[0x2B, 0x0E, 0x03, 0x02, 0x1A] => Self::Sha1,
+			[prefix @ .., 0x01] if prefix == NIST_HASH => Self::Sha256,
+			[prefix @ .., 0x02] if prefix == NIST_HASH => Self::Sha384,
+			[prefix @ .., 0x03] if prefix == NIST_HASH => Self::Sha512,
+			_ => Self::Other(oid),
+		}
+	}
+}
+
+/// Reads one DER element, returning its tag, contents and what follows it.
+fn read_tlv(data: &'static [u8]) -> Option<(u8, &'static [u8], &'static [u8])> {
+	let (&tag, rest) = data.split_first()?;
+	let (&first, rest) = rest.split_first()?;
+	let (len, rest) = match first {
+		len if len & 0x80 == 0 => (len as usize, rest),
+		len => {
+			let count = (len & 0x7F) as usize;
+			if count == 0 || count > size_of::<usize>() || count > rest.len() {
+				return None;
+			}
+			let (bytes, rest) = rest.split_at(count);
+			let len = bytes
+				.iter()
+				.fold(0usize, |len, &byte| (len << 8) | byte as usize);
+			(len, rest)
+		}
+	};
+	if len > rest.len() {
+		return None;
+	}
+	let (contents, rest) = rest.split_at(len);
+	Some((tag, contents, rest))
+}
+
+/// Contents of the element at the start of `data` if it has `tag`, and what follows it.
+fn expect(data: &'static [u8], tag: u8) -> Option<(&'static [u8], &'static [u8])> {
+	let (actual, contents, rest) = read_tlv(data)?;
+	(actual == tag).then_some((contents, rest))
+}
+
+/// The elements of a constructed value.
+fn elements(data: &'static [u8]) -> impl Iterator<Item = (u8, &'static [u8])> {
+	let mut rest = data;
+	core::iter::from_fn(move || {
+		let (tag, contents, next) = read_tlv(rest)?;
+		rest = next;
+		Some((tag, contents))
+	})
+}
+
+fn digits(text: &[u8]) -> Option<u32> {
+	text.iter().try_fold(0u32, |value, &digit| {
+		digit
+			.is_ascii_digit()
+			.then(|| value * 10 + (digit - b'0') as u32)
+	})
+}
+
+/// Seconds since the Unix epoch of a `UTCTime` or `GeneralizedTime` in UTC.
+fn parse_time(tag: u8, text: &[u8]) -> Option<i64> {
+	let (year, rest) = match tag {
+		TAG_UTC_TIME => {
+			let year = digits(text.get(..2)?)?;
+			(
+				if year < 50 { 2000 + year } else { 1900 + year },
+				&text[2..],
+			)
+		}
+		TAG_GENERALIZED_TIME => (digits(text.get(..4)?)?, &text[4..]),
+		_ => return None,
+	};
+	let field = |index: usize| digits(rest.get(index * 2..index * 2 + 2)?);
+	let (month, day) = (field(0)?, field(1)?);
+	let (hour, minute, second) = (field(2)?, field(3)?, field(4)?);
+	if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+		return None;
+	}
+
+	// Days from civil, shifting the year to start in March.
+	let year = year as i64 - (month <= 2) as i64;
+	let era = year.div_euclid(400);
+	let year_of_era = year - era * 400;
+	let month = month as i64;
+	let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	let days = era * 146097 + day_of_era - 719468;
+	Some(days * 86400 + hour as i64 * 3600 + minute as i64 * 60 + second as i64)
+}
+
+/// An entry of the certificate table.
+#[derive(Clone, Copy, Debug)]
+pub struct WinCertificate {
+	pub revision: u16,
+	pub certificate_type: u16,
+	/// The certificate, without the header and padding.
+	pub data: &'static [u8],
+}
+
+impl WinCertificate {
+	/// The PKCS#7 `SignedData` of a [`WIN_CERT_TYPE_PKCS_SIGNED_DATA`] entry.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn signed_data(&self) -> Result<SignedData> {
+		if self.certificate_type != WIN_CERT_TYPE_PKCS_SIGNED_DATA {
+			return Err(Error::Authenticode);
+		}
+		SignedData::parse(self.data)
+	}
+}
+
+/// Entries of the certificate table, each aligned to 8 bytes.
+pub struct WinCertificates {
+	data: &'static [u8],
+}
+
+impl Iterator for WinCertificates {
+	type Item = WinCertificate;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let header = self.data.get(..8)?;
+		let length = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+		let data = self.data.get(8..length)?;
+		self.data = self
+			.data
+			.get(length.next_multiple_of(8)..)
+			.unwrap_or_default();
+		Some(WinCertificate {
+			revision: u16::from_le_bytes(header[4..6].try_into().unwrap()),
+			certificate_type: u16::from_le_bytes(header[6..8].try_into().unwrap()),
+			data,
+		})
+	}
+}
+
+/// Signing time from a counter-signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timestamp {
+	/// `genTime` of an RFC 3161 time-stamp token.
+	Rfc3161(i64),
+	/// Signing time of a PKCS#9 counter-signature.
+	Legacy(i64),
+}
+
+impl Timestamp {
+	/// Seconds since the Unix epoch.
+	pub fn time(&self) -> i64 {
+		match *self {
+			Timestamp::Rfc3161(time) | Timestamp::Legacy(time) => time,
+		}
+	}
+}
+
+/// The contents of a PKCS#7 `SignedData`.
+#[derive(Clone, Copy, Debug)]
+pub struct SignedData {
+	pub data: &'static [u8],
+}
+
+impl SignedData {
+	/// Parses a `ContentInfo` holding `SignedData`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(content_info: &'static [u8]) -> Result<Self> {
+		let (content_info, _) = expect(content_info, TAG_SEQUENCE).ok_or(Error::Authenticode)?;
+		let (oid, rest) = expect(content_info, TAG_OID).ok_or(Error::Authenticode)?;
+		if oid != OID_SIGNED_DATA {
+			return Err(Error::Authenticode);
+		}
+		let (content, _) = expect(rest, TAG_CONTEXT_0).ok_or(Error::Authenticode)?;
+		let (data, _) = expect(content, TAG_SEQUENCE).ok_or(Error::Authenticode)?;
+		Ok(Self { data })
+	}
+
+	/// The encapsulated `ContentInfo`'s type and content, `SpcIndirectDataContent` for
+	/// Authenticode and `TSTInfo` for a time-stamp token.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn content(&self) -> Option<(&'static [u8], &'static [u8])> {
+		let (_, content_info) = elements(self.data).find(|&(tag, _)| tag == TAG_SEQUENCE)?;
+		let (oid, rest) = expect(content_info, TAG_OID)?;
+		let (content, _) = expect(rest, TAG_CONTEXT_0)?;
+		Some((oid, content))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn signer_infos(&self) -> impl Iterator<Item = SignerInfo> {
+		elements(self.data)
+			.filter(|&(tag, _)| tag == TAG_SET)
+			.last()
+			.into_iter()
+			.flat_map(|(_, signer_infos)| elements(signer_infos))
+			.filter(|&(tag, _)| tag == TAG_SEQUENCE)
+			.map(|(_, data)| SignerInfo { data })
+	}
+
+	/// `genTime` of the `TSTInfo` if this is an RFC 3161 time-stamp token.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn tst_info_time(&self) -> Option<i64> {
+		let (oid, content) = self.content()?;
+		if oid != OID_TST_INFO {
+			return None;
+		}
+		let (tst_info, _) = expect(content, TAG_OCTET_STRING)?;
+		let (tst_info, _) = expect(tst_info, TAG_SEQUENCE)?;
+		// version, policy, messageImprint, serialNumber, genTime.
+		let (tag, gen_time) = elements(tst_info).nth(4)?;
+		parse_time(tag, gen_time)
+	}
+}
+
+/// An attribute type and its set of values.
+#[derive(Clone, Copy, Debug)]
+pub struct Attribute {
+	pub oid: &'static [u8],
+	pub values: &'static [u8],
+}
+
+impl Attribute {
+	/// The encoded values, each with its tag and length.
+	pub fn values(&self) -> impl Iterator<Item = &'static [u8]> {
+		let mut rest = self.values;
+		core::iter::from_fn(move || {
+			let (_, _, next) = read_tlv(rest)?;
+			let value = &rest[..rest.len() - next.len()];
+			rest = next;
+			Some(value)
+		})
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SignerInfo {
+	pub data: &'static [u8],
+}
+
+impl SignerInfo {
+	/// The encoded issuer `Name` of the signing certificate.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn issuer(&self) -> Option<&'static [u8]> {
+		let (_, issuer_and_serial) = elements(self.data).nth(1)?;
+		let (_, _, rest) = read_tlv(issuer_and_serial)?;
+		Some(&issuer_and_serial[..issuer_and_serial.len() - rest.len()])
+	}
+
+	/// Big-endian serial number of the signing certificate.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn serial_number(&self) -> Option<&'static [u8]> {
+		let (_, issuer_and_serial) = elements(self.data).nth(1)?;
+		let (_, serial_number) = elements(issuer_and_serial).nth(1)?;
+		Some(serial_number)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn digest_algorithm(&self) -> Option<DigestAlgorithm> {
+		let (_, algorithm) = elements(self.data).nth(2)?;
+		let (oid, _) = expect(algorithm, TAG_OID)?;
+		Some(DigestAlgorithm::from_oid(oid))
+	}
+
+	/// Additional signatures nested in the unauthenticated attributes, used to sign with
+	/// both SHA-1 and SHA-256.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn nested_signatures(&self) -> impl Iterator<Item = SignedData> {
+		self.unauthenticated_attributes()
+			.filter(|attribute| attribute.oid == OID_NESTED_SIGNATURE)
+			.flat_map(|attribute| attribute.values())
+			.filter_map(|value| SignedData::parse(value).ok())
+	}
+
+	fn attributes(&self, tag: u8) -> impl Iterator<Item = Attribute> {
+		elements(self.data)
+			.find(move |&(actual, _)| actual == tag)
+			.into_iter()
+			.flat_map(|(_, attributes)| elements(attributes))
+			.filter_map(|(_, attribute)| {
+				let (oid, rest) = expect(attribute, TAG_OID)?;
+				let (values, _) = expect(rest, TAG_SET)?;
+				Some(Attribute { oid, values })
+			})
+	}
+
+	pub fn authenticated_attributes(&self) -> impl Iterator<Item = Attribute> {
+		self.attributes(TAG_CONTEXT_0)
+	}
+
+	pub fn unauthenticated_attributes(&self) -> impl Iterator<Item = Attribute> {
+		self.attributes(TAG_CONTEXT_1)
+	}
+
+	/// The signing time claimed by the signer, which unlike a timestamp is not trusted.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn signing_time(&self) -> Option<i64> {
+		let attribute = self
+			.authenticated_attributes()
+			.find(|attribute| attribute.oid == OID_SIGNING_TIME)?;
+		let (tag, time, _) = read_tlv(attribute.values)?;
+		parse_time(tag, time)
+	}
+
+	/// Times of the counter-signatures in the unauthenticated attributes.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn timestamps(&self) -> impl Iterator<Item = Timestamp> {
+		self.unauthenticated_attributes()
+			.flat_map(|attribute| attribute.values().map(move |value| (attribute.oid, value)))
+			.filter_map(|(oid, value)| match oid {
+				OID_RFC3161_COUNTER_SIGNATURE => SignedData::parse(value)
+					.ok()?
+					.tst_info_time()
+					.map(Timestamp::Rfc3161),
+				OID_COUNTER_SIGNATURE => {
+					let (data, _) = expect(value, TAG_SEQUENCE)?;
+					SignerInfo { data }.signing_time().map(Timestamp::Legacy)
+				}
+				_ => None,
+			})
+	}
+}
+
+/// A signature from the certificate table, either a top-level one or nested in one of its
+/// signers.
+#[derive(Clone, Copy, Debug)]
+pub struct Signature {
+	/// Index of the certificate table entry.
+	pub certificate: usize,
+	pub nested: bool,
+	pub signed_data: SignedData,
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// The certificate table, whose data directory holds a file offset rather than an RVA, so
+	/// `image_base` has to point to the file.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn certificates(&self, image_base: *const u8) -> Result<WinCertificates> {
+		if self.options.layout != Layout::File {
+			return Err(Error::Authenticode);
+		}
+		let Some(data_dir) = self.data_directory(IMAGE_DIRECTORY_ENTRY_SECURITY) else {
+			return Ok(WinCertificates { data: &[] });
+		};
+		let offset = data_dir.virtual_address.get(LittleEndian) as usize;
+		let size = data_dir.size.get(LittleEndian) as usize;
+		let data_ptr = image_base.wrapping_add(offset);
+		unsafe { check_range(&self.options, data_ptr, size)? };
+		let data = unsafe { slice::from_raw_parts(data_ptr, size) };
+		Ok(WinCertificates { data })
+	}
+
+	/// All signatures, each top-level one followed by those nested in it. Windows only nests
+	/// one level deep, deeper nesting is not followed.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn signatures(
+		&self,
+		image_base: *const u8,
+	) -> Result<impl Iterator<Item = Signature>> {
+		let certificates = unsafe { self.certificates(image_base)? };
+		Ok(certificates
+			.enumerate()
+			.filter_map(|(certificate, entry)| Some((certificate, entry.signed_data().ok()?)))
+			.flat_map(|(certificate, signed_data)| {
+				let nested = signed_data
+					.signer_infos()
+					.flat_map(|signer_info| signer_info.nested_signatures())
+					.map(move |signed_data| Signature {
+						certificate,
+						nested: true,
+						signed_data,
+					});
+				core::iter::once(Signature {
+					certificate,
+					nested: false,
+					signed_data,
+				})
+				.chain(nested)
+			}))
+	}
+}
@@ -0,0 +1,57 @@
+use crate::{analyze::Report, error::FileError, reader::AnyPeReader, ParseOptions};
+use rayon::prelude::*;
+use std::{
+	fs::{self, File},
+	io::{self, BufReader, Read},
+	path::{Path, PathBuf},
+};
+
+/// Analyzes every PE file under `path` in parallel on the rayon pool, calling `on_report` from
+/// the worker threads as each file is done. Files not starting with `MZ` are skipped, as are
+/// symlinks and subdirectories that cannot be read; only an unreadable `path` fails.
+/// Files are read through [`AnyPeReader`], so PE32 and PE32+ images are both analyzed.
+#[cfg_attr(feature = "debug", inline(never))]
+pub fn analyze_dir(
+	path: impl AsRef<Path>,
+	options: ParseOptions,
+	on_report: impl Fn(&Path, Result<Report, FileError>) + Sync,
+) -> io::Result<()> {
+	let mut files = Vec::new();
+	collect_files(path.as_ref(), &mut files)?;
+	files
+		.par_iter()
+		.for_each(|file| match analyze_file(file, options) {
+			Ok(Some(report)) => on_report(file, Ok(report)),
+			Ok(None) => {}
+			Err(err) => on_report(file, Err(err)),
+		});
+	Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+	for entry in fs::read_dir(dir)? {
+		let Ok(entry) = entry else {
+			continue;
+		};
+		match entry.file_type() {
+			Ok(file_type) if file_type.is_dir() => {
+				let _ = collect_files(&entry.path(), files);
+			}
+			Ok(file_type) if file_type.is_file() => files.push(entry.path()),
+			_ => {}
+		}
+	}
+	Ok(())
+}
+
+/// `None` for files that are not PE images.
+fn analyze_file(path: &Path, options: ParseOptions) -> Result<Option<Report>, FileError> {
+	let mut file = File::open(path)?;
+	let mut magic = [0; 2];
+	if file.read_exact(&mut magic).is_err() || magic != *b"MZ" {
+		return Ok(None);
+	}
+	let mut reader = AnyPeReader::open_with(BufReader::new(file), options)?;
+	reader.load_rva_range(0, u32::MAX)?;
+	Ok(Some(reader.analyze()))
+}
@@ -0,0 +1,165 @@
+use crate::error::{Error, Result};
+
+/// SHA-256 of ".net core bundle", placed by the SDK right after the bundle header offset.
+pub const BUNDLE_SIGNATURE: [u8; 32] = [
+	0x8b, 0x12, 0x02, 0xb9, 0x6a, 0x61, 0x20, 0x38, 0x72, 0x7b, 0x93, 0x02, 0x14, 0xd7, 0xa0, 0x32,
+	0x13, 0xf5, 0xb9, 0xe6, 0xef, 0xae, 0x33, 0x18, 0xee, 0x3b, 0x2d, 0xce, 0x24, 0xb3, 0x6a, 0xae,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BundleFileType {
+	Unknown,
+	Assembly,
+	NativeBinary,
+	DepsJson,
+	RuntimeConfigJson,
+	Symbols,
+}
+
+impl BundleFileType {
+	fn from_u8(value: u8) -> Self {
+		match value {
+			1 => Self::Assembly,
+			2 => Self::NativeBinary,
+			3 => Self::DepsJson,
+			4 => Self::RuntimeConfigJson,
+			5 => Self::Symbols,
+			_ => Self::Unknown,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BundleEntry {
+	pub offset: u64,
+	pub size: u64,
+	/// Zero when stored uncompressed, the data is deflated otherwise.
+	pub compressed_size: u64,
+	pub file_type: BundleFileType,
+	pub path: &'static str,
+}
+
+/// A `BinaryReader` style cursor over the bundle manifest.
+struct Reader {
+	data: &'static [u8],
+	offset: usize,
+}
+
+impl Reader {
+	fn bytes(&mut self, len: usize) -> Result<&'static [u8]> {
+		let end = self.offset.checked_add(len).ok_or(Error::Bundle)?;
+		let bytes = self.data.get(self.offset..end).ok_or(Error::Bundle)?;
+		self.offset = end;
+		Ok(bytes)
+	}
+
+	fn u8(&mut self) -> Result<u8> {
+		Ok(self.bytes(1)?[0])
+	}
+
+	fn u32(&mut self) -> Result<u32> {
+		Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+	}
+
+	fn u64(&mut self) -> Result<u64> {
+		Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+	}
+
+	/// Length prefixed with the 7-bit encoding of `BinaryWriter.Write(string)`.
+	fn string(&mut self) -> Result<&'static str> {
+		let mut len = 0usize;
+		for shift in (0..35).step_by(7) {
+			let byte = self.u8()?;
+			len |= ((byte & 0x7f) as usize) << shift;
+			if byte & 0x80 == 0 {
+				return core::str::from_utf8(self.bytes(len)?).map_err(|_| Error::Bundle);
+			}
+		}
+		Err(Error::Bundle)
+	}
+}
+
+/// The manifest of a .NET single-file bundle appended to an apphost.
+pub struct Bundle {
+	/// The whole file, entry offsets are relative to its start.
+	pub data: &'static [u8],
+	pub major_version: u32,
+	pub minor_version: u32,
+	pub bundle_id: &'static str,
+	pub flags: u64,
+	number_of_files: u32,
+	manifest_offset: usize,
+}
+
+impl Bundle {
+	/// `None` for anything but a bundle, including an apphost still waiting to be bundled.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn find(data: &'static [u8]) -> Option<Result<Self>> {
+		let signature_offset = data
+			.windows(BUNDLE_SIGNATURE.len())
+			.position(|window| window == BUNDLE_SIGNATURE)?;
+		let header_offset = data.get(signature_offset.checked_sub(8)?..signature_offset)?;
+		match u64::from_le_bytes(header_offset.try_into().ok()?) {
+			0 => None,
+			header_offset => Some(Self::parse(data, header_offset)),
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(data: &'static [u8], header_offset: u64) -> Result<Self> {
+		let mut reader = Reader {
+			data,
+			offset: usize::try_from(header_offset).map_err(|_| Error::Bundle)?,
+		};
+		let major_version = reader.u32()?;
+		let minor_version = reader.u32()?;
+		let number_of_files = reader.u32()?;
+		let bundle_id = reader.string()?;
+		let mut flags = 0;
+		if major_version >= 2 {
+			// deps.json and runtimeconfig.json locations, repeated in the manifest entries.
+			reader.bytes(32)?;
+			flags = reader.u64()?;
+		}
+
+		Ok(Self {
+			data,
+			major_version,
+			minor_version,
+			bundle_id,
+			flags,
+			number_of_files,
+			manifest_offset: reader.offset,
+		})
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn entries(&self) -> impl Iterator<Item = Result<BundleEntry>> + '_ {
+		let mut reader = Reader {
+			data: self.data,
+			offset: self.manifest_offset,
+		};
+		let compressed = self.major_version >= 6;
+		(0..self.number_of_files).map(move |_| {
+			Ok(BundleEntry {
+				offset: reader.u64()?,
+				size: reader.u64()?,
+				compressed_size: if compressed { reader.u64()? } else { 0 },
+				file_type: BundleFileType::from_u8(reader.u8()?),
+				path: reader.string()?,
+			})
+		})
+	}
+
+	/// The stored bytes of `entry`, still deflated when `compressed_size` is set.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn entry_data(&self, entry: &BundleEntry) -> Option<&'static [u8]> {
+		let len = match entry.compressed_size {
+			0 => entry.size,
+			compressed_size => compressed_size,
+		};
+		let start = usize::try_from(entry.offset).ok()?;
+		let end = start.checked_add(usize::try_from(len).ok()?)?;
+		self.data.get(start..end)
+	}
+}
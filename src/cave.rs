@@ -0,0 +1,60 @@
+use crate::{nt::NtHeaders, section, PeHeaders};
+use object::{pe::ImageSectionHeader, LittleEndian};
+
+/// Bytes compilers and linkers pad code with.
+pub const CAVE_FILL: [u8; 2] = [0x00, 0xCC];
+
+#[derive(Clone, Copy, Debug)]
+pub struct CodeCave {
+	pub section: &'static ImageSectionHeader,
+	pub rva: u32,
+	pub size: u32,
+	/// One of [`CAVE_FILL`].
+	pub fill: u8,
+}
+
+/// Runs of a single [`CAVE_FILL`] byte in `data` of at least `min_size` bytes.
+fn caves_in(data: &[u8], min_size: usize) -> impl Iterator<Item = (usize, usize, u8)> + '_ {
+	let mut offset = 0;
+	core::iter::from_fn(move || {
+		while offset < data.len() {
+			let fill = data[offset];
+			let start = offset;
+			offset += data[start..]
+				.iter()
+				.position(|&b| b != fill)
+				.unwrap_or(data.len() - start);
+			if CAVE_FILL.contains(&fill) && offset - start >= min_size.max(1) {
+				return Some((start, offset - start, fill));
+			}
+		}
+		None
+	})
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Padding runs in executable sections, in the raw data for a file and up to the virtual
+	/// size, including the zero-filled tail, for a mapped image.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn find_code_caves(
+		&self,
+		image_base: *const u8,
+		min_size: usize,
+	) -> impl Iterator<Item = CodeCave> + '_ {
+		self.section_headers
+			.iter()
+			.filter(|section| section::section_is_executable(section))
+			.filter_map(move |section| {
+				let data = unsafe { self.section_data(image_base, section) }.ok()?;
+				Some(
+					caves_in(data, min_size).map(move |(offset, size, fill)| CodeCave {
+						section,
+						rva: section.virtual_address.get(LittleEndian) + offset as u32,
+						size: size as u32,
+						fill,
+					}),
+				)
+			})
+			.flatten()
+	}
+}
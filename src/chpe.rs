@@ -0,0 +1,285 @@
+use crate::{
+	error::{Error, Result},
+	nt::NtHeaders,
+	rva_ptr, PeHeaders,
+};
+use core::{mem::offset_of, slice};
+use object::{
+	pe::{
+		ImageLoadConfigDirectory32, ImageLoadConfigDirectory64, IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG,
+	},
+	LittleEndian, U32,
+};
+
+/// `IMAGE_ARM64EC_METADATA`, pointed to by the load config of ARM64EC and ARM64X images.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ImageArm64EcMetadata {
+	pub version: U32<LittleEndian>,
+	pub code_map: U32<LittleEndian>,
+	pub code_map_count: U32<LittleEndian>,
+	pub code_ranges_to_entry_points: U32<LittleEndian>,
+	pub redirection_metadata: U32<LittleEndian>,
+	pub dispatch_call_no_redirect: U32<LittleEndian>,
+	pub dispatch_ret: U32<LittleEndian>,
+	pub dispatch_call: U32<LittleEndian>,
+	pub dispatch_icall: U32<LittleEndian>,
+	pub dispatch_icall_cfg: U32<LittleEndian>,
+	pub alternate_entry_point: U32<LittleEndian>,
+	pub auxiliary_iat: U32<LittleEndian>,
+	pub code_ranges_to_entry_points_count: U32<LittleEndian>,
+	pub redirection_metadata_count: U32<LittleEndian>,
+	pub get_x64_information_function_pointer: U32<LittleEndian>,
+	pub set_x64_information_function_pointer: U32<LittleEndian>,
+	pub extra_rfe_table: U32<LittleEndian>,
+	pub extra_rfe_table_size: U32<LittleEndian>,
+	pub dispatch_fptr: U32<LittleEndian>,
+	pub auxiliary_iat_copy: U32<LittleEndian>,
+}
+
+/// `IMAGE_CHPE_METADATA_X86`, the compiled-hybrid x86 images of Windows on ARM.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ImageChpeMetadataX86 {
+	pub version: U32<LittleEndian>,
+	pub code_address_range_offset: U32<LittleEndian>,
+	pub code_address_range_count: U32<LittleEndian>,
+	pub wow_a64_exception_handler_function_pointer: U32<LittleEndian>,
+	pub wow_a64_dispatch_call_function_pointer: U32<LittleEndian>,
+	pub wow_a64_dispatch_indirect_call_function_pointer: U32<LittleEndian>,
+	pub wow_a64_dispatch_indirect_call_cfg_function_pointer: U32<LittleEndian>,
+	pub wow_a64_dispatch_ret_function_pointer: U32<LittleEndian>,
+	pub wow_a64_dispatch_ret_leaf_function_pointer: U32<LittleEndian>,
+	pub wow_a64_dispatch_jump_function_pointer: U32<LittleEndian>,
+}
+
+/// `IMAGE_CHPE_RANGE_ENTRY`, the low bits of `start_offset` hold the code kind.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ImageChpeRangeEntry {
+	pub start_offset: U32<LittleEndian>,
+	pub length: U32<LittleEndian>,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ImageArm64EcRedirectionEntry {
+	pub source: U32<LittleEndian>,
+	pub destination: U32<LittleEndian>,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ImageArm64EcCodeRangeEntryPoint {
+	pub start_rva: U32<LittleEndian>,
+	pub end_rva: U32<LittleEndian>,
+	pub entry_point: U32<LittleEndian>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeKind {
+	Arm64,
+	/// ARM64 code following the x64 calling convention, callable from emulated x64.
+	Arm64Ec,
+	/// x64 code running under emulation.
+	Amd64,
+	/// x86 code of a CHPE image.
+	X86,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CodeRange {
+	pub start: u32,
+	pub length: u32,
+	pub kind: CodeKind,
+}
+
+impl CodeRange {
+	pub fn contains(&self, rva: u32) -> bool {
+		rva.wrapping_sub(self.start) < self.length
+	}
+}
+
+pub struct Arm64EcMetadata {
+	pub metadata: &'static ImageArm64EcMetadata,
+	pub code_map: &'static [ImageChpeRangeEntry],
+	pub redirections: &'static [ImageArm64EcRedirectionEntry],
+	pub entry_points: &'static [ImageArm64EcCodeRangeEntryPoint],
+}
+
+impl Arm64EcMetadata {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse(image_base: *const u8, rva: u32) -> Result<Self> {
+		let metadata = unsafe { &*rva_ptr(image_base, rva as _)?.cast::<ImageArm64EcMetadata>() };
+		Ok(Self {
+			metadata,
+			code_map: unsafe { table(image_base, metadata.code_map, metadata.code_map_count)? },
+			redirections: unsafe {
+				table(
+					image_base,
+					metadata.redirection_metadata,
+					metadata.redirection_metadata_count,
+				)?
+			},
+			entry_points: unsafe {
+				table(
+					image_base,
+					metadata.code_ranges_to_entry_points,
+					metadata.code_ranges_to_entry_points_count,
+				)?
+			},
+		})
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn code_ranges(&self) -> impl Iterator<Item = CodeRange> + '_ {
+		self.code_map.iter().map(|entry| {
+			let start_offset = entry.start_offset.get(LittleEndian);
+			CodeRange {
+				start: start_offset & !3,
+				length: entry.length.get(LittleEndian),
+				kind: match start_offset & 3 {
+					0 => CodeKind::Arm64,
+					1 => CodeKind::Arm64Ec,
+					_ => CodeKind::Amd64,
+				},
+			}
+		})
+	}
+
+	/// The x64 entry thunk an ARM64EC function at `rva` is redirected to for emulated callers.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn redirect(&self, rva: u32) -> Option<u32> {
+		self.redirections
+			.iter()
+			.find(|entry| entry.source.get(LittleEndian) == rva)
+			.map(|entry| entry.destination.get(LittleEndian))
+	}
+}
+
+pub struct ChpeX86Metadata {
+	pub metadata: &'static ImageChpeMetadataX86,
+	pub code_map: &'static [ImageChpeRangeEntry],
+}
+
+impl ChpeX86Metadata {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse(image_base: *const u8, rva: u32) -> Result<Self> {
+		let metadata = unsafe { &*rva_ptr(image_base, rva as _)?.cast::<ImageChpeMetadataX86>() };
+		Ok(Self {
+			metadata,
+			code_map: unsafe {
+				table(
+					image_base,
+					metadata.code_address_range_offset,
+					metadata.code_address_range_count,
+				)?
+			},
+		})
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn code_ranges(&self) -> impl Iterator<Item = CodeRange> + '_ {
+		self.code_map.iter().map(|entry| {
+			let start_offset = entry.start_offset.get(LittleEndian);
+			CodeRange {
+				start: start_offset & !1,
+				length: entry.length.get(LittleEndian),
+				kind: match start_offset & 1 {
+					0 => CodeKind::X86,
+					_ => CodeKind::Arm64,
+				},
+			}
+		})
+	}
+}
+
+pub enum HybridMetadata {
+	Arm64Ec(Arm64EcMetadata),
+	X86(ChpeX86Metadata),
+}
+
+impl HybridMetadata {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn code_ranges(&self) -> impl Iterator<Item = CodeRange> + '_ {
+		let (arm64ec, x86) = match self {
+			HybridMetadata::Arm64Ec(metadata) => (Some(metadata.code_ranges()), None),
+			HybridMetadata::X86(metadata) => (None, Some(metadata.code_ranges())),
+		};
+		arm64ec
+			.into_iter()
+			.flatten()
+			.chain(x86.into_iter().flatten())
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn code_kind(&self, rva: u32) -> Option<CodeKind> {
+		self.code_ranges()
+			.find(|range| range.contains(rva))
+			.map(|range| range.kind)
+	}
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn table<T>(
+	image_base: *const u8,
+	rva: U32<LittleEndian>,
+	count: U32<LittleEndian>,
+) -> Result<&'static [T]> {
+	let (rva, count) = (rva.get(LittleEndian), count.get(LittleEndian));
+	if rva == 0 || count == 0 {
+		return Ok(&[]);
+	}
+	let ptr = rva_ptr(image_base, rva as _)?;
+	Ok(unsafe { slice::from_raw_parts(ptr.cast(), count as _) })
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// CHPE metadata of ARM64EC/ARM64X and hybrid x86 images, `None` for everything else.
+	///
+	/// ARM64X images also carry the x64 view of their exports in ARM64X dynamic relocations,
+	/// which are not applied here: the export table is the native ARM64 one.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn hybrid_metadata(&self, image_base: *const u8) -> Result<Option<HybridMetadata>> {
+		let Some(load_config_data_dir) = self.data_directory(IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG)
+		else {
+			return Ok(None);
+		};
+		let load_config_rva = load_config_data_dir.virtual_address.get(LittleEndian);
+		let load_config_ptr = rva_ptr(image_base, load_config_rva as _)?;
+		// The load config grew over time, `Size` tells which fields are present.
+		let load_config_size =
+			u32::from_le(unsafe { load_config_ptr.cast::<u32>().read_unaligned() }) as usize;
+		let is_64 = self.nt_header.is_type_64();
+		let (offset, width) = match is_64 {
+			true => (
+				offset_of!(ImageLoadConfigDirectory64, chpe_metadata_pointer),
+				8,
+			),
+			false => (
+				offset_of!(ImageLoadConfigDirectory32, chpe_metadata_pointer),
+				4,
+			),
+		};
+		if load_config_size < offset + width {
+			return Ok(None);
+		}
+		let pointer_ptr = load_config_ptr.wrapping_add(offset);
+		let chpe_va = if is_64 {
+			u64::from_le(unsafe { pointer_ptr.cast::<u64>().read_unaligned() })
+		} else {
+			u32::from_le(unsafe { pointer_ptr.cast::<u32>().read_unaligned() }).into()
+		};
+		if chpe_va == 0 {
+			return Ok(None);
+		}
+		let chpe_rva = self
+			.va_to_rva(image_base, chpe_va)
+			.ok_or(Error::ChpeMetadata)?;
+		Ok(Some(if is_64 {
+			HybridMetadata::Arm64Ec(unsafe { Arm64EcMetadata::parse(image_base, chpe_rva)? })
+		} else {
+			HybridMetadata::X86(unsafe { ChpeX86Metadata::parse(image_base, chpe_rva)? })
+		}))
+	}
+}
@@ -0,0 +1,298 @@
+use crate::{
+	error::{Error, Result},
+	metadata::{Metadata, TABLE_MANIFEST_RESOURCE},
+	nt::NtHeaders,
+	rva_ptr, PeHeaders,
+};
+use core::{ffi::CStr, mem::size_of, slice};
+use object::{
+	pe::{
+		ImageCor20Header, ImageDataDirectory, ImageRuntimeFunctionEntry,
+		COMIMAGE_FLAGS_32BITPREFERRED, COMIMAGE_FLAGS_32BITREQUIRED, COMIMAGE_FLAGS_ILONLY,
+		COMIMAGE_FLAGS_STRONGNAMESIGNED, IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR,
+	},
+	LittleEndian, U16, U32,
+};
+
+pub const READYTORUN_SIGNATURE: u32 = 0x0052_5452;
+
+pub const READYTORUN_SECTION_COMPILER_IDENTIFIER: u32 = 100;
+pub const READYTORUN_SECTION_IMPORT_SECTIONS: u32 = 101;
+pub const READYTORUN_SECTION_RUNTIME_FUNCTIONS: u32 = 102;
+pub const READYTORUN_SECTION_METHODDEF_ENTRYPOINTS: u32 = 103;
+pub const READYTORUN_SECTION_EXCEPTION_INFO: u32 = 104;
+pub const READYTORUN_SECTION_DEBUG_INFO: u32 = 105;
+pub const READYTORUN_SECTION_DELAYLOAD_METHODCALL_THUNKS: u32 = 106;
+pub const READYTORUN_SECTION_AVAILABLE_TYPES: u32 = 108;
+pub const READYTORUN_SECTION_INSTANCE_METHOD_ENTRYPOINTS: u32 = 109;
+pub const READYTORUN_SECTION_INLINING_INFO: u32 = 110;
+pub const READYTORUN_SECTION_PROFILEDATA_INFO: u32 = 111;
+pub const READYTORUN_SECTION_MANIFEST_METADATA: u32 = 112;
+pub const READYTORUN_SECTION_ATTRIBUTEPRESENCE: u32 = 113;
+pub const READYTORUN_SECTION_INLINING_INFO2: u32 = 114;
+pub const READYTORUN_SECTION_COMPONENT_ASSEMBLIES: u32 = 115;
+pub const READYTORUN_SECTION_OWNER_COMPOSITE_EXECUTABLE: u32 = 116;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ImageReadyToRunHeader {
+	pub signature: U32<LittleEndian>,
+	pub major_version: U16<LittleEndian>,
+	pub minor_version: U16<LittleEndian>,
+	pub flags: U32<LittleEndian>,
+	pub number_of_sections: U32<LittleEndian>,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ImageReadyToRunSection {
+	pub section_type: U32<LittleEndian>,
+	pub section: ImageDataDirectory,
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn directory_data(
+	image_base: *const u8,
+	directory: &ImageDataDirectory,
+) -> Result<Option<&'static [u8]>> {
+	let rva = directory.virtual_address.get(LittleEndian);
+	if rva == 0 {
+		return Ok(None);
+	}
+	let ptr = rva_ptr(image_base, rva as _)?;
+	let size = directory.size.get(LittleEndian);
+	Ok(Some(unsafe { slice::from_raw_parts(ptr, size as _) }))
+}
+
+pub struct ClrHeader {
+	pub cor20_header: &'static ImageCor20Header,
+}
+
+impl ClrHeader {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(address: *const u8) -> Self {
+		let cor20_header = unsafe { &*address.cast::<ImageCor20Header>() };
+
+		Self { cor20_header }
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn ready_to_run(&self, image_base: *const u8) -> Result<Option<ReadyToRunHeader>> {
+		let directory = &self.cor20_header.managed_native_header;
+		let rva = directory.virtual_address.get(LittleEndian);
+		if rva == 0 {
+			return Ok(None);
+		}
+		let header_ptr = rva_ptr(image_base, rva as _)?;
+		let header = unsafe { &*header_ptr.cast::<ImageReadyToRunHeader>() };
+		if header.signature.get(LittleEndian) != READYTORUN_SIGNATURE {
+			return Ok(None);
+		}
+		let sections_ptr = header_ptr
+			.wrapping_add(size_of::<ImageReadyToRunHeader>())
+			.cast::<ImageReadyToRunSection>();
+		let sections_len = header.number_of_sections.get(LittleEndian) as _;
+		let sections = unsafe { slice::from_raw_parts(sections_ptr, sections_len) };
+
+		Ok(Some(ReadyToRunHeader { header, sections }))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn metadata(&self, image_base: *const u8) -> Result<Option<Metadata>> {
+		let directory = &self.cor20_header.meta_data;
+		match unsafe { directory_data(image_base, directory)? } {
+			Some(data) => Metadata::parse(data).map(Some),
+			None => Ok(None),
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn managed_resources(
+		&self,
+		image_base: *const u8,
+		metadata: &Metadata,
+	) -> Result<impl Iterator<Item = ManagedResource>> {
+		let resources = unsafe { directory_data(image_base, &self.cor20_header.resources)? };
+		let resources = resources.unwrap_or_default();
+		let tables = metadata.tables()?;
+		let strings = metadata.strings().ok_or(Error::ClrMetadata)?;
+		let row_count = tables.row_count(TABLE_MANIFEST_RESOURCE);
+		Ok((1..=row_count).filter_map(move |row| {
+			let offset = tables.get(TABLE_MANIFEST_RESOURCE, row, 0)? as usize;
+			let flags = tables.get(TABLE_MANIFEST_RESOURCE, row, 1)?;
+			let name = strings.get(tables.get(TABLE_MANIFEST_RESOURCE, row, 2)?)?;
+			// Resources living in other files or assemblies have no blob in this image.
+			if tables.get(TABLE_MANIFEST_RESOURCE, row, 3)? != 0 {
+				return None;
+			}
+			let len = resources.get(offset..offset.checked_add(4)?)?;
+			let len = u32::from_le_bytes(len.try_into().ok()?) as usize;
+			let data = resources.get(offset + 4..(offset + 4).checked_add(len)?)?;
+			Some(ManagedResource {
+				name,
+				data,
+				public: flags & MANIFEST_RESOURCE_PUBLIC != 0,
+			})
+		}))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn managed_resource(
+		&self,
+		image_base: *const u8,
+		metadata: &Metadata,
+		name: &str,
+	) -> Result<Option<ManagedResource>> {
+		Ok(unsafe { self.managed_resources(image_base, metadata)? }
+			.find(|resource| resource.name.to_bytes() == name.as_bytes()))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn strong_name_signature(
+		&self,
+		image_base: *const u8,
+	) -> Result<Option<StrongNameSignature>> {
+		let directory = &self.cor20_header.strong_name_signature;
+		let data = unsafe { directory_data(image_base, directory)? };
+		Ok(data.map(|data| StrongNameSignature {
+			data,
+			signed: self.cor20_header.flags.get(LittleEndian) & COMIMAGE_FLAGS_STRONGNAMESIGNED
+				!= 0,
+		}))
+	}
+}
+
+const MANIFEST_RESOURCE_PUBLIC: u32 = 0x0001;
+
+#[derive(Clone, Copy)]
+pub struct ManagedResource {
+	pub name: &'static CStr,
+	pub data: &'static [u8],
+	pub public: bool,
+}
+
+pub struct StrongNameSignature {
+	pub data: &'static [u8],
+	/// `COMIMAGE_FLAGS_STRONGNAMESIGNED` is set, the signature space is reserved otherwise.
+	pub signed: bool,
+}
+
+impl StrongNameSignature {
+	/// The signature is as long as the RSA modulus, 1024 to 4096 bits.
+	pub fn is_valid_length(&self) -> bool {
+		matches!(self.data.len(), 128 | 256 | 384 | 512)
+	}
+
+	/// Delay-signed assemblies reserve the space but leave it zeroed.
+	pub fn is_delay_signed(&self) -> bool {
+		self.data.iter().all(|&b| b == 0)
+	}
+}
+
+/// What triage needs to know about a managed image, see [`PeHeaders::clr_version`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClrVersion {
+	/// `MajorRuntimeVersion` and `MinorRuntimeVersion`, 2.5 for everything since .NET 2.0.
+	pub runtime_version: (u16, u16),
+	/// Version string of the metadata root, e.g. `v4.0.30319`, `None` if the metadata does not
+	/// parse.
+	pub metadata_version: Option<&'static [u8]>,
+	/// No native code besides the entry stub, `COMIMAGE_FLAGS_ILONLY`.
+	pub il_only: bool,
+	/// `COMIMAGE_FLAGS_32BITREQUIRED` without `COMIMAGE_FLAGS_32BITPREFERRED`, the image only
+	/// runs in a 32-bit process.
+	pub requires_32bit: bool,
+	/// Both flags, AnyCPU that prefers a 32-bit process.
+	pub prefers_32bit: bool,
+}
+
+pub struct ReadyToRunHeader {
+	pub header: &'static ImageReadyToRunHeader,
+	pub sections: &'static [ImageReadyToRunSection],
+}
+
+impl ReadyToRunHeader {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section(&self, section_type: u32) -> Option<&'static ImageDataDirectory> {
+		self.sections
+			.iter()
+			.find(|section| section.section_type.get(LittleEndian) == section_type)
+			.map(|section| &section.section)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn section_data(
+		&self,
+		image_base: *const u8,
+		section_type: u32,
+	) -> Result<Option<&'static [u8]>> {
+		match self.section(section_type) {
+			Some(directory) => unsafe { directory_data(image_base, directory) },
+			None => Ok(None),
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn compiler_identifier(
+		&self,
+		image_base: *const u8,
+	) -> Result<Option<&'static CStr>> {
+		let data =
+			unsafe { self.section_data(image_base, READYTORUN_SECTION_COMPILER_IDENTIFIER)? };
+		match data {
+			Some(data) => CStr::from_bytes_until_nul(data)
+				.map(Some)
+				.map_err(|_| Error::ClrHeader),
+			None => Ok(None),
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn runtime_functions(
+		&self,
+		image_base: *const u8,
+	) -> Result<&'static [ImageRuntimeFunctionEntry]> {
+		let data = unsafe { self.section_data(image_base, READYTORUN_SECTION_RUNTIME_FUNCTIONS)? };
+		let data = data.unwrap_or_default();
+		let len = data.len() / size_of::<ImageRuntimeFunctionEntry>();
+		Ok(unsafe { slice::from_raw_parts(data.as_ptr().cast(), len) })
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Whether the COM descriptor directory is present, which is how the loader tells managed
+	/// images apart. Only the headers are looked at.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn is_dotnet(&self) -> bool {
+		self.data_directory(IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR)
+			.is_some_and(|directory| {
+				directory.size.get(LittleEndian) as usize >= size_of::<ImageCor20Header>()
+			})
+	}
+
+	/// Runtime version, flags and metadata version of the image mapped at `image_base`, without
+	/// parsing the metadata tables. `None` for native images.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn clr_version(&self, image_base: *const u8) -> Result<Option<ClrVersion>> {
+		if !self.is_dotnet() {
+			return Ok(None);
+		}
+		let Some(clr_header) = (unsafe { self.clr_header_mem(image_base)? }) else {
+			return Ok(None);
+		};
+		let header = clr_header.cor20_header;
+		let flags = header.flags.get(LittleEndian);
+		let bitness = flags & (COMIMAGE_FLAGS_32BITREQUIRED | COMIMAGE_FLAGS_32BITPREFERRED);
+		let metadata = unsafe { clr_header.metadata(image_base) }.ok().flatten();
+		Ok(Some(ClrVersion {
+			runtime_version: (
+				header.major_runtime_version.get(LittleEndian),
+				header.minor_runtime_version.get(LittleEndian),
+			),
+			metadata_version: metadata.map(|metadata| metadata.version),
+			il_only: flags & COMIMAGE_FLAGS_ILONLY != 0,
+			requires_32bit: bitness == COMIMAGE_FLAGS_32BITREQUIRED,
+			prefers_32bit: bitness == COMIMAGE_FLAGS_32BITREQUIRED | COMIMAGE_FLAGS_32BITPREFERRED,
+		}))
+	}
+}
@@ -0,0 +1,221 @@
+use crate::{
+	error::{Error, Result},
+	section,
+};
+use core::{ffi::CStr, mem::size_of};
+use object::{
+	pe::{
+		ImageAuxSymbolSection, ImageFileHeader, ImageRelocation, ImageSectionHeader, ImageSymbol,
+		IMAGE_SCN_LNK_COMDAT, IMAGE_SCN_LNK_NRELOC_OVFL, IMAGE_SIZEOF_SYMBOL,
+		IMAGE_SYM_CLASS_STATIC,
+	},
+	pod, LittleEndian,
+};
+
+/// A COFF symbol and the auxiliary records following it.
+#[derive(Clone, Copy, Debug)]
+pub struct CoffSymbol {
+	/// Index in the symbol table, as used by relocations.
+	pub index: usize,
+	pub symbol: &'static ImageSymbol,
+	pub aux: &'static [u8],
+}
+
+impl CoffSymbol {
+	/// 1-based section number, or one of the `IMAGE_SYM_*` special values.
+	pub fn section_number(&self) -> i16 {
+		self.symbol.section_number.get(LittleEndian) as i16
+	}
+}
+
+/// Selection of a COMDAT section, from the auxiliary record of its section symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Comdat {
+	/// `IMAGE_COMDAT_SELECT_*`.
+	pub selection: u8,
+	/// 1-based section number for `IMAGE_COMDAT_SELECT_ASSOCIATIVE`.
+	pub associated_section: u16,
+	/// Index of the COMDAT symbol, the first symbol after the section symbol with its section.
+	pub symbol: Option<usize>,
+}
+
+/// A relocatable object file, a COFF file without optional header as produced by compilers.
+pub struct CoffFile {
+	pub data: &'static [u8],
+	pub file_header: &'static ImageFileHeader,
+	pub section_headers: &'static [ImageSectionHeader],
+	/// Symbol table including auxiliary records, in `IMAGE_SIZEOF_SYMBOL` units.
+	pub symbol_table: &'static [u8],
+	/// String table including its size field.
+	pub string_table: &'static [u8],
+}
+
+impl CoffFile {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(data: &'static [u8]) -> Result<Self> {
+		let (file_header, rest) =
+			pod::from_bytes::<ImageFileHeader>(data).map_err(|_| Error::Coff)?;
+		// Images have an optional header, anonymous and bigobj objects start with machine 0 and
+		// number of sections 0xFFFF.
+		if file_header.size_of_optional_header.get(LittleEndian) != 0
+			|| (file_header.machine.get(LittleEndian) == 0
+				&& file_header.number_of_sections.get(LittleEndian) == 0xFFFF)
+		{
+			return Err(Error::Coff);
+		}
+		let number_of_sections = file_header.number_of_sections.get(LittleEndian) as usize;
+		let (section_headers, _) =
+			pod::slice_from_bytes::<ImageSectionHeader>(rest, number_of_sections)
+				.map_err(|_| Error::Coff)?;
+
+		let symbol_table_offset = file_header.pointer_to_symbol_table.get(LittleEndian) as usize;
+		let number_of_symbols = file_header.number_of_symbols.get(LittleEndian) as usize;
+		let (symbol_table, string_table) = match symbol_table_offset {
+			0 => (&[][..], &[][..]),
+			_ => {
+				let symbol_table_end = number_of_symbols
+					.checked_mul(IMAGE_SIZEOF_SYMBOL)
+					.and_then(|size| size.checked_add(symbol_table_offset))
+					.ok_or(Error::Coff)?;
+				let symbol_table = data
+					.get(symbol_table_offset..symbol_table_end)
+					.ok_or(Error::Coff)?;
+				let string_table_size = data
+					.get(symbol_table_end..symbol_table_end + size_of::<u32>())
+					.map_or(0, |size| {
+						u32::from_le_bytes(size.try_into().unwrap()) as usize
+					});
+				let string_table = data
+					.get(symbol_table_end..symbol_table_end + string_table_size)
+					.ok_or(Error::Coff)?;
+				(symbol_table, string_table)
+			}
+		};
+
+		Ok(Self {
+			data,
+			file_header,
+			section_headers,
+			symbol_table,
+			string_table,
+		})
+	}
+
+	/// Raw data of `section`, `None` for uninitialized data.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section_data(&self, section: &ImageSectionHeader) -> Option<&'static [u8]> {
+		let offset = section.pointer_to_raw_data.get(LittleEndian) as usize;
+		if offset == 0 {
+			return None;
+		}
+		let size = section.size_of_raw_data.get(LittleEndian) as usize;
+		self.data.get(offset..offset.checked_add(size)?)
+	}
+
+	/// Relocations of `section`, with the count taken from the first entry for sections with
+	/// more than 0xFFFF of them.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn relocations(&self, section: &ImageSectionHeader) -> Result<&'static [ImageRelocation]> {
+		let offset = section.pointer_to_relocations.get(LittleEndian) as usize;
+		let mut count = section.number_of_relocations.get(LittleEndian) as usize;
+		let data = self.data.get(offset..).ok_or(Error::Coff)?;
+		let overflow = section.characteristics.get(LittleEndian) & IMAGE_SCN_LNK_NRELOC_OVFL != 0
+			&& count == 0xFFFF;
+		if overflow {
+			let (first, _) = pod::from_bytes::<ImageRelocation>(data).map_err(|_| Error::Coff)?;
+			count = first.virtual_address.get(LittleEndian) as usize;
+		}
+		let (relocations, _) =
+			pod::slice_from_bytes::<ImageRelocation>(data, count).map_err(|_| Error::Coff)?;
+		// The first entry only holds the count.
+		Ok(if overflow {
+			relocations.get(1..).unwrap_or_default()
+		} else {
+			relocations
+		})
+	}
+
+	/// The symbol at `index`, which must not be an auxiliary record.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn symbol(&self, index: usize) -> Option<CoffSymbol> {
+		let offset = index.checked_mul(IMAGE_SIZEOF_SYMBOL)?;
+		let (symbol, rest) =
+			pod::from_bytes::<ImageSymbol>(self.symbol_table.get(offset..)?).ok()?;
+		let aux_len = symbol.number_of_aux_symbols as usize * IMAGE_SIZEOF_SYMBOL;
+		Some(CoffSymbol {
+			index,
+			symbol,
+			aux: rest.get(..aux_len)?,
+		})
+	}
+
+	/// Symbols in table order, skipping auxiliary records.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn symbols(&self) -> impl Iterator<Item = CoffSymbol> + '_ {
+		let mut index = 0;
+		core::iter::from_fn(move || {
+			let symbol = self.symbol(index)?;
+			index += 1 + symbol.symbol.number_of_aux_symbols as usize;
+			Some(symbol)
+		})
+	}
+
+	/// Name of `symbol`, inline or from the string table.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn symbol_name(&self, symbol: &'static ImageSymbol) -> Option<&'static [u8]> {
+		match symbol.name {
+			[0, 0, 0, 0, offset @ ..] => {
+				let offset = u32::from_le_bytes(offset) as usize;
+				let name = CStr::from_bytes_until_nul(self.string_table.get(offset..)?).ok()?;
+				Some(name.to_bytes())
+			}
+			_ => {
+				let name = &symbol.name;
+				let len = name
+					.iter()
+					.position(|&byte| byte == 0)
+					.unwrap_or(name.len());
+				Some(&name[..len])
+			}
+		}
+	}
+
+	/// Name of `section`, resolving `/offset` names through the string table.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section_name(&self, section: &'static ImageSectionHeader) -> Option<&'static [u8]> {
+		let name = section::section_name_bytes(section);
+		match name.strip_prefix(b"/") {
+			Some(offset) => {
+				let offset = core::str::from_utf8(offset).ok()?.parse::<usize>().ok()?;
+				let name = CStr::from_bytes_until_nul(self.string_table.get(offset..)?).ok()?;
+				Some(name.to_bytes())
+			}
+			None => Some(name),
+		}
+	}
+
+	/// COMDAT information of the section at 0-based `index`, `None` if it is not a COMDAT.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn comdat(&self, index: usize) -> Option<Comdat> {
+		let section = self.section_headers.get(index)?;
+		if section.characteristics.get(LittleEndian) & IMAGE_SCN_LNK_COMDAT == 0 {
+			return None;
+		}
+		// Compared unsigned, numbers past 0x7FFF are negative as `i16`.
+		let section_number = u16::try_from(index.checked_add(1)?).ok()?;
+		let mut symbols = self
+			.symbols()
+			.filter(|symbol| symbol.symbol.section_number.get(LittleEndian) == section_number);
+		let section_symbol = symbols.find(|symbol| {
+			symbol.symbol.storage_class == IMAGE_SYM_CLASS_STATIC
+				&& symbol.symbol.value.get(LittleEndian) == 0
+				&& !symbol.aux.is_empty()
+		})?;
+		let (aux, _) = pod::from_bytes::<ImageAuxSymbolSection>(section_symbol.aux).ok()?;
+		Some(Comdat {
+			selection: aux.selection,
+			associated_section: aux.number.get(LittleEndian),
+			symbol: symbols.next().map(|symbol| symbol.index),
+		})
+	}
+}
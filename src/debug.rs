@@ -0,0 +1,272 @@
+use crate::{
+	check_range,
+	error::{Error, Result},
+	nt::NtHeaders,
+	rva_ptr, DebugTable, Layout, ParseOptions, PeHeaders,
+};
+use core::{fmt, slice};
+use object::{
+	pe::{
+		ImageDebugDirectory, IMAGE_DEBUG_TYPE_CODEVIEW, IMAGE_DEBUG_TYPE_POGO,
+		IMAGE_DEBUG_TYPE_REPRO, IMAGE_DEBUG_TYPE_VC_FEATURE,
+	},
+	LittleEndian,
+};
+
+pub const IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS: u32 = 20;
+
+/// `"RSDS"`, PDB 7.0 CodeView record.
+pub const CODEVIEW_RSDS_SIGNATURE: u32 = 0x5344_5352;
+/// `"NB10"`, PDB 2.0 CodeView record.
+pub const CODEVIEW_NB10_SIGNATURE: u32 = 0x3031_424e;
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+	let bytes = data.get(offset..offset + 4)?;
+	Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// A GUID in its in-memory layout, the first three fields little endian.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Guid(pub [u8; 16]);
+
+impl Guid {
+	pub fn data1(&self) -> u32 {
+		u32::from_le_bytes([self.0[0], self.0[1], self.0[2], self.0[3]])
+	}
+
+	pub fn data2(&self) -> u16 {
+		u16::from_le_bytes([self.0[4], self.0[5]])
+	}
+
+	pub fn data3(&self) -> u16 {
+		u16::from_le_bytes([self.0[6], self.0[7]])
+	}
+
+	pub fn data4(&self) -> [u8; 8] {
+		[
+			self.0[8], self.0[9], self.0[10], self.0[11], self.0[12], self.0[13], self.0[14],
+			self.0[15],
+		]
+	}
+}
+
+/// `XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX` in upper case.
+impl fmt::Display for Guid {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let data4 = self.data4();
+		write!(
+			f,
+			"{:08X}-{:04X}-{:04X}-{:02X}{:02X}-",
+			self.data1(),
+			self.data2(),
+			self.data3(),
+			data4[0],
+			data4[1]
+		)?;
+		data4[2..].iter().try_for_each(|b| write!(f, "{b:02X}"))
+	}
+}
+
+/// Identifies the PDB matching an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdbId {
+	Guid(Guid),
+	/// Time stamp of an NB10 record.
+	Signature(u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CodeView {
+	pub id: PdbId,
+	pub age: u32,
+	/// Path as written by the linker, without the terminating null.
+	pub pdb_path: &'static [u8],
+}
+
+impl CodeView {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(data: &'static [u8]) -> Result<Self> {
+		let signature = read_u32(data, 0).ok_or(Error::DebugTable)?;
+		let (id, age, path_offset) = match signature {
+			CODEVIEW_RSDS_SIGNATURE => {
+				let guid = data.get(4..20).ok_or(Error::DebugTable)?;
+				let age = read_u32(data, 20).ok_or(Error::DebugTable)?;
+				(PdbId::Guid(Guid(guid.try_into().unwrap())), age, 24)
+			}
+			CODEVIEW_NB10_SIGNATURE => {
+				let time_date_stamp = read_u32(data, 8).ok_or(Error::DebugTable)?;
+				let age = read_u32(data, 12).ok_or(Error::DebugTable)?;
+				(PdbId::Signature(time_date_stamp), age, 16)
+			}
+			_ => return Err(Error::DebugTable),
+		};
+		let path = &data[path_offset..];
+		let len = path.iter().position(|&b| b == 0).unwrap_or(path.len());
+		Ok(Self {
+			id,
+			age,
+			pdb_path: &path[..len],
+		})
+	}
+
+	pub fn guid(&self) -> Option<Guid> {
+		match self.id {
+			PdbId::Guid(guid) => Some(guid),
+			PdbId::Signature(_) => None,
+		}
+	}
+
+	/// Last component of the path, split at both `\` and `/`.
+	pub fn pdb_file_name(&self) -> &'static [u8] {
+		let path = self.pdb_path;
+		path.rsplit(|&b| b == b'\\' || b == b'/')
+			.next()
+			.unwrap_or(path)
+	}
+
+	/// [`CodeView::pdb_file_name`] if it is UTF-8 and usable as a file name on its own: not
+	/// empty, not `.` or `..`, and free of control characters and of the characters Windows
+	/// reserves, which also rules out drive prefixes and alternate data streams.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn pdb_name(&self) -> Option<&'static str> {
+		let name = core::str::from_utf8(self.pdb_file_name()).ok()?;
+		let reserved =
+			|c: char| c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*');
+		if name.is_empty() || name == "." || name == ".." || name.contains(reserved) {
+			return None;
+		}
+		Some(name)
+	}
+
+	/// Directory of the PDB on a symbol server, the GUID or NB10 time stamp in upper case hex
+	/// followed by the age in hex without leading zeros.
+	#[cfg(feature = "alloc")]
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn symsrv_key(&self) -> alloc::string::String {
+		use alloc::format;
+		use core::fmt::Write;
+		match self.id {
+			PdbId::Guid(guid) => {
+				let mut key = format!(
+					"{:08X}{:04X}{:04X}",
+					guid.data1(),
+					guid.data2(),
+					guid.data3()
+				);
+				for b in &guid.data4() {
+					let _ = write!(key, "{b:02X}");
+				}
+				let _ = write!(key, "{:X}", self.age);
+				key
+			}
+			PdbId::Signature(signature) => format!("{signature:08X}{:X}", self.age),
+		}
+	}
+
+	/// `base_url/name.pdb/KEY/name.pdb`, `None` without a usable [`CodeView::pdb_name`].
+	#[cfg(feature = "alloc")]
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn symsrv_url(&self, base_url: &str) -> Option<alloc::string::String> {
+		let name = self.pdb_name()?;
+		let base_url = base_url.trim_end_matches('/');
+		Some(alloc::format!(
+			"{base_url}/{name}/{}/{name}",
+			self.symsrv_key()
+		))
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DebugData {
+	CodeView(CodeView),
+	/// Profile guided optimization records, kept raw.
+	Pogo(&'static [u8]),
+	/// Hash of a deterministic build, empty when the time stamps were left as is.
+	Repro(&'static [u8]),
+	/// Counts of `/GS`, SDL and guard objects, kept raw.
+	VcFeature(&'static [u8]),
+	/// `IMAGE_DLLCHARACTERISTICS_EX_*` flags such as CET compatibility.
+	ExDllCharacteristics(u32),
+	/// Any other type, or a record whose data is not present in the image.
+	Other(&'static [u8]),
+}
+
+impl DebugData {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(typ: u32, data: &'static [u8]) -> Result<Self> {
+		if data.is_empty() {
+			return Ok(Self::Other(data));
+		}
+		Ok(match typ {
+			IMAGE_DEBUG_TYPE_CODEVIEW => Self::CodeView(CodeView::parse(data)?),
+			IMAGE_DEBUG_TYPE_POGO => Self::Pogo(data),
+			IMAGE_DEBUG_TYPE_REPRO => {
+				let len = read_u32(data, 0).ok_or(Error::DebugTable)?;
+				let hash = data.get(4..).and_then(|hash| hash.get(..len as usize));
+				Self::Repro(hash.ok_or(Error::DebugTable)?)
+			}
+			IMAGE_DEBUG_TYPE_VC_FEATURE => Self::VcFeature(data),
+			IMAGE_DEBUG_TYPE_EX_DLLCHARACTERISTICS => {
+				Self::ExDllCharacteristics(read_u32(data, 0).ok_or(Error::DebugTable)?)
+			}
+			_ => Self::Other(data),
+		})
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DebugEntry {
+	pub descriptor: &'static ImageDebugDirectory,
+	pub data: DebugData,
+}
+
+impl DebugTable {
+	/// Entries of an image at `image_base` with their data, found through `AddressOfRawData` for
+	/// a mapped image and `PointerToRawData` for file contents. A malformed record yields an
+	/// error without ending the iteration.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn iter_typed(
+		&self,
+		image_base: *const u8,
+		layout: Layout,
+	) -> impl Iterator<Item = Result<DebugEntry>> {
+		unsafe { self.iter_typed_in(image_base, ParseOptions::new().layout(layout)) }
+	}
+
+	/// [`DebugTable::iter_typed`] in the layout `headers` were parsed with, the data of every
+	/// record is checked against their region.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn iter_typed_with<Nt: NtHeaders>(
+		&self,
+		headers: &PeHeaders<Nt>,
+		image_base: *const u8,
+	) -> impl Iterator<Item = Result<DebugEntry>> {
+		unsafe { self.iter_typed_in(image_base, headers.options) }
+	}
+
+	unsafe fn iter_typed_in(
+		&self,
+		image_base: *const u8,
+		options: ParseOptions,
+	) -> impl Iterator<Item = Result<DebugEntry>> {
+		self.debug_descriptors.iter().map(move |descriptor| {
+			let offset = match options.layout {
+				Layout::Mapped => descriptor.address_of_raw_data.get(LittleEndian),
+				Layout::File => descriptor.pointer_to_raw_data.get(LittleEndian),
+			};
+			let data = match offset {
+				0 => &[][..],
+				_ => {
+					let ptr = rva_ptr(image_base, offset as _)?;
+					let size = descriptor.size_of_data.get(LittleEndian);
+					unsafe { check_range(&options, ptr, size as _)? };
+					unsafe { slice::from_raw_parts(ptr, size as _) }
+				}
+			};
+			Ok(DebugEntry {
+				descriptor,
+				data: DebugData::parse(descriptor.typ.get(LittleEndian), data)?,
+			})
+		})
+	}
+}
@@ -0,0 +1,38 @@
+use crate::{diff::export_entries, nt::NtHeaders, ExportTable, PeHeaders};
+use alloc::{format, string::String};
+use core::fmt::Write;
+
+impl ExportTable {
+	/// Module-definition file listing every export of the image at `image_base` by ordinal,
+	/// with forwarders as `name = target` and ordinal-only exports as `OrdinalN ... NONAME`.
+	/// Names and forwarders are read according to the layout of `headers`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn to_def<Nt: NtHeaders>(
+		&self,
+		headers: &PeHeaders<Nt>,
+		image_base: *const u8,
+		module_name: &str,
+	) -> String {
+		let mut def = String::new();
+		let _ = writeln!(def, "LIBRARY \"{module_name}\"");
+		def.push_str("EXPORTS\n");
+		for entry in unsafe { export_entries(self, headers, image_base) } {
+			let name = match entry.name {
+				Some(name) => String::from_utf8_lossy(name).into_owned(),
+				None => format!("Ordinal{}", entry.ordinal),
+			};
+			let _ = write!(def, "\t{name}");
+			if entry.rva.wrapping_sub(self.rva) < self.size {
+				let forwarder =
+					unsafe { headers.export_name(image_base, entry.rva) }.unwrap_or_default();
+				let _ = write!(def, " = {}", forwarder.to_string_lossy());
+			}
+			let _ = write!(def, " @{}", entry.ordinal);
+			if entry.name.is_none() {
+				def.push_str(" NONAME");
+			}
+			def.push('\n');
+		}
+		def
+	}
+}
@@ -0,0 +1,553 @@
+use crate::{
+	import::ImportName,
+	nt::NtHeaders,
+	resource::{ResourceEntryData, ResourceName},
+	rva_ptr, section, ExportTable, PeHeaders,
+};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::slice;
+use object::{
+	pe::{ImageFileHeader, ImageSectionHeader},
+	read::pe::ImageOptionalHeader,
+	LittleEndian,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExportEntry {
+	pub name: Option<&'static [u8]>,
+	/// Biased by `Base`.
+	pub ordinal: u32,
+	pub rva: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportChange {
+	Added(ExportEntry),
+	Removed(ExportEntry),
+	/// Same ordinal, different name.
+	Renamed {
+		old: ExportEntry,
+		new: ExportEntry,
+	},
+	RvaChanged {
+		old: ExportEntry,
+		new: ExportEntry,
+	},
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportDiff {
+	pub changes: Vec<ExportChange>,
+}
+
+impl ExportDiff {
+	pub fn is_empty(&self) -> bool {
+		self.changes.is_empty()
+	}
+}
+
+/// Exports in ordinal order, names are read through `headers` and left out if unreadable.
+#[cfg_attr(feature = "debug", inline(never))]
+pub(crate) unsafe fn export_entries<Nt: NtHeaders>(
+	table: &ExportTable,
+	headers: &PeHeaders<Nt>,
+	image_base: *const u8,
+) -> Vec<ExportEntry> {
+	let mut names = BTreeMap::new();
+	for (name_rva, index) in table.iter_name_index() {
+		if let Ok(name) = unsafe { headers.export_name(image_base, name_rva) } {
+			names.entry(index as usize).or_insert(name.to_bytes());
+		}
+	}
+	table
+		.address_table
+		.iter()
+		.map(|rva| rva.get(LittleEndian))
+		.enumerate()
+		// Unused ordinals in the middle of the table are zero.
+		.filter(|&(_, rva)| rva != 0)
+		.map(|(index, rva)| ExportEntry {
+			name: names.get(&index).copied(),
+			ordinal: table.index_to_ordinal(index),
+			rva,
+		})
+		.collect()
+}
+
+impl ExportTable {
+	/// Exports are matched by name, or by ordinal when exported by ordinal only.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn diff<Nt: NtHeaders>(
+		&self,
+		headers: &PeHeaders<Nt>,
+		image_base: *const u8,
+		other: &ExportTable,
+		other_headers: &PeHeaders<Nt>,
+		other_base: *const u8,
+	) -> ExportDiff {
+		let old = unsafe { export_entries(self, headers, image_base) };
+		let new = unsafe { export_entries(other, other_headers, other_base) };
+		let key = |entry: &ExportEntry| (entry.name, entry.name.is_none().then_some(entry.ordinal));
+		let old_by_key: BTreeMap<_, _> = old.iter().map(|entry| (key(entry), *entry)).collect();
+		let new_by_key: BTreeMap<_, _> = new.iter().map(|entry| (key(entry), *entry)).collect();
+
+		let mut removed: Vec<ExportEntry> = Vec::new();
+		let mut changes = Vec::new();
+		for old_entry in &old {
+			match new_by_key.get(&key(old_entry)) {
+				Some(new_entry) if new_entry.rva != old_entry.rva => {
+					changes.push(ExportChange::RvaChanged {
+						old: *old_entry,
+						new: *new_entry,
+					})
+				}
+				Some(_) => {}
+				None => removed.push(*old_entry),
+			}
+		}
+		let mut added: Vec<ExportEntry> = new
+			.iter()
+			.filter(|entry| !old_by_key.contains_key(&key(entry)))
+			.copied()
+			.collect();
+
+		for old_entry in removed {
+			let renamed = added.iter().position(|new_entry| {
+				old_entry.name.is_some()
+					&& new_entry.name.is_some()
+					&& new_entry.ordinal == old_entry.ordinal
+			});
+			match renamed {
+				Some(position) => changes.push(ExportChange::Renamed {
+					old: old_entry,
+					new: added.swap_remove(position),
+				}),
+				None => changes.push(ExportChange::Removed(old_entry)),
+			}
+		}
+		changes.extend(added.into_iter().map(ExportChange::Added));
+
+		ExportDiff { changes }
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+	pub field: &'static str,
+	pub old: u64,
+	pub new: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SectionChange {
+	Added(&'static [u8]),
+	Removed(&'static [u8]),
+	Changed {
+		name: &'static [u8],
+		fields: Vec<FieldChange>,
+		data_changed: bool,
+	},
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportChange {
+	Added {
+		dll: &'static [u8],
+		name: Option<ImportName<'static>>,
+	},
+	Removed {
+		dll: &'static [u8],
+		name: Option<ImportName<'static>>,
+	},
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ResourceKey {
+	Id(u16),
+	Name(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+	Added,
+	Removed,
+	Modified,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceChange {
+	pub ty: ResourceKey,
+	pub name: ResourceKey,
+	pub lang_id: u16,
+	pub kind: ChangeKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DebugEntry {
+	pub debug_type: u32,
+	pub time_date_stamp: u32,
+	pub data: &'static [u8],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugChange {
+	Added(DebugEntry),
+	Removed(DebugEntry),
+	Modified { old: DebugEntry, new: DebugEntry },
+}
+
+/// Differences between two mapped images, each category empty when nothing changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeDiff {
+	pub headers: Vec<FieldChange>,
+	pub sections: Vec<SectionChange>,
+	pub imports: Vec<ImportChange>,
+	pub exports: ExportDiff,
+	pub resources: Vec<ResourceChange>,
+	pub debug: Vec<DebugChange>,
+}
+
+impl PeDiff {
+	/// Tables missing from both images compare equal, a table missing from one counts as empty.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn compare<Nt: NtHeaders>(a: &PeHeaders<Nt>, b: &PeHeaders<Nt>) -> Self {
+		Self {
+			headers: header_changes(a, b),
+			sections: unsafe { section_changes(a, b) },
+			imports: unsafe { import_changes(a, b) },
+			exports: match (a.export_table(), b.export_table()) {
+				(Ok(old), Ok(new)) => unsafe { old.diff(a, a.image_base, new, b, b.image_base) },
+				(Ok(old), Err(_)) => ExportDiff {
+					changes: unsafe { export_entries(old, a, a.image_base) }
+						.into_iter()
+						.map(ExportChange::Removed)
+						.collect(),
+				},
+				(Err(_), Ok(new)) => ExportDiff {
+					changes: unsafe { export_entries(new, b, b.image_base) }
+						.into_iter()
+						.map(ExportChange::Added)
+						.collect(),
+				},
+				(Err(_), Err(_)) => ExportDiff::default(),
+			},
+			resources: unsafe { resource_changes(a, b) },
+			debug: debug_changes(a, b),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.headers.is_empty()
+			&& self.sections.is_empty()
+			&& self.imports.is_empty()
+			&& self.exports.is_empty()
+			&& self.resources.is_empty()
+			&& self.debug.is_empty()
+	}
+}
+
+type Field<T> = (&'static str, fn(&T) -> u64);
+
+fn push_change(changes: &mut Vec<FieldChange>, field: &'static str, old: u64, new: u64) {
+	if old != new {
+		changes.push(FieldChange { field, old, new });
+	}
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+fn header_changes<Nt: NtHeaders>(a: &PeHeaders<Nt>, b: &PeHeaders<Nt>) -> Vec<FieldChange> {
+	let (file_a, file_b) = (a.nt_header.file_header(), b.nt_header.file_header());
+	let (opt_a, opt_b) = (a.nt_header.optional_header(), b.nt_header.optional_header());
+	let file_fields: [Field<ImageFileHeader>; 4] = [
+		("machine", |h| h.machine.get(LittleEndian).into()),
+		("number_of_sections", |h| {
+			h.number_of_sections.get(LittleEndian).into()
+		}),
+		("time_date_stamp", |h| {
+			h.time_date_stamp.get(LittleEndian).into()
+		}),
+		("characteristics", |h| {
+			h.characteristics.get(LittleEndian).into()
+		}),
+	];
+	let optional_fields: [Field<Nt::ImageOptionalHeader>; 13] = [
+		("address_of_entry_point", |h| {
+			h.address_of_entry_point().into()
+		}),
+		("image_base", |h| h.image_base()),
+		("section_alignment", |h| h.section_alignment().into()),
+		("file_alignment", |h| h.file_alignment().into()),
+		("size_of_code", |h| h.size_of_code().into()),
+		("size_of_image", |h| h.size_of_image().into()),
+		("size_of_headers", |h| h.size_of_headers().into()),
+		("check_sum", |h| h.check_sum().into()),
+		("subsystem", |h| h.subsystem().into()),
+		("dll_characteristics", |h| h.dll_characteristics().into()),
+		("major_linker_version", |h| h.major_linker_version().into()),
+		("major_operating_system_version", |h| {
+			h.major_operating_system_version().into()
+		}),
+		("size_of_stack_reserve", |h| h.size_of_stack_reserve()),
+	];
+	let mut changes = Vec::new();
+	for (field, get) in file_fields {
+		push_change(&mut changes, field, get(file_a), get(file_b));
+	}
+	for (field, get) in optional_fields {
+		push_change(&mut changes, field, get(opt_a), get(opt_b));
+	}
+	changes
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn section_changes<Nt: NtHeaders>(
+	a: &PeHeaders<Nt>,
+	b: &PeHeaders<Nt>,
+) -> Vec<SectionChange> {
+	let find = |headers: &PeHeaders<Nt>, name: &[u8]| {
+		headers
+			.section_headers
+			.iter()
+			.find(|section| section::section_name_bytes(section) == name)
+	};
+	let mut changes = Vec::new();
+	for old in a.section_headers {
+		let name = section::section_name_bytes(old);
+		let Some(new) = find(b, name) else {
+			changes.push(SectionChange::Removed(name));
+			continue;
+		};
+		let mut fields = Vec::new();
+		let section_fields: [Field<ImageSectionHeader>; 5] = [
+			("virtual_address", |s| {
+				s.virtual_address.get(LittleEndian).into()
+			}),
+			("virtual_size", |s| s.virtual_size.get(LittleEndian).into()),
+			("size_of_raw_data", |s| {
+				s.size_of_raw_data.get(LittleEndian).into()
+			}),
+			("pointer_to_raw_data", |s| {
+				s.pointer_to_raw_data.get(LittleEndian).into()
+			}),
+			("characteristics", |s| {
+				s.characteristics.get(LittleEndian).into()
+			}),
+		];
+		for (field, get) in section_fields {
+			push_change(&mut fields, field, get(old), get(new));
+		}
+		let data_changed = match unsafe {
+			(
+				a.section_data(a.image_base, old),
+				b.section_data(b.image_base, new),
+			)
+		} {
+			(Ok(old_data), Ok(new_data)) => old_data != new_data,
+			_ => true,
+		};
+		if !fields.is_empty() || data_changed {
+			changes.push(SectionChange::Changed {
+				name,
+				fields,
+				data_changed,
+			});
+		}
+	}
+	for new in b.section_headers {
+		let name = section::section_name_bytes(new);
+		if find(a, name).is_none() {
+			changes.push(SectionChange::Added(name));
+		}
+	}
+	changes
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn imports<Nt: NtHeaders>(
+	headers: &PeHeaders<Nt>,
+) -> Vec<(&'static [u8], Option<ImportName<'static>>)> {
+	let Ok(import_table) = headers.import_table() else {
+		return Vec::new();
+	};
+	let image_base = headers.image_base.cast_mut();
+	let mut imports = Vec::new();
+	for descriptor in import_table.import_descriptors {
+		let Ok(dll) = (unsafe { import_table.dll_name_with(descriptor, headers, image_base) })
+		else {
+			continue;
+		};
+		let Ok(thunks) = (unsafe { headers.import_thunks(descriptor, image_base) }) else {
+			continue;
+		};
+		for thunk in thunks.map_while(Result::ok) {
+			imports.push((dll.to_bytes(), thunk.name));
+		}
+	}
+	imports
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn import_changes<Nt: NtHeaders>(a: &PeHeaders<Nt>, b: &PeHeaders<Nt>) -> Vec<ImportChange> {
+	let (old, new) = unsafe { (imports(a), imports(b)) };
+	let removed = old
+		.iter()
+		.filter(|import| !new.contains(import))
+		.map(|&(dll, name)| ImportChange::Removed { dll, name });
+	let added = new
+		.iter()
+		.filter(|import| !old.contains(import))
+		.map(|&(dll, name)| ImportChange::Added { dll, name });
+	removed.chain(added).collect()
+}
+
+fn resource_key(name: ResourceName) -> ResourceKey {
+	match name {
+		ResourceName::Id(id) => ResourceKey::Id(id),
+		name => ResourceKey::Name(name.to_string_lossy()),
+	}
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn resources<Nt: NtHeaders>(
+	headers: &PeHeaders<Nt>,
+) -> BTreeMap<(ResourceKey, ResourceKey, u16), &'static [u8]> {
+	let mut resources = BTreeMap::new();
+	let Ok(resource_table) = headers.resource_table() else {
+		return resources;
+	};
+	let Ok(root) = (unsafe { resource_table.root() }) else {
+		return resources;
+	};
+	for ty_entry in root.entries {
+		let Ok(ResourceEntryData::Directory(ty_dir)) = (unsafe { root.entry_data(ty_entry) })
+		else {
+			continue;
+		};
+		let Ok(ty) = (unsafe { root.entry_name(ty_entry) }) else {
+			continue;
+		};
+		let ty = resource_key(ty);
+		for name_entry in ty_dir.entries {
+			let Ok(ResourceEntryData::Directory(name_dir)) =
+				(unsafe { ty_dir.entry_data(name_entry) })
+			else {
+				continue;
+			};
+			let Ok(name) = (unsafe { ty_dir.entry_name(name_entry) }) else {
+				continue;
+			};
+			let name = resource_key(name);
+			for lang_entry in name_dir.entries {
+				let Ok(ResourceEntryData::Data(data)) =
+					(unsafe { name_dir.entry_data(lang_entry) })
+				else {
+					continue;
+				};
+				let lang_id = lang_entry.name_or_id.get(LittleEndian) as u16;
+				let bytes = headers.resource_data(data).unwrap_or(&[]);
+				resources.insert((ty.clone(), name.clone(), lang_id), bytes);
+			}
+		}
+	}
+	resources
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn resource_changes<Nt: NtHeaders>(
+	a: &PeHeaders<Nt>,
+	b: &PeHeaders<Nt>,
+) -> Vec<ResourceChange> {
+	let (old, new) = unsafe { (resources(a), resources(b)) };
+	let mut changes: Vec<ResourceChange> = old
+		.iter()
+		.filter_map(|(key, old_data)| {
+			let kind = match new.get(key) {
+				None => ChangeKind::Removed,
+				Some(new_data) if new_data != old_data => ChangeKind::Modified,
+				Some(_) => return None,
+			};
+			Some((key, kind))
+		})
+		.chain(
+			new.keys()
+				.filter(|key| !old.contains_key(*key))
+				.map(|key| (key, ChangeKind::Added)),
+		)
+		.map(|((ty, name, lang_id), kind)| ResourceChange {
+			ty: ty.clone(),
+			name: name.clone(),
+			lang_id: *lang_id,
+			kind,
+		})
+		.collect();
+	changes.sort_by(|x, y| (&x.ty, &x.name, x.lang_id).cmp(&(&y.ty, &y.name, y.lang_id)));
+	changes
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+fn debug_entries<Nt: NtHeaders>(headers: &PeHeaders<Nt>) -> Vec<DebugEntry> {
+	let Ok(debug_table) = headers.debug_table() else {
+		return Vec::new();
+	};
+	debug_table
+		.debug_descriptors
+		.iter()
+		.map(|descriptor| {
+			let data = rva_ptr(
+				headers.image_base,
+				descriptor.address_of_raw_data.get(LittleEndian) as _,
+			)
+			.ok()
+			.filter(|_| descriptor.address_of_raw_data.get(LittleEndian) != 0)
+			.map_or(&[][..], |ptr| unsafe {
+				slice::from_raw_parts(ptr, descriptor.size_of_data.get(LittleEndian) as _)
+			});
+			DebugEntry {
+				debug_type: descriptor.typ.get(LittleEndian),
+				time_date_stamp: descriptor.time_date_stamp.get(LittleEndian),
+				data,
+			}
+		})
+		.collect()
+}
+
+/// Entries keyed by type and the number of entries of that type before them.
+fn keyed_debug_entries(entries: &[DebugEntry]) -> Vec<((u32, usize), DebugEntry)> {
+	let mut counts = BTreeMap::new();
+	entries
+		.iter()
+		.map(|entry| {
+			let count = counts.entry(entry.debug_type).or_insert(0);
+			let key = (entry.debug_type, *count);
+			*count += 1;
+			(key, *entry)
+		})
+		.collect()
+}
+
+/// Entries are matched by type and their position among the entries of that type, so the n-th
+/// entry of a type is compared with the n-th one of the other image.
+#[cfg_attr(feature = "debug", inline(never))]
+fn debug_changes<Nt: NtHeaders>(a: &PeHeaders<Nt>, b: &PeHeaders<Nt>) -> Vec<DebugChange> {
+	let old = keyed_debug_entries(&debug_entries(a));
+	let new = keyed_debug_entries(&debug_entries(b));
+	let old_by_key: BTreeMap<_, _> = old.iter().copied().collect();
+	let new_by_key: BTreeMap<_, _> = new.iter().copied().collect();
+	let mut changes = Vec::new();
+	for (key, old_entry) in &old {
+		match new_by_key.get(key) {
+			None => changes.push(DebugChange::Removed(*old_entry)),
+			Some(new_entry) if new_entry != old_entry => changes.push(DebugChange::Modified {
+				old: *old_entry,
+				new: *new_entry,
+			}),
+			Some(_) => {}
+		}
+	}
+	for (key, new_entry) in &new {
+		if !old_by_key.contains_key(key) {
+			changes.push(DebugChange::Added(*new_entry));
+		}
+	}
+	changes
+}
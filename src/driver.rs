@@ -0,0 +1,79 @@
+use crate::{nt::NtHeaders, section, PeHeaders};
+use core::ffi::CStr;
+use object::{
+	pe::{ImageSectionHeader, IMAGE_DLLCHARACTERISTICS_WDM_DRIVER, IMAGE_SUBSYSTEM_NATIVE},
+	read::pe::ImageOptionalHeader,
+};
+
+/// Modules only kernel-mode images import from.
+pub const KERNEL_MODULES: &[&str] = &[
+	"ntoskrnl.exe",
+	"ntkrnlpa.exe",
+	"ntkrnlmp.exe",
+	"ntkrpamp.exe",
+	"hal.dll",
+	"fltmgr.sys",
+	"ndis.sys",
+	"wdfldr.sys",
+	"cng.sys",
+	"ksecdd.sys",
+	"clfs.sys",
+	"tm.sys",
+];
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Native subsystem, shared by drivers and native user-mode programs such as `smss.exe`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn is_native(&self) -> bool {
+		self.nt_header.optional_header().subsystem() == IMAGE_SUBSYSTEM_NATIVE
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn is_wdm_driver(&self) -> bool {
+		self.nt_header.optional_header().dll_characteristics() & IMAGE_DLLCHARACTERISTICS_WDM_DRIVER
+			!= 0
+	}
+
+	/// Names of the imported modules from [`KERNEL_MODULES`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn kernel_imports(
+		&self,
+		image_base: *const u8,
+	) -> impl Iterator<Item = &'static CStr> + '_ {
+		let import_table = unsafe { self.import_table_mem(image_base) }.ok();
+		import_table.into_iter().flat_map(move |import_table| {
+			let descriptors = import_table.import_descriptors;
+			descriptors.iter().filter_map(move |descriptor| {
+				let name =
+					unsafe { import_table.dll_name_with(descriptor, self, image_base) }.ok()?;
+				KERNEL_MODULES
+					.iter()
+					.any(|module| name.to_bytes().eq_ignore_ascii_case(module.as_bytes()))
+					.then_some(name)
+			})
+		})
+	}
+
+	/// A native image importing from the kernel, as opposed to a native user-mode program.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn is_driver(&self, image_base: *const u8) -> bool {
+		self.is_native() && unsafe { self.kernel_imports(image_base) }.next().is_some()
+	}
+
+	/// The `INIT` section holding `DriverEntry` and other code freed after initialization.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn init_section(&self) -> Option<&'static ImageSectionHeader> {
+		self.section_headers
+			.iter()
+			.find(|section| section::section_name_bytes(section) == b"INIT")
+	}
+
+	/// Sections that are safe to read in an image taken from memory, see
+	/// [`section::section_is_discardable`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn resident_sections(&self) -> impl Iterator<Item = &'static ImageSectionHeader> {
+		self.section_headers
+			.iter()
+			.filter(|section| !section::section_is_discardable(section))
+	}
+}
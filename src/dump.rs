@@ -0,0 +1,272 @@
+use crate::{diff::export_entries, import::ImportName, nt::NtHeaders, section, PeHeaders};
+use alloc::{
+	string::{String, ToString},
+	vec,
+	vec::Vec,
+};
+use core::fmt::Write;
+use object::LittleEndian;
+#[cfg(feature = "std")]
+use std::io::IsTerminal;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DumpFormat {
+	/// `kind key=value ...` per line.
+	Plain,
+	/// [`DumpFormat::Plain`] with ANSI colors for terminals.
+	Color,
+	/// One JSON object per line with the kind under `"kind"`, numbers as JSON numbers.
+	JsonLines,
+}
+
+impl DumpFormat {
+	/// [`DumpFormat::Color`] when stdout is a terminal and `NO_COLOR` is unset.
+	#[cfg(feature = "std")]
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn detect() -> Self {
+		if std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+			Self::Color
+		} else {
+			Self::Plain
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+	Bool(bool),
+	Int(u64),
+	/// Printed in hex by the text formats, an integer in JSON.
+	Hex(u64),
+	Str(String),
+}
+
+/// A line of dump output. Kinds and their fields:
+///
+/// - `header`: `machine`, `is_64`, `time_date_stamp`, `entry_point`, `image_base`,
+///   `size_of_image`, `subsystem`, `dll_characteristics`, `check_sum`
+/// - `section`: `index`, `name`, `virtual_address`, `virtual_size`, `raw_offset`, `raw_size`,
+///   `characteristics`, `flags` as [`section::SectionFlags`] names
+/// - `import`: `dll`, then `name` and `hint` or `ordinal`
+/// - `export`: `ordinal`, `name` if named, `rva`, `forwarder` for forwarders
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+	pub kind: &'static str,
+	pub fields: Vec<(&'static str, Value)>,
+}
+
+const BOLD: &str = "\x1b[1m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+fn write_json_string(out: &mut String, value: &str) {
+	out.push('"');
+	for c in value.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 => {
+				let _ = write!(out, "\\u{:04x}", c as u32);
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+impl Record {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn write(&self, format: DumpFormat, out: &mut String) {
+		if format == DumpFormat::JsonLines {
+			out.push_str("{\"kind\":");
+			write_json_string(out, self.kind);
+			for (key, value) in &self.fields {
+				let _ = write!(out, ",\"{key}\":");
+				match value {
+					Value::Bool(value) => {
+						let _ = write!(out, "{value}");
+					}
+					Value::Int(value) | Value::Hex(value) => {
+						let _ = write!(out, "{value}");
+					}
+					Value::Str(value) => write_json_string(out, value),
+				}
+			}
+			out.push_str("}\n");
+			return;
+		}
+
+		let color = format == DumpFormat::Color;
+		if color {
+			let _ = write!(out, "{BOLD}{}{RESET}", self.kind);
+		} else {
+			out.push_str(self.kind);
+		}
+		for (key, value) in &self.fields {
+			if color {
+				let _ = write!(out, " {CYAN}{key}{RESET}=");
+			} else {
+				let _ = write!(out, " {key}=");
+			}
+			match value {
+				Value::Bool(value) => {
+					let _ = write!(out, "{value}");
+				}
+				Value::Int(value) => {
+					let _ = write!(out, "{value}");
+				}
+				Value::Hex(value) => {
+					let _ = write!(out, "{value:#x}");
+				}
+				// Quoted only when needed to keep the line splittable on spaces.
+				Value::Str(value)
+					if value.is_empty()
+						|| value.chars().any(|c| {
+							c.is_whitespace() || c.is_control() || c == '"' || c == '='
+						}) =>
+				{
+					write_json_string(out, value)
+				}
+				Value::Str(value) => out.push_str(value),
+			}
+		}
+		out.push('\n');
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Header, section, import and export records of the image at `image_base`. Tables
+	/// that fail to parse are left out.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn dump_records(&self, image_base: *const u8) -> Vec<Record> {
+		let info = self.info();
+		let mut records = vec![Record {
+			kind: "header",
+			fields: vec![
+				("machine", Value::Hex(info.machine as u64)),
+				("is_64", Value::Bool(info.is_64)),
+				("time_date_stamp", Value::Int(info.time_date_stamp as u64)),
+				(
+					"entry_point",
+					Value::Hex(info.address_of_entry_point as u64),
+				),
+				("image_base", Value::Hex(info.image_base)),
+				("size_of_image", Value::Hex(info.size_of_image as u64)),
+				("subsystem", Value::Int(info.subsystem as u64)),
+				(
+					"dll_characteristics",
+					Value::Hex(info.dll_characteristics as u64),
+				),
+				("check_sum", Value::Hex(info.check_sum as u64)),
+			],
+		}];
+
+		for (index, section) in self.section_headers.iter().enumerate() {
+			let name = section::section_name_bytes(section);
+			records.push(Record {
+				kind: "section",
+				fields: vec![
+					("index", Value::Int(index as u64)),
+					(
+						"name",
+						Value::Str(String::from_utf8_lossy(name).into_owned()),
+					),
+					(
+						"virtual_address",
+						Value::Hex(section.virtual_address.get(LittleEndian) as u64),
+					),
+					(
+						"virtual_size",
+						Value::Hex(section::section_virtual_size(section) as u64),
+					),
+					(
+						"raw_offset",
+						Value::Hex(section.pointer_to_raw_data.get(LittleEndian) as u64),
+					),
+					(
+						"raw_size",
+						Value::Hex(section.size_of_raw_data.get(LittleEndian) as u64),
+					),
+					(
+						"characteristics",
+						Value::Hex(section.characteristics.get(LittleEndian) as u64),
+					),
+					(
+						"flags",
+						Value::Str(section::SectionFlags::of(section).to_string()),
+					),
+				],
+			});
+		}
+
+		if let Ok(import_table) = unsafe { self.import_table_mem(image_base) } {
+			for descriptor in import_table.import_descriptors {
+				let Ok(dll) = (unsafe { import_table.dll_name_with(descriptor, self, image_base) })
+				else {
+					continue;
+				};
+				let dll = dll.to_string_lossy().into_owned();
+				let Ok(thunks) = (unsafe { self.import_thunks(descriptor, image_base.cast_mut()) })
+				else {
+					continue;
+				};
+				for thunk in thunks.map_while(Result::ok) {
+					let mut fields = vec![("dll", Value::Str(dll.clone()))];
+					match thunk.name {
+						Some(ImportName::Name { hint, name }) => {
+							fields.push(("name", Value::Str(name.to_string_lossy().into_owned())));
+							fields.push(("hint", Value::Int(hint as u64)));
+						}
+						Some(ImportName::Ordinal(ordinal)) => {
+							fields.push(("ordinal", Value::Int(ordinal as u64)))
+						}
+						None => {}
+					}
+					records.push(Record {
+						kind: "import",
+						fields,
+					});
+				}
+			}
+		}
+
+		if let Ok(export_table) = unsafe { self.export_table_mem(image_base) } {
+			for entry in unsafe { export_entries(&export_table, self, image_base) } {
+				let mut fields = vec![("ordinal", Value::Int(entry.ordinal as u64))];
+				if let Some(name) = entry.name {
+					fields.push((
+						"name",
+						Value::Str(String::from_utf8_lossy(name).into_owned()),
+					));
+				}
+				fields.push(("rva", Value::Hex(entry.rva as u64)));
+				if entry.rva.wrapping_sub(export_table.rva) < export_table.size {
+					let forwarder =
+						unsafe { self.export_name(image_base, entry.rva) }.unwrap_or_default();
+					fields.push((
+						"forwarder",
+						Value::Str(forwarder.to_string_lossy().into_owned()),
+					));
+				}
+				records.push(Record {
+					kind: "export",
+					fields,
+				});
+			}
+		}
+		records
+	}
+
+	/// [`PeHeaders::dump_records`] formatted as `format`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn dump(&self, image_base: *const u8, format: DumpFormat) -> String {
+		let mut out = String::new();
+		for record in unsafe { self.dump_records(image_base) } {
+			record.write(format, &mut out);
+		}
+		out
+	}
+}
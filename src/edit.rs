@@ -0,0 +1,67 @@
+use crate::{
+	error::{Error, Result},
+	loader::IMAGE_BASE_ALIGNMENT,
+	nt::NtHeaders,
+	offsets::HeaderField,
+	PeHeaders,
+};
+use object::{pe::IMAGE_DIRECTORY_ENTRY_BASERELOC, read::pe::ImageOptionalHeader};
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Writes `AddressOfEntryPoint` of the image at `image_base`, in either layout, returning the
+	/// previous value. The headers have to be writable, see `patch::patch_entry_point` for a
+	/// loaded module.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn set_entry_point(&self, image_base: *mut u8, rva: u32) -> Result<u32> {
+		let optional_header = self.nt_header.optional_header();
+		if rva >= optional_header.size_of_image() {
+			return Err(Error::RvaOutOfBounds {
+				rva,
+				size_of_image: optional_header.size_of_image(),
+			});
+		}
+		let old = optional_header.address_of_entry_point();
+		let span = self
+			.field_span(HeaderField::AddressOfEntryPoint)
+			.ok_or(Error::PeHeaders)?;
+		let field = image_base.wrapping_add(span.offset).cast::<u32>();
+		unsafe { field.write_unaligned(rva.to_le()) };
+		Ok(old)
+	}
+
+	/// Writes `ImageBase`, returning the previous value. With `relocate`, the relocations are
+	/// applied for the difference first, see [`PeHeaders::apply_relocations`], so that the image
+	/// is ready to run at `new_base` without being relocated by the loader. Relocating an image
+	/// without a relocation directory fails with [`Error::RelocationTable`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn set_image_base(
+		&self,
+		image_base: *mut u8,
+		new_base: u64,
+		relocate: bool,
+	) -> Result<u64> {
+		if !new_base.is_multiple_of(IMAGE_BASE_ALIGNMENT)
+			|| (!self.nt_header.is_type_64() && new_base > u32::MAX as u64)
+		{
+			return Err(Error::PeHeaders);
+		}
+		let old = self.nt_header.optional_header().image_base();
+		let span = self
+			.field_span(HeaderField::ImageBase)
+			.ok_or(Error::PeHeaders)?;
+		if relocate && new_base != old {
+			// Without relocations the image would still hold addresses for the old base.
+			if self
+				.data_directory(IMAGE_DIRECTORY_ENTRY_BASERELOC)
+				.is_none()
+			{
+				return Err(Error::RelocationTable);
+			}
+			unsafe { self.apply_relocations(image_base, new_base.wrapping_sub(old))? };
+		}
+		let field = image_base.wrapping_add(span.offset);
+		let bytes = new_base.to_le_bytes();
+		unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), field, span.len) };
+		Ok(old)
+	}
+}
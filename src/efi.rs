@@ -0,0 +1,64 @@
+use crate::{error::Result, nt::NtHeaders, PeHeaders};
+use object::{
+	pe::{
+		IMAGE_SUBSYSTEM_EFI_APPLICATION, IMAGE_SUBSYSTEM_EFI_BOOT_SERVICE_DRIVER,
+		IMAGE_SUBSYSTEM_EFI_ROM, IMAGE_SUBSYSTEM_EFI_RUNTIME_DRIVER,
+	},
+	read::pe::ImageOptionalHeader,
+	LittleEndian,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EfiSubsystem {
+	Application,
+	BootServiceDriver,
+	RuntimeDriver,
+	Rom,
+}
+
+impl EfiSubsystem {
+	pub fn from_subsystem(subsystem: u16) -> Option<Self> {
+		match subsystem {
+			IMAGE_SUBSYSTEM_EFI_APPLICATION => Some(Self::Application),
+			IMAGE_SUBSYSTEM_EFI_BOOT_SERVICE_DRIVER => Some(Self::BootServiceDriver),
+			IMAGE_SUBSYSTEM_EFI_RUNTIME_DRIVER => Some(Self::RuntimeDriver),
+			IMAGE_SUBSYSTEM_EFI_ROM => Some(Self::Rom),
+			_ => None,
+		}
+	}
+}
+
+// EFI images usually have no imports, exports or TLS at all; the table loaders report their
+// absence as errors rather than reading the headers as a table.
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn efi_subsystem(&self) -> Option<EfiSubsystem> {
+		EfiSubsystem::from_subsystem(self.nt_header.optional_header().subsystem())
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn is_efi(&self) -> bool {
+		self.efi_subsystem().is_some()
+	}
+
+	/// Firmware toolchains often use one alignment for both the file and the sections, so the file
+	/// parses the same in either [`crate::Layout`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn is_flat(&self) -> bool {
+		let optional_header = self.nt_header.optional_header();
+		optional_header.section_alignment() == optional_header.file_alignment()
+			&& self.section_headers.iter().all(|section| {
+				section.pointer_to_raw_data.get(LittleEndian)
+					== section.virtual_address.get(LittleEndian)
+			})
+	}
+
+	/// `None` when `AddressOfEntryPoint` is zero, which only resource-only images use.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn entry_point(&self, image_base: *const u8) -> Result<Option<*const u8>> {
+		match self.nt_header.optional_header().address_of_entry_point() {
+			0 => Ok(None),
+			rva => self.rva_to_ptr(image_base, rva).map(Some),
+		}
+	}
+}
@@ -1,9 +1,36 @@
 use thiserror::Error;
 
-pub type Result<T> = core::result::Result<T, Error>;
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+/// An [`Error`] with the operation that failed, attached by [`ResultExt::context`]. The error
+/// is the source rather than part of the message, so reporters print `import table: RVA overflow`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Error)]
+#[error("{context}")]
+pub struct Context {
+	pub context: &'static str,
+	#[source]
+	pub error: Error,
+}
+
+/// Drops the context, so `?` keeps working in functions returning a plain [`Error`].
+impl From<Context> for Error {
+	fn from(context: Context) -> Self {
+		context.error
+	}
+}
+
+pub trait ResultExt<T> {
+	fn context(self, context: &'static str) -> Result<T, Context>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+	fn context(self, context: &'static str) -> Result<T, Context> {
+		self.map_err(|error| Context { context, error })
+	}
+}
 
 #[repr(u16)]
-#[derive(Debug, Error)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Error)]
 pub enum Error {
 	#[error("PE headers")]
 	PeHeaders,
@@ -15,4 +42,111 @@ pub enum Error {
 	DebugTable,
 	#[error("TLS table")]
 	TlsTable,
+	#[error("Resource table")]
+	ResourceTable,
+	#[error("Delay import table")]
+	DelayImportTable,
+	#[error("Import resolution")]
+	ImportResolution,
+	#[error("CLR header")]
+	ClrHeader,
+	#[error("CLR metadata")]
+	ClrMetadata,
+	#[error("RVA overflow")]
+	RvaOverflow,
+	#[error("RVA {rva:#x} outside sections")]
+	RvaOutsideSections { rva: u32 },
+	/// Memory that is not committed and readable, or could not be reprotected.
+	#[error("Invalid memory: {len:#x} bytes at {address:#x}")]
+	InvalidMemory { address: usize, len: usize },
+	#[error("Section name")]
+	SectionName,
+	#[error("UTF-16 string")]
+	Utf16,
+	#[error("Module not found")]
+	ModuleNotFound,
+	#[error("Remote process")]
+	RemoteProcess,
+	#[error("Minidump")]
+	Minidump,
+	#[error("TE header")]
+	TeHeader,
+	#[error("CHPE metadata")]
+	ChpeMetadata,
+	#[error("Single-file bundle")]
+	Bundle,
+	#[error("I/O")]
+	Io,
+	#[error("No space for a section header")]
+	NoHeaderSpace,
+	#[error("Relocation table")]
+	RelocationTable,
+	#[error("Authenticode signature")]
+	Authenticode,
+	#[error("COFF object")]
+	Coff,
+	#[error("Archive")]
+	Archive,
+	#[error("Import object")]
+	ImportObject,
+	#[error("Limit of {limit} exceeded")]
+	LimitExceeded { limit: usize },
+	/// The file header's `Machine` (`found`) does not use the optional header layout being
+	/// parsed, `expected` being `IMAGE_NT_OPTIONAL_HDR32_MAGIC` or `IMAGE_NT_OPTIONAL_HDR64_MAGIC`.
+	#[error("Architecture mismatch: machine {found:#06x} parsed as optional header {expected:#x}")]
+	ArchMismatch { expected: u16, found: u16 },
+	/// An RVA of a mapped image past `SizeOfImage`.
+	#[error("RVA {rva:#x} out of bounds (image size {size_of_image:#x})")]
+	RvaOutOfBounds { rva: u32, size_of_image: u32 },
+	/// A range outside [`crate::ParseOptions::region`], `offset` being relative to its start.
+	#[error("{len:#x} bytes at offset {offset:#x} out of bounds (region size {size:#x})")]
+	OutOfRegion {
+		offset: usize,
+		len: usize,
+		size: usize,
+	},
+}
+
+impl Error {
+	/// The discriminant, stable across releases since variants are only appended.
+	pub fn code(&self) -> u16 {
+		// SAFETY: `repr(u16)` puts the discriminant first, also for variants with fields.
+		unsafe { *(self as *const Self).cast::<u16>() }
+	}
+
+	/// The closest [`std::io::ErrorKind`], `InvalidData` for malformed images.
+	#[cfg(feature = "std")]
+	pub fn io_kind(&self) -> std::io::ErrorKind {
+		match self {
+			Error::Io => std::io::ErrorKind::Other,
+			Error::ModuleNotFound => std::io::ErrorKind::NotFound,
+			_ => std::io::ErrorKind::InvalidData,
+		}
+	}
+}
+
+/// [`Error`] of APIs that read files, keeping the [`std::io::Error`] that stopped them.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum FileError {
+	#[error("I/O")]
+	Io(#[from] std::io::Error),
+	#[error(transparent)]
+	Parse(#[from] Error),
+	#[error(transparent)]
+	Context(#[from] Context),
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+	fn from(error: Error) -> Self {
+		std::io::Error::new(error.io_kind(), error)
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<Context> for std::io::Error {
+	fn from(context: Context) -> Self {
+		std::io::Error::new(context.error.io_kind(), context)
+	}
 }
@@ -0,0 +1,40 @@
+use crate::ExportTable;
+use core::ffi::CStr;
+use std::collections::HashMap;
+
+/// Name to RVA map built once from an [`ExportTable`] for repeated lookups.
+pub struct ExportIndex {
+	names: HashMap<&'static [u8], u32>,
+}
+
+impl ExportIndex {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn build(export_table: &ExportTable, image_base: *const u8) -> Self {
+		let names = export_table
+			.iter_name_index()
+			.filter_map(|(name_rva, index)| {
+				let name = unsafe { CStr::from_ptr(image_base.wrapping_add(name_rva as _).cast()) };
+				let rva = export_table.rva_by_index(index as _)?;
+				Some((name.to_bytes(), rva))
+			})
+			.collect();
+
+		Self { names }
+	}
+
+	pub fn rva(&self, name: &[u8]) -> Option<u32> {
+		self.names.get(name).copied()
+	}
+
+	pub fn address(&self, image_base: *const u8, name: &[u8]) -> Option<*const u8> {
+		self.rva(name).map(|rva| image_base.wrapping_add(rva as _))
+	}
+
+	pub fn len(&self) -> usize {
+		self.names.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.names.is_empty()
+	}
+}
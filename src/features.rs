@@ -0,0 +1,232 @@
+use crate::{nt::NtHeaders, section, PeHeaders};
+use object::{
+	pe::{
+		IMAGE_DIRECTORY_ENTRY_EXPORT, IMAGE_DIRECTORY_ENTRY_IMPORT,
+		IMAGE_NUMBEROF_DIRECTORY_ENTRIES,
+	},
+	read::pe::ImageOptionalHeader,
+	LittleEndian,
+};
+
+/// Shannon entropy in bits per byte, `0.0..=8.0`.
+pub fn entropy(data: &[u8]) -> f64 {
+	if data.is_empty() {
+		return 0.0;
+	}
+	let mut counts = [0u32; 256];
+	for &b in data {
+		counts[b as usize] += 1;
+	}
+	let len = data.len() as f64;
+	counts
+		.iter()
+		.filter(|&&count| count != 0)
+		.map(|&count| {
+			let p = count as f64 / len;
+			-p * p.log2()
+		})
+		.sum()
+}
+
+pub const FEATURE_COUNT: usize = 38;
+
+/// Names of the values returned by [`Features::to_vector`], in order.
+///
+/// New features are only ever appended so that trained models keep working.
+pub const FEATURE_NAMES: [&str; FEATURE_COUNT] = [
+	"machine",
+	"characteristics",
+	"dll_characteristics",
+	"subsystem",
+	"timestamp",
+	"size_of_image",
+	"size_of_headers",
+	"checksum_zero",
+	"number_of_sections",
+	"entry_point_in_executable_section",
+	"entry_point_in_last_section",
+	"entry_point_outside_sections",
+	"executable_sections",
+	"writable_sections",
+	"rwx_sections",
+	"empty_raw_sections",
+	"section_entropy_min",
+	"section_entropy_mean",
+	"section_entropy_max",
+	"import_dlls",
+	"import_functions",
+	"export_functions",
+	"export_names",
+	"dir_export",
+	"dir_import",
+	"dir_resource",
+	"dir_exception",
+	"dir_security",
+	"dir_basereloc",
+	"dir_debug",
+	"dir_architecture",
+	"dir_globalptr",
+	"dir_tls",
+	"dir_load_config",
+	"dir_bound_import",
+	"dir_com_descriptor",
+	"dir_iat",
+	"dir_delay_import",
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Features {
+	pub machine: u16,
+	pub characteristics: u16,
+	pub dll_characteristics: u16,
+	pub subsystem: u16,
+	pub timestamp: u32,
+	pub size_of_image: u32,
+	pub size_of_headers: u32,
+	pub checksum_zero: bool,
+	pub number_of_sections: u16,
+	pub entry_point_in_executable_section: bool,
+	pub entry_point_in_last_section: bool,
+	pub entry_point_outside_sections: bool,
+	pub executable_sections: u32,
+	pub writable_sections: u32,
+	pub rwx_sections: u32,
+	pub empty_raw_sections: u32,
+	pub section_entropy_min: f64,
+	pub section_entropy_mean: f64,
+	pub section_entropy_max: f64,
+	pub import_dlls: u32,
+	pub import_functions: u32,
+	pub export_functions: u32,
+	pub export_names: u32,
+	pub directories: [bool; IMAGE_NUMBEROF_DIRECTORY_ENTRIES],
+}
+
+impl Features {
+	pub fn to_vector(&self) -> [f64; FEATURE_COUNT] {
+		let flag = |b: bool| if b { 1.0 } else { 0.0 };
+		let d = |index: usize| flag(self.directories[index]);
+		[
+			self.machine as f64,
+			self.characteristics as f64,
+			self.dll_characteristics as f64,
+			self.subsystem as f64,
+			self.timestamp as f64,
+			self.size_of_image as f64,
+			self.size_of_headers as f64,
+			flag(self.checksum_zero),
+			self.number_of_sections as f64,
+			flag(self.entry_point_in_executable_section),
+			flag(self.entry_point_in_last_section),
+			flag(self.entry_point_outside_sections),
+			self.executable_sections as f64,
+			self.writable_sections as f64,
+			self.rwx_sections as f64,
+			self.empty_raw_sections as f64,
+			self.section_entropy_min,
+			self.section_entropy_mean,
+			self.section_entropy_max,
+			self.import_dlls as f64,
+			self.import_functions as f64,
+			self.export_functions as f64,
+			self.export_names as f64,
+			d(0),
+			d(1),
+			d(2),
+			d(3),
+			d(4),
+			d(5),
+			d(6),
+			d(7),
+			d(8),
+			d(9),
+			d(10),
+			d(11),
+			d(14),
+			d(12),
+			d(13),
+		]
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn features(&self, image_base: *const u8) -> Features {
+		let file_header = self.nt_header.file_header();
+		let optional_header = self.nt_header.optional_header();
+		let mut features = Features {
+			machine: file_header.machine.get(LittleEndian),
+			characteristics: file_header.characteristics.get(LittleEndian),
+			dll_characteristics: optional_header.dll_characteristics(),
+			subsystem: optional_header.subsystem(),
+			timestamp: file_header.time_date_stamp.get(LittleEndian),
+			size_of_image: optional_header.size_of_image(),
+			size_of_headers: optional_header.size_of_headers(),
+			checksum_zero: optional_header.check_sum() == 0,
+			number_of_sections: self.section_headers.len() as _,
+			..Default::default()
+		};
+
+		let entry_point = optional_header.address_of_entry_point();
+		match self.section_for_rva(entry_point) {
+			Some(entry_section) => {
+				features.entry_point_in_executable_section =
+					section::section_is_executable(entry_section);
+				features.entry_point_in_last_section = self
+					.section_headers
+					.last()
+					.is_some_and(|last| core::ptr::eq(last, entry_section));
+			}
+			None => features.entry_point_outside_sections = entry_point != 0,
+		}
+
+		let mut entropy_sum = 0.0;
+		features.section_entropy_min = if self.section_headers.is_empty() {
+			0.0
+		} else {
+			f64::MAX
+		};
+		for section in self.section_headers {
+			let executable = section::section_is_executable(section);
+			let writable = section::section_is_writable(section);
+			features.executable_sections += executable as u32;
+			features.writable_sections += writable as u32;
+			features.rwx_sections += (executable && writable) as u32;
+			features.empty_raw_sections += (section.size_of_raw_data.get(LittleEndian) == 0) as u32;
+
+			let data = unsafe { self.section_data(image_base, section) };
+			let section_entropy = entropy(data.unwrap_or_default());
+			entropy_sum += section_entropy;
+			features.section_entropy_min = features.section_entropy_min.min(section_entropy);
+			features.section_entropy_max = features.section_entropy_max.max(section_entropy);
+		}
+		if !self.section_headers.is_empty() {
+			features.section_entropy_mean = entropy_sum / self.section_headers.len() as f64;
+		}
+
+		for (present, index) in features.directories.iter_mut().zip(0..) {
+			*present = self.data_directory(index).is_some();
+		}
+
+		if self.data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT).is_some() {
+			if let Ok(import_table) = unsafe { self.import_table_mem(image_base) } {
+				for descriptor in import_table.import_descriptors {
+					features.import_dlls += 1;
+					let thunks = unsafe { self.import_thunks(descriptor, image_base.cast_mut()) };
+					if let Ok(thunks) = thunks {
+						features.import_functions += thunks.map_while(Result::ok).count() as u32;
+					}
+				}
+			}
+		}
+
+		if self.data_directory(IMAGE_DIRECTORY_ENTRY_EXPORT).is_some() {
+			if let Ok(export_table) = unsafe { self.export_table_mem(image_base) } {
+				features.export_functions = export_table.address_table.len() as _;
+				features.export_names = export_table.name_table.len() as _;
+			}
+		}
+
+		features
+	}
+}
@@ -0,0 +1,168 @@
+use crate::{error::Error, import::ImportName, ExportKind, ExportTable, PeHeaders};
+use core::ffi::{c_char, c_void, CStr};
+
+/// Functions declared in `include/objparse.h` return this, one of the negative codes below, or
+/// [`Error::code`] + 1 for an [`Error`] from the parser, listed in the header's `ObjparseError`.
+pub const OBJPARSE_OK: i32 = 0;
+pub const OBJPARSE_ERROR_NULL_POINTER: i32 = -1;
+pub const OBJPARSE_ERROR_NOT_FOUND: i32 = -2;
+/// The export forwards to another module, the address is that of its `module.name` string.
+pub const OBJPARSE_FORWARDED: i32 = -3;
+
+/// Called for every import, `name` is null for imports by ordinal and `ordinal` holds the hint
+/// for imports by name. Returning `false` stops the enumeration.
+pub type ObjparseImportCallback = unsafe extern "C" fn(
+	context: *mut c_void,
+	dll: *const c_char,
+	name: *const c_char,
+	ordinal: u16,
+	iat_slot: *mut c_void,
+) -> bool;
+
+/// Opaque handle to the parsed headers of an image that stays mapped while it is alive.
+pub struct ObjparseImage {
+	headers: PeHeaders,
+}
+
+fn status(error: Error) -> i32 {
+	error.code() as i32 + 1
+}
+
+/// Stores the address of the export at `rva` and returns its status, forwarders pointing at
+/// their string.
+unsafe fn export_status(
+	headers: &PeHeaders,
+	export_table: &ExportTable,
+	rva: u32,
+	address: *mut *mut c_void,
+) -> i32 {
+	// Unused slots of the address table are zero.
+	if rva == 0 {
+		return OBJPARSE_ERROR_NOT_FOUND;
+	}
+	unsafe { *address = headers.image_base.wrapping_add(rva as _).cast_mut().cast() };
+	match export_table.export_kind(headers, rva) {
+		ExportKind::Forwarder => OBJPARSE_FORWARDED,
+		_ => OBJPARSE_OK,
+	}
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn objparse_parse(
+	image_base: *const u8,
+	image: *mut *mut ObjparseImage,
+) -> i32 {
+	if image_base.is_null() || image.is_null() {
+		return OBJPARSE_ERROR_NULL_POINTER;
+	}
+	match unsafe { PeHeaders::parse(image_base) } {
+		Ok(headers) => {
+			unsafe { *image = Box::into_raw(Box::new(ObjparseImage { headers })) };
+			OBJPARSE_OK
+		}
+		Err(err) => status(err),
+	}
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn objparse_free(image: *mut ObjparseImage) {
+	if !image.is_null() {
+		drop(unsafe { Box::from_raw(image) });
+	}
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn objparse_find_export(
+	image: *const ObjparseImage,
+	name: *const c_char,
+	address: *mut *mut c_void,
+) -> i32 {
+	if image.is_null() || name.is_null() || address.is_null() {
+		return OBJPARSE_ERROR_NULL_POINTER;
+	}
+	let headers = unsafe { &(*image).headers };
+	let export_table = match headers.export_table() {
+		Ok(export_table) => export_table,
+		Err(err) => return status(err.into()),
+	};
+	let name = unsafe { CStr::from_ptr(name) };
+	let image_base = headers.image_base.cast_mut();
+	let exports = unsafe { export_table.iter_string_addr_checked(headers, image_base) };
+	match exports
+		.filter_map(Result::ok)
+		.find(|(export, _)| *export == name)
+	{
+		Some((_, export_address)) => {
+			let rva = (export_address as usize).wrapping_sub(image_base as usize) as u32;
+			unsafe { export_status(headers, export_table, rva, address) }
+		}
+		None => OBJPARSE_ERROR_NOT_FOUND,
+	}
+}
+
+/// `ordinal` is biased by the export directory's `Base`, as in import thunks.
+#[no_mangle]
+pub unsafe extern "C" fn objparse_find_export_by_ordinal(
+	image: *const ObjparseImage,
+	ordinal: u32,
+	address: *mut *mut c_void,
+) -> i32 {
+	if image.is_null() || address.is_null() {
+		return OBJPARSE_ERROR_NULL_POINTER;
+	}
+	let headers = unsafe { &(*image).headers };
+	let export_table = match headers.export_table() {
+		Ok(export_table) => export_table,
+		Err(err) => return status(err.into()),
+	};
+	match export_table.rva_by_ordinal(ordinal) {
+		Some(rva) => unsafe { export_status(headers, export_table, rva, address) },
+		None => OBJPARSE_ERROR_NOT_FOUND,
+	}
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn objparse_enum_imports(
+	image: *const ObjparseImage,
+	callback: Option<ObjparseImportCallback>,
+	context: *mut c_void,
+) -> i32 {
+	let Some(callback) = callback else {
+		return OBJPARSE_ERROR_NULL_POINTER;
+	};
+	if image.is_null() {
+		return OBJPARSE_ERROR_NULL_POINTER;
+	}
+	let headers = unsafe { &(*image).headers };
+	let import_table = match headers.import_table() {
+		Ok(import_table) => import_table,
+		Err(err) => return status(err.into()),
+	};
+	let image_base = headers.image_base.cast_mut();
+	for descriptor in import_table.import_descriptors {
+		let dll = match unsafe { import_table.dll_name_with(descriptor, headers, image_base) } {
+			Ok(dll) => dll,
+			Err(err) => return status(err),
+		};
+		let thunks = match unsafe { headers.import_thunks(descriptor, image_base) } {
+			Ok(thunks) => thunks,
+			Err(err) => return status(err),
+		};
+		for thunk in thunks {
+			let thunk = match thunk {
+				Ok(thunk) => thunk,
+				Err(err) => return status(err),
+			};
+			let (name, ordinal) = match thunk.name {
+				Some(ImportName::Name { hint, name }) => (name.as_ptr(), hint),
+				Some(ImportName::Ordinal(ordinal)) => (core::ptr::null(), ordinal),
+				None => continue,
+			};
+			let iat_slot = thunk.iat_slot.cast();
+			if !unsafe { callback(context, dll.as_ptr(), name, ordinal, iat_slot) } {
+				return OBJPARSE_OK;
+			}
+		}
+	}
+	OBJPARSE_OK
+}
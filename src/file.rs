@@ -0,0 +1,95 @@
+use crate::{
+	analyze::Report,
+	error::{FileError, Result},
+	import_map::ImportMap,
+	nt::{NativeNtHeaders, NtHeaders},
+	section, ParseOptions, PeHeaders,
+};
+use memmap2::Mmap;
+use object::{
+	pe::{ImageDataDirectory, ImageSectionHeader},
+	LittleEndian,
+};
+use std::{fs::File, path::Path};
+
+/// A PE file mapped from disk and parsed in place in file layout.
+///
+/// The headers and the tables they load hold `'static` references into the mapping, so they
+/// are only reachable through the unsafe [`PeFile::headers`]. Everything else borrows `self` or
+/// is owned.
+pub struct PeFile<Nt: NtHeaders = NativeNtHeaders> {
+	map: Mmap,
+	headers: PeHeaders<Nt>,
+}
+
+impl PeFile {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn open(path: impl AsRef<Path>) -> Result<Self, FileError> {
+		Self::open_with(path, ParseOptions::new())
+	}
+
+	/// `options.layout` is ignored, the file is always parsed in file layout.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn open_with(path: impl AsRef<Path>, options: ParseOptions) -> Result<Self, FileError> {
+		Self::open_nt(path, options)
+	}
+}
+
+impl<Nt: NtHeaders> PeFile<Nt> {
+	/// `options.layout` is ignored, the file is always parsed in file layout.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn open_nt(path: impl AsRef<Path>, options: ParseOptions) -> Result<Self, FileError> {
+		let file = File::open(path)?;
+		let map = unsafe { Mmap::map(&file)? };
+		let headers = unsafe { PeHeaders::parse_file_raw(map.as_ptr(), map.len(), options)? };
+		Ok(Self { map, headers })
+	}
+
+	/// # Safety
+	///
+	/// Nothing taken from the headers, including the tables and names they load, may be used
+	/// after `self` is dropped.
+	pub unsafe fn headers(&self) -> &PeHeaders<Nt> {
+		&self.headers
+	}
+
+	pub fn nt_header(&self) -> &Nt {
+		self.headers.nt_header
+	}
+
+	pub fn section_headers(&self) -> &[ImageSectionHeader] {
+		self.headers.section_headers
+	}
+
+	pub fn data_directory(&self, index: usize) -> Option<&ImageDataDirectory> {
+		self.headers.data_directory(index)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn analyze(&self) -> Report {
+		unsafe { self.headers.analyze(self.map.as_ptr()) }
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn import_map(&self) -> Result<ImportMap> {
+		unsafe { self.headers.import_map(self.map.as_ptr().cast_mut()) }
+	}
+
+	pub fn data(&self) -> &[u8] {
+		&self.map
+	}
+
+	/// Start of the mapping, to pass as `image_base` to the `*_mem` loaders. What they return
+	/// must not outlive `self`.
+	pub fn image_base(&self) -> *const u8 {
+		self.map.as_ptr()
+	}
+
+	/// Raw data of `section`, cut off at the end of the file.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section_data(&self, section: &ImageSectionHeader) -> Option<&[u8]> {
+		let start = section.pointer_to_raw_data.get(LittleEndian) as usize;
+		let end = start.saturating_add(section::section_file_size(section) as usize);
+		self.map.get(start..end.min(self.map.len()))
+	}
+}
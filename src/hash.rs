@@ -0,0 +1,44 @@
+use crate::widestring;
+use core::char::{decode_utf16, REPLACEMENT_CHARACTER};
+
+pub trait ExportHasher {
+	fn hash(bytes: impl Iterator<Item = u8>) -> u32;
+
+	/// Hashes a UTF-16 module name the way the loader compares them: every code unit is folded
+	/// with [`widestring::upcase`], then ASCII letters are lowered again so that ASCII names
+	/// hash as their lowercase UTF-8. Other letters hash as their uppercase.
+	fn hash_module_name(units: impl Iterator<Item = u16>) -> u32 {
+		Self::hash(
+			decode_utf16(units.map(widestring::upcase))
+				.map(|c| c.unwrap_or(REPLACEMENT_CHARACTER).to_ascii_lowercase())
+				.flat_map(|c| {
+					let mut buf = [0; 4];
+					let len = c.encode_utf8(&mut buf).len();
+					buf.into_iter().take(len)
+				}),
+		)
+	}
+}
+
+pub struct Fnv1a;
+
+impl Fnv1a {
+	pub const fn hash_const(bytes: &[u8]) -> u32 {
+		let mut hash = 0x811C_9DC5u32;
+		let mut i = 0;
+		while i < bytes.len() {
+			hash ^= bytes[i] as u32;
+			hash = hash.wrapping_mul(0x0100_0193);
+			i += 1;
+		}
+		hash
+	}
+}
+
+impl ExportHasher for Fnv1a {
+	fn hash(bytes: impl Iterator<Item = u8>) -> u32 {
+		bytes.fold(0x811C_9DC5, |hash, b| {
+			(hash ^ b as u32).wrapping_mul(0x0100_0193)
+		})
+	}
+}
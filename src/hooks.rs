@@ -0,0 +1,149 @@
+use crate::{import::ImportName, nt::NtHeaders, PeHeaders};
+use object::{pe::ImageImportDescriptor, read::pe::ImageOptionalHeader, LittleEndian};
+
+/// A module of the inspected process, from the PEB, a Toolhelp snapshot or a minidump.
+pub trait ModuleRange {
+	fn base(&self) -> u64;
+	fn size(&self) -> u32;
+	/// Whether the file name of the module is `name`, ignoring ASCII case.
+	fn has_name(&self, name: &str) -> bool;
+
+	fn contains(&self, address: u64) -> bool {
+		address.wrapping_sub(self.base()) < self.size() as u64
+	}
+
+	/// Address `import` of this module leads to after following forwarders, `None` if the module
+	/// cannot be read, which is the default.
+	///
+	/// # Safety
+	///
+	/// The module and the modules its forwarders name have to be mapped in this process.
+	unsafe fn resolve_export(&self, _import: ImportName) -> Option<u64> {
+		None
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookKind {
+	/// The target lies in no module, typically a trampoline in allocated memory.
+	OutsideModules,
+	/// The target lies in `modules[index]` instead of the expected module. Forwarded exports
+	/// such as kernel32 to ntdll only land here if [`ModuleRange::resolve_export`] of the
+	/// expected module does not resolve to the target.
+	OtherModule(usize),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct IatHook {
+	pub descriptor: &'static ImageImportDescriptor,
+	pub iat_slot: *mut u8,
+	pub target: u64,
+	pub kind: HookKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EatHook {
+	/// Index into `address_table`.
+	pub index: usize,
+	pub target: u64,
+	pub kind: HookKind,
+}
+
+/// API set names are resolved to a different host module by the loader.
+fn is_api_set(name: &str) -> bool {
+	let prefix = name.as_bytes().get(..7).unwrap_or_default();
+	prefix.eq_ignore_ascii_case(b"api-ms-") || prefix.eq_ignore_ascii_case(b"ext-ms-")
+}
+
+unsafe fn classify<M: ModuleRange>(
+	modules: &[M],
+	target: u64,
+	expected: Option<&str>,
+	import: Option<ImportName>,
+) -> Option<HookKind> {
+	let Some(index) = modules.iter().position(|module| module.contains(target)) else {
+		return Some(HookKind::OutsideModules);
+	};
+	let name = expected.filter(|name| !modules[index].has_name(name))?;
+	let resolved = modules
+		.iter()
+		.find(|module| module.has_name(name))
+		.zip(import)
+		.and_then(|(module, import)| unsafe { module.resolve_export(import) });
+	(resolved != Some(target)).then_some(HookKind::OtherModule(index))
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Resolved IAT slots pointing outside every module in `modules`, or into a module other than
+	/// the one named by their import descriptor and not where that module forwards the import.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn iat_hooks<'a, M: ModuleRange>(
+		&'a self,
+		image_base: *mut u8,
+		modules: &'a [M],
+	) -> impl Iterator<Item = IatHook> + 'a {
+		let is_64 = self.nt_header.is_type_64();
+		let import_table = unsafe { self.import_table_mem(image_base) }.ok();
+		import_table.into_iter().flat_map(move |import_table| {
+			let descriptors = import_table.import_descriptors;
+			descriptors.iter().flat_map(move |descriptor| {
+				let dll = unsafe { import_table.dll_name(descriptor, image_base) }
+					.ok()
+					.and_then(|name| name.to_str().ok())
+					.filter(|name| !is_api_set(name));
+				let thunks = unsafe { self.import_thunks(descriptor, image_base) }.ok();
+				thunks.into_iter().flatten().filter_map(move |thunk| {
+					let thunk = thunk.ok().filter(|thunk| thunk.resolved)?;
+					let target = match is_64 {
+						true => {
+							u64::from_le(unsafe { thunk.iat_slot.cast::<u64>().read_unaligned() })
+						}
+						false => {
+							u32::from_le(unsafe { thunk.iat_slot.cast::<u32>().read_unaligned() })
+								.into()
+						}
+					};
+					Some(IatHook {
+						descriptor,
+						iat_slot: thunk.iat_slot,
+						target,
+						kind: unsafe { classify(modules, target, dll, thunk.name)? },
+					})
+				})
+			})
+		})
+	}
+
+	/// Export RVAs leading outside the image loaded at `loaded_base`, which is where
+	/// `image_base` was read from.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn eat_hooks<'a, M: ModuleRange>(
+		&'a self,
+		image_base: *const u8,
+		loaded_base: u64,
+		modules: &'a [M],
+	) -> impl Iterator<Item = EatHook> + 'a {
+		let size_of_image = self.nt_header.optional_header().size_of_image();
+		let export_table = unsafe { self.export_table_mem(image_base) }.ok();
+		export_table.into_iter().flat_map(move |export_table| {
+			let address_table = export_table.address_table;
+			address_table
+				.iter()
+				.map(|rva| rva.get(LittleEndian))
+				.enumerate()
+				.filter(move |&(_, rva)| rva >= size_of_image)
+				.map(move |(index, rva)| {
+					let target = loaded_base.wrapping_add(rva as u64);
+					let kind = match modules.iter().position(|module| module.contains(target)) {
+						Some(module) => HookKind::OtherModule(module),
+						None => HookKind::OutsideModules,
+					};
+					EatHook {
+						index,
+						target,
+						kind,
+					}
+				})
+		})
+	}
+}
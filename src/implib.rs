@@ -0,0 +1,458 @@
+use crate::{
+	diff::export_entries,
+	error::{Error, Result},
+	nt::NtHeaders,
+	ExportKind, ExportTable, PeHeaders,
+};
+use alloc::{collections::BTreeMap, format, string::ToString, vec, vec::Vec};
+use core::mem::size_of;
+use object::{
+	archive::{Header, MAGIC, TERMINATOR},
+	bytes_of,
+	pe::{
+		ImageFileHeader, ImageImportDescriptor, ImageRelocation, ImageSectionHeader, ImageSymbol,
+		ImportObjectHeader, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64,
+		IMAGE_FILE_MACHINE_ARMNT, IMAGE_FILE_MACHINE_I386, IMAGE_REL_AMD64_ADDR32NB,
+		IMAGE_REL_ARM64_ADDR32NB, IMAGE_REL_ARM_ADDR32NB, IMAGE_REL_I386_DIR32NB,
+		IMAGE_SCN_ALIGN_2BYTES, IMAGE_SCN_ALIGN_4BYTES, IMAGE_SCN_ALIGN_8BYTES,
+		IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE,
+		IMAGE_SYM_CLASS_EXTERNAL, IMAGE_SYM_CLASS_SECTION, IMAGE_SYM_CLASS_STATIC,
+		IMPORT_OBJECT_CODE, IMPORT_OBJECT_DATA, IMPORT_OBJECT_HDR_SIG2, IMPORT_OBJECT_NAME,
+		IMPORT_OBJECT_NAME_NO_PREFIX, IMPORT_OBJECT_ORDINAL,
+	},
+	LittleEndian, U16Bytes, U32Bytes, U16, U32,
+};
+
+const IDATA_CHARACTERISTICS: u32 =
+	IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE;
+
+struct Section<'a> {
+	name: &'static [u8; 8],
+	characteristics: u32,
+	data: &'a [u8],
+	/// Offsets in `data` with the index of the symbol they refer to.
+	relocations: &'a [(u32, u32)],
+}
+
+struct Symbol<'a> {
+	name: &'a [u8],
+	section_number: u16,
+	storage_class: u8,
+}
+
+struct Member {
+	data: Vec<u8>,
+	symbols: Vec<Vec<u8>>,
+}
+
+fn relocation_type(machine: u16) -> Option<u16> {
+	match machine {
+		IMAGE_FILE_MACHINE_AMD64 => Some(IMAGE_REL_AMD64_ADDR32NB),
+		IMAGE_FILE_MACHINE_I386 => Some(IMAGE_REL_I386_DIR32NB),
+		IMAGE_FILE_MACHINE_ARM64 => Some(IMAGE_REL_ARM64_ADDR32NB),
+		IMAGE_FILE_MACHINE_ARMNT => Some(IMAGE_REL_ARM_ADDR32NB),
+		_ => None,
+	}
+}
+
+fn coff_object(machine: u16, sections: &[Section], symbols: &[Symbol]) -> Vec<u8> {
+	let relocation_type = relocation_type(machine).unwrap_or(0);
+	let mut offset =
+		size_of::<ImageFileHeader>() + sections.len() * size_of::<ImageSectionHeader>();
+	let mut section_headers = Vec::new();
+	let mut contents = Vec::new();
+	for section in sections {
+		let relocations_offset = offset + section.data.len();
+		let section_header = ImageSectionHeader {
+			name: *section.name,
+			virtual_size: U32::new(LittleEndian, 0),
+			virtual_address: U32::new(LittleEndian, 0),
+			size_of_raw_data: U32::new(LittleEndian, section.data.len() as u32),
+			pointer_to_raw_data: U32::new(LittleEndian, offset as u32),
+			pointer_to_relocations: U32::new(
+				LittleEndian,
+				match section.relocations {
+					[] => 0,
+					_ => relocations_offset as u32,
+				},
+			),
+			pointer_to_linenumbers: U32::new(LittleEndian, 0),
+			number_of_relocations: U16::new(LittleEndian, section.relocations.len() as u16),
+			number_of_linenumbers: U16::new(LittleEndian, 0),
+			characteristics: U32::new(LittleEndian, section.characteristics),
+		};
+		section_headers.extend_from_slice(bytes_of(&section_header));
+		contents.extend_from_slice(section.data);
+		for &(virtual_address, symbol) in section.relocations {
+			let relocation = ImageRelocation {
+				virtual_address: U32Bytes::new(LittleEndian, virtual_address),
+				symbol_table_index: U32Bytes::new(LittleEndian, symbol),
+				typ: U16Bytes::new(LittleEndian, relocation_type),
+			};
+			contents.extend_from_slice(bytes_of(&relocation));
+		}
+		offset = relocations_offset + section.relocations.len() * size_of::<ImageRelocation>();
+	}
+
+	let file_header = ImageFileHeader {
+		machine: U16::new(LittleEndian, machine),
+		number_of_sections: U16::new(LittleEndian, sections.len() as u16),
+		time_date_stamp: U32::new(LittleEndian, 0),
+		pointer_to_symbol_table: U32::new(LittleEndian, offset as u32),
+		number_of_symbols: U32::new(LittleEndian, symbols.len() as u32),
+		size_of_optional_header: U16::new(LittleEndian, 0),
+		characteristics: U16::new(LittleEndian, 0),
+	};
+	let mut object = bytes_of(&file_header).to_vec();
+	object.extend_from_slice(&section_headers);
+	object.extend_from_slice(&contents);
+
+	// The string table size includes its own field.
+	let mut string_table = 0u32.to_le_bytes().to_vec();
+	for symbol in symbols {
+		let mut name = [0; 8];
+		if symbol.name.len() <= name.len() {
+			name[..symbol.name.len()].copy_from_slice(symbol.name);
+		} else {
+			name[4..].copy_from_slice(&(string_table.len() as u32).to_le_bytes());
+			string_table.extend_from_slice(symbol.name);
+			string_table.push(0);
+		}
+		let symbol = ImageSymbol {
+			name,
+			value: U32Bytes::new(LittleEndian, 0),
+			section_number: U16Bytes::new(LittleEndian, symbol.section_number),
+			typ: U16Bytes::new(LittleEndian, 0),
+			storage_class: symbol.storage_class,
+			number_of_aux_symbols: 0,
+		};
+		object.extend_from_slice(bytes_of(&symbol));
+	}
+	let string_table_size = string_table.len() as u32;
+	string_table[..4].copy_from_slice(&string_table_size.to_le_bytes());
+	object.extend_from_slice(&string_table);
+	object
+}
+
+/// The member defining `__IMPORT_DESCRIPTOR_<library>`, the descriptor pointing at the
+/// `.idata$4`, `.idata$5` and `.idata$6` contributions of the short import members.
+fn import_descriptor(machine: u16, dll: &[u8], library: &[u8]) -> Member {
+	let name = [b"__IMPORT_DESCRIPTOR_", library].concat();
+	let null_thunk = [b"\x7f", library, b"_NULL_THUNK_DATA"].concat();
+	let mut dll_name = [dll, b"\0"].concat();
+	if !dll_name.len().is_multiple_of(2) {
+		dll_name.push(0);
+	}
+	let descriptor = [0; size_of::<ImageImportDescriptor>()];
+	let sections = [
+		Section {
+			name: b".idata$2",
+			characteristics: IMAGE_SCN_ALIGN_4BYTES | IDATA_CHARACTERISTICS,
+			data: &descriptor,
+			// Name, OriginalFirstThunk and FirstThunk.
+			relocations: &[(12, 2), (0, 3), (16, 4)],
+		},
+		Section {
+			name: b".idata$6",
+			characteristics: IMAGE_SCN_ALIGN_2BYTES | IDATA_CHARACTERISTICS,
+			data: &dll_name,
+			relocations: &[],
+		},
+	];
+	let external = |name, section_number| Symbol {
+		name,
+		section_number,
+		storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+	};
+	let section = |name, section_number| Symbol {
+		name,
+		section_number,
+		storage_class: IMAGE_SYM_CLASS_SECTION,
+	};
+	let symbols = [
+		external(&name, 1),
+		section(b".idata$2", 1),
+		Symbol {
+			name: b".idata$6",
+			section_number: 2,
+			storage_class: IMAGE_SYM_CLASS_STATIC,
+		},
+		section(b".idata$4", 0),
+		section(b".idata$5", 0),
+		external(b"__NULL_IMPORT_DESCRIPTOR", 0),
+		external(&null_thunk, 0),
+	];
+	Member {
+		data: coff_object(machine, &sections, &symbols),
+		symbols: vec![name],
+	}
+}
+
+/// The member defining `__NULL_IMPORT_DESCRIPTOR`, the terminator of the descriptor array.
+fn null_import_descriptor(machine: u16) -> Member {
+	let name = b"__NULL_IMPORT_DESCRIPTOR";
+	let sections = [Section {
+		name: b".idata$3",
+		characteristics: IMAGE_SCN_ALIGN_4BYTES | IDATA_CHARACTERISTICS,
+		data: &[0; size_of::<ImageImportDescriptor>()],
+		relocations: &[],
+	}];
+	let symbols = [Symbol {
+		name,
+		section_number: 1,
+		storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+	}];
+	Member {
+		data: coff_object(machine, &sections, &symbols),
+		symbols: vec![name.to_vec()],
+	}
+}
+
+/// The member defining `\x7f<library>_NULL_THUNK_DATA`, the terminators of the name table and
+/// the IAT.
+fn null_thunk(machine: u16, library: &[u8]) -> Member {
+	let name = [b"\x7f", library, b"_NULL_THUNK_DATA"].concat();
+	let (thunk, alignment): (&[u8], _) = match machine {
+		IMAGE_FILE_MACHINE_AMD64 | IMAGE_FILE_MACHINE_ARM64 => (&[0; 8], IMAGE_SCN_ALIGN_8BYTES),
+		_ => (&[0; 4], IMAGE_SCN_ALIGN_4BYTES),
+	};
+	let sections = [b".idata$5", b".idata$4"].map(|name| Section {
+		name,
+		characteristics: alignment | IDATA_CHARACTERISTICS,
+		data: thunk,
+		relocations: &[],
+	});
+	let symbols = [Symbol {
+		name: &name,
+		section_number: 1,
+		storage_class: IMAGE_SYM_CLASS_EXTERNAL,
+	}];
+	Member {
+		data: coff_object(machine, &sections, &symbols),
+		symbols: vec![name],
+	}
+}
+
+fn short_import(
+	machine: u16,
+	dll: &[u8],
+	symbol: &[u8],
+	ordinal_or_hint: u16,
+	import_type: u16,
+	name_type: u16,
+) -> Member {
+	let strings = [symbol, b"\0", dll, b"\0"].concat();
+	let header = ImportObjectHeader {
+		sig1: U16::new(LittleEndian, 0),
+		sig2: U16::new(LittleEndian, IMPORT_OBJECT_HDR_SIG2),
+		version: U16::new(LittleEndian, 0),
+		machine: U16::new(LittleEndian, machine),
+		time_date_stamp: U32::new(LittleEndian, 0),
+		size_of_data: U32::new(LittleEndian, strings.len() as u32),
+		ordinal_or_hint: U16::new(LittleEndian, ordinal_or_hint),
+		name_type: U16::new(LittleEndian, name_type << 2 | import_type),
+	};
+	let mut symbols = vec![[b"__imp_", symbol].concat()];
+	// Data can only be imported through the IAT slot, there is no thunk to call.
+	if import_type != IMPORT_OBJECT_DATA {
+		symbols.push(symbol.to_vec());
+	}
+	Member {
+		data: [bytes_of(&header), &strings].concat(),
+		symbols,
+	}
+}
+
+fn member_header(archive: &mut Vec<u8>, name: &[u8], size: usize) {
+	let mut header = Header {
+		name: [b' '; 16],
+		date: [b' '; 12],
+		uid: [b' '; 6],
+		gid: [b' '; 6],
+		mode: [b' '; 8],
+		size: [b' '; 10],
+		terminator: TERMINATOR,
+	};
+	header.name[..name.len()].copy_from_slice(name);
+	header.date[..1].copy_from_slice(b"0");
+	header.mode[..1].copy_from_slice(b"0");
+	let size = size.to_string();
+	header.size[..size.len()].copy_from_slice(size.as_bytes());
+	archive.extend_from_slice(bytes_of(&header));
+}
+
+fn push_member(archive: &mut Vec<u8>, name: &[u8], data: &[u8]) {
+	member_header(archive, name, data.len());
+	archive.extend_from_slice(data);
+	if !archive.len().is_multiple_of(2) {
+		archive.push(b'\n');
+	}
+}
+
+/// Archive with both linker members, all members named after `dll`. The second linker member
+/// refers to members by 16-bit index, more than 65535 members fail with
+/// [`Error::LimitExceeded`].
+fn archive(dll: &[u8], members: &[Member]) -> Result<Vec<u8>> {
+	if members.len() > u16::MAX as usize {
+		return Err(Error::LimitExceeded {
+			limit: u16::MAX as usize,
+		});
+	}
+	let (member_name, long_names) = match dll.len() {
+		0..=15 => ([dll, b"/"].concat(), Vec::new()),
+		_ => (b"/0".to_vec(), [dll, b"\0"].concat()),
+	};
+	let padded = |size: usize| size + size % 2;
+	let symbols: Vec<(&[u8], usize)> = members
+		.iter()
+		.enumerate()
+		.flat_map(|(index, member)| {
+			member
+				.symbols
+				.iter()
+				.map(move |symbol| (symbol.as_slice(), index))
+		})
+		.collect();
+	let names_size: usize = symbols.iter().map(|(symbol, _)| symbol.len() + 1).sum();
+	let first_linker_size = 4 + 4 * symbols.len() + names_size;
+	let second_linker_size = 4 + 4 * members.len() + 4 + 2 * symbols.len() + names_size;
+
+	let mut offset = MAGIC.len()
+		+ size_of::<Header>()
+		+ padded(first_linker_size)
+		+ size_of::<Header>()
+		+ padded(second_linker_size);
+	if !long_names.is_empty() {
+		offset += size_of::<Header>() + padded(long_names.len());
+	}
+	let member_offsets = members
+		.iter()
+		.map(|member| {
+			// Linker members store 32-bit offsets.
+			let member_offset = u32::try_from(offset).map_err(|_| Error::LimitExceeded {
+				limit: u32::MAX as usize,
+			})?;
+			offset += size_of::<Header>() + padded(member.data.len());
+			Ok(member_offset)
+		})
+		.collect::<Result<Vec<u32>>>()?;
+
+	// The first linker member lists symbols in member order with big-endian offsets, the
+	// second sorts them by name and refers to members by 1-based index.
+	let mut first_linker = (symbols.len() as u32).to_be_bytes().to_vec();
+	for &(_, index) in &symbols {
+		first_linker.extend_from_slice(&member_offsets[index].to_be_bytes());
+	}
+	for (symbol, _) in &symbols {
+		first_linker.extend_from_slice(symbol);
+		first_linker.push(0);
+	}
+
+	let mut sorted = symbols.clone();
+	sorted.sort_by(|a, b| a.0.cmp(b.0));
+	let mut second_linker = (members.len() as u32).to_le_bytes().to_vec();
+	for member_offset in &member_offsets {
+		second_linker.extend_from_slice(&member_offset.to_le_bytes());
+	}
+	second_linker.extend_from_slice(&(sorted.len() as u32).to_le_bytes());
+	for &(_, index) in &sorted {
+		// Checked above, the 1-based index fits.
+		second_linker.extend_from_slice(&(index as u16 + 1).to_le_bytes());
+	}
+	for (symbol, _) in &sorted {
+		second_linker.extend_from_slice(symbol);
+		second_linker.push(0);
+	}
+
+	let mut archive = MAGIC.to_vec();
+	push_member(&mut archive, b"/", &first_linker);
+	push_member(&mut archive, b"/", &second_linker);
+	if !long_names.is_empty() {
+		push_member(&mut archive, b"//", &long_names);
+	}
+	for member in members {
+		push_member(&mut archive, &member_name, &member.data);
+	}
+	Ok(archive)
+}
+
+impl ExportTable {
+	/// Import library for the exports of the image at `image_base`, in the form `lib.exe` and
+	/// `llvm-dlltool` produce. Exports in executable sections and forwarders are imported as
+	/// code, the rest as data. Ordinal-only exports get the `OrdinalN` names of
+	/// [`ExportTable::to_def`]; x86 symbols carry the C decoration stripped at link time.
+	/// Hints, ordinals and member indices are 16-bit, past that it fails with
+	/// [`Error::LimitExceeded`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn to_import_library<Nt: NtHeaders>(
+		&self,
+		headers: &PeHeaders<Nt>,
+		image_base: *const u8,
+		dll_name: &str,
+	) -> Result<Vec<u8>> {
+		let machine = headers.nt_header.file_header().machine.get(LittleEndian);
+		relocation_type(machine).ok_or(Error::ImportObject)?;
+		let dll = dll_name.as_bytes();
+		let library = match dll.iter().rposition(|&byte| byte == b'.') {
+			Some(dot) => &dll[..dot],
+			None => dll,
+		};
+
+		// Hints and ordinals are 16-bit in import objects.
+		let too_large = Error::LimitExceeded {
+			limit: u16::MAX as usize,
+		};
+		let mut hints = BTreeMap::new();
+		for (hint, name_rva) in self.name_table.iter().enumerate() {
+			let Ok(name) = (unsafe { headers.export_name(image_base, name_rva.get(LittleEndian)) })
+			else {
+				continue;
+			};
+			let hint = u16::try_from(hint).map_err(|_| too_large)?;
+			hints.entry(name.to_bytes()).or_insert(hint);
+		}
+
+		let mut members = vec![
+			import_descriptor(machine, dll, library),
+			null_import_descriptor(machine),
+			null_thunk(machine, library),
+		];
+		for entry in unsafe { export_entries(self, headers, image_base) } {
+			let import_type = match self.export_kind(headers, entry.rva) {
+				ExportKind::Data => IMPORT_OBJECT_DATA,
+				_ => IMPORT_OBJECT_CODE,
+			};
+			let (name, ordinal_or_hint, name_type) = match entry.name {
+				Some(name) => (
+					name.to_vec(),
+					hints.get(name).copied().unwrap_or(0),
+					IMPORT_OBJECT_NAME,
+				),
+				None => (
+					format!("Ordinal{}", entry.ordinal).into_bytes(),
+					u16::try_from(entry.ordinal).map_err(|_| too_large)?,
+					IMPORT_OBJECT_ORDINAL,
+				),
+			};
+			let (symbol, name_type) = match machine {
+				IMAGE_FILE_MACHINE_I386 if !name.starts_with(b"?") => (
+					[b"_", name.as_slice()].concat(),
+					match name_type {
+						IMPORT_OBJECT_NAME => IMPORT_OBJECT_NAME_NO_PREFIX,
+						name_type => name_type,
+					},
+				),
+				_ => (name, name_type),
+			};
+			members.push(short_import(
+				machine,
+				dll,
+				&symbol,
+				ordinal_or_hint,
+				import_type,
+				name_type,
+			));
+		}
+		archive(dll, &members)
+	}
+}
@@ -0,0 +1,369 @@
+use crate::{
+	error::{Error, Result},
+	nt::NtHeaders,
+	options::DEFAULT_MAX_IMPORT_THUNKS,
+	rva_ptr, ImportTable, PeHeaders, RvaMap,
+};
+use core::{ffi::CStr, mem::size_of, slice};
+use object::{
+	pe::{self, ImageDelayloadDescriptor, ImageImportByName, ImageImportDescriptor},
+	read::pe::ImageThunkData as Thunk,
+	LittleEndian,
+};
+
+#[cfg(target_pointer_width = "64")]
+pub type ImageThunkData = pe::ImageThunkData64;
+#[cfg(target_pointer_width = "32")]
+pub type ImageThunkData = pe::ImageThunkData32;
+
+#[cfg(target_pointer_width = "64")]
+pub const IMAGE_ORDINAL_FLAG: usize = pe::IMAGE_ORDINAL_FLAG64 as usize;
+#[cfg(target_pointer_width = "32")]
+pub const IMAGE_ORDINAL_FLAG: usize = pe::IMAGE_ORDINAL_FLAG32 as usize;
+
+// Set for delay-load descriptors using RVAs instead of VAs (every linker since VC7).
+const DELAY_ATTRIBUTE_RVA_BASED: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportName<'a> {
+	Name { hint: u16, name: &'a CStr },
+	Ordinal(u16),
+}
+
+pub trait ImportResolver {
+	fn load_module(&mut self, name: &CStr) -> Option<usize>;
+	fn resolve(&mut self, module: usize, import: ImportName) -> Option<usize>;
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn thunk_import_name<'a, T: Thunk>(
+	image_base: *const u8,
+	thunk: T,
+) -> Result<ImportName<'a>> {
+	unsafe { thunk_import_name_in(&RvaMap::mapped(image_base), thunk) }
+}
+
+/// [`thunk_import_name`] in an image of either layout, reading only inside its region.
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn thunk_import_name_in<T: Thunk>(
+	map: &RvaMap,
+	thunk: T,
+) -> Result<ImportName<'static>> {
+	if thunk.is_ordinal() {
+		return Ok(ImportName::Ordinal(thunk.ordinal()));
+	}
+	let import_by_name_ptr = map.ptr(thunk.address())?;
+	unsafe { map.check(import_by_name_ptr, size_of::<ImageImportByName>())? };
+	let hint = unsafe { &*import_by_name_ptr.cast::<ImageImportByName>() }
+		.hint
+		.get(LittleEndian);
+	let name_rva = thunk
+		.address()
+		.checked_add(size_of::<ImageImportByName>() as u32)
+		.ok_or(Error::RvaOverflow)?;
+	let name = unsafe { map.c_str(name_rva)? };
+	Ok(ImportName::Name { hint, name })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImportThunk<'a> {
+	/// `None` when the descriptor has no name table and the IAT was already overwritten.
+	pub name: Option<ImportName<'a>>,
+	/// Points at a thunk of the image's width, which is not `usize` for a WOW64 image.
+	pub iat_slot: *mut u8,
+	/// The IAT slot holds a resolved address rather than the original thunk.
+	pub resolved: bool,
+}
+
+pub struct ImportThunks<T: Thunk = ImageThunkData> {
+	map: RvaMap,
+	name_thunk: Option<*const T>,
+	address_thunk: *mut T,
+	size_of_image: usize,
+	remaining: usize,
+	limit: usize,
+	done: bool,
+}
+
+impl<T: Thunk> ImportThunks<T> {
+	/// Thunks of an image mapped at `image_base`, whose RVAs must lie below `size_of_image`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn new(
+		image_base: *mut u8,
+		name_table_rva: u32,
+		address_table_rva: u32,
+		size_of_image: usize,
+	) -> Result<Self> {
+		let map = RvaMap {
+			size_of_image: u32::try_from(size_of_image).unwrap_or(u32::MAX),
+			..RvaMap::mapped(image_base)
+		};
+		Self::with_map(map, name_table_rva, address_table_rva, size_of_image)
+	}
+
+	/// Thunks of an image of either layout, see [`PeHeaders::rva_map`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn with_map(
+		map: RvaMap,
+		name_table_rva: u32,
+		address_table_rva: u32,
+		size_of_image: usize,
+	) -> Result<Self> {
+		let name_thunk = match name_table_rva {
+			0 => None,
+			rva => Some(map.ptr(rva)?.cast::<T>()),
+		};
+		let address_thunk = map.ptr(address_table_rva)?.cast_mut().cast::<T>();
+		Ok(Self {
+			map,
+			name_thunk,
+			address_thunk,
+			size_of_image,
+			remaining: DEFAULT_MAX_IMPORT_THUNKS,
+			limit: DEFAULT_MAX_IMPORT_THUNKS,
+			done: false,
+		})
+	}
+
+	/// Yields [`Error::LimitExceeded`] instead of a thunk past the first `max_thunks`, for
+	/// thunk arrays without terminator. Defaults to [`DEFAULT_MAX_IMPORT_THUNKS`].
+	pub fn with_limit(mut self, max_thunks: usize) -> Self {
+		self.remaining = max_thunks;
+		self.limit = max_thunks;
+		self
+	}
+}
+
+impl<T: Thunk> Iterator for ImportThunks<T> {
+	type Item = Result<ImportThunk<'static>>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		let iat_slot = self.address_thunk;
+		if let Err(err) = unsafe { self.map.check(iat_slot.cast(), size_of::<T>()) } {
+			self.done = true;
+			return Some(Err(err));
+		}
+		let iat_value = unsafe { iat_slot.read_unaligned() };
+		let (thunk, resolved) = match self.name_thunk {
+			Some(name_thunk) => {
+				if let Err(err) = unsafe { self.map.check(name_thunk.cast(), size_of::<T>()) } {
+					self.done = true;
+					return Some(Err(err));
+				}
+				let thunk = unsafe { name_thunk.read_unaligned() };
+				self.name_thunk = Some(name_thunk.wrapping_add(1));
+				(thunk, iat_value.raw() != thunk.raw())
+			}
+			None => (
+				iat_value,
+				!iat_value.is_ordinal() && iat_value.raw() >= self.size_of_image as u64,
+			),
+		};
+		if thunk.raw() == 0 {
+			return None;
+		}
+		if self.remaining == 0 {
+			self.done = true;
+			return Some(Err(Error::LimitExceeded { limit: self.limit }));
+		}
+		self.remaining -= 1;
+		self.address_thunk = self.address_thunk.wrapping_add(1);
+		let name = if self.name_thunk.is_none() && resolved {
+			None
+		} else {
+			match unsafe { thunk_import_name_in(&self.map, thunk) } {
+				Ok(name) => Some(name),
+				Err(err) => return Some(Err(err)),
+			}
+		};
+		Some(Ok(ImportThunk {
+			name,
+			iat_slot: iat_slot.cast(),
+			resolved,
+		}))
+	}
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn resolve_thunks(
+	thunks: ImportThunks,
+	module: usize,
+	resolver: &mut impl ImportResolver,
+) -> Result<()> {
+	for thunk in thunks {
+		let thunk = thunk?;
+		let import = thunk.name.ok_or(Error::ImportResolution)?;
+		let address = resolver
+			.resolve(module, import)
+			.ok_or(Error::ImportResolution)?;
+		unsafe { thunk.iat_slot.cast::<usize>().write_unaligned(address) };
+	}
+	Ok(())
+}
+
+impl ImportTable {
+	/// The `Name` of `descriptor` in a mapped image.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn dll_name(
+		&self,
+		descriptor: &ImageImportDescriptor,
+		image_base: *const u8,
+	) -> Result<&'static CStr> {
+		let name_ptr = rva_ptr(image_base, descriptor.name.get(LittleEndian) as _)?;
+		Ok(unsafe { CStr::from_ptr(name_ptr.cast()) })
+	}
+
+	/// The `Name` of `descriptor`, translated according to the layout `headers` were parsed with.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn dll_name_with<Nt: NtHeaders>(
+		&self,
+		descriptor: &ImageImportDescriptor,
+		headers: &PeHeaders<Nt>,
+		image_base: *const u8,
+	) -> Result<&'static CStr> {
+		let map = headers.rva_map(image_base);
+		unsafe { map.c_str(descriptor.name.get(LittleEndian)) }
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn resolve(
+		&self,
+		image_base: *mut u8,
+		resolver: &mut impl ImportResolver,
+	) -> Result<()> {
+		for descriptor in self.import_descriptors {
+			let name = unsafe { self.dll_name(descriptor, image_base)? };
+			let module = resolver.load_module(name).ok_or(Error::ImportResolution)?;
+			// While mapping, the IAT still holds name thunks for every entry.
+			let thunks = ImportThunks::new(
+				image_base,
+				descriptor.original_first_thunk.get(LittleEndian),
+				descriptor.first_thunk.get(LittleEndian),
+				usize::MAX,
+			)?;
+			unsafe { resolve_thunks(thunks, module, resolver)? };
+		}
+		Ok(())
+	}
+
+	/// Looks up `name` imported from `dll`, both compared ignoring ASCII case.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn find_import(
+		&self,
+		image_base: *mut u8,
+		size_of_image: u32,
+		dll: &str,
+		name: &str,
+	) -> Option<IatSlot> {
+		let descriptor = self.import_descriptors.iter().find(|descriptor| {
+			unsafe { self.dll_name(descriptor, image_base) }
+				.is_ok_and(|dll_name| dll_name.to_bytes().eq_ignore_ascii_case(dll.as_bytes()))
+		})?;
+		let thunk = unsafe { self.thunks(descriptor, image_base, size_of_image) }
+			.ok()?
+			.map_while(Result::ok)
+			.find(|thunk| match thunk.name {
+				Some(ImportName::Name { name: import, .. }) => {
+					import.to_bytes().eq_ignore_ascii_case(name.as_bytes())
+				}
+				_ => false,
+			})?;
+		Some(IatSlot {
+			descriptor,
+			slot: thunk.iat_slot,
+			rva: (thunk.iat_slot as usize).wrapping_sub(image_base as usize) as u32,
+			resolved: thunk.resolved,
+		})
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn imports_function(
+		&self,
+		image_base: *mut u8,
+		size_of_image: u32,
+		dll: &str,
+		name: &str,
+	) -> bool {
+		unsafe { self.find_import(image_base, size_of_image, dll, name) }.is_some()
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn thunks(
+		&self,
+		descriptor: &ImageImportDescriptor,
+		image_base: *mut u8,
+		size_of_image: u32,
+	) -> Result<ImportThunks> {
+		ImportThunks::new(
+			image_base,
+			descriptor.original_first_thunk.get(LittleEndian),
+			descriptor.first_thunk.get(LittleEndian),
+			size_of_image as _,
+		)
+	}
+}
+
+/// An IAT entry found by [`ImportTable::find_import`].
+#[derive(Clone, Copy, Debug)]
+pub struct IatSlot {
+	pub descriptor: &'static ImageImportDescriptor,
+	pub slot: *mut u8,
+	pub rva: u32,
+	pub resolved: bool,
+}
+
+pub struct DelayImportTable {
+	pub delay_descriptors: &'static [ImageDelayloadDescriptor],
+}
+
+impl DelayImportTable {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(address: *const u8, size: usize) -> Self {
+		let max_entries = size / size_of::<ImageDelayloadDescriptor>();
+		let delay_descriptor_ptr = address.cast::<ImageDelayloadDescriptor>();
+		let all_descriptors = unsafe { slice::from_raw_parts(delay_descriptor_ptr, max_entries) };
+		let number_of_entries = all_descriptors
+			.iter()
+			.position(|descriptor| descriptor.dll_name_rva.get(LittleEndian) == 0)
+			.unwrap_or(max_entries);
+
+		Self {
+			delay_descriptors: &all_descriptors[..number_of_entries],
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn resolve(
+		&self,
+		image_base: *mut u8,
+		resolver: &mut impl ImportResolver,
+		write_module_handle: bool,
+	) -> Result<()> {
+		for descriptor in self.delay_descriptors {
+			if descriptor.attributes.get(LittleEndian) & DELAY_ATTRIBUTE_RVA_BASED == 0 {
+				return Err(Error::DelayImportTable);
+			}
+			let name_ptr = rva_ptr(image_base, descriptor.dll_name_rva.get(LittleEndian) as _)?;
+			let name = unsafe { CStr::from_ptr(name_ptr.cast()) };
+			let module = resolver.load_module(name).ok_or(Error::ImportResolution)?;
+			let thunks = ImportThunks::new(
+				image_base,
+				descriptor.import_name_table_rva.get(LittleEndian),
+				descriptor.import_address_table_rva.get(LittleEndian),
+				usize::MAX,
+			)?;
+			unsafe { resolve_thunks(thunks, module, resolver)? };
+			let module_handle_rva = descriptor.module_handle_rva.get(LittleEndian);
+			if write_module_handle && module_handle_rva != 0 {
+				let module_handle_ptr = rva_ptr(image_base, module_handle_rva as _)?
+					.cast_mut()
+					.cast::<usize>();
+				unsafe { module_handle_ptr.write_unaligned(module) };
+			}
+		}
+		Ok(())
+	}
+}
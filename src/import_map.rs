@@ -0,0 +1,89 @@
+use crate::{error::Result, import::ImportName, nt::NtHeaders, PeHeaders};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use object::{
+	pe::IMAGE_DIRECTORY_ENTRY_IMPORT,
+	read::pe::{ImageNtHeaders, ImageThunkData},
+};
+
+/// [`ImportName`] owning its name.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ImportEntryName {
+	Name { hint: u16, name: String },
+	Ordinal(u16),
+}
+
+impl From<ImportName<'_>> for ImportEntryName {
+	fn from(import: ImportName) -> Self {
+		match import {
+			ImportName::Name { hint, name } => Self::Name {
+				hint,
+				name: name.to_string_lossy().into_owned(),
+			},
+			ImportName::Ordinal(ordinal) => Self::Ordinal(ordinal),
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImportEntry {
+	/// `None` when the descriptor has no name table and the IAT was already overwritten.
+	pub name: Option<ImportEntryName>,
+	pub iat_rva: u32,
+	/// The IAT slot at the time of the snapshot, a name thunk before binding and the resolved
+	/// address after.
+	pub current_value: u64,
+}
+
+/// Owned snapshot of the import directory, entries grouped by dll name as written in the image.
+/// Descriptors naming the same dll are merged in table order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportMap {
+	pub dlls: BTreeMap<String, Vec<ImportEntry>>,
+}
+
+impl ImportMap {
+	pub fn get(&self, dll: &str) -> Option<&[ImportEntry]> {
+		self.dlls.get(dll).map(Vec::as_slice)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &ImportEntry)> {
+		self.dlls
+			.iter()
+			.flat_map(|(dll, entries)| entries.iter().map(move |entry| (dll.as_str(), entry)))
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// [`ImportMap`] of the image mapped at `image_base`, empty without an import directory.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn import_map(&self, image_base: *mut u8) -> Result<ImportMap> {
+		let mut map = ImportMap::default();
+		if self.data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT).is_none() {
+			return Ok(map);
+		}
+		let import_table = unsafe { self.import_table_mem(image_base)? };
+		for descriptor in import_table.import_descriptors {
+			let dll = unsafe { import_table.dll_name_with(descriptor, self, image_base)? };
+			let entries = map
+				.dlls
+				.entry(dll.to_string_lossy().into_owned())
+				.or_default();
+			for thunk in unsafe { self.import_thunks(descriptor, image_base)? } {
+				let thunk = thunk?;
+				let current_value = unsafe {
+					thunk
+						.iat_slot
+						.cast::<<Nt as ImageNtHeaders>::ImageThunkData>()
+						.read_unaligned()
+				}
+				.raw();
+				entries.push(ImportEntry {
+					name: thunk.name.map(ImportEntryName::from),
+					iat_rva: (thunk.iat_slot as usize).wrapping_sub(image_base as usize) as u32,
+					current_value,
+				});
+			}
+		}
+		Ok(map)
+	}
+}
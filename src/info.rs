@@ -0,0 +1,73 @@
+use crate::{nt::NtHeaders, PeHeaders};
+use object::{pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES, read::pe::ImageOptionalHeader, LittleEndian};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DataDirectory {
+	pub virtual_address: u32,
+	pub size: u32,
+}
+
+/// Header scalars copied out in native endianness, for hot loops that would otherwise keep
+/// doing unaligned little-endian reads through the zero-copy headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeInfo {
+	pub is_64: bool,
+	pub machine: u16,
+	pub number_of_sections: u16,
+	pub time_date_stamp: u32,
+	pub characteristics: u16,
+	pub address_of_entry_point: u32,
+	pub image_base: u64,
+	pub section_alignment: u32,
+	pub file_alignment: u32,
+	pub size_of_image: u32,
+	pub size_of_headers: u32,
+	pub check_sum: u32,
+	pub subsystem: u16,
+	pub dll_characteristics: u16,
+	/// Directories past the ones the image declares are zeroed.
+	pub data_directories: [DataDirectory; IMAGE_NUMBEROF_DIRECTORY_ENTRIES],
+}
+
+impl PeInfo {
+	/// Same contract as [`PeHeaders::data_directory`], `None` when unused.
+	pub fn data_directory(&self, index: usize) -> Option<DataDirectory> {
+		self.data_directories
+			.get(index)
+			.copied()
+			.filter(|data_dir| data_dir.virtual_address != 0)
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn info(&self) -> PeInfo {
+		let file_header = self.nt_header.file_header();
+		let optional_header = self.nt_header.optional_header();
+		let mut data_directories = [DataDirectory::default(); IMAGE_NUMBEROF_DIRECTORY_ENTRIES];
+		for (info, data_dir) in data_directories.iter_mut().zip(self.data_directories) {
+			*info = DataDirectory {
+				virtual_address: data_dir.virtual_address.get(LittleEndian),
+				size: data_dir.size.get(LittleEndian),
+			};
+		}
+
+		PeInfo {
+			is_64: self.nt_header.is_type_64(),
+			machine: file_header.machine.get(LittleEndian),
+			number_of_sections: file_header.number_of_sections.get(LittleEndian),
+			time_date_stamp: file_header.time_date_stamp.get(LittleEndian),
+			characteristics: file_header.characteristics.get(LittleEndian),
+			address_of_entry_point: optional_header.address_of_entry_point(),
+			image_base: optional_header.image_base(),
+			section_alignment: optional_header.section_alignment(),
+			file_alignment: optional_header.file_alignment(),
+			size_of_image: optional_header.size_of_image(),
+			size_of_headers: optional_header.size_of_headers(),
+			check_sum: optional_header.check_sum(),
+			subsystem: optional_header.subsystem(),
+			dll_characteristics: optional_header.dll_characteristics(),
+			data_directories,
+		}
+	}
+}
@@ -1,60 +1,374 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::missing_safety_doc)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+/// Debug-level event with the `tracing` feature, nothing without it.
+macro_rules! trace_event {
+	($($arg:tt)*) => {
+		#[cfg(feature = "tracing")]
+		tracing::debug!($($arg)*);
+	};
+}
+
+/// Debug-level span entered until the end of the enclosing block with the `tracing` feature.
+macro_rules! trace_span {
+	($($arg:tt)*) => {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::debug_span!($($arg)*).entered();
+	};
+}
+
+#[cfg(feature = "alloc")]
+pub mod analyze;
+#[cfg(any(feature = "pe32", feature = "pe64"))]
+pub mod any;
+pub mod archive;
+pub mod authenticode;
+#[cfg(all(feature = "rayon", any(feature = "pe32", feature = "pe64")))]
+pub mod batch;
+pub mod bundle;
+pub mod cave;
+pub mod chpe;
+pub mod clr;
+pub mod coff;
+pub mod debug;
+#[cfg(feature = "alloc")]
+pub mod def;
+#[cfg(feature = "alloc")]
+pub mod diff;
+pub mod driver;
+#[cfg(feature = "alloc")]
+pub mod dump;
+pub mod edit;
+pub mod efi;
 pub mod error;
+#[cfg(feature = "std")]
+pub mod export_index;
+#[cfg(feature = "std")]
+pub mod features;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "mmap")]
+pub mod file;
+pub mod hash;
+pub mod hooks;
+#[cfg(feature = "alloc")]
+pub mod implib;
+pub mod import;
+#[cfg(feature = "alloc")]
+pub mod import_map;
+pub mod info;
+pub mod loader;
+#[cfg(feature = "alloc")]
+pub mod map;
+#[cfg(all(windows, feature = "virtual-query"))]
+pub mod memory;
+pub mod metadata;
+pub mod minidump;
+pub mod nt;
+pub mod offsets;
+pub mod options;
+#[cfg(all(windows, feature = "patch"))]
+pub mod patch;
+#[cfg(all(
+	windows,
+	any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")
+))]
+pub mod peb;
+#[cfg(feature = "std")]
+pub mod reader;
+pub mod reloc;
+#[cfg(all(windows, feature = "remote"))]
+pub mod remote;
+pub mod resource;
+pub mod scan;
+pub mod section;
+pub mod source;
+pub mod te;
+pub mod tls;
+pub mod widestring;
+#[cfg(feature = "alloc")]
+pub mod writer;
 
-use crate::error::{Error, Result};
-use core::{ffi::CStr, mem::size_of, slice};
+use crate::clr::ClrHeader;
+use crate::error::{Context, Error, Result, ResultExt};
+use crate::import::{DelayImportTable, ImportThunks};
+use crate::nt::{NativeNtHeaders, NtHeaders, TlsDirectory};
+use crate::offsets::FieldSpan;
+use crate::options::DEFAULT_MAX_TLS_CALLBACKS;
+pub use crate::options::{Layout, ParseOptions, Strictness};
+use crate::resource::ResourceTable;
+pub use crate::section::{section_name, section_name_bytes, section_protection};
+use core::{cell::OnceCell, cmp::Ordering, ffi::CStr, mem::size_of, slice};
 use object::{
 	pe::{
-		self, ImageDataDirectory, ImageDebugDirectory, ImageDosHeader, ImageExportDirectory,
-		ImageImportDescriptor, ImageSectionHeader, ImageTlsDirectory64,
-		IMAGE_DIRECTORY_ENTRY_DEBUG, IMAGE_DIRECTORY_ENTRY_EXPORT, IMAGE_DIRECTORY_ENTRY_IMPORT,
-		IMAGE_DIRECTORY_ENTRY_TLS, IMAGE_DOS_SIGNATURE, IMAGE_NT_SIGNATURE,
+		self, ImageCor20Header, ImageDataDirectory, ImageDebugDirectory, ImageDosHeader,
+		ImageExportDirectory, ImageImportDescriptor, ImageSectionHeader,
+		IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR, IMAGE_DIRECTORY_ENTRY_DEBUG,
+		IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT, IMAGE_DIRECTORY_ENTRY_EXPORT,
+		IMAGE_DIRECTORY_ENTRY_IMPORT, IMAGE_DIRECTORY_ENTRY_RESOURCE, IMAGE_DIRECTORY_ENTRY_TLS,
+		IMAGE_DOS_SIGNATURE, IMAGE_NT_SIGNATURE,
 	},
-	read::pe::{ImageNtHeaders, ImageOptionalHeader},
-	LittleEndian,
+	read::pe::ImageOptionalHeader,
+	LittleEndian, U16Bytes, U32Bytes,
 };
-use windows_sys::Win32::System::SystemServices::PIMAGE_TLS_CALLBACK;
 
-pub struct PeHeaders {
-	pub dos_header: &'static ImageDosHeader,
-	#[cfg(target_arch = "x86_64")]
-	pub nt_header: &'static pe::ImageNtHeaders64,
-	#[cfg(target_arch = "x86")]
-	pub nt_header: &'static pe::ImageNtHeaders32,
-	pub data_directories: &'static [ImageDataDirectory],
+pub type PeHeaders32 = PeHeaders<pe::ImageNtHeaders32>;
+pub type PeHeaders64 = PeHeaders<pe::ImageNtHeaders64>;
+
+fn rva_ptr(image_base: *const u8, rva: usize) -> Result<*const u8> {
+	(image_base as usize)
+		.checked_add(rva)
+		.ok_or(Error::RvaOverflow)?;
+	Ok(image_base.wrapping_add(rva))
+}
+
+unsafe fn check_range(options: &ParseOptions, address: *const u8, len: usize) -> Result<()> {
+	if let Some((start, size)) = options.region {
+		let offset = (address as usize).wrapping_sub(start);
+		if offset > size || len > size - offset {
+			trace_event!(offset, len, size, "range outside the region");
+			return Err(Error::OutOfRegion { offset, len, size });
+		}
+	}
+	#[cfg(all(windows, feature = "virtual-query"))]
+	if options.validate_memory {
+		return unsafe { memory::validate_range(address, len) };
+	}
+	Ok(())
+}
+
+/// Translates the RVAs of an image like [`PeHeaders::rva_to_ptr`], for iterators that read
+/// the image after the headers are gone.
+#[derive(Clone, Copy, Debug)]
+pub struct RvaMap {
+	pub image_base: *const u8,
+	pub options: ParseOptions,
+	/// Only consulted for [`Layout::File`].
 	pub section_headers: &'static [ImageSectionHeader],
+	pub size_of_headers: u32,
+	pub size_of_image: u32,
 }
 
-impl PeHeaders {
+impl RvaMap {
+	/// An image mapped at `image_base` and not bounded by a region.
+	pub fn mapped(image_base: *const u8) -> Self {
+		Self {
+			image_base,
+			options: ParseOptions::new(),
+			section_headers: &[],
+			size_of_headers: 0,
+			size_of_image: u32::MAX,
+		}
+	}
+
 	#[cfg_attr(feature = "debug", inline(never))]
-	pub unsafe fn parse(address: *const u8) -> Result<Self> {
+	pub fn ptr(&self, rva: u32) -> Result<*const u8> {
+		if self.options.layout == Layout::Mapped {
+			if rva >= self.size_of_image {
+				return Err(Error::RvaOutOfBounds {
+					rva,
+					size_of_image: self.size_of_image,
+				});
+			}
+			return rva_ptr(self.image_base, rva as _);
+		}
+		let section = self
+			.section_headers
+			.iter()
+			.find(|section| section::section_contains_rva(section, rva));
+		match section {
+			Some(section) => {
+				let section_offset = rva - section.virtual_address.get(LittleEndian);
+				if section_offset >= section::section_file_size(section) {
+					trace_event!(rva, "RVA in the zero-filled part of a section");
+					return Err(Error::RvaOutsideSections { rva });
+				}
+				let offset = section
+					.pointer_to_raw_data
+					.get(LittleEndian)
+					.checked_add(section_offset)
+					.ok_or(Error::RvaOverflow)?;
+				rva_ptr(self.image_base, offset as _)
+			}
+			None if rva < self.size_of_headers => rva_ptr(self.image_base, rva as _),
+			None => Err(Error::RvaOutsideSections { rva }),
+		}
+	}
+
+	/// `len` bytes at `ptr` checked against the region and, with `validate_memory`, the
+	/// process's memory.
+	pub(crate) unsafe fn check(&self, ptr: *const u8, len: usize) -> Result<()> {
+		unsafe { check_range(&self.options, ptr, len) }
+	}
+
+	/// The nul-terminated string at `rva`, which must end inside the region if one is set.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn c_str(&self, rva: u32) -> Result<&'static CStr> {
+		let ptr = self.ptr(rva)?;
+		let Some((start, size)) = self.options.region else {
+			return Ok(unsafe { CStr::from_ptr(ptr.cast()) });
+		};
+		let offset = (ptr as usize).wrapping_sub(start);
+		let len = size.checked_sub(offset).ok_or(Error::OutOfRegion {
+			offset,
+			len: 1,
+			size,
+		})?;
+		let bytes = unsafe { slice::from_raw_parts(ptr, len) };
+		// Not terminated inside the region, so its end is out of bounds.
+		CStr::from_bytes_until_nul(bytes).map_err(|_| Error::OutOfRegion {
+			offset,
+			len: len + 1,
+			size,
+		})
+	}
+}
+
+pub struct HeadersOnly<Nt: NtHeaders = NativeNtHeaders> {
+	pub dos_header: &'static ImageDosHeader,
+	pub nt_header: &'static Nt,
+	pub nt_header_offset: usize,
+}
+
+impl<Nt: NtHeaders> HeadersOnly<Nt> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub(crate) unsafe fn parse(address: *const u8, options: &ParseOptions) -> Result<Self> {
+		let headers = unsafe { Self::parse_any_magic(address, options)? };
+		let machine = headers.nt_header.file_header().machine.get(LittleEndian);
+		let layout_is_64 = headers.nt_header.is_type_64();
+		if nt::machine_is_64(machine).is_some_and(|is_64| is_64 != layout_is_64) {
+			trace_event!(machine, "machine does not match the header layout");
+			return Err(Error::ArchMismatch {
+				expected: match layout_is_64 {
+					true => pe::IMAGE_NT_OPTIONAL_HDR64_MAGIC,
+					false => pe::IMAGE_NT_OPTIONAL_HDR32_MAGIC,
+				},
+				found: machine,
+			});
+		}
+		if !headers.nt_header.is_valid_optional_magic() {
+			trace_event!(
+				headers.nt_header_offset,
+				"optional header magic does not match"
+			);
+			return Err(Error::PeHeaders);
+		}
+		Ok(headers)
+	}
+
+	/// [`HeadersOnly::parse`] without checking that the optional header is of `Nt`'s layout.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub(crate) unsafe fn parse_any_magic(
+		address: *const u8,
+		options: &ParseOptions,
+	) -> Result<Self> {
 		let dos_header_ptr = address;
+		unsafe { check_range(options, dos_header_ptr, size_of::<ImageDosHeader>())? };
 		let dos_header = unsafe { &*dos_header_ptr.cast::<ImageDosHeader>() };
 		if dos_header.e_magic.get(LittleEndian) != IMAGE_DOS_SIGNATURE {
+			trace_event!(
+				e_magic = dos_header.e_magic.get(LittleEndian),
+				"bad DOS signature"
+			);
 			return Err(Error::PeHeaders);
 		}
-		let nt_header_offset = dos_header.nt_headers_offset() as usize;
+		let nt_header_offset = offsets::nt_headers_offset(dos_header.nt_headers_offset());
 		// Sanity check
-		if nt_header_offset > 1024 {
+		if nt_header_offset > options.max_nt_offset {
+			trace_event!(
+				nt_header_offset,
+				options.max_nt_offset,
+				"NT headers too far"
+			);
 			return Err(Error::PeHeaders);
 		}
 		let nt_header_ptr = unsafe { address.add(nt_header_offset) };
-		#[cfg(target_arch = "x86_64")]
-		let nt_header = unsafe { &*nt_header_ptr.cast::<pe::ImageNtHeaders64>() };
-		#[cfg(target_arch = "x86")]
-		let nt_header = unsafe { &*nt_header_ptr.cast::<ImageNtHeaders32>() };
-		if nt_header.signature.get(LittleEndian) != IMAGE_NT_SIGNATURE {
-			return Err(Error::PeHeaders);
-		}
-		if !nt_header.is_valid_optional_magic() {
+		unsafe { check_range(options, nt_header_ptr, size_of::<Nt>())? };
+		let nt_header = unsafe { &*nt_header_ptr.cast::<Nt>() };
+		if nt_header.signature() != IMAGE_NT_SIGNATURE {
+			trace_event!(nt_header_offset, "bad NT signature");
 			return Err(Error::PeHeaders);
 		}
-		#[cfg(target_arch = "x86_64")]
-		let data_directories_ptr = unsafe { nt_header_ptr.add(size_of::<pe::ImageNtHeaders64>()) };
-		#[cfg(target_arch = "x86")]
-		let data_directories_ptr = unsafe { nt_header_ptr.add(size_of::<pe::ImageNtHeaders32>()) };
-		let num_data_directories = nt_header.optional_header().number_of_rva_and_sizes() as _;
+
+		Ok(Self {
+			dos_header,
+			nt_header,
+			nt_header_offset,
+		})
+	}
+}
+
+pub struct PeHeaders<Nt: NtHeaders = NativeNtHeaders> {
+	pub dos_header: &'static ImageDosHeader,
+	pub nt_header: &'static Nt,
+	pub data_directories: &'static [ImageDataDirectory],
+	pub section_headers: &'static [ImageSectionHeader],
+	pub options: ParseOptions,
+	pub image_base: *const u8,
+	export_table: OnceCell<Result<ExportTable, Context>>,
+	import_table: OnceCell<Result<ImportTable, Context>>,
+	debug_table: OnceCell<Result<DebugTable, Context>>,
+	tls_table: OnceCell<Result<Option<TlsDir<Nt::TlsDirectory>>, Context>>,
+	resource_table: OnceCell<Result<ResourceTable, Context>>,
+}
+
+impl PeHeaders {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse(address: *const u8) -> Result<Self> {
+		unsafe { Self::parse_with(address, ParseOptions::new()) }
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse_headers_only(address: *const u8) -> Result<HeadersOnly> {
+		unsafe { HeadersOnly::parse(address, &ParseOptions::new()) }
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse_with(address: *const u8, options: ParseOptions) -> Result<Self> {
+		unsafe { Self::parse_nt(address, options) }
+	}
+
+	/// Parses an image known to lie in `size_of_region` bytes at `address`, see
+	/// [`PeHeaders::parse_nt_with_size`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse_with_size(address: *const u8, size_of_region: usize) -> Result<Self> {
+		unsafe { Self::parse_nt_with_size(address, size_of_region, ParseOptions::new()) }
+	}
+
+	/// Parses the raw contents of a file, see [`PeHeaders::parse_file_nt`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse_file(data: &'static [u8], options: ParseOptions) -> Result<Self> {
+		Self::parse_file_nt(data, options)
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Parses with the header layout of `Nt` rather than the host's, e.g. `PeHeaders32::parse_nt`
+	/// for a WOW64 module read from a 64-bit process.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse_nt(address: *const u8, options: ParseOptions) -> Result<Self> {
+		trace_span!("parse_nt", ?address, layout = ?options.layout);
+		let HeadersOnly {
+			dos_header,
+			nt_header,
+			nt_header_offset,
+		} = unsafe { HeadersOnly::<Nt>::parse(address, &options)? };
+		let data_directories_ptr =
+			unsafe { address.add(offsets::data_directories_offset_of::<Nt>(nt_header_offset)) };
+		let declared_data_directories = nt_header.optional_header().number_of_rva_and_sizes() as _;
+		let num_data_directories = options
+			.limit(declared_data_directories, options.max_data_directories)
+			.ok_or(Error::PeHeaders)?;
+		unsafe {
+			check_range(
+				&options,
+				data_directories_ptr,
+				num_data_directories * size_of::<ImageDataDirectory>(),
+			)?
+		};
 		let data_directories = unsafe {
 			slice::from_raw_parts(
 				data_directories_ptr.cast::<ImageDataDirectory>(),
@@ -62,168 +376,1018 @@ impl PeHeaders {
 			)
 		};
 		let section_headers_ptr = unsafe {
-			data_directories_ptr.add(num_data_directories * size_of::<ImageDataDirectory>())
+			address.add(offsets::section_table_offset(
+				nt_header_offset,
+				nt_header
+					.file_header()
+					.size_of_optional_header
+					.get(LittleEndian) as _,
+			))
+		};
+		let num_section_headers = options
+			.limit(
+				nt_header.file_header().number_of_sections.get(LittleEndian) as _,
+				options.max_sections,
+			)
+			.ok_or(Error::PeHeaders)?;
+		unsafe {
+			check_range(
+				&options,
+				section_headers_ptr,
+				num_section_headers * size_of::<ImageSectionHeader>(),
+			)?
 		};
-		let num_section_headers = nt_header.file_header().number_of_sections.get(LittleEndian) as _;
 		let section_headers = unsafe {
 			slice::from_raw_parts(
 				section_headers_ptr.cast::<ImageSectionHeader>(),
 				num_section_headers,
 			)
 		};
+		trace_event!(
+			nt_header_offset,
+			data_directories = num_data_directories,
+			sections = num_section_headers,
+			"parsed headers"
+		);
 
 		Ok(Self {
 			dos_header,
 			nt_header,
 			data_directories,
 			section_headers,
+			options,
+			image_base: address,
+			export_table: OnceCell::new(),
+			import_table: OnceCell::new(),
+			debug_table: OnceCell::new(),
+			tls_table: OnceCell::new(),
+			resource_table: OnceCell::new(),
+		})
+	}
+
+	/// [`PeHeaders::parse_nt`] with [`ParseOptions::region`] set to `size_of_region` bytes at
+	/// `address`, so headers, directories and the tables read through them are checked against
+	/// the region, e.g. for a candidate carved out of a heap dump.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse_nt_with_size(
+		address: *const u8,
+		size_of_region: usize,
+		options: ParseOptions,
+	) -> Result<Self> {
+		unsafe { Self::parse_nt(address, options.region(address, size_of_region)) }
+	}
+
+	/// Parses `data` in file layout after checking that the headers lie inside it, the entry
+	/// point for callers without a mapped image or raw pointers, e.g. on wasm.
+	/// `options.layout` and `options.region` are replaced, every later read is checked against
+	/// `data`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse_file_nt(data: &'static [u8], options: ParseOptions) -> Result<Self> {
+		unsafe { Self::parse_file_raw(data.as_ptr(), data.len(), options) }
+	}
+
+	pub(crate) unsafe fn parse_file_raw(
+		address: *const u8,
+		len: usize,
+		options: ParseOptions,
+	) -> Result<Self> {
+		let options = options.layout(Layout::File).region(address, len);
+		let max_nt_offset = len.checked_sub(size_of::<Nt>()).ok_or(Error::PeHeaders)?;
+		let probe_options = options.max_nt_offset(options.max_nt_offset.min(max_nt_offset));
+		let headers_only = unsafe { HeadersOnly::<Nt>::parse(address, &probe_options)? };
+		let nt_header = headers_only.nt_header;
+		let section_headers_end = offsets::section_table_entry_offset(
+			headers_only.nt_header_offset,
+			nt_header
+				.file_header()
+				.size_of_optional_header
+				.get(LittleEndian) as _,
+			nt_header.file_header().number_of_sections.get(LittleEndian) as _,
+		);
+		if section_headers_end > len {
+			trace_event!(
+				section_headers_end,
+				len,
+				"section headers past the end of the file"
+			);
+			return Err(Error::PeHeaders);
+		}
+		unsafe { Self::parse_nt(address, options) }
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn data_directory(&self, index: usize) -> Option<&'static ImageDataDirectory> {
+		self.data_directories
+			.get(index)
+			.filter(|data_dir| data_dir.virtual_address.get(LittleEndian) != 0)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section_for_rva(&self, rva: u32) -> Option<&'static ImageSectionHeader> {
+		self.section_headers
+			.iter()
+			.find(|section| section::section_contains_rva(section, rva))
+	}
+
+	/// RVA of `va`, which is relative to `image_base` once relocated and to `ImageBase` before.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn va_to_rva(&self, image_base: *const u8, va: u64) -> Option<u32> {
+		let optional_header = self.nt_header.optional_header();
+		let size_of_image = optional_header.size_of_image() as u64;
+		[image_base as u64, optional_header.image_base()]
+			.into_iter()
+			.map(|base| va.wrapping_sub(base))
+			.find(|&rva| rva < size_of_image)
+			.map(|rva| rva as u32)
+	}
+
+	/// Section and offsets of `address` in the module parsed at `image_base`, which must be
+	/// mapped. `None` past `SizeOfImage`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn locate(&self, address: *const u8) -> Option<section::Location> {
+		if self.options.layout != Layout::Mapped {
+			return None;
+		}
+		let rva = (address as usize).checked_sub(self.image_base as usize)?;
+		let rva = u32::try_from(rva)
+			.ok()
+			.filter(|&rva| rva < self.nt_header.optional_header().size_of_image())?;
+		let section = self.section_for_rva(rva);
+		let section_offset = section.map_or(rva, |section| {
+			rva - section.virtual_address.get(LittleEndian)
+		});
+		Some(section::Location {
+			section,
+			rva,
+			section_offset,
+		})
+	}
+
+	/// Translates `rva` according to `options.layout`, through the section headers for a file.
+	/// In a file, an RVA in the zero-filled tail of a section has no bytes and is
+	/// [`Error::RvaOutsideSections`], in a mapped image RVAs past `SizeOfImage` are
+	/// [`Error::RvaOutOfBounds`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn rva_to_ptr(&self, image_base: *const u8, rva: u32) -> Result<*const u8> {
+		self.rva_map(image_base).ptr(rva)
+	}
+
+	/// [`PeHeaders::rva_to_ptr`] for the image at `image_base`, detached from the headers.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn rva_map(&self, image_base: *const u8) -> RvaMap {
+		RvaMap {
+			image_base,
+			options: self.options,
+			section_headers: self.section_headers,
+			size_of_headers: self.nt_header.optional_header().size_of_headers(),
+			size_of_image: self.nt_header.optional_header().size_of_image(),
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section_size_anomalies(
+		&self,
+	) -> impl Iterator<Item = (&'static ImageSectionHeader, section::SectionSizeAnomaly)> {
+		let file_alignment = self.nt_header.optional_header().file_alignment();
+		self.section_headers.iter().filter_map(move |section| {
+			section::section_size_anomaly(section, file_alignment).map(|anomaly| (section, anomaly))
+		})
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section_slack_spaces(&self) -> impl Iterator<Item = section::SectionSlack> + '_ {
+		let optional_header = self.nt_header.optional_header();
+		let file_alignment = optional_header.file_alignment().max(1);
+		let section_alignment = optional_header.section_alignment().max(1);
+		self.section_headers.iter().map(move |section| {
+			let pointer_to_raw_data = section.pointer_to_raw_data.get(LittleEndian);
+			let file_used_end =
+				pointer_to_raw_data.saturating_add(section::section_file_size(section));
+			let file_end = self
+				.section_headers
+				.iter()
+				.map(|next| next.pointer_to_raw_data.get(LittleEndian))
+				.filter(|&next| next > pointer_to_raw_data)
+				.fold(
+					pointer_to_raw_data.saturating_add(
+						section
+							.size_of_raw_data
+							.get(LittleEndian)
+							.checked_next_multiple_of(file_alignment)
+							.unwrap_or(u32::MAX),
+					),
+					u32::min,
+				);
+			let virtual_address = section.virtual_address.get(LittleEndian);
+			let virtual_size = section::section_virtual_size(section);
+			let used_end = virtual_address.saturating_add(virtual_size);
+			let memory_end = self
+				.section_headers
+				.iter()
+				.map(|next| next.virtual_address.get(LittleEndian))
+				.filter(|&next| next > virtual_address)
+				.fold(
+					virtual_address.saturating_add(
+						virtual_size
+							.checked_next_multiple_of(section_alignment)
+							.unwrap_or(u32::MAX),
+					),
+					u32::min,
+				);
+			section::SectionSlack {
+				section,
+				file_offset: file_used_end,
+				file_slack: match pointer_to_raw_data {
+					0 => 0,
+					_ => file_end.saturating_sub(file_used_end),
+				},
+				rva: used_end,
+				memory_slack: memory_end.saturating_sub(used_end),
+			}
 		})
 	}
 
+	/// The virtual size when mapped, only the bytes the loader would copy for a file.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn section_data(
+		&self,
+		image_base: *const u8,
+		section: &ImageSectionHeader,
+	) -> Result<&'static [u8]> {
+		let (offset, size) = match self.options.layout {
+			Layout::Mapped => (
+				section.virtual_address.get(LittleEndian),
+				section::section_virtual_size(section),
+			),
+			Layout::File => (
+				section.pointer_to_raw_data.get(LittleEndian),
+				section::section_file_size(section),
+			),
+		};
+		let ptr = rva_ptr(image_base, offset as _)?;
+		unsafe { check_range(&self.options, ptr, size as _)? };
+		Ok(unsafe { slice::from_raw_parts(ptr, size as _) })
+	}
+
+	/// The export name at `name_rva`, read only from the readable section holding it, so a wild
+	/// RVA in a corrupt name table is an error rather than a fault.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn export_name(
+		&self,
+		image_base: *const u8,
+		name_rva: u32,
+	) -> Result<&'static CStr> {
+		let section = self
+			.section_for_rva(name_rva)
+			.filter(|section| section::section_is_readable(section))
+			.ok_or(Error::RvaOutsideSections { rva: name_rva })?;
+		let data = unsafe { self.section_data(image_base, section)? };
+		let offset = (name_rva - section.virtual_address.get(LittleEndian)) as usize;
+		let name = data
+			.get(offset..)
+			.ok_or(Error::RvaOutsideSections { rva: name_rva })?;
+		CStr::from_bytes_until_nul(name).map_err(|_| Error::ExportTable)
+	}
+
 	#[cfg_attr(feature = "debug", inline(never))]
-	pub unsafe fn export_table_mem(&self, image_base: *const u8) -> Result<ExportTable> {
+	pub unsafe fn export_table_mem(&self, image_base: *const u8) -> Result<ExportTable, Context> {
 		let export_table_data_dir = self
 			.data_directories
 			.get(IMAGE_DIRECTORY_ENTRY_EXPORT)
-			.ok_or(Error::ExportTable)?;
+			.ok_or(Error::ExportTable)
+			.context("export table")?;
 		let export_table_rva = export_table_data_dir.virtual_address.get(LittleEndian);
-		let export_table_ptr = unsafe { image_base.add(export_table_rva as _) };
+		trace_event!(
+			directory = "export",
+			rva = export_table_rva,
+			size = export_table_data_dir.size.get(LittleEndian)
+		);
+		// Drivers and firmware images commonly leave the directory out entirely.
+		if export_table_rva == 0 {
+			return Err(Error::ExportTable).context("export table");
+		}
+		let export_table_ptr = self
+			.rva_to_ptr(image_base, export_table_rva)
+			.context("export table")?;
 		let export_table_size = export_table_data_dir.size.get(LittleEndian);
-		Ok(ExportTable::parse(
-			export_table_ptr,
-			export_table_rva as _,
-			export_table_size,
-		))
+		unsafe {
+			check_range(&self.options, export_table_ptr, export_table_size as _)
+				.context("export table")?
+		};
+		unsafe {
+			ExportTable::parse_translated(
+				export_table_ptr,
+				export_table_rva,
+				export_table_size,
+				&self.options,
+				|rva| self.rva_to_ptr(image_base, rva),
+			)
+		}
+		.context("export table")
 	}
 
 	#[cfg_attr(feature = "debug", inline(never))]
-	pub unsafe fn import_table_mem(&self, image_base: *const u8) -> Result<ImportTable> {
+	pub unsafe fn import_table_mem(&self, image_base: *const u8) -> Result<ImportTable, Context> {
 		let import_table_data_dir = self
 			.data_directories
 			.get(IMAGE_DIRECTORY_ENTRY_IMPORT)
-			.ok_or(Error::ImportTable)?;
+			.ok_or(Error::ImportTable)
+			.context("import table")?;
 		let import_table_rva = import_table_data_dir.virtual_address.get(LittleEndian);
+		if import_table_rva == 0 {
+			return Err(Error::ImportTable).context("import table");
+		}
 		let import_table_size = import_table_data_dir.size.get(LittleEndian);
-		let import_table_ptr = unsafe { image_base.add(import_table_rva as _) };
-		Ok(ImportTable::parse(import_table_ptr, import_table_size as _))
+		trace_event!(
+			directory = "import",
+			rva = import_table_rva,
+			size = import_table_size
+		);
+		let import_table_ptr = self
+			.rva_to_ptr(image_base, import_table_rva)
+			.context("import table")?;
+		unsafe {
+			check_range(&self.options, import_table_ptr, import_table_size as _)
+				.context("import table")?
+		};
+		unsafe { ImportTable::parse_with(import_table_ptr, import_table_size as _, &self.options) }
+			.context("import table")
 	}
 
 	#[cfg_attr(feature = "debug", inline(never))]
-	pub unsafe fn debug_table_mem(&self, image_base: *const u8) -> Result<DebugTable> {
+	pub unsafe fn delay_import_table_mem(
+		&self,
+		image_base: *const u8,
+	) -> Result<DelayImportTable, Context> {
+		let delay_import_table_data_dir = self
+			.data_directories
+			.get(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT)
+			.ok_or(Error::DelayImportTable)
+			.context("delay import table")?;
+		let delay_import_table_rva = delay_import_table_data_dir
+			.virtual_address
+			.get(LittleEndian);
+		if delay_import_table_rva == 0 {
+			return Err(Error::DelayImportTable).context("delay import table");
+		}
+		let delay_import_table_size = delay_import_table_data_dir.size.get(LittleEndian);
+		trace_event!(
+			directory = "delay import",
+			rva = delay_import_table_rva,
+			size = delay_import_table_size
+		);
+		let delay_import_table_ptr = self
+			.rva_to_ptr(image_base, delay_import_table_rva)
+			.context("delay import table")?;
+		unsafe {
+			check_range(
+				&self.options,
+				delay_import_table_ptr,
+				delay_import_table_size as _,
+			)
+			.context("delay import table")?
+		};
+		Ok(DelayImportTable::parse(
+			delay_import_table_ptr,
+			delay_import_table_size as _,
+		))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn debug_table_mem(&self, image_base: *const u8) -> Result<DebugTable, Context> {
 		let debug_table_data_dir = self
 			.data_directories
 			.get(IMAGE_DIRECTORY_ENTRY_DEBUG)
-			.ok_or(Error::DebugTable)?;
+			.ok_or(Error::DebugTable)
+			.context("debug table")?;
 		let debug_table_rva = debug_table_data_dir.virtual_address.get(LittleEndian);
+		if debug_table_rva == 0 {
+			return Err(Error::DebugTable).context("debug table");
+		}
 		let debug_table_size = debug_table_data_dir.size.get(LittleEndian);
-		let debug_table_ptr = unsafe { image_base.add(debug_table_rva as _) };
+		trace_event!(
+			directory = "debug",
+			rva = debug_table_rva,
+			size = debug_table_size
+		);
+		let debug_table_ptr = self
+			.rva_to_ptr(image_base, debug_table_rva)
+			.context("debug table")?;
+		let entry_size = size_of::<ImageDebugDirectory>() as u32;
+		if debug_table_size < entry_size
+			|| (!debug_table_size.is_multiple_of(entry_size)
+				&& self.options.strictness == Strictness::Strict)
+		{
+			return Err(Error::DebugTable).context("debug table");
+		}
+		unsafe {
+			check_range(&self.options, debug_table_ptr, debug_table_size as _)
+				.context("debug table")?
+		};
 		Ok(DebugTable::parse(debug_table_ptr, debug_table_size as _))
 	}
 
 	#[cfg_attr(feature = "debug", inline(never))]
-	pub unsafe fn tls_table_mem(&self, image_base: *const u8) -> Result<Option<TlsDir>> {
+	pub unsafe fn tls_table_mem(
+		&self,
+		image_base: *const u8,
+	) -> Result<Option<TlsDir<Nt::TlsDirectory>>, Context> {
 		let tls_table_data_dir = self
 			.data_directories
 			.get(IMAGE_DIRECTORY_ENTRY_TLS)
-			.ok_or(Error::TlsTable)?;
+			.ok_or(Error::TlsTable)
+			.context("TLS table")?;
 		let tls_table_rva = tls_table_data_dir.virtual_address.get(LittleEndian);
 		let _tls_table_size = tls_table_data_dir.size.get(LittleEndian);
+		trace_event!(
+			directory = "TLS",
+			rva = tls_table_rva,
+			size = _tls_table_size
+		);
 		if tls_table_rva == 0 {
 			return Ok(None);
 		}
-		let tls_table_ptr = unsafe { image_base.add(tls_table_rva as _) };
+		let tls_table_ptr = self
+			.rva_to_ptr(image_base, tls_table_rva)
+			.context("TLS table")?;
+		unsafe {
+			check_range(&self.options, tls_table_ptr, size_of::<Nt::TlsDirectory>())
+				.context("TLS table")?
+		};
 		Ok(Some(TlsDir::parse(tls_table_ptr)))
 	}
+
+	/// Follows `options.layout`, the offsets inside the tree are relative to its start, which
+	/// holds in both layouts as long as the tree lies in one section.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn resource_table_mem(
+		&self,
+		image_base: *const u8,
+	) -> Result<ResourceTable, Context> {
+		let resource_table_data_dir = self
+			.data_directories
+			.get(IMAGE_DIRECTORY_ENTRY_RESOURCE)
+			.ok_or(Error::ResourceTable)
+			.context("resource table")?;
+		let resource_table_rva = resource_table_data_dir.virtual_address.get(LittleEndian);
+		let resource_table_size = resource_table_data_dir.size.get(LittleEndian);
+		trace_event!(
+			directory = "resource",
+			rva = resource_table_rva,
+			size = resource_table_size
+		);
+		if resource_table_rva == 0 {
+			return Err(Error::ResourceTable).context("resource table");
+		}
+		let resource_table_ptr = self
+			.rva_to_ptr(image_base, resource_table_rva)
+			.context("resource table")?;
+		unsafe {
+			check_range(&self.options, resource_table_ptr, resource_table_size as _)
+				.context("resource table")?
+		};
+		let mut resource_table = ResourceTable::parse(resource_table_ptr, resource_table_size);
+		resource_table.max_nodes = self.options.max_resource_nodes;
+		Ok(resource_table)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn clr_header_mem(
+		&self,
+		image_base: *const u8,
+	) -> Result<Option<ClrHeader>, Context> {
+		let clr_header_data_dir = self
+			.data_directories
+			.get(IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR)
+			.ok_or(Error::ClrHeader)
+			.context("CLR header")?;
+		let clr_header_rva = clr_header_data_dir.virtual_address.get(LittleEndian);
+		trace_event!(
+			directory = "CLR",
+			rva = clr_header_rva,
+			size = clr_header_data_dir.size.get(LittleEndian)
+		);
+		if clr_header_rva == 0 {
+			return Ok(None);
+		}
+		let clr_header_ptr = self
+			.rva_to_ptr(image_base, clr_header_rva)
+			.context("CLR header")?;
+		unsafe {
+			check_range(&self.options, clr_header_ptr, size_of::<ImageCor20Header>())
+				.context("CLR header")?
+		};
+		Ok(Some(ClrHeader::parse(clr_header_ptr)))
+	}
+
+	/// The thunks of `descriptor`, read with the thunk width of the image.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn import_thunks(
+		&self,
+		descriptor: &ImageImportDescriptor,
+		image_base: *mut u8,
+	) -> Result<ImportThunks<Nt::ImageThunkData>> {
+		ImportThunks::with_map(
+			self.rva_map(image_base),
+			descriptor.original_first_thunk.get(LittleEndian),
+			descriptor.first_thunk.get(LittleEndian),
+			self.nt_header.optional_header().size_of_image() as _,
+		)
+	}
+}
+
+// The lazy accessors read the image at `image_base` in `options.layout`. This is sound as every
+// constructor guarantees that image stays readable for `'static`: the `unsafe` ones require it
+// of the caller, `parse_file` borrows it and bounds every read with `options.region`.
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn export_table(&self) -> Result<&ExportTable, Context> {
+		self.export_table
+			.get_or_init(|| unsafe { self.export_table_mem(self.image_base) })
+			.as_ref()
+			.map_err(|&err| err)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn import_table(&self) -> Result<&ImportTable, Context> {
+		self.import_table
+			.get_or_init(|| unsafe { self.import_table_mem(self.image_base) })
+			.as_ref()
+			.map_err(|&err| err)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn debug_table(&self) -> Result<&DebugTable, Context> {
+		self.debug_table
+			.get_or_init(|| unsafe { self.debug_table_mem(self.image_base) })
+			.as_ref()
+			.map_err(|&err| err)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn tls_table(&self) -> Result<Option<&TlsDir<Nt::TlsDirectory>>, Context> {
+		self.tls_table
+			.get_or_init(|| unsafe { self.tls_table_mem(self.image_base) })
+			.as_ref()
+			.map(Option::as_ref)
+			.map_err(|&err| err)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn resource_table(&self) -> Result<&ResourceTable, Context> {
+		self.resource_table
+			.get_or_init(|| unsafe { self.resource_table_mem(self.image_base) })
+			.as_ref()
+			.map_err(|&err| err)
+	}
 }
 
 pub struct ExportTable {
 	pub export_directory: &'static ImageExportDirectory,
-	pub address_table: &'static [u32],
-	pub name_table: &'static [u32],
-	pub ordinal_table: &'static [u16],
+	/// Stored little-endian and possibly unaligned, read through [`ExportTable::rva_by_index`].
+	pub address_table: &'static [U32Bytes<LittleEndian>],
+	pub name_table: &'static [U32Bytes<LittleEndian>],
+	pub ordinal_table: &'static [U16Bytes<LittleEndian>],
 	pub start_address: *const u8,
+	pub rva: u32,
 	pub size: u32,
 }
 
 impl ExportTable {
+	/// [`ExportTable::parse_with`] with the default [`ParseOptions`].
 	#[cfg_attr(feature = "debug", inline(never))]
-	pub unsafe fn parse(address: *const u8, rva: usize, size: u32) -> Self {
+	pub unsafe fn parse(address: *const u8, rva: usize, size: u32) -> Result<Self> {
+		unsafe { Self::parse_with(address, rva, size, &ParseOptions::new()) }
+	}
+
+	/// Counts past [`ParseOptions::max_exports`] fail with [`Error::LimitExceeded`], or are
+	/// clamped when lenient. The image must be mapped, see [`PeHeaders::export_table_mem`] for a
+	/// file.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse_with(
+		address: *const u8,
+		rva: usize,
+		size: u32,
+		options: &ParseOptions,
+	) -> Result<Self> {
+		(address as usize)
+			.checked_sub(rva)
+			.ok_or(Error::RvaOverflow)?;
+		let image_base = address.wrapping_sub(rva);
+		unsafe {
+			Self::parse_translated(address, rva as _, size, options, |rva| {
+				rva_ptr(image_base, rva as _)
+			})
+		}
+	}
+
+	/// [`ExportTable::parse_with`] finding the tables through `rva_to_ptr`, which follows the
+	/// layout of the image.
+	unsafe fn parse_translated(
+		address: *const u8,
+		rva: u32,
+		size: u32,
+		options: &ParseOptions,
+		rva_to_ptr: impl Fn(u32) -> Result<*const u8>,
+	) -> Result<Self> {
 		let export_directory_ptr = address;
+		unsafe {
+			check_range(
+				options,
+				export_directory_ptr,
+				size_of::<ImageExportDirectory>(),
+			)?
+		};
 		let export_directory = unsafe { &*export_directory_ptr.cast::<ImageExportDirectory>() };
 
-		let address_table_ptr = unsafe {
-			address
-				.add(export_directory.address_of_functions.get(LittleEndian) as _)
-				.wrapping_sub(rva)
-				.cast::<u32>()
-		};
-		let address_table_len = export_directory.number_of_functions.get(LittleEndian) as _;
+		let address_table_len = options
+			.limit(
+				export_directory.number_of_functions.get(LittleEndian) as _,
+				options.max_exports,
+			)
+			.ok_or(Error::LimitExceeded {
+				limit: options.max_exports,
+			})?;
+		let address_table_ptr =
+			rva_to_ptr(export_directory.address_of_functions.get(LittleEndian))?
+				.cast::<U32Bytes<LittleEndian>>();
+		unsafe { check_range(options, address_table_ptr.cast(), address_table_len * 4)? };
 		let address_table = unsafe { slice::from_raw_parts(address_table_ptr, address_table_len) };
 
-		let name_table_ptr = unsafe {
-			address
-				.add(export_directory.address_of_names.get(LittleEndian) as _)
-				.wrapping_sub(rva)
-				.cast::<u32>()
-		};
-		let name_table_len = export_directory.number_of_names.get(LittleEndian) as _;
+		let name_table_len = options
+			.limit(
+				export_directory.number_of_names.get(LittleEndian) as _,
+				options.max_exports,
+			)
+			.ok_or(Error::LimitExceeded {
+				limit: options.max_exports,
+			})?;
+		let name_table_ptr = rva_to_ptr(export_directory.address_of_names.get(LittleEndian))?
+			.cast::<U32Bytes<LittleEndian>>();
+		unsafe { check_range(options, name_table_ptr.cast(), name_table_len * 4)? };
 		let name_table = unsafe { slice::from_raw_parts(name_table_ptr, name_table_len) };
 
-		let ordinal_table_ptr = unsafe {
-			address
-				.add(export_directory.address_of_name_ordinals.get(LittleEndian) as _)
-				.wrapping_sub(rva)
-				.cast::<u16>()
-		};
-		let ordinal_table_len = export_directory.number_of_names.get(LittleEndian) as _;
+		let ordinal_table_ptr =
+			rva_to_ptr(export_directory.address_of_name_ordinals.get(LittleEndian))?
+				.cast::<U16Bytes<LittleEndian>>();
+		let ordinal_table_len = name_table_len;
+		unsafe { check_range(options, ordinal_table_ptr.cast(), ordinal_table_len * 2)? };
 		let ordinal_table = unsafe { slice::from_raw_parts(ordinal_table_ptr, ordinal_table_len) };
 
-		Self {
+		Ok(Self {
 			export_directory,
 			address_table,
 			name_table,
 			ordinal_table,
 			start_address: address,
+			rva,
 			size,
-		}
+		})
+	}
+
+	/// Span of the export directory structure in the image at `image_base`.
+	pub fn directory_span(&self, image_base: *const u8) -> Option<FieldSpan> {
+		FieldSpan::of(image_base, self.export_directory)
+	}
+
+	pub fn address_table_span(&self, image_base: *const u8) -> Option<FieldSpan> {
+		FieldSpan::of(image_base, self.address_table)
+	}
+
+	pub fn name_table_span(&self, image_base: *const u8) -> Option<FieldSpan> {
+		FieldSpan::of(image_base, self.name_table)
+	}
+
+	pub fn ordinal_table_span(&self, image_base: *const u8) -> Option<FieldSpan> {
+		FieldSpan::of(image_base, self.ordinal_table)
+	}
+
+	/// Ordinals here are biased by `Base`, the values import thunks and `GetProcAddress` use.
+	/// The `index` APIs take raw indices into `address_table` instead.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn ordinal_base(&self) -> u32 {
+		self.export_directory.base.get(LittleEndian)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn ordinal_to_index(&self, ordinal: u32) -> Option<usize> {
+		let index = ordinal.checked_sub(self.ordinal_base())? as usize;
+		(index < self.address_table.len()).then_some(index)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn index_to_ordinal(&self, index: usize) -> u32 {
+		self.ordinal_base().wrapping_add(index as u32)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn rva_by_index(&self, index: usize) -> Option<u32> {
+		self.address_table
+			.get(index)
+			.map(|rva| rva.get(LittleEndian))
 	}
 
 	#[cfg_attr(feature = "debug", inline(never))]
-	pub fn iter_name_ord(&self) -> impl Iterator<Item = (u32, u16)> + '_ {
+	pub fn rva_by_ordinal(&self, ordinal: u32) -> Option<u32> {
+		self.rva_by_index(self.ordinal_to_index(ordinal)?)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn address_by_ordinal(&self, image_base: *const u8, ordinal: u32) -> Option<*const u8> {
+		self.rva_by_ordinal(ordinal)
+			.map(|rva| image_base.wrapping_add(rva as _))
+	}
+
+	/// Name RVAs with the raw `address_table` index from the name ordinal table.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn iter_name_index(&self) -> impl Iterator<Item = (u32, u16)> + '_ {
 		self.name_table
 			.iter()
-			.copied()
-			.zip(self.ordinal_table.iter().copied())
+			.map(|name_rva| name_rva.get(LittleEndian))
+			.zip(
+				self.ordinal_table
+					.iter()
+					.map(|index| index.get(LittleEndian)),
+			)
+	}
+
+	/// Name RVAs with their biased ordinal.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn iter_name_ord(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+		self.iter_name_index()
+			.map(|(name_rva, index)| (name_rva, self.index_to_ordinal(index as _)))
 	}
 
+	/// Trusts the name table and skips names whose ordinal is past the address table, see
+	/// [`ExportTable::iter_string_addr_checked`] for images that may be corrupt.
 	#[cfg_attr(feature = "debug", inline(never))]
 	pub unsafe fn iter_string_addr(
 		&self,
 		image_base: *mut u8,
 	) -> impl Iterator<Item = (&CStr, *mut u8)> {
-		self.iter_name_ord().map(move |(name_rva, ord)| {
-			let string_ptr = unsafe { image_base.add(name_rva as _) };
+		self.iter_name_index().filter_map(move |(name_rva, index)| {
+			let address_rva = self.rva_by_index(index as _)?;
+			let string_ptr = image_base.wrapping_add(name_rva as _);
 			let string = unsafe { CStr::from_ptr(string_ptr as _) };
-			let address_rva = unsafe { *self.address_table.get_unchecked(ord as usize) };
-			let address = unsafe { image_base.add(address_rva as _) };
-			(string, address)
+			let address = image_base.wrapping_add(address_rva as _);
+			Some((string, address))
 		})
 	}
+
+	/// [`ExportTable::iter_string_addr`] with names read through [`PeHeaders::export_name`] and
+	/// name ordinals checked against the address table, yielding an error per bad entry.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn iter_string_addr_checked<'a, Nt: NtHeaders>(
+		&'a self,
+		headers: &'a PeHeaders<Nt>,
+		image_base: *mut u8,
+	) -> impl Iterator<Item = Result<(&'static CStr, *mut u8)>> + 'a {
+		self.iter_name_index().map(move |(name_rva, index)| {
+			let string = unsafe { headers.export_name(image_base, name_rva)? };
+			let address_rva = self.rva_by_index(index as _).ok_or(Error::ExportTable)?;
+			Ok((string, image_base.wrapping_add(address_rva as _)))
+		})
+	}
+
+	/// Binary search over the name table, which linkers sort and `GetProcAddress` relies on.
+	/// Names are read through [`PeHeaders::export_name`], one it rejects sorts before the rest.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn find_by_name<Nt: NtHeaders>(
+		&self,
+		headers: &PeHeaders<Nt>,
+		image_base: *mut u8,
+		name: &[u8],
+	) -> Option<*mut u8> {
+		let position = self
+			.name_table
+			.binary_search_by(|name_rva| {
+				match unsafe { headers.export_name(image_base, name_rva.get(LittleEndian)) } {
+					Ok(string) => string.to_bytes().cmp(name),
+					Err(_) => Ordering::Less,
+				}
+			})
+			.ok()?;
+		let index = self.ordinal_table.get(position)?.get(LittleEndian);
+		self.rva_by_index(index as _)
+			.map(|rva| image_base.wrapping_add(rva as _))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn find_by_hash<H: hash::ExportHasher>(
+		&self,
+		image_base: *mut u8,
+		hash: u32,
+	) -> Option<*mut u8> {
+		self.iter_name_index()
+			.find(|&(name_rva, _)| {
+				let string_ptr = image_base.wrapping_add(name_rva as _);
+				let name = unsafe { CStr::from_ptr(string_ptr.cast()) };
+				H::hash(name.to_bytes().iter().copied()) == hash
+			})
+			.and_then(|(_, index)| self.rva_by_index(index as _))
+			.map(|rva| image_base.wrapping_add(rva as _))
+	}
+
+	/// Named export with the greatest RVA not past `address` in the same section, and the offset
+	/// of `address` from it. Forwarders are skipped; `None` for an address outside the sections
+	/// of the image mapped at `image_base`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn symbolize<Nt: NtHeaders>(
+		&self,
+		headers: &PeHeaders<Nt>,
+		image_base: *const u8,
+		address: *const u8,
+	) -> Option<(&'static CStr, usize)> {
+		let target = (address as usize).checked_sub(image_base as usize)?;
+		let target = u32::try_from(target).ok()?;
+		let section = headers.section_for_rva(target)?;
+		let (name_rva, rva) = self
+			.iter_name_index()
+			.filter_map(|(name_rva, index)| Some((name_rva, self.rva_by_index(index as _)?)))
+			.filter(|&(_, rva)| rva != 0 && rva.wrapping_sub(self.rva) >= self.size)
+			.filter(|&(_, rva)| rva <= target && section::section_contains_rva(section, rva))
+			.max_by_key(|&(_, rva)| rva)?;
+		let name = unsafe { headers.export_name(image_base, name_rva) }.ok()?;
+		Some((name, (target - rva) as usize))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn export_kind<Nt: NtHeaders>(&self, headers: &PeHeaders<Nt>, rva: u32) -> ExportKind {
+		if rva >= self.rva && rva - self.rva < self.size {
+			return ExportKind::Forwarder;
+		}
+		match headers.section_for_rva(rva) {
+			Some(section) if section::section_is_executable(section) => ExportKind::Code,
+			Some(_) => ExportKind::Data,
+			None => ExportKind::Unknown,
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn iter_string_addr_kind<'a>(
+		&'a self,
+		image_base: *mut u8,
+		headers: &'a PeHeaders,
+	) -> impl Iterator<Item = (&'a CStr, *mut u8, ExportKind)> {
+		unsafe { self.iter_string_addr(image_base) }.map(move |(string, address)| {
+			let rva = (address as usize).wrapping_sub(image_base as usize) as u32;
+			(string, address, self.export_kind(headers, rva))
+		})
+	}
+
+	/// Every export of the image at `image_base` in ordinal order, once per name and once for
+	/// each function exported by ordinal only. Unused slots of the address table are skipped, as
+	/// are names and forwarders [`PeHeaders::export_name`] cannot read.
+	#[cfg(feature = "alloc")]
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn exports<'a, Nt: NtHeaders>(
+		&'a self,
+		headers: &'a PeHeaders<Nt>,
+		image_base: *const u8,
+	) -> impl Iterator<Item = Export> + 'a {
+		let mut named = alloc::vec![false; self.address_table.len()];
+		let mut entries = alloc::vec::Vec::with_capacity(self.address_table.len());
+		for (name_rva, index) in self.iter_name_index() {
+			let Some(is_named) = named.get_mut(index as usize) else {
+				continue;
+			};
+			let Ok(name) = (unsafe { headers.export_name(image_base, name_rva) }) else {
+				continue;
+			};
+			*is_named = true;
+			entries.push((index as usize, Some(name)));
+		}
+		entries.extend(
+			named
+				.iter()
+				.enumerate()
+				.filter(|&(_, &is_named)| !is_named)
+				.map(|(index, _)| (index, None)),
+		);
+		entries.sort_by_key(|&(index, _)| index);
+		entries.into_iter().filter_map(move |(index, name)| {
+			let rva = self.rva_by_index(index)?;
+			// Unused ordinals in the middle of the table are zero.
+			if rva == 0 {
+				return None;
+			}
+			let target = if rva.wrapping_sub(self.rva) < self.size {
+				ExportTarget::Forwarder(unsafe { headers.export_name(image_base, rva) }.ok()?)
+			} else {
+				ExportTarget::Address(image_base.wrapping_add(rva as _))
+			};
+			Some(Export {
+				name,
+				ordinal: self.index_to_ordinal(index) as u16,
+				rva,
+				target,
+			})
+		})
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportTarget {
+	Address(*const u8),
+	/// `module.name` or `module.#ordinal` string stored in the export directory.
+	Forwarder(&'static CStr),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Export {
+	/// `None` for a function exported by ordinal only.
+	pub name: Option<&'static CStr>,
+	/// Biased by `Base` and truncated to the 16 bits import thunks carry.
+	pub ordinal: u16,
+	pub rva: u32,
+	pub target: ExportTarget,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportKind {
+	Code,
+	Data,
+	Forwarder,
+	/// The RVA is not covered by any section header.
+	Unknown,
 }
 
 pub struct ImportTable {
 	pub import_descriptors: &'static [ImageImportDescriptor],
 }
 
+/// Like the loader, a descriptor without `Name` or `FirstThunk` ends the table, which covers the
+/// all-zero terminator.
+pub(crate) fn is_import_terminator(descriptor: &ImageImportDescriptor) -> bool {
+	descriptor.name.get(LittleEndian) == 0 || descriptor.first_thunk.get(LittleEndian) == 0
+}
+
 impl ImportTable {
+	/// Descriptors up to the terminator, at most as many as fit in `size`.
 	#[cfg_attr(feature = "debug", inline(never))]
 	pub fn parse(address: *const u8, size: usize) -> Self {
-		let number_of_entries = size / size_of::<ImageImportDescriptor>() - 1;
+		let max_entries = size / size_of::<ImageImportDescriptor>();
+		let import_descriptor_ptr = address.cast::<ImageImportDescriptor>();
+		let all_descriptors = unsafe { slice::from_raw_parts(import_descriptor_ptr, max_entries) };
+		let number_of_entries = all_descriptors
+			.iter()
+			.position(is_import_terminator)
+			.unwrap_or(max_entries);
+
+		Self {
+			import_descriptors: &all_descriptors[..number_of_entries],
+		}
+	}
+
+	/// Descriptors up to the terminator, which may lie past `size` when the directory size is
+	/// wrong. More than [`ParseOptions::max_import_descriptors`] fail with
+	/// [`Error::LimitExceeded`], or end the table when lenient.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse_with(
+		address: *const u8,
+		size: usize,
+		options: &ParseOptions,
+	) -> Result<Self> {
 		let import_descriptor_ptr = address.cast::<ImageImportDescriptor>();
+		let in_directory = size / size_of::<ImageImportDescriptor>();
+		let mut number_of_entries = 0;
+		loop {
+			if number_of_entries >= options.max_import_descriptors {
+				options
+					.limit(number_of_entries + 1, options.max_import_descriptors)
+					.ok_or(Error::LimitExceeded {
+						limit: options.max_import_descriptors,
+					})?;
+				break;
+			}
+			let descriptor_ptr = import_descriptor_ptr.wrapping_add(number_of_entries);
+			if number_of_entries >= in_directory {
+				unsafe {
+					check_range(
+						options,
+						descriptor_ptr.cast(),
+						size_of::<ImageImportDescriptor>(),
+					)?
+				};
+			}
+			if is_import_terminator(unsafe { &*descriptor_ptr }) {
+				break;
+			}
+			number_of_entries += 1;
+		}
 		let import_descriptors =
 			unsafe { slice::from_raw_parts(import_descriptor_ptr, number_of_entries) };
 
-		Self { import_descriptors }
+		Ok(Self { import_descriptors })
+	}
+
+	/// Span of the descriptors including the null terminator.
+	pub fn span(&self, image_base: *const u8) -> Option<FieldSpan> {
+		let span = FieldSpan::of(image_base, self.import_descriptors)?;
+		Some(FieldSpan {
+			len: span.len + size_of::<ImageImportDescriptor>(),
+			..span
+		})
 	}
 }
 
@@ -232,56 +1396,172 @@ pub struct DebugTable {
 }
 
 impl DebugTable {
+	/// The debug directory has no terminator, every whole entry in `size` counts.
 	#[cfg_attr(feature = "debug", inline(never))]
 	pub fn parse(address: *const u8, size: usize) -> Self {
-		let number_of_entries = size / size_of::<ImageDebugDirectory>() - 1;
+		let number_of_entries = size / size_of::<ImageDebugDirectory>();
 		let debug_descriptor_ptr = address.cast::<ImageDebugDirectory>();
 		let debug_descriptors =
 			unsafe { slice::from_raw_parts(debug_descriptor_ptr, number_of_entries) };
 
 		Self { debug_descriptors }
 	}
+
+	pub fn span(&self, image_base: *const u8) -> Option<FieldSpan> {
+		FieldSpan::of(image_base, self.debug_descriptors)
+	}
 }
 
-pub struct TlsDir {
-	pub tls_dir: &'static ImageTlsDirectory64,
+pub struct TlsDir<T: TlsDirectory = nt::NativeTlsDirectory> {
+	pub tls_dir: &'static T,
 }
 
-impl TlsDir {
+impl<T: TlsDirectory> TlsDir<T> {
 	#[cfg_attr(feature = "debug", inline(never))]
 	pub fn parse(address: *const u8) -> Self {
-		#[cfg(target_arch = "x86_64")]
-		let tls_dir = unsafe { &*address.cast::<pe::ImageTlsDirectory64>() };
-		#[cfg(target_arch = "x86")]
-		let tls_dir = unsafe { &*address.cast::<pe::ImageTlsDirectory32>() };
+		let tls_dir = unsafe { &*address.cast::<T>() };
 
 		Self { tls_dir }
 	}
 
+	pub fn span(&self, image_base: *const u8) -> Option<FieldSpan> {
+		FieldSpan::of(image_base, self.tls_dir)
+	}
+
+	/// Callback VAs of an image mapped at `image_base` whose VAs are relative to `loaded_base`,
+	/// which differ for a copy read out of another process.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn callback_addresses(
+		&self,
+		image_base: *const u8,
+		loaded_base: u64,
+	) -> TlsCallbackAddresses<T> {
+		self.callback_addresses_in(RvaMap::mapped(image_base), loaded_base)
+	}
+
+	/// [`TlsDir::callback_addresses`] of an image laid out as `headers` were parsed, every slot
+	/// is checked against their region.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn callback_addresses_with<Nt: NtHeaders>(
+		&self,
+		headers: &PeHeaders<Nt>,
+		image_base: *const u8,
+		loaded_base: u64,
+	) -> TlsCallbackAddresses<T> {
+		self.callback_addresses_in(headers.rva_map(image_base), loaded_base)
+	}
+
+	fn callback_addresses_in(&self, map: RvaMap, loaded_base: u64) -> TlsCallbackAddresses<T> {
+		let va = self.tls_dir.address_of_call_backs();
+		TlsCallbackAddresses {
+			map,
+			// A VA outside the image yields an error on the first call.
+			rva: match va {
+				0 => None,
+				_ => Some(u32::try_from(va.wrapping_sub(loaded_base)).unwrap_or(u32::MAX)),
+			},
+			remaining: DEFAULT_MAX_TLS_CALLBACKS,
+			limit: DEFAULT_MAX_TLS_CALLBACKS,
+			marker: core::marker::PhantomData,
+		}
+	}
+}
+
+impl TlsDir {
+	/// Callbacks of an image loaded into this process, as function pointers.
 	#[cfg_attr(feature = "debug", inline(never))]
 	pub fn callbacks(&self) -> TlsCallbacks {
-		let callback_addr =
-			self.tls_dir.address_of_call_backs.get(LittleEndian) as *const PIMAGE_TLS_CALLBACK;
-		TlsCallbacks { callback_addr }
+		let callback_addr = self.tls_dir.address_of_call_backs() as *const TlsCallbackFn;
+		TlsCallbacks {
+			callback_addr,
+			remaining: DEFAULT_MAX_TLS_CALLBACKS,
+			limit: DEFAULT_MAX_TLS_CALLBACKS,
+		}
+	}
+}
+
+/// Yields [`Error::LimitExceeded`] after [`DEFAULT_MAX_TLS_CALLBACKS`] callbacks, or the limit
+/// set with [`TlsCallbackAddresses::with_limit`], instead of reading on past a missing terminator.
+pub struct TlsCallbackAddresses<T> {
+	map: RvaMap,
+	rva: Option<u32>,
+	remaining: usize,
+	limit: usize,
+	marker: core::marker::PhantomData<T>,
+}
+
+impl<T> TlsCallbackAddresses<T> {
+	pub fn with_limit(mut self, max_callbacks: usize) -> Self {
+		self.remaining = max_callbacks;
+		self.limit = max_callbacks;
+		self
+	}
+}
+
+impl<T: TlsDirectory> Iterator for TlsCallbackAddresses<T> {
+	type Item = Result<u64>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let rva = self.rva.take()?;
+		let slot = self.map.ptr(rva).and_then(|slot| {
+			unsafe { self.map.check(slot, T::POINTER_SIZE)? };
+			Ok(slot)
+		});
+		let slot = match slot {
+			Ok(slot) => slot,
+			Err(err) => return Some(Err(err)),
+		};
+		let ret = unsafe { nt::read_va::<T>(slot) };
+		if ret == 0 {
+			return None;
+		}
+		if self.remaining == 0 {
+			return Some(Err(Error::LimitExceeded { limit: self.limit }));
+		}
+		self.remaining -= 1;
+		self.rva = rva.checked_add(T::POINTER_SIZE as u32);
+		Some(Ok(ret))
 	}
 }
 
+/// Limited like [`TlsCallbackAddresses`].
 pub struct TlsCallbacks {
-	callback_addr: *const PIMAGE_TLS_CALLBACK,
+	callback_addr: *const TlsCallbackFn,
+	remaining: usize,
+	limit: usize,
+}
+
+impl TlsCallbacks {
+	pub fn with_limit(mut self, max_callbacks: usize) -> Self {
+		self.remaining = max_callbacks;
+		self.limit = max_callbacks;
+		self
+	}
 }
 
 type TlsCallback = unsafe extern "system" fn(
-	dllhandle: *mut std::ffi::c_void,
+	dllhandle: *mut core::ffi::c_void,
 	reason: u32,
-	reserved: *mut std::ffi::c_void,
+	reserved: *mut core::ffi::c_void,
 );
 
+/// An entry of the TLS callback array, `None` for the terminating null.
+pub type TlsCallbackFn = Option<TlsCallback>;
+
 impl Iterator for TlsCallbacks {
-	type Item = TlsCallback;
+	type Item = Result<TlsCallback>;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		let ret = unsafe { *self.callback_addr };
-		unsafe { self.callback_addr.add(1) };
-		ret
+		if self.callback_addr.is_null() {
+			return None;
+		}
+		let ret = unsafe { *self.callback_addr }?;
+		if self.remaining == 0 {
+			self.callback_addr = core::ptr::null();
+			return Some(Err(Error::LimitExceeded { limit: self.limit }));
+		}
+		self.remaining -= 1;
+		self.callback_addr = unsafe { self.callback_addr.add(1) };
+		Some(Ok(ret))
 	}
 }
@@ -0,0 +1,163 @@
+use crate::{nt::NtHeaders, offsets, section, PeHeaders};
+use core::mem::size_of;
+use object::{pe::IMAGE_FILE_EXECUTABLE_IMAGE, read::pe::ImageOptionalHeader, LittleEndian};
+
+/// Smallest page size of any Windows target, below it images use the low alignment rules.
+pub const PAGE_SIZE: u32 = 0x1000;
+/// Images are mapped at 64 KiB allocation granularity.
+pub const IMAGE_BASE_ALIGNMENT: u64 = 0x10000;
+/// Section limit enforced by the loader before Windows 10.
+pub const MAX_SECTIONS: usize = 96;
+/// `MM_SIZE_OF_LARGEST_IMAGE`.
+pub const MAX_SIZE_OF_IMAGE: u32 = 0x7700_0000;
+
+/// A rule of the kernel image mapper or ntdll that makes Windows refuse to load an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoaderRule {
+	/// `IMAGE_FILE_EXECUTABLE_IMAGE` is not set.
+	NotExecutable,
+	/// More than [`MAX_SECTIONS`] sections.
+	TooManySections,
+	/// `SizeOfOptionalHeader` is smaller than the fixed part of the optional header.
+	SizeOfOptionalHeader,
+	/// `FileAlignment` is not a power of two, or below 512 without matching `SectionAlignment`.
+	FileAlignment,
+	/// `SectionAlignment` is smaller than `FileAlignment`, or differs from it below a page.
+	SectionAlignment,
+	/// `ImageBase` is not 64 KiB aligned.
+	ImageBase,
+	/// `SizeOfHeaders` is zero, does not cover the section table or exceeds `SizeOfImage`.
+	SizeOfHeaders,
+	/// `SizeOfImage` is zero, larger than [`MAX_SIZE_OF_IMAGE`] or does not cover the sections.
+	SizeOfImage,
+	/// A section does not start where the previous one ended, rounded to `SectionAlignment`.
+	SectionLayout,
+	/// With low alignment, a section's raw data does not sit at its virtual address.
+	SectionRawData,
+	/// `AddressOfEntryPoint` is past `SizeOfImage`.
+	EntryPoint,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeOfImageMismatch {
+	pub declared: u32,
+	pub computed: u32,
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Checks the headers the way the loader does when creating an image section, returning the
+	/// first rule that would make `NtCreateSection`/`LoadLibrary` fail.
+	///
+	/// Only the headers are looked at, raw data past the end of the file is not detected.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn check_loader_rules(&self) -> Result<(), LoaderRule> {
+		let file_header = self.nt_header.file_header();
+		let optional_header = self.nt_header.optional_header();
+
+		if file_header.characteristics.get(LittleEndian) & IMAGE_FILE_EXECUTABLE_IMAGE == 0 {
+			return Err(LoaderRule::NotExecutable);
+		}
+		let number_of_sections = file_header.number_of_sections.get(LittleEndian) as usize;
+		if number_of_sections > MAX_SECTIONS {
+			return Err(LoaderRule::TooManySections);
+		}
+		if (file_header.size_of_optional_header.get(LittleEndian) as usize)
+			< size_of::<Nt::ImageOptionalHeader>()
+		{
+			return Err(LoaderRule::SizeOfOptionalHeader);
+		}
+
+		let file_alignment = optional_header.file_alignment();
+		let section_alignment = optional_header.section_alignment();
+		let low_alignment = section_alignment < PAGE_SIZE;
+		if !file_alignment.is_power_of_two()
+			|| (file_alignment < 0x200 && file_alignment != section_alignment)
+		{
+			return Err(LoaderRule::FileAlignment);
+		}
+		if section_alignment < file_alignment
+			|| (low_alignment && file_alignment != section_alignment)
+		{
+			return Err(LoaderRule::SectionAlignment);
+		}
+		if !optional_header
+			.image_base()
+			.is_multiple_of(IMAGE_BASE_ALIGNMENT)
+		{
+			return Err(LoaderRule::ImageBase);
+		}
+
+		let size_of_headers = optional_header.size_of_headers();
+		let size_of_image = optional_header.size_of_image();
+		let nt_headers_offset =
+			offsets::nt_headers_offset(self.dos_header.e_lfanew.get(LittleEndian));
+		let section_table_end = offsets::section_table_entry_offset(
+			nt_headers_offset,
+			file_header.size_of_optional_header.get(LittleEndian) as _,
+			number_of_sections,
+		);
+		if size_of_headers == 0
+			|| (size_of_headers as usize) < section_table_end
+			|| size_of_headers > size_of_image
+		{
+			return Err(LoaderRule::SizeOfHeaders);
+		}
+		if size_of_image == 0 || size_of_image > MAX_SIZE_OF_IMAGE {
+			return Err(LoaderRule::SizeOfImage);
+		}
+
+		let mut next_va = size_of_headers
+			.checked_next_multiple_of(section_alignment)
+			.ok_or(LoaderRule::SizeOfHeaders)?;
+		for section in self.section_headers {
+			let virtual_address = section.virtual_address.get(LittleEndian);
+			if virtual_address != next_va {
+				return Err(LoaderRule::SectionLayout);
+			}
+			if low_alignment
+				&& section.size_of_raw_data.get(LittleEndian) != 0
+				&& section.pointer_to_raw_data.get(LittleEndian) != virtual_address
+			{
+				return Err(LoaderRule::SectionRawData);
+			}
+			next_va = section::section_virtual_size(section)
+				.checked_next_multiple_of(section_alignment)
+				.and_then(|size| virtual_address.checked_add(size))
+				.ok_or(LoaderRule::SectionLayout)?;
+		}
+		if size_of_image.checked_next_multiple_of(section_alignment) < Some(next_va) {
+			return Err(LoaderRule::SizeOfImage);
+		}
+
+		if optional_header.address_of_entry_point() >= size_of_image {
+			return Err(LoaderRule::EntryPoint);
+		}
+		Ok(())
+	}
+
+	/// `SizeOfImage` as a linker would set it, the end of the highest section rounded to
+	/// `SectionAlignment`, or `None` if that overflows.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn compute_size_of_image(&self) -> Option<u32> {
+		let optional_header = self.nt_header.optional_header();
+		let section_alignment = optional_header.section_alignment().max(1);
+		self.section_headers
+			.iter()
+			.try_fold(optional_header.size_of_headers(), |end, section| {
+				let section_end = section
+					.virtual_address
+					.get(LittleEndian)
+					.checked_add(section::section_virtual_size(section))?;
+				Some(end.max(section_end))
+			})?
+			.checked_next_multiple_of(section_alignment)
+	}
+
+	/// Declared and computed `SizeOfImage` if they differ, `computed` is `u32::MAX` on overflow.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn size_of_image_mismatch(&self) -> Option<SizeOfImageMismatch> {
+		let declared = self.nt_header.optional_header().size_of_image();
+		let computed = self.compute_size_of_image().unwrap_or(u32::MAX);
+		(declared != computed).then_some(SizeOfImageMismatch { declared, computed })
+	}
+}
@@ -0,0 +1,79 @@
+use crate::{diff::export_entries, nt::NtHeaders, section, PeHeaders};
+use alloc::{format, string::String};
+use core::fmt::Write;
+use object::{read::pe::ImageOptionalHeader, LittleEndian};
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Report in the layout of an MSVC linker map: the sections with their ranges, then the
+	/// exports as publics sorted by RVA. Forwarders are skipped since they have no address in
+	/// the image, ordinal-only exports are named as in [`crate::ExportTable::to_def`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn map_report(&self, image_base: *const u8, module_name: &str) -> String {
+		let optional_header = self.nt_header.optional_header();
+		let preferred_base = optional_header.image_base();
+		let mut report = String::new();
+		let _ = writeln!(report, " {module_name}\n");
+		let _ = writeln!(
+			report,
+			" Timestamp is {:08x}\n",
+			self.nt_header
+				.file_header()
+				.time_date_stamp
+				.get(LittleEndian)
+		);
+		let _ = writeln!(report, " Preferred load address is {preferred_base:016x}\n");
+
+		let _ = writeln!(
+			report,
+			" Start         Length     Name                   Class"
+		);
+		for (index, section) in self.section_headers.iter().enumerate() {
+			let _ = writeln!(
+				report,
+				" {:04x}:00000000 {:08x}H {:<22} {}",
+				index + 1,
+				section::section_virtual_size(section),
+				String::from_utf8_lossy(section::section_name_bytes(section)),
+				if section::section_is_executable(section) {
+					"CODE"
+				} else {
+					"DATA"
+				}
+			);
+		}
+
+		let _ = writeln!(
+			report,
+			"\n  Address         Publics by Value              Rva+Base"
+		);
+		let Ok(export_table) = (unsafe { self.export_table_mem(image_base) }) else {
+			return report;
+		};
+		let mut exports = unsafe { export_entries(&export_table, self, image_base) };
+		exports.retain(|entry| entry.rva.wrapping_sub(export_table.rva) >= export_table.size);
+		exports.sort_by_key(|entry| entry.rva);
+		for entry in exports {
+			// Section 0 holds addresses outside any section, relative to the image.
+			let (section_number, offset) = self
+				.section_headers
+				.iter()
+				.position(|section| section::section_contains_rva(section, entry.rva))
+				.map_or((0, entry.rva), |index| {
+					let start = self.section_headers[index]
+						.virtual_address
+						.get(LittleEndian);
+					(index + 1, entry.rva - start)
+				});
+			let name = match entry.name {
+				Some(name) => String::from_utf8_lossy(name).into_owned(),
+				None => format!("Ordinal{}", entry.ordinal),
+			};
+			let _ = writeln!(
+				report,
+				" {section_number:04x}:{offset:08x}       {name:<26} {:016x}",
+				preferred_base.wrapping_add(entry.rva as u64)
+			);
+		}
+		report
+	}
+}
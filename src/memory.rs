@@ -0,0 +1,51 @@
+use crate::error::{Error, Result};
+use core::mem::{size_of, MaybeUninit};
+use windows_sys::Win32::System::Memory::{
+	VirtualQuery, MEMORY_BASIC_INFORMATION, MEM_COMMIT, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE,
+	PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_NOACCESS, PAGE_READONLY, PAGE_READWRITE,
+	PAGE_WRITECOPY,
+};
+
+const READABLE: u32 = PAGE_READONLY
+	| PAGE_READWRITE
+	| PAGE_WRITECOPY
+	| PAGE_EXECUTE_READ
+	| PAGE_EXECUTE_READWRITE
+	| PAGE_EXECUTE_WRITECOPY;
+
+/// Checks that every page of `[address, address + len)` is committed and readable.
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn validate_range(address: *const u8, len: usize) -> Result<()> {
+	let end = (address as usize)
+		.checked_add(len)
+		.ok_or(Error::RvaOverflow)?;
+	let mut current = address as usize;
+	while current < end {
+		let mut info = MaybeUninit::<MEMORY_BASIC_INFORMATION>::zeroed();
+		let written = unsafe {
+			VirtualQuery(
+				current as _,
+				info.as_mut_ptr(),
+				size_of::<MEMORY_BASIC_INFORMATION>(),
+			)
+		};
+		let invalid = Error::InvalidMemory {
+			address: current,
+			len: end - current,
+		};
+		if written == 0 {
+			return Err(invalid);
+		}
+		let info = unsafe { info.assume_init() };
+		if info.State != MEM_COMMIT
+			|| info.Protect & (PAGE_NOACCESS | PAGE_GUARD) != 0
+			|| info.Protect & READABLE == 0
+		{
+			return Err(invalid);
+		}
+		current = (info.BaseAddress as usize)
+			.checked_add(info.RegionSize)
+			.ok_or(invalid)?;
+	}
+	Ok(())
+}
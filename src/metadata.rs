@@ -0,0 +1,502 @@
+use crate::error::{Error, Result};
+use core::ffi::CStr;
+
+pub const METADATA_SIGNATURE: u32 = 0x424A_5342;
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+	let bytes = data.get(offset..offset.checked_add(2)?)?;
+	Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+	let bytes = data.get(offset..offset.checked_add(4)?)?;
+	Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn align4(value: usize) -> Option<usize> {
+	Some(value.checked_add(3)? & !3)
+}
+
+/// Decodes an ECMA-335 compressed unsigned integer, returning it with its encoded length.
+pub fn decompress_u32(data: &[u8]) -> Option<(u32, usize)> {
+	let first = *data.first()?;
+	match first {
+		b if b & 0x80 == 0 => Some((b as u32, 1)),
+		b if b & 0xC0 == 0x80 => {
+			let second = *data.get(1)?;
+			Some(((((b & 0x3F) as u32) << 8) | second as u32, 2))
+		}
+		b if b & 0xE0 == 0xC0 => {
+			let rest = data.get(1..4)?;
+			let value = (((b & 0x1F) as u32) << 24)
+				| ((rest[0] as u32) << 16)
+				| ((rest[1] as u32) << 8)
+				| rest[2] as u32;
+			Some((value, 4))
+		}
+		_ => None,
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct MetadataStream {
+	pub name: &'static CStr,
+	pub data: &'static [u8],
+}
+
+pub struct Metadata {
+	pub data: &'static [u8],
+	pub major_version: u16,
+	pub minor_version: u16,
+	pub version: &'static [u8],
+	pub flags: u16,
+	pub number_of_streams: u16,
+	stream_headers_offset: usize,
+}
+
+impl Metadata {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(data: &'static [u8]) -> Result<Self> {
+		if read_u32(data, 0) != Some(METADATA_SIGNATURE) {
+			return Err(Error::ClrMetadata);
+		}
+		let major_version = read_u16(data, 4).ok_or(Error::ClrMetadata)?;
+		let minor_version = read_u16(data, 6).ok_or(Error::ClrMetadata)?;
+		let version_len = read_u32(data, 12).ok_or(Error::ClrMetadata)? as usize;
+		let version_end = version_len.checked_add(16).ok_or(Error::ClrMetadata)?;
+		let version = data.get(16..version_end).ok_or(Error::ClrMetadata)?;
+		let version = match version.iter().position(|&b| b == 0) {
+			Some(len) => &version[..len],
+			None => version,
+		};
+		let flags_offset = align4(version_end).ok_or(Error::ClrMetadata)?;
+		let flags = read_u16(data, flags_offset).ok_or(Error::ClrMetadata)?;
+		let number_of_streams = read_u16(data, flags_offset + 2).ok_or(Error::ClrMetadata)?;
+
+		Ok(Self {
+			data,
+			major_version,
+			minor_version,
+			version,
+			flags,
+			number_of_streams,
+			stream_headers_offset: flags_offset + 4,
+		})
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn streams(&self) -> impl Iterator<Item = MetadataStream> + '_ {
+		let mut offset = self.stream_headers_offset;
+		(0..self.number_of_streams).map_while(move |_| {
+			let stream_offset = read_u32(self.data, offset)? as usize;
+			let stream_size = read_u32(self.data, offset + 4)? as usize;
+			let name = CStr::from_bytes_until_nul(self.data.get(offset + 8..)?).ok()?;
+			offset += 8 + align4(name.to_bytes_with_nul().len())?;
+			let data = self
+				.data
+				.get(stream_offset..stream_offset.checked_add(stream_size)?)?;
+			Some(MetadataStream { name, data })
+		})
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn stream(&self, name: &str) -> Option<&'static [u8]> {
+		self.streams()
+			.find(|stream| stream.name.to_bytes() == name.as_bytes())
+			.map(|stream| stream.data)
+	}
+
+	pub fn strings(&self) -> Option<StringsHeap> {
+		self.stream("#Strings").map(StringsHeap)
+	}
+
+	pub fn user_strings(&self) -> Option<UserStringsHeap> {
+		self.stream("#US").map(UserStringsHeap)
+	}
+
+	pub fn blobs(&self) -> Option<BlobHeap> {
+		self.stream("#Blob").map(BlobHeap)
+	}
+
+	pub fn guids(&self) -> Option<GuidHeap> {
+		self.stream("#GUID").map(GuidHeap)
+	}
+
+	pub fn tables(&self) -> Result<TablesStream> {
+		let data = self
+			.stream("#~")
+			.or_else(|| self.stream("#-"))
+			.ok_or(Error::ClrMetadata)?;
+		TablesStream::parse(data)
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct StringsHeap(pub &'static [u8]);
+
+impl StringsHeap {
+	pub fn get(&self, index: u32) -> Option<&'static CStr> {
+		CStr::from_bytes_until_nul(self.0.get(index as usize..)?).ok()
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct BlobHeap(pub &'static [u8]);
+
+impl BlobHeap {
+	pub fn get(&self, index: u32) -> Option<&'static [u8]> {
+		let data = self.0.get(index as usize..)?;
+		let (len, prefix) = decompress_u32(data)?;
+		data.get(prefix..prefix.checked_add(len as usize)?)
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct UserStringsHeap(pub &'static [u8]);
+
+impl UserStringsHeap {
+	/// Returns the UTF-16LE bytes of the string, without the trailing flag byte.
+	pub fn get(&self, index: u32) -> Option<&'static [u8]> {
+		let blob = BlobHeap(self.0).get(index)?;
+		Some(&blob[..blob.len() & !1])
+	}
+
+	#[cfg(feature = "alloc")]
+	pub fn get_string(&self, index: u32) -> Option<Result<alloc::string::String>> {
+		use crate::widestring::{to_string, units_from_bytes};
+		self.get(index)
+			.map(|bytes| to_string(units_from_bytes(bytes)))
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct GuidHeap(pub &'static [u8]);
+
+impl GuidHeap {
+	/// GUID indices are 1-based, 0 means no GUID.
+	pub fn get(&self, index: u32) -> Option<&'static [u8; 16]> {
+		let start = (index as usize).checked_sub(1)?.checked_mul(16)?;
+		self.0.get(start..start.checked_add(16)?)?.try_into().ok()
+	}
+}
+
+pub const TABLE_MODULE: usize = 0x00;
+pub const TABLE_TYPE_REF: usize = 0x01;
+pub const TABLE_TYPE_DEF: usize = 0x02;
+pub const TABLE_FIELD_PTR: usize = 0x03;
+pub const TABLE_FIELD: usize = 0x04;
+pub const TABLE_METHOD_PTR: usize = 0x05;
+pub const TABLE_METHOD_DEF: usize = 0x06;
+pub const TABLE_PARAM_PTR: usize = 0x07;
+pub const TABLE_PARAM: usize = 0x08;
+pub const TABLE_INTERFACE_IMPL: usize = 0x09;
+pub const TABLE_MEMBER_REF: usize = 0x0A;
+pub const TABLE_CONSTANT: usize = 0x0B;
+pub const TABLE_CUSTOM_ATTRIBUTE: usize = 0x0C;
+pub const TABLE_FIELD_MARSHAL: usize = 0x0D;
+pub const TABLE_DECL_SECURITY: usize = 0x0E;
+pub const TABLE_CLASS_LAYOUT: usize = 0x0F;
+pub const TABLE_FIELD_LAYOUT: usize = 0x10;
+pub const TABLE_STAND_ALONE_SIG: usize = 0x11;
+pub const TABLE_EVENT_MAP: usize = 0x12;
+pub const TABLE_EVENT_PTR: usize = 0x13;
+pub const TABLE_EVENT: usize = 0x14;
+pub const TABLE_PROPERTY_MAP: usize = 0x15;
+pub const TABLE_PROPERTY_PTR: usize = 0x16;
+pub const TABLE_PROPERTY: usize = 0x17;
+pub const TABLE_METHOD_SEMANTICS: usize = 0x18;
+pub const TABLE_METHOD_IMPL: usize = 0x19;
+pub const TABLE_MODULE_REF: usize = 0x1A;
+pub const TABLE_TYPE_SPEC: usize = 0x1B;
+pub const TABLE_IMPL_MAP: usize = 0x1C;
+pub const TABLE_FIELD_RVA: usize = 0x1D;
+pub const TABLE_ENC_LOG: usize = 0x1E;
+pub const TABLE_ENC_MAP: usize = 0x1F;
+pub const TABLE_ASSEMBLY: usize = 0x20;
+pub const TABLE_ASSEMBLY_PROCESSOR: usize = 0x21;
+pub const TABLE_ASSEMBLY_OS: usize = 0x22;
+pub const TABLE_ASSEMBLY_REF: usize = 0x23;
+pub const TABLE_ASSEMBLY_REF_PROCESSOR: usize = 0x24;
+pub const TABLE_ASSEMBLY_REF_OS: usize = 0x25;
+pub const TABLE_FILE: usize = 0x26;
+pub const TABLE_EXPORTED_TYPE: usize = 0x27;
+pub const TABLE_MANIFEST_RESOURCE: usize = 0x28;
+pub const TABLE_NESTED_CLASS: usize = 0x29;
+pub const TABLE_GENERIC_PARAM: usize = 0x2A;
+pub const TABLE_METHOD_SPEC: usize = 0x2B;
+pub const TABLE_GENERIC_PARAM_CONSTRAINT: usize = 0x2C;
+
+const NUM_TABLES: usize = 64;
+// Tables missing from the spec count as empty when sizing coded indices.
+const NONE: usize = NUM_TABLES - 1;
+
+#[derive(Clone, Copy)]
+enum Column {
+	Fixed(usize),
+	String,
+	Guid,
+	Blob,
+	Table(usize),
+	Coded(&'static [usize]),
+}
+
+const TYPE_DEF_OR_REF: &[usize] = &[TABLE_TYPE_DEF, TABLE_TYPE_REF, TABLE_TYPE_SPEC];
+const HAS_CONSTANT: &[usize] = &[TABLE_FIELD, TABLE_PARAM, TABLE_PROPERTY];
+const HAS_CUSTOM_ATTRIBUTE: &[usize] = &[
+	TABLE_METHOD_DEF,
+	TABLE_FIELD,
+	TABLE_TYPE_REF,
+	TABLE_TYPE_DEF,
+	TABLE_PARAM,
+	TABLE_INTERFACE_IMPL,
+	TABLE_MEMBER_REF,
+	TABLE_MODULE,
+	TABLE_DECL_SECURITY,
+	TABLE_PROPERTY,
+	TABLE_EVENT,
+	TABLE_STAND_ALONE_SIG,
+	TABLE_MODULE_REF,
+	TABLE_TYPE_SPEC,
+	TABLE_ASSEMBLY,
+	TABLE_ASSEMBLY_REF,
+	TABLE_FILE,
+	TABLE_EXPORTED_TYPE,
+	TABLE_MANIFEST_RESOURCE,
+	TABLE_GENERIC_PARAM,
+	TABLE_GENERIC_PARAM_CONSTRAINT,
+	TABLE_METHOD_SPEC,
+];
+const HAS_FIELD_MARSHAL: &[usize] = &[TABLE_FIELD, TABLE_PARAM];
+const HAS_DECL_SECURITY: &[usize] = &[TABLE_TYPE_DEF, TABLE_METHOD_DEF, TABLE_ASSEMBLY];
+const MEMBER_REF_PARENT: &[usize] = &[
+	TABLE_TYPE_DEF,
+	TABLE_TYPE_REF,
+	TABLE_MODULE_REF,
+	TABLE_METHOD_DEF,
+	TABLE_TYPE_SPEC,
+];
+const HAS_SEMANTICS: &[usize] = &[TABLE_EVENT, TABLE_PROPERTY];
+const METHOD_DEF_OR_REF: &[usize] = &[TABLE_METHOD_DEF, TABLE_MEMBER_REF];
+const MEMBER_FORWARDED: &[usize] = &[TABLE_FIELD, TABLE_METHOD_DEF];
+const IMPLEMENTATION: &[usize] = &[TABLE_FILE, TABLE_ASSEMBLY_REF, TABLE_EXPORTED_TYPE];
+const CUSTOM_ATTRIBUTE_TYPE: &[usize] = &[NONE, NONE, TABLE_METHOD_DEF, TABLE_MEMBER_REF, NONE];
+const RESOLUTION_SCOPE: &[usize] = &[
+	TABLE_MODULE,
+	TABLE_MODULE_REF,
+	TABLE_ASSEMBLY_REF,
+	TABLE_TYPE_REF,
+];
+const TYPE_OR_METHOD_DEF: &[usize] = &[TABLE_TYPE_DEF, TABLE_METHOD_DEF];
+
+const fn table_schema(table: usize) -> &'static [Column] {
+	use Column::*;
+	match table {
+		TABLE_MODULE => &[Fixed(2), String, Guid, Guid, Guid],
+		TABLE_TYPE_REF => &[Coded(RESOLUTION_SCOPE), String, String],
+		TABLE_TYPE_DEF => &[
+			Fixed(4),
+			String,
+			String,
+			Coded(TYPE_DEF_OR_REF),
+			Table(TABLE_FIELD),
+			Table(TABLE_METHOD_DEF),
+		],
+		TABLE_FIELD_PTR => &[Table(TABLE_FIELD)],
+		TABLE_FIELD => &[Fixed(2), String, Blob],
+		TABLE_METHOD_PTR => &[Table(TABLE_METHOD_DEF)],
+		TABLE_METHOD_DEF => &[
+			Fixed(4),
+			Fixed(2),
+			Fixed(2),
+			String,
+			Blob,
+			Table(TABLE_PARAM),
+		],
+		TABLE_PARAM_PTR => &[Table(TABLE_PARAM)],
+		TABLE_PARAM => &[Fixed(2), Fixed(2), String],
+		TABLE_INTERFACE_IMPL => &[Table(TABLE_TYPE_DEF), Coded(TYPE_DEF_OR_REF)],
+		TABLE_MEMBER_REF => &[Coded(MEMBER_REF_PARENT), String, Blob],
+		TABLE_CONSTANT => &[Fixed(2), Coded(HAS_CONSTANT), Blob],
+		TABLE_CUSTOM_ATTRIBUTE => &[
+			Coded(HAS_CUSTOM_ATTRIBUTE),
+			Coded(CUSTOM_ATTRIBUTE_TYPE),
+			Blob,
+		],
+		TABLE_FIELD_MARSHAL => &[Coded(HAS_FIELD_MARSHAL), Blob],
+		TABLE_DECL_SECURITY => &[Fixed(2), Coded(HAS_DECL_SECURITY), Blob],
+		TABLE_CLASS_LAYOUT => &[Fixed(2), Fixed(4), Table(TABLE_TYPE_DEF)],
+		TABLE_FIELD_LAYOUT => &[Fixed(4), Table(TABLE_FIELD)],
+		TABLE_STAND_ALONE_SIG => &[Blob],
+		TABLE_EVENT_MAP => &[Table(TABLE_TYPE_DEF), Table(TABLE_EVENT)],
+		TABLE_EVENT_PTR => &[Table(TABLE_EVENT)],
+		TABLE_EVENT => &[Fixed(2), String, Coded(TYPE_DEF_OR_REF)],
+		TABLE_PROPERTY_MAP => &[Table(TABLE_TYPE_DEF), Table(TABLE_PROPERTY)],
+		TABLE_PROPERTY_PTR => &[Table(TABLE_PROPERTY)],
+		TABLE_PROPERTY => &[Fixed(2), String, Blob],
+		TABLE_METHOD_SEMANTICS => &[Fixed(2), Table(TABLE_METHOD_DEF), Coded(HAS_SEMANTICS)],
+		TABLE_METHOD_IMPL => &[
+			Table(TABLE_TYPE_DEF),
+			Coded(METHOD_DEF_OR_REF),
+			Coded(METHOD_DEF_OR_REF),
+		],
+		TABLE_MODULE_REF => &[String],
+		TABLE_TYPE_SPEC => &[Blob],
+		TABLE_IMPL_MAP => &[
+			Fixed(2),
+			Coded(MEMBER_FORWARDED),
+			String,
+			Table(TABLE_MODULE_REF),
+		],
+		TABLE_FIELD_RVA => &[Fixed(4), Table(TABLE_FIELD)],
+		TABLE_ENC_LOG => &[Fixed(4), Fixed(4)],
+		TABLE_ENC_MAP => &[Fixed(4)],
+		TABLE_ASSEMBLY => &[
+			Fixed(4),
+			Fixed(2),
+			Fixed(2),
+			Fixed(2),
+			Fixed(2),
+			Fixed(4),
+			Blob,
+			String,
+			String,
+		],
+		TABLE_ASSEMBLY_PROCESSOR => &[Fixed(4)],
+		TABLE_ASSEMBLY_OS => &[Fixed(4), Fixed(4), Fixed(4)],
+		TABLE_ASSEMBLY_REF => &[
+			Fixed(2),
+			Fixed(2),
+			Fixed(2),
+			Fixed(2),
+			Fixed(4),
+			Blob,
+			String,
+			String,
+			Blob,
+		],
+		TABLE_ASSEMBLY_REF_PROCESSOR => &[Fixed(4), Table(TABLE_ASSEMBLY_REF)],
+		TABLE_ASSEMBLY_REF_OS => &[Fixed(4), Fixed(4), Fixed(4), Table(TABLE_ASSEMBLY_REF)],
+		TABLE_FILE => &[Fixed(4), String, Blob],
+		TABLE_EXPORTED_TYPE => &[Fixed(4), Fixed(4), String, String, Coded(IMPLEMENTATION)],
+		TABLE_MANIFEST_RESOURCE => &[Fixed(4), Fixed(4), String, Coded(IMPLEMENTATION)],
+		TABLE_NESTED_CLASS => &[Table(TABLE_TYPE_DEF), Table(TABLE_TYPE_DEF)],
+		TABLE_GENERIC_PARAM => &[Fixed(2), Fixed(2), Coded(TYPE_OR_METHOD_DEF), String],
+		TABLE_METHOD_SPEC => &[Coded(METHOD_DEF_OR_REF), Blob],
+		TABLE_GENERIC_PARAM_CONSTRAINT => &[Table(TABLE_GENERIC_PARAM), Coded(TYPE_DEF_OR_REF)],
+		_ => &[],
+	}
+}
+
+const HEAP_STRING_WIDE: u8 = 0x01;
+const HEAP_GUID_WIDE: u8 = 0x02;
+const HEAP_BLOB_WIDE: u8 = 0x04;
+const HEAP_EXTRA_DATA: u8 = 0x40;
+
+/// The `#~` (or uncompressed `#-`) table stream, decoded just enough to address rows.
+pub struct TablesStream {
+	pub data: &'static [u8],
+	pub heap_sizes: u8,
+	pub valid: u64,
+	pub row_counts: [u32; NUM_TABLES],
+	table_offsets: [usize; NUM_TABLES],
+}
+
+impl TablesStream {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(data: &'static [u8]) -> Result<Self> {
+		let heap_sizes = *data.get(6).ok_or(Error::ClrMetadata)?;
+		let valid_bytes = data.get(8..16).ok_or(Error::ClrMetadata)?;
+		let valid = u64::from_le_bytes(valid_bytes.try_into().map_err(|_| Error::ClrMetadata)?);
+		let mut row_counts = [0; NUM_TABLES];
+		let mut offset = 24;
+		for (table, row_count) in row_counts.iter_mut().enumerate() {
+			if valid & (1 << table) != 0 {
+				*row_count = read_u32(data, offset).ok_or(Error::ClrMetadata)?;
+				offset += 4;
+			}
+		}
+		if heap_sizes & HEAP_EXTRA_DATA != 0 {
+			offset += 4;
+		}
+		let mut stream = Self {
+			data,
+			heap_sizes,
+			valid,
+			row_counts,
+			table_offsets: [0; NUM_TABLES],
+		};
+		for table in 0..NUM_TABLES {
+			stream.table_offsets[table] = offset;
+			let table_size = (stream.row_counts[table] as usize)
+				.checked_mul(stream.row_size(table))
+				.ok_or(Error::ClrMetadata)?;
+			offset = offset.checked_add(table_size).ok_or(Error::ClrMetadata)?;
+		}
+		if offset > data.len() {
+			return Err(Error::ClrMetadata);
+		}
+		Ok(stream)
+	}
+
+	fn column_size(&self, column: Column) -> usize {
+		let wide = |flag| if self.heap_sizes & flag != 0 { 4 } else { 2 };
+		match column {
+			Column::Fixed(size) => size,
+			Column::String => wide(HEAP_STRING_WIDE),
+			Column::Guid => wide(HEAP_GUID_WIDE),
+			Column::Blob => wide(HEAP_BLOB_WIDE),
+			Column::Table(table) => {
+				if self.row_counts[table] < 0x1_0000 {
+					2
+				} else {
+					4
+				}
+			}
+			Column::Coded(tables) => {
+				let tag_bits = usize::BITS - (tables.len() - 1).leading_zeros();
+				let max_rows = tables
+					.iter()
+					.map(|&table| self.row_counts[table])
+					.max()
+					.unwrap_or(0);
+				if max_rows < 1 << (16 - tag_bits) {
+					2
+				} else {
+					4
+				}
+			}
+		}
+	}
+
+	pub fn row_size(&self, table: usize) -> usize {
+		table_schema(table)
+			.iter()
+			.map(|&column| self.column_size(column))
+			.sum()
+	}
+
+	pub fn row_count(&self, table: usize) -> u32 {
+		self.row_counts.get(table).copied().unwrap_or(0)
+	}
+
+	/// Reads a column of a row, rows are 1-based like metadata tokens.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn get(&self, table: usize, row: u32, column: usize) -> Option<u32> {
+		if row == 0 || row > self.row_count(table) {
+			return None;
+		}
+		let schema = table_schema(table);
+		let column_offset: usize = schema
+			.get(..column)?
+			.iter()
+			.map(|&column| self.column_size(column))
+			.sum();
+		let offset =
+			self.table_offsets[table] + (row as usize - 1) * self.row_size(table) + column_offset;
+		match self.column_size(*schema.get(column)?) {
+			1 => self.data.get(offset).map(|&b| b as u32),
+			2 => read_u16(self.data, offset).map(u32::from),
+			_ => read_u32(self.data, offset),
+		}
+	}
+}
@@ -0,0 +1,250 @@
+use crate::{
+	error::{Error, Result},
+	hooks::ModuleRange,
+	source::MemorySource,
+	widestring,
+};
+
+pub const MINIDUMP_SIGNATURE: u32 = 0x504d_444d;
+
+pub const MODULE_LIST_STREAM: u32 = 4;
+pub const MEMORY_LIST_STREAM: u32 = 5;
+pub const MEMORY64_LIST_STREAM: u32 = 9;
+
+const HEADER_SIZE: usize = 32;
+const DIRECTORY_SIZE: usize = 12;
+const MODULE_SIZE: usize = 108;
+const MEMORY_DESCRIPTOR_SIZE: usize = 16;
+const MEMORY_DESCRIPTOR64_SIZE: usize = 16;
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+	let bytes = data.get(offset..offset.checked_add(4)?)?;
+	Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+	let bytes = data.get(offset..offset.checked_add(8)?)?;
+	Some(u64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// A `MINIDUMP_LOCATION_DESCRIPTOR`, `size` then `rva` into the dump.
+fn location(data: &'static [u8], offset: usize) -> Option<&'static [u8]> {
+	let size = read_u32(data, offset)? as usize;
+	let rva = read_u32(data, offset + 4)? as usize;
+	data.get(rva..rva.checked_add(size)?)
+}
+
+#[derive(Clone, Copy)]
+pub struct DumpModule {
+	pub base: u64,
+	pub size: u32,
+	pub checksum: u32,
+	pub time_date_stamp: u32,
+	/// The full path, as UTF-16.
+	pub name: &'static [u8],
+}
+
+impl DumpModule {
+	pub fn contains(&self, address: u64) -> bool {
+		address.wrapping_sub(self.base) < self.size as u64
+	}
+
+	/// The file name part of `name`, as UTF-16.
+	pub fn file_name(&self) -> &'static [u8] {
+		let file_name_start = self
+			.name
+			.chunks_exact(2)
+			.rposition(|unit| unit == b"\\\0" || unit == b"/\0")
+			.map_or(0, |index| (index + 1) * 2);
+		&self.name[file_name_start..]
+	}
+}
+
+impl ModuleRange for DumpModule {
+	fn base(&self) -> u64 {
+		self.base
+	}
+
+	fn size(&self) -> u32 {
+		self.size
+	}
+
+	fn has_name(&self, name: &str) -> bool {
+		widestring::eq_str_ignore_ascii_case(widestring::units_from_bytes(self.file_name()), name)
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct MemoryRange {
+	pub address: u64,
+	pub data: &'static [u8],
+}
+
+pub struct Minidump {
+	pub data: &'static [u8],
+	modules: &'static [u8],
+	memory: &'static [u8],
+	memory64: &'static [u8],
+	memory64_base: u64,
+}
+
+impl Minidump {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(data: &'static [u8]) -> Result<Self> {
+		if data.len() < HEADER_SIZE || read_u32(data, 0) != Some(MINIDUMP_SIGNATURE) {
+			return Err(Error::Minidump);
+		}
+		let number_of_streams = read_u32(data, 8).ok_or(Error::Minidump)? as usize;
+		let directory_rva = read_u32(data, 12).ok_or(Error::Minidump)? as usize;
+		let directory_len = number_of_streams
+			.checked_mul(DIRECTORY_SIZE)
+			.ok_or(Error::Minidump)?;
+		let directory = directory_rva
+			.checked_add(directory_len)
+			.and_then(|end| data.get(directory_rva..end))
+			.ok_or(Error::Minidump)?;
+
+		let mut dump = Self {
+			data,
+			modules: &[],
+			memory: &[],
+			memory64: &[],
+			memory64_base: 0,
+		};
+		for (index, entry) in directory.chunks_exact(DIRECTORY_SIZE).enumerate() {
+			let stream_type = read_u32(entry, 0).ok_or(Error::Minidump)?;
+			let stream = location(data, directory_rva + index * DIRECTORY_SIZE + 4)
+				.ok_or(Error::Minidump)?;
+			match stream_type {
+				MODULE_LIST_STREAM => {
+					dump.modules = list(stream, 4, MODULE_SIZE, read_u32(stream, 0).map(u64::from))?
+				}
+				MEMORY_LIST_STREAM => {
+					dump.memory = list(
+						stream,
+						4,
+						MEMORY_DESCRIPTOR_SIZE,
+						read_u32(stream, 0).map(u64::from),
+					)?
+				}
+				MEMORY64_LIST_STREAM => {
+					dump.memory64 =
+						list(stream, 16, MEMORY_DESCRIPTOR64_SIZE, read_u64(stream, 0))?;
+					dump.memory64_base = read_u64(stream, 8).ok_or(Error::Minidump)?;
+				}
+				_ => {}
+			}
+		}
+		Ok(dump)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn modules(&self) -> impl Iterator<Item = DumpModule> + '_ {
+		self.modules.chunks_exact(MODULE_SIZE).filter_map(|module| {
+			// `MINIDUMP_STRING`: a byte length followed by the UTF-16 buffer.
+			let name_rva = read_u32(module, 20)? as usize;
+			let name_len = read_u32(self.data, name_rva)? as usize;
+			let name_start = name_rva.checked_add(4)?;
+			Some(DumpModule {
+				base: read_u64(module, 0)?,
+				size: read_u32(module, 8)?,
+				checksum: read_u32(module, 12)?,
+				time_date_stamp: read_u32(module, 16)?,
+				name: self
+					.data
+					.get(name_start..name_start.checked_add(name_len)?)?,
+			})
+		})
+	}
+
+	/// Finds a module by its file name, ignoring the directory and ASCII case.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn find_module(&self, name: &str) -> Option<DumpModule> {
+		self.modules().find(|module| {
+			let file_name = widestring::units_from_bytes(module.file_name());
+			widestring::eq_str_ignore_ascii_case(file_name, name)
+		})
+	}
+
+	/// Ranges of `MemoryListStream` followed by those of `Memory64ListStream`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn memory_ranges(&self) -> impl Iterator<Item = MemoryRange> + '_ {
+		let memory = self
+			.memory
+			.chunks_exact(MEMORY_DESCRIPTOR_SIZE)
+			.filter_map(|descriptor| {
+				Some(MemoryRange {
+					address: read_u64(descriptor, 0)?,
+					data: location(self.data, self.offset_of(descriptor) + 8)?,
+				})
+			});
+		// Full-memory dumps store the ranges back to back from `BaseRva`.
+		let mut rva = self.memory64_base;
+		let memory64 = self
+			.memory64
+			.chunks_exact(MEMORY_DESCRIPTOR64_SIZE)
+			.map_while(move |descriptor| {
+				let address = read_u64(descriptor, 0)?;
+				let size = read_u64(descriptor, 8)?;
+				let start = usize::try_from(rva).ok()?;
+				let end = usize::try_from(rva.checked_add(size)?).ok()?;
+				rva += size;
+				Some(MemoryRange {
+					address,
+					data: self.data.get(start..end)?,
+				})
+			});
+		memory.chain(memory64)
+	}
+
+	/// The image of `module` as captured, see [`crate::source::read_image`].
+	#[cfg(feature = "alloc")]
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn module_image(&self, module: &DumpModule) -> Result<alloc::vec::Vec<u8>> {
+		crate::source::read_image(self, module.base, module.size).ok_or(Error::Minidump)
+	}
+
+	fn offset_of(&self, slice: &[u8]) -> usize {
+		slice.as_ptr() as usize - self.data.as_ptr() as usize
+	}
+}
+
+fn list(
+	stream: &'static [u8],
+	header_size: usize,
+	entry_size: usize,
+	count: Option<u64>,
+) -> Result<&'static [u8]> {
+	let len = usize::try_from(count.ok_or(Error::Minidump)?)
+		.ok()
+		.and_then(|count| count.checked_mul(entry_size))
+		.ok_or(Error::Minidump)?;
+	stream
+		.get(header_size..header_size.checked_add(len).ok_or(Error::Minidump)?)
+		.ok_or(Error::Minidump)
+}
+
+impl MemorySource for Minidump {
+	fn read(&self, address: u64, buf: &mut [u8]) -> bool {
+		let Some(end) = address.checked_add(buf.len() as u64) else {
+			return false;
+		};
+		// Ranges may overlap, so fill from whichever one holds the next missing byte.
+		let mut cursor = address;
+		while cursor < end {
+			let Some((range, range_end)) = self.memory_ranges().find_map(|range| {
+				let range_end = range.address.saturating_add(range.data.len() as u64);
+				(range.address <= cursor && cursor < range_end).then_some((range, range_end))
+			}) else {
+				return false;
+			};
+			let stop = end.min(range_end);
+			let len = (stop - cursor) as usize;
+			let from = (cursor - range.address) as usize;
+			let to = (cursor - address) as usize;
+			buf[to..to + len].copy_from_slice(&range.data[from..from + len]);
+			cursor = stop;
+		}
+		true
+	}
+}
@@ -0,0 +1,144 @@
+use core::fmt::Debug;
+use object::{pe, read::pe::ImageNtHeaders, LittleEndian};
+
+#[cfg(target_pointer_width = "64")]
+pub type NativeNtHeaders = pe::ImageNtHeaders64;
+#[cfg(target_pointer_width = "32")]
+pub type NativeNtHeaders = pe::ImageNtHeaders32;
+
+pub type NativeTlsDirectory = <NativeNtHeaders as NtHeaders>::TlsDirectory;
+
+pub const IMAGE_FILE_MACHINE_ARM64EC: u16 = 0xa641;
+pub const IMAGE_FILE_MACHINE_ARM64X: u16 = 0xa64e;
+
+/// Whether `machine` uses the 64-bit optional header, `None` for machines this does not know
+/// about and for EFI byte code, which runs either way.
+pub fn machine_is_64(machine: u16) -> Option<bool> {
+	match machine {
+		pe::IMAGE_FILE_MACHINE_I386
+		| pe::IMAGE_FILE_MACHINE_ARM
+		| pe::IMAGE_FILE_MACHINE_THUMB
+		| pe::IMAGE_FILE_MACHINE_ARMNT
+		| pe::IMAGE_FILE_MACHINE_RISCV32 => Some(false),
+		pe::IMAGE_FILE_MACHINE_AMD64
+		| pe::IMAGE_FILE_MACHINE_ARM64
+		| IMAGE_FILE_MACHINE_ARM64EC
+		| IMAGE_FILE_MACHINE_ARM64X
+		| pe::IMAGE_FILE_MACHINE_IA64
+		| pe::IMAGE_FILE_MACHINE_ALPHA64
+		| pe::IMAGE_FILE_MACHINE_RISCV64 => Some(true),
+		_ => None,
+	}
+}
+
+/// NT headers of either bitness, independent of the host, e.g. a WOW64 module seen from x64.
+pub trait NtHeaders: ImageNtHeaders + 'static {
+	type TlsDirectory: TlsDirectory;
+}
+
+impl NtHeaders for pe::ImageNtHeaders64 {
+	type TlsDirectory = pe::ImageTlsDirectory64;
+}
+
+impl NtHeaders for pe::ImageNtHeaders32 {
+	type TlsDirectory = pe::ImageTlsDirectory32;
+}
+
+/// The TLS directory with its VAs widened to `u64`.
+pub trait TlsDirectory: Debug + 'static {
+	/// Width of the VAs in the callback array.
+	const POINTER_SIZE: usize;
+
+	fn start_address_of_raw_data(&self) -> u64;
+	fn end_address_of_raw_data(&self) -> u64;
+	fn address_of_index(&self) -> u64;
+	fn address_of_call_backs(&self) -> u64;
+	/// Truncates `va` for a 32-bit directory.
+	fn set_address_of_call_backs(&mut self, va: u64);
+	fn size_of_zero_fill(&self) -> u32;
+	fn characteristics(&self) -> u32;
+}
+
+impl TlsDirectory for pe::ImageTlsDirectory64 {
+	const POINTER_SIZE: usize = 8;
+
+	fn start_address_of_raw_data(&self) -> u64 {
+		self.start_address_of_raw_data.get(LittleEndian)
+	}
+
+	fn end_address_of_raw_data(&self) -> u64 {
+		self.end_address_of_raw_data.get(LittleEndian)
+	}
+
+	fn address_of_index(&self) -> u64 {
+		self.address_of_index.get(LittleEndian)
+	}
+
+	fn address_of_call_backs(&self) -> u64 {
+		self.address_of_call_backs.get(LittleEndian)
+	}
+
+	fn set_address_of_call_backs(&mut self, va: u64) {
+		self.address_of_call_backs.set(LittleEndian, va);
+	}
+
+	fn size_of_zero_fill(&self) -> u32 {
+		self.size_of_zero_fill.get(LittleEndian)
+	}
+
+	fn characteristics(&self) -> u32 {
+		self.characteristics.get(LittleEndian)
+	}
+}
+
+impl TlsDirectory for pe::ImageTlsDirectory32 {
+	const POINTER_SIZE: usize = 4;
+
+	fn start_address_of_raw_data(&self) -> u64 {
+		self.start_address_of_raw_data.get(LittleEndian).into()
+	}
+
+	fn end_address_of_raw_data(&self) -> u64 {
+		self.end_address_of_raw_data.get(LittleEndian).into()
+	}
+
+	fn address_of_index(&self) -> u64 {
+		self.address_of_index.get(LittleEndian).into()
+	}
+
+	fn address_of_call_backs(&self) -> u64 {
+		self.address_of_call_backs.get(LittleEndian).into()
+	}
+
+	fn set_address_of_call_backs(&mut self, va: u64) {
+		self.address_of_call_backs.set(LittleEndian, va as u32);
+	}
+
+	fn size_of_zero_fill(&self) -> u32 {
+		self.size_of_zero_fill.get(LittleEndian)
+	}
+
+	fn characteristics(&self) -> u32 {
+		self.characteristics.get(LittleEndian)
+	}
+}
+
+/// Reads a little-endian VA of the image's pointer width.
+#[cfg_attr(feature = "debug", inline(never))]
+pub(crate) unsafe fn read_va<T: TlsDirectory>(address: *const u8) -> u64 {
+	if T::POINTER_SIZE == 8 {
+		u64::from_le(unsafe { address.cast::<u64>().read_unaligned() })
+	} else {
+		u32::from_le(unsafe { address.cast::<u32>().read_unaligned() }).into()
+	}
+}
+
+/// Writes a VA of the image's pointer width, truncating it for a 32-bit image.
+#[cfg_attr(feature = "debug", inline(never))]
+pub(crate) unsafe fn write_va<T: TlsDirectory>(address: *mut u8, va: u64) {
+	if T::POINTER_SIZE == 8 {
+		unsafe { address.cast::<u64>().write_unaligned(va.to_le()) }
+	} else {
+		unsafe { address.cast::<u32>().write_unaligned((va as u32).to_le()) }
+	}
+}
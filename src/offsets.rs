@@ -0,0 +1,233 @@
+use crate::{nt::NtHeaders, PeHeaders};
+use core::mem::{offset_of, size_of};
+use object::{
+	pe::{
+		self, ImageDataDirectory, ImageDosHeader, ImageFileHeader, ImageOptionalHeader32,
+		ImageOptionalHeader64, ImageSectionHeader,
+	},
+	LittleEndian,
+};
+
+#[cfg(target_pointer_width = "64")]
+const NT_HEADERS_SIZE: usize = size_of::<pe::ImageNtHeaders64>();
+#[cfg(target_pointer_width = "32")]
+const NT_HEADERS_SIZE: usize = size_of::<pe::ImageNtHeaders32>();
+
+pub const fn nt_headers_offset(e_lfanew: u32) -> usize {
+	e_lfanew as usize
+}
+
+pub const fn data_directories_offset(nt_headers_offset: usize) -> usize {
+	nt_headers_offset + NT_HEADERS_SIZE
+}
+
+/// [`data_directories_offset`] for NT headers of either bitness.
+pub const fn data_directories_offset_of<Nt>(nt_headers_offset: usize) -> usize {
+	nt_headers_offset + size_of::<Nt>()
+}
+
+pub const fn data_directory_offset(nt_headers_offset: usize, index: usize) -> usize {
+	data_directories_offset(nt_headers_offset) + index * size_of::<ImageDataDirectory>()
+}
+
+pub const fn section_headers_offset(
+	nt_headers_offset: usize,
+	num_data_directories: usize,
+) -> usize {
+	data_directory_offset(nt_headers_offset, num_data_directories)
+}
+
+pub const fn section_headers_offset_of<Nt>(
+	nt_headers_offset: usize,
+	num_data_directories: usize,
+) -> usize {
+	data_directories_offset_of::<Nt>(nt_headers_offset)
+		+ num_data_directories * size_of::<ImageDataDirectory>()
+}
+
+pub const fn section_header_offset(
+	nt_headers_offset: usize,
+	num_data_directories: usize,
+	index: usize,
+) -> usize {
+	section_headers_offset(nt_headers_offset, num_data_directories)
+		+ index * size_of::<ImageSectionHeader>()
+}
+
+pub const fn section_header_offset_of<Nt>(
+	nt_headers_offset: usize,
+	num_data_directories: usize,
+	index: usize,
+) -> usize {
+	section_headers_offset_of::<Nt>(nt_headers_offset, num_data_directories)
+		+ index * size_of::<ImageSectionHeader>()
+}
+
+/// Offset of the section table, which follows the optional header of `size_of_optional_header`
+/// bytes whatever `NumberOfRvaAndSizes` says.
+pub const fn section_table_offset(
+	nt_headers_offset: usize,
+	size_of_optional_header: usize,
+) -> usize {
+	nt_headers_offset + size_of::<u32>() + size_of::<ImageFileHeader>() + size_of_optional_header
+}
+
+pub const fn section_table_entry_offset(
+	nt_headers_offset: usize,
+	size_of_optional_header: usize,
+	index: usize,
+) -> usize {
+	section_table_offset(nt_headers_offset, size_of_optional_header)
+		+ index * size_of::<ImageSectionHeader>()
+}
+
+/// A header field whose location [`PeHeaders::field_span`] can report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderField {
+	Lfanew,
+	Machine,
+	NumberOfSections,
+	TimeDateStamp,
+	SizeOfOptionalHeader,
+	Characteristics,
+	AddressOfEntryPoint,
+	ImageBase,
+	SectionAlignment,
+	FileAlignment,
+	SizeOfImage,
+	SizeOfHeaders,
+	CheckSum,
+	Subsystem,
+	DllCharacteristics,
+	NumberOfRvaAndSizes,
+	/// The whole `IMAGE_DATA_DIRECTORY` at this index.
+	DataDirectory(usize),
+	Section(usize, SectionField),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionField {
+	Name,
+	VirtualSize,
+	VirtualAddress,
+	SizeOfRawData,
+	PointerToRawData,
+	Characteristics,
+}
+
+/// Location of a field or structure from the start of the image. Header fields are at the same
+/// offset in file and mapped layout since the headers are mapped as they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldSpan {
+	pub offset: usize,
+	pub len: usize,
+}
+
+impl FieldSpan {
+	/// Span of `value` in the image starting at `image_base`, `None` when it lies before it.
+	pub fn of<T: ?Sized>(image_base: *const u8, value: &T) -> Option<Self> {
+		Some(Self {
+			offset: ((value as *const T).cast::<u8>() as usize).checked_sub(image_base as usize)?,
+			len: core::mem::size_of_val(value),
+		})
+	}
+
+	pub fn end(&self) -> usize {
+		self.offset + self.len
+	}
+}
+
+const fn field_len<T, F>(_: fn(&T) -> &F) -> usize {
+	size_of::<F>()
+}
+
+macro_rules! span {
+	($base:expr, $ty:ty, $field:ident) => {
+		FieldSpan {
+			offset: $base + offset_of!($ty, $field),
+			len: field_len(|header: &$ty| &header.$field),
+		}
+	};
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// `None` for a data directory or section past the parsed ones.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn field_span(&self, field: HeaderField) -> Option<FieldSpan> {
+		let nt_headers_offset = nt_headers_offset(self.dos_header.e_lfanew.get(LittleEndian));
+		// Signature, then the file header, then the optional header.
+		let file_header = nt_headers_offset + size_of::<u32>();
+		let optional_header = file_header + size_of::<ImageFileHeader>();
+		let is_64 = self.nt_header.is_type_64();
+		macro_rules! optional_span {
+			($field:ident) => {
+				match is_64 {
+					true => span!(optional_header, ImageOptionalHeader64, $field),
+					false => span!(optional_header, ImageOptionalHeader32, $field),
+				}
+			};
+		}
+
+		Some(match field {
+			HeaderField::Lfanew => span!(0, ImageDosHeader, e_lfanew),
+			HeaderField::Machine => span!(file_header, ImageFileHeader, machine),
+			HeaderField::NumberOfSections => {
+				span!(file_header, ImageFileHeader, number_of_sections)
+			}
+			HeaderField::TimeDateStamp => span!(file_header, ImageFileHeader, time_date_stamp),
+			HeaderField::SizeOfOptionalHeader => {
+				span!(file_header, ImageFileHeader, size_of_optional_header)
+			}
+			HeaderField::Characteristics => span!(file_header, ImageFileHeader, characteristics),
+			HeaderField::AddressOfEntryPoint => optional_span!(address_of_entry_point),
+			HeaderField::ImageBase => optional_span!(image_base),
+			HeaderField::SectionAlignment => optional_span!(section_alignment),
+			HeaderField::FileAlignment => optional_span!(file_alignment),
+			HeaderField::SizeOfImage => optional_span!(size_of_image),
+			HeaderField::SizeOfHeaders => optional_span!(size_of_headers),
+			HeaderField::CheckSum => optional_span!(check_sum),
+			HeaderField::Subsystem => optional_span!(subsystem),
+			HeaderField::DllCharacteristics => optional_span!(dll_characteristics),
+			HeaderField::NumberOfRvaAndSizes => optional_span!(number_of_rva_and_sizes),
+			HeaderField::DataDirectory(index) => {
+				if index >= self.data_directories.len() {
+					return None;
+				}
+				FieldSpan {
+					offset: data_directories_offset_of::<Nt>(nt_headers_offset)
+						+ index * size_of::<ImageDataDirectory>(),
+					len: size_of::<ImageDataDirectory>(),
+				}
+			}
+			HeaderField::Section(index, field) => {
+				if index >= self.section_headers.len() {
+					return None;
+				}
+				let section = section_table_entry_offset(
+					nt_headers_offset,
+					self.nt_header
+						.file_header()
+						.size_of_optional_header
+						.get(LittleEndian) as _,
+					index,
+				);
+				match field {
+					SectionField::Name => span!(section, ImageSectionHeader, name),
+					SectionField::VirtualSize => span!(section, ImageSectionHeader, virtual_size),
+					SectionField::VirtualAddress => {
+						span!(section, ImageSectionHeader, virtual_address)
+					}
+					SectionField::SizeOfRawData => {
+						span!(section, ImageSectionHeader, size_of_raw_data)
+					}
+					SectionField::PointerToRawData => {
+						span!(section, ImageSectionHeader, pointer_to_raw_data)
+					}
+					SectionField::Characteristics => {
+						span!(section, ImageSectionHeader, characteristics)
+					}
+				}
+			}
+		})
+	}
+}
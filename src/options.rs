@@ -0,0 +1,156 @@
+use crate::loader::MAX_SECTIONS;
+use object::pe::IMAGE_NUMBEROF_DIRECTORY_ENTRIES;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strictness {
+	Strict,
+	Lenient,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+	/// Image mapped by the loader, RVAs are offsets from the base.
+	Mapped,
+	/// Raw file contents, RVAs have to be translated through the section headers.
+	File,
+}
+
+/// Large DOS stubs from old installers and some packers push `e_lfanew` past 1 KiB.
+pub const DEFAULT_MAX_NT_OFFSET: usize = 0x1000;
+/// Callbacks walked before a TLS callback array without terminator is given up on.
+pub const DEFAULT_MAX_TLS_CALLBACKS: usize = 0x400;
+/// Thunks of one import descriptor, as many as a dll can export by ordinal.
+pub const DEFAULT_MAX_IMPORT_THUNKS: usize = 0x10000;
+/// Forwarders followed while resolving an export, loops between modules hit it quickly.
+pub const DEFAULT_MAX_FORWARDERS: usize = 16;
+/// Resource trees have three levels, type, name and language.
+pub const DEFAULT_MAX_RESOURCE_DEPTH: usize = 8;
+/// Import descriptors, one per imported dll.
+pub const DEFAULT_MAX_IMPORT_DESCRIPTORS: usize = 0x1000;
+/// Entries of each export table, the ordinal table indexes at most this many functions.
+pub const DEFAULT_MAX_EXPORTS: usize = 0x10000;
+/// Directories and entries visited by [`crate::resource::ResourceTable::walk`].
+pub const DEFAULT_MAX_RESOURCE_NODES: usize = 0x10000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseOptions {
+	pub max_nt_offset: usize,
+	pub max_sections: usize,
+	pub max_data_directories: usize,
+	pub max_import_descriptors: usize,
+	/// Applies to `NumberOfFunctions` and `NumberOfNames`.
+	pub max_exports: usize,
+	pub max_resource_nodes: usize,
+	pub strictness: Strictness,
+	pub layout: Layout,
+	/// Query every range with `VirtualQuery` before reading it (`virtual-query` feature).
+	pub validate_memory: bool,
+	/// Start address and length of the memory holding the image. Ranges outside it fail with
+	/// [`crate::error::Error::OutOfRegion`] before being read.
+	pub region: Option<(usize, usize)>,
+}
+
+impl ParseOptions {
+	/// [`ParseOptions::strict`].
+	pub const fn new() -> Self {
+		Self::strict()
+	}
+
+	/// Rejects anything the Windows loader would refuse to map.
+	pub const fn strict() -> Self {
+		Self {
+			max_nt_offset: DEFAULT_MAX_NT_OFFSET,
+			max_sections: MAX_SECTIONS,
+			max_data_directories: IMAGE_NUMBEROF_DIRECTORY_ENTRIES,
+			max_import_descriptors: DEFAULT_MAX_IMPORT_DESCRIPTORS,
+			max_exports: DEFAULT_MAX_EXPORTS,
+			max_resource_nodes: DEFAULT_MAX_RESOURCE_NODES,
+			strictness: Strictness::Strict,
+			layout: Layout::Mapped,
+			validate_memory: false,
+			region: None,
+		}
+	}
+
+	/// Accepts malformed but analyzable images, clamping oversized counts instead of failing.
+	pub const fn lenient() -> Self {
+		Self {
+			max_nt_offset: u32::MAX as usize,
+			max_sections: u16::MAX as usize,
+			max_data_directories: IMAGE_NUMBEROF_DIRECTORY_ENTRIES,
+			max_import_descriptors: DEFAULT_MAX_IMPORT_DESCRIPTORS,
+			max_exports: DEFAULT_MAX_EXPORTS,
+			max_resource_nodes: DEFAULT_MAX_RESOURCE_NODES,
+			strictness: Strictness::Lenient,
+			layout: Layout::Mapped,
+			validate_memory: false,
+			region: None,
+		}
+	}
+
+	pub const fn max_nt_offset(mut self, max_nt_offset: usize) -> Self {
+		self.max_nt_offset = max_nt_offset;
+		self
+	}
+
+	pub const fn max_sections(mut self, max_sections: usize) -> Self {
+		self.max_sections = max_sections;
+		self
+	}
+
+	pub const fn max_data_directories(mut self, max_data_directories: usize) -> Self {
+		self.max_data_directories = max_data_directories;
+		self
+	}
+
+	pub const fn max_import_descriptors(mut self, max_import_descriptors: usize) -> Self {
+		self.max_import_descriptors = max_import_descriptors;
+		self
+	}
+
+	pub const fn max_exports(mut self, max_exports: usize) -> Self {
+		self.max_exports = max_exports;
+		self
+	}
+
+	pub const fn max_resource_nodes(mut self, max_resource_nodes: usize) -> Self {
+		self.max_resource_nodes = max_resource_nodes;
+		self
+	}
+
+	pub const fn strictness(mut self, strictness: Strictness) -> Self {
+		self.strictness = strictness;
+		self
+	}
+
+	pub const fn layout(mut self, layout: Layout) -> Self {
+		self.layout = layout;
+		self
+	}
+
+	pub const fn validate_memory(mut self, validate_memory: bool) -> Self {
+		self.validate_memory = validate_memory;
+		self
+	}
+
+	pub fn region(mut self, address: *const u8, len: usize) -> Self {
+		self.region = Some((address as usize, len));
+		self
+	}
+
+	pub(crate) fn limit(&self, count: usize, max: usize) -> Option<usize> {
+		if count <= max {
+			return Some(count);
+		}
+		match self.strictness {
+			Strictness::Strict => None,
+			Strictness::Lenient => Some(max),
+		}
+	}
+}
+
+impl Default for ParseOptions {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,68 @@
+use crate::{
+	error::{Error, Result},
+	nt::NtHeaders,
+	offsets::HeaderField,
+	PeHeaders,
+};
+use windows_sys::Win32::System::Memory::{VirtualProtect, PAGE_EXECUTE_READWRITE};
+
+/// Makes a range writable until dropped, then restores the previous protection.
+pub struct ProtectGuard {
+	address: *const u8,
+	len: usize,
+	old_protect: u32,
+}
+
+/// Makes `[address, address + len)` of the current process writable. Executable pages stay
+/// executable, so this is also fine for patching code.
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn unprotect(address: *const u8, len: usize) -> Result<ProtectGuard> {
+	let mut old_protect = 0;
+	if unsafe {
+		VirtualProtect(
+			address.cast(),
+			len,
+			PAGE_EXECUTE_READWRITE,
+			&mut old_protect,
+		)
+	} == 0
+	{
+		return Err(Error::InvalidMemory {
+			address: address as usize,
+			len,
+		});
+	}
+	Ok(ProtectGuard {
+		address,
+		len,
+		old_protect,
+	})
+}
+
+impl Drop for ProtectGuard {
+	fn drop(&mut self) {
+		let mut old_protect = 0;
+		unsafe {
+			VirtualProtect(
+				self.address.cast(),
+				self.len,
+				self.old_protect,
+				&mut old_protect,
+			)
+		};
+	}
+}
+
+/// [`PeHeaders::set_entry_point`] on a loaded module, making its headers writable meanwhile.
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn patch_entry_point<Nt: NtHeaders>(
+	headers: &PeHeaders<Nt>,
+	image_base: *mut u8,
+	rva: u32,
+) -> Result<u32> {
+	let span = headers
+		.field_span(HeaderField::AddressOfEntryPoint)
+		.ok_or(Error::PeHeaders)?;
+	let _guard = unsafe { unprotect(image_base.wrapping_add(span.offset), span.len)? };
+	unsafe { headers.set_entry_point(image_base, rva) }
+}
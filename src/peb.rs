@@ -0,0 +1,288 @@
+use crate::{
+	error::{Error, Result},
+	hash::ExportHasher,
+	hooks::ModuleRange,
+	import::ImportName,
+	options::DEFAULT_MAX_FORWARDERS,
+	widestring, PeHeaders,
+};
+use core::{
+	arch::asm,
+	ffi::{c_void, CStr},
+	slice, str,
+};
+
+#[repr(C)]
+pub struct ListEntry {
+	pub flink: *const ListEntry,
+	pub blink: *const ListEntry,
+}
+
+#[repr(C)]
+pub struct UnicodeString {
+	pub length: u16,
+	pub maximum_length: u16,
+	pub buffer: *const u16,
+}
+
+impl UnicodeString {
+	pub unsafe fn as_slice(&self) -> &'static [u16] {
+		if self.buffer.is_null() {
+			return &[];
+		}
+		unsafe { slice::from_raw_parts(self.buffer, self.length as usize / 2) }
+	}
+}
+
+#[repr(C)]
+pub struct PebLdrData {
+	pub length: u32,
+	pub initialized: u8,
+	pub ss_handle: *const c_void,
+	pub in_load_order_module_list: ListEntry,
+	pub in_memory_order_module_list: ListEntry,
+	pub in_initialization_order_module_list: ListEntry,
+}
+
+#[repr(C)]
+pub struct LdrDataTableEntry {
+	pub in_load_order_links: ListEntry,
+	pub in_memory_order_links: ListEntry,
+	pub in_initialization_order_links: ListEntry,
+	pub dll_base: *const u8,
+	pub entry_point: *const c_void,
+	pub size_of_image: u32,
+	pub full_dll_name: UnicodeString,
+	pub base_dll_name: UnicodeString,
+}
+
+#[repr(C)]
+pub struct Peb {
+	pub inherited_address_space: u8,
+	pub read_image_file_exec_options: u8,
+	pub being_debugged: u8,
+	pub bit_field: u8,
+	pub mutant: *const c_void,
+	pub image_base_address: *const u8,
+	pub ldr: *const PebLdrData,
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn peb() -> *const Peb {
+	let peb: *const Peb;
+	#[cfg(target_arch = "x86_64")]
+	unsafe {
+		asm!("mov {}, gs:[0x60]", out(reg) peb, options(nostack, readonly, preserves_flags))
+	};
+	#[cfg(target_arch = "x86")]
+	unsafe {
+		asm!("mov {}, fs:[0x30]", out(reg) peb, options(nostack, readonly, preserves_flags))
+	};
+	// x18 holds the TEB, whose `ProcessEnvironmentBlock` is at 0x60.
+	#[cfg(target_arch = "aarch64")]
+	unsafe {
+		asm!("ldr {}, [x18, #0x60]", out(reg) peb, options(nostack, readonly, preserves_flags))
+	};
+	peb
+}
+
+#[derive(Clone, Copy)]
+pub struct LoadedModule {
+	pub base: *const u8,
+	pub size: u32,
+	pub entry_point: *const c_void,
+	pub full_name: &'static [u16],
+	pub base_name: &'static [u16],
+}
+
+impl LoadedModule {
+	pub fn contains(&self, address: *const u8) -> bool {
+		(address as usize).wrapping_sub(self.base as usize) < self.size as usize
+	}
+
+	pub unsafe fn headers(&self) -> Result<PeHeaders> {
+		unsafe { PeHeaders::parse(self.base) }
+	}
+
+	/// Compares the base name without a `.dll` extension, the way forwarders name modules.
+	fn has_stem(&self, stem: &str) -> bool {
+		let name = match self.base_name.len().checked_sub(4) {
+			Some(len)
+				if widestring::eq_str_ignore_ascii_case(
+					self.base_name[len..].iter().copied(),
+					".dll",
+				) =>
+			{
+				&self.base_name[..len]
+			}
+			_ => self.base_name,
+		};
+		widestring::eq_str_ignore_ascii_case(name.iter().copied(), stem)
+	}
+}
+
+impl ModuleRange for LoadedModule {
+	fn base(&self) -> u64 {
+		self.base as u64
+	}
+
+	fn size(&self) -> u32 {
+		self.size
+	}
+
+	fn has_name(&self, name: &str) -> bool {
+		widestring::eq_str_ignore_ascii_case(self.base_name.iter().copied(), name)
+	}
+
+	unsafe fn resolve_export(&self, import: ImportName) -> Option<u64> {
+		let export = match import {
+			ImportName::Name { name, .. } => ExportRef::Name(name.to_bytes()),
+			ImportName::Ordinal(ordinal) => ExportRef::Ordinal(ordinal.into()),
+		};
+		unsafe { resolve_export_in(*self, export) }
+			.ok()
+			.map(|address| address as u64)
+	}
+}
+
+/// Walks `InLoadOrderModuleList` of the current process.
+pub struct LoadedModules {
+	head: *const ListEntry,
+	current: *const ListEntry,
+}
+
+impl Iterator for LoadedModules {
+	type Item = LoadedModule;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.current.is_null() || self.current == self.head {
+			return None;
+		}
+		// `in_load_order_links` is the first field, the list entry is the table entry.
+		let entry = unsafe { &*self.current.cast::<LdrDataTableEntry>() };
+		self.current = entry.in_load_order_links.flink;
+		Some(LoadedModule {
+			base: entry.dll_base,
+			size: entry.size_of_image,
+			entry_point: entry.entry_point,
+			full_name: unsafe { entry.full_dll_name.as_slice() },
+			base_name: unsafe { entry.base_dll_name.as_slice() },
+		})
+	}
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn loaded_modules() -> LoadedModules {
+	let ldr = unsafe { (*peb()).ldr };
+	let head = unsafe { &(*ldr).in_load_order_module_list as *const ListEntry };
+	LoadedModules {
+		head,
+		current: unsafe { (*head).flink },
+	}
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn module_containing(address: *const u8) -> Option<LoadedModule> {
+	unsafe { loaded_modules() }.find(|module| module.contains(address))
+}
+
+/// Finds and parses the module containing `address`, e.g. a function of the caller.
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn parse_module_containing(address: *const u8) -> Result<(*const u8, PeHeaders)> {
+	let module = unsafe { module_containing(address) }.ok_or(Error::ModuleNotFound)?;
+	Ok((module.base, unsafe { module.headers()? }))
+}
+
+/// Finds a loaded module by the [`ExportHasher::hash_module_name`] of its `BaseDllName`.
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn find_module_by_hash<H: ExportHasher>(hash: u32) -> Option<LoadedModule> {
+	unsafe { loaded_modules() }
+		.find(|module| H::hash_module_name(module.base_name.iter().copied()) == hash)
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn find_module(name: &str) -> Option<LoadedModule> {
+	unsafe { loaded_modules() }
+		.find(|module| widestring::eq_str_ignore_ascii_case(module.base_name.iter().copied(), name))
+}
+
+/// Address of the export `name` of the loaded module `module`, following forwarders into other
+/// loaded modules. A `#N` name is looked up by ordinal, as in forwarder strings. Chains of more
+/// than [`DEFAULT_MAX_FORWARDERS`] forwarders fail with [`Error::LimitExceeded`], API set names
+/// are not resolved.
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn resolve_export(module: &str, name: &[u8]) -> Result<*const u8> {
+	let module = unsafe { find_module(module) }.ok_or(Error::ModuleNotFound)?;
+	unsafe { resolve_export_in(module, ExportRef::parse(name)) }
+}
+
+/// An export as named by a forwarder string.
+#[derive(Clone, Copy)]
+enum ExportRef<'a> {
+	Name(&'a [u8]),
+	Ordinal(u32),
+}
+
+impl<'a> ExportRef<'a> {
+	/// `#N` is an ordinal, anything else a name.
+	fn parse(name: &'a [u8]) -> Self {
+		let ordinal = name
+			.strip_prefix(b"#")
+			.and_then(|ordinal| str::from_utf8(ordinal).ok())
+			.and_then(|ordinal| ordinal.parse().ok());
+		match ordinal {
+			Some(ordinal) => Self::Ordinal(ordinal),
+			None => Self::Name(name),
+		}
+	}
+}
+
+unsafe fn resolve_export_in(mut module: LoadedModule, export: ExportRef) -> Result<*const u8> {
+	let mut export = export;
+	for _ in 0..=DEFAULT_MAX_FORWARDERS {
+		let headers = unsafe { module.headers()? };
+		let export_table = headers.export_table()?;
+		let address = match export {
+			ExportRef::Ordinal(ordinal) => export_table.address_by_ordinal(module.base, ordinal),
+			ExportRef::Name(name) => {
+				unsafe { export_table.find_by_name(&headers, module.base.cast_mut(), name) }
+					.map(<*mut u8>::cast_const)
+			}
+		}
+		.ok_or(Error::ImportResolution)?;
+		let rva = (address as usize).wrapping_sub(module.base as usize) as u32;
+		if rva.wrapping_sub(export_table.rva) >= export_table.size {
+			return Ok(address);
+		}
+
+		let forwarder = unsafe { CStr::from_ptr(address.cast()) }.to_bytes();
+		let dot = forwarder
+			.iter()
+			.rposition(|&byte| byte == b'.')
+			.ok_or(Error::ExportTable)?;
+		let dll = str::from_utf8(&forwarder[..dot]).map_err(|_| Error::ExportTable)?;
+		module = unsafe { loaded_modules() }
+			.find(|module| module.has_stem(dll))
+			.ok_or(Error::ModuleNotFound)?;
+		export = ExportRef::parse(&forwarder[dot + 1..]);
+	}
+	Err(Error::LimitExceeded {
+		limit: DEFAULT_MAX_FORWARDERS,
+	})
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+unsafe fn resolve_module(name: &str) -> Result<(*const u8, PeHeaders)> {
+	let module = unsafe { find_module(name) }.ok_or(Error::ModuleNotFound)?;
+	Ok((module.base, unsafe { module.headers()? }))
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn resolve_ntdll() -> Result<(*const u8, PeHeaders)> {
+	unsafe { resolve_module("ntdll.dll") }
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn resolve_kernel32() -> Result<(*const u8, PeHeaders)> {
+	unsafe { resolve_module("kernel32.dll") }
+}
@@ -0,0 +1,377 @@
+#[cfg(any(feature = "pe32", feature = "pe64"))]
+use crate::any;
+use crate::{
+	analyze::Report,
+	error::{Error, FileError, Result},
+	import::ImportName,
+	import_map::ImportMap,
+	nt::{NativeNtHeaders, NtHeaders},
+	offsets, section, HeadersOnly, Layout, ParseOptions, PeHeaders,
+};
+#[cfg(any(feature = "pe32", feature = "pe64"))]
+use object::pe;
+use object::{
+	pe::{
+		ImageDataDirectory, ImageSectionHeader, IMAGE_DIRECTORY_ENTRY_EXPORT,
+		IMAGE_DIRECTORY_ENTRY_IMPORT,
+	},
+	read::pe::ImageOptionalHeader,
+	LittleEndian,
+};
+use std::io::{Read, Seek, SeekFrom};
+
+/// Enough for the DOS header, a typical stub and the NT headers.
+const PROBE_SIZE: usize = 0x1000;
+/// Largest image buffer [`PeReader::open_with`] grows, `SizeOfImage` can claim up to 4 GiB.
+pub const DEFAULT_MAX_IMAGE_BUFFER: usize = 0x1000_0000;
+
+/// Parses a PE file from a reader without reading all of it.
+///
+/// Sections are read into a mapped-layout buffer the first time a table inside them is
+/// requested. The buffer only reaches the end of the last section read, up to a limit whatever
+/// `SizeOfImage` claims, and the headers are parsed with it as their region.
+///
+/// The headers and the tables they load hold `'static` references into the buffer, which moves
+/// as it grows, so they are only reachable through the unsafe [`PeReader::headers`].
+pub struct PeReader<R, Nt: NtHeaders = NativeNtHeaders> {
+	reader: R,
+	image: Vec<u8>,
+	max_len: usize,
+	size_of_headers: usize,
+	options: ParseOptions,
+	loaded: Vec<bool>,
+	headers: PeHeaders<Nt>,
+}
+
+impl<R: Read + Seek> PeReader<R> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn open(reader: R) -> Result<Self, FileError> {
+		Self::open_with(reader, ParseOptions::new())
+	}
+
+	/// `options.layout` and `options.region` are ignored, the buffer is always in mapped layout.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn open_with(reader: R, options: ParseOptions) -> Result<Self, FileError> {
+		Self::open_nt(reader, options)
+	}
+}
+
+impl<R: Read + Seek, Nt: NtHeaders> PeReader<R, Nt> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn open_nt(reader: R, options: ParseOptions) -> Result<Self, FileError> {
+		Self::open_nt_with_limit(reader, options, DEFAULT_MAX_IMAGE_BUFFER)
+	}
+
+	/// Reading past `max_len` bytes of the image fails with [`Error::LimitExceeded`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn open_nt_with_limit(
+		mut reader: R,
+		options: ParseOptions,
+		max_len: usize,
+	) -> Result<Self, FileError> {
+		let mut probe = vec![0u8; PROBE_SIZE];
+		let probe_len = read_up_to(&mut reader, 0, &mut probe)?;
+		let probe_options = options.region(probe.as_ptr(), probe_len);
+		let headers_only = unsafe { HeadersOnly::<Nt>::parse(probe.as_ptr(), &probe_options)? };
+		let nt_header = headers_only.nt_header;
+		let size_of_headers = nt_header.optional_header().size_of_headers() as usize;
+		let image_len = nt_header.optional_header().size_of_image() as usize;
+		let section_headers_end = offsets::section_table_entry_offset(
+			headers_only.nt_header_offset,
+			nt_header
+				.file_header()
+				.size_of_optional_header
+				.get(LittleEndian) as _,
+			nt_header.file_header().number_of_sections.get(LittleEndian) as _,
+		);
+		if size_of_headers > image_len || section_headers_end > size_of_headers {
+			return Err(Error::PeHeaders.into());
+		}
+		if size_of_headers > max_len {
+			return Err(Error::LimitExceeded { limit: max_len }.into());
+		}
+
+		let mut image = vec![0u8; size_of_headers];
+		read_up_to(&mut reader, 0, &mut image)?;
+		let options = options.layout(Layout::Mapped);
+		let headers = unsafe { parse_image(&image, options)? };
+		Ok(Self {
+			reader,
+			image,
+			max_len,
+			size_of_headers,
+			options,
+			loaded: vec![false; headers.section_headers.len()],
+			headers,
+		})
+	}
+
+	/// # Safety
+	///
+	/// Nothing taken from the headers, including the tables and names they load, may be used
+	/// after `self` is dropped or reads more of the file.
+	pub unsafe fn headers(&self) -> &PeHeaders<Nt> {
+		&self.headers
+	}
+
+	pub fn nt_header(&self) -> &Nt {
+		self.headers.nt_header
+	}
+
+	pub fn section_headers(&self) -> &[ImageSectionHeader] {
+		self.headers.section_headers
+	}
+
+	pub fn data_directory(&self, index: usize) -> Option<&ImageDataDirectory> {
+		self.headers.data_directory(index)
+	}
+
+	/// The mapped-layout buffer, only headers and loaded sections are filled in.
+	pub fn image(&self) -> &[u8] {
+		&self.image
+	}
+
+	/// Start of [`PeReader::image`], to pass as `image_base` to the `*_mem` loaders. What they
+	/// return must not outlive the buffer, see [`PeReader::headers`].
+	pub fn image_base(&self) -> *const u8 {
+		self.image.as_ptr()
+	}
+
+	/// Reads every section overlapping `rva..rva + len` that was not read yet.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn load_rva_range(&mut self, rva: u32, len: u32) -> Result<(), FileError> {
+		let end = rva.saturating_add(len.max(1));
+		for index in 0..self.loaded.len() {
+			let section = &self.headers.section_headers[index];
+			let start = section.virtual_address.get(LittleEndian);
+			let section_end = start.saturating_add(section::section_virtual_size(section));
+			if !self.loaded[index] && start < end && rva < section_end {
+				self.load_section(index)?;
+			}
+		}
+		Ok(())
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn load_directory(&mut self, index: usize) -> Result<(), FileError> {
+		match self.headers.data_directory(index) {
+			Some(data_dir) => self.load_rva_range(
+				data_dir.virtual_address.get(LittleEndian),
+				data_dir.size.get(LittleEndian),
+			),
+			None => Ok(()),
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	fn load_section(&mut self, index: usize) -> Result<(), FileError> {
+		let section = self.headers.section_headers[index];
+		let size_of_image = self.headers.nt_header.optional_header().size_of_image() as usize;
+		let va = section.virtual_address.get(LittleEndian) as usize;
+		let end = va
+			.saturating_add(section::section_virtual_size(&section) as usize)
+			.min(size_of_image);
+		// Sections never overlap the headers once mapped.
+		let start = va.max(self.size_of_headers);
+		let file_end = va
+			.saturating_add(section::section_file_size(&section) as usize)
+			.min(end);
+		if end > self.image.len() {
+			if end > self.max_len {
+				return Err(Error::LimitExceeded {
+					limit: self.max_len,
+				}
+				.into());
+			}
+			self.image.resize(end, 0);
+		}
+		if start < file_end {
+			let offset = section.pointer_to_raw_data.get(LittleEndian) as u64 + (start - va) as u64;
+			read_up_to(&mut self.reader, offset, &mut self.image[start..file_end])?;
+		}
+		self.loaded[index] = true;
+		// The buffer may have moved, and tables loaded before this section miss its data.
+		self.headers = unsafe { parse_image(&self.image, self.options)? };
+		Ok(())
+	}
+
+	/// Data of `section` as mapped, cut off at `SizeOfImage`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section_data(&mut self, section: &ImageSectionHeader) -> Result<&[u8], FileError> {
+		let va = section.virtual_address.get(LittleEndian);
+		let len = section::section_virtual_size(section);
+		self.load_rva_range(va, len)?;
+		let start = (va as usize).min(self.image.len());
+		let end = start.saturating_add(len as usize).min(self.image.len());
+		Ok(&self.image[start..end])
+	}
+
+	/// Reads the export directory along with the tables and names it points to.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn load_exports(&mut self) -> Result<(), FileError> {
+		self.load_directory(IMAGE_DIRECTORY_ENTRY_EXPORT)?;
+		let export_directory =
+			unsafe { self.headers.export_table_mem(self.image.as_ptr())? }.export_directory;
+		for rva in [
+			export_directory.address_of_functions.get(LittleEndian),
+			export_directory.address_of_names.get(LittleEndian),
+			export_directory.address_of_name_ordinals.get(LittleEndian),
+		] {
+			self.load_rva_range(rva, 1)?;
+		}
+		// The tables may only now have been read.
+		let name_rvas: Vec<u32> = unsafe { self.headers.export_table_mem(self.image.as_ptr())? }
+			.name_table
+			.iter()
+			.map(|name_rva| name_rva.get(LittleEndian))
+			.collect();
+		for name_rva in name_rvas {
+			self.load_rva_range(name_rva, 1)?;
+		}
+		Ok(())
+	}
+
+	/// Reads the import directory along with the dll names, thunks and names it points to.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn load_imports(&mut self) -> Result<(), FileError> {
+		self.load_directory(IMAGE_DIRECTORY_ENTRY_IMPORT)?;
+		let descriptors: Vec<[u32; 3]> =
+			unsafe { self.headers.import_table_mem(self.image.as_ptr())? }
+				.import_descriptors
+				.iter()
+				.map(|descriptor| {
+					[
+						descriptor.name.get(LittleEndian),
+						descriptor.original_first_thunk.get(LittleEndian),
+						descriptor.first_thunk.get(LittleEndian),
+					]
+				})
+				.collect();
+		for (index, rvas) in descriptors.into_iter().enumerate() {
+			for rva in rvas {
+				self.load_rva_range(rva, 1)?;
+			}
+			let image_base = self.image.as_ptr();
+			let import_table = unsafe { self.headers.import_table_mem(image_base)? };
+			let descriptor = &import_table.import_descriptors[index];
+			let thunks = unsafe {
+				self.headers
+					.import_thunks(descriptor, image_base.cast_mut())?
+			};
+			let name_rvas: Vec<u32> = thunks
+				.map_while(Result::ok)
+				.filter_map(|thunk| match thunk.name {
+					Some(ImportName::Name { name, .. }) => {
+						Some((name.as_ptr() as usize - image_base as usize) as u32)
+					}
+					_ => None,
+				})
+				.collect();
+			for rva in name_rvas {
+				self.load_rva_range(rva, 1)?;
+			}
+		}
+		Ok(())
+	}
+
+	/// [`PeHeaders::import_map`] after [`PeReader::load_imports`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn import_map(&mut self) -> Result<ImportMap, FileError> {
+		self.load_imports()?;
+		Ok(unsafe { self.headers.import_map(self.image.as_mut_ptr())? })
+	}
+
+	/// [`PeHeaders::analyze`] of the sections read so far.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn analyze(&self) -> Report {
+		unsafe { self.headers.analyze(self.image.as_ptr()) }
+	}
+}
+
+/// [`PeReader`] of whichever layout the file declares, see [`crate::any::AnyPeHeaders`].
+#[cfg(any(feature = "pe32", feature = "pe64"))]
+pub enum AnyPeReader<R> {
+	#[cfg(feature = "pe32")]
+	Pe32(PeReader<R, pe::ImageNtHeaders32>),
+	#[cfg(feature = "pe64")]
+	Pe64(PeReader<R, pe::ImageNtHeaders64>),
+}
+
+#[cfg(any(feature = "pe32", feature = "pe64"))]
+impl<R: Read + Seek> AnyPeReader<R> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn open_with(mut reader: R, options: ParseOptions) -> Result<Self, FileError> {
+		let mut probe = vec![0u8; PROBE_SIZE];
+		let probe_len = read_up_to(&mut reader, 0, &mut probe)?;
+		let probe_options = options.region(probe.as_ptr(), probe_len);
+		match unsafe { any::supported_magic(probe.as_ptr(), &probe_options)? } {
+			#[cfg(feature = "pe32")]
+			pe::IMAGE_NT_OPTIONAL_HDR32_MAGIC => PeReader::open_nt(reader, options).map(Self::Pe32),
+			#[cfg(feature = "pe64")]
+			pe::IMAGE_NT_OPTIONAL_HDR64_MAGIC => PeReader::open_nt(reader, options).map(Self::Pe64),
+			_ => Err(Error::PeHeaders.into()),
+		}
+	}
+
+	pub fn is_64(&self) -> bool {
+		match self {
+			#[cfg(feature = "pe32")]
+			Self::Pe32(_) => false,
+			#[cfg(feature = "pe64")]
+			Self::Pe64(_) => true,
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn load_rva_range(&mut self, rva: u32, len: u32) -> Result<(), FileError> {
+		match self {
+			#[cfg(feature = "pe32")]
+			Self::Pe32(reader) => reader.load_rva_range(rva, len),
+			#[cfg(feature = "pe64")]
+			Self::Pe64(reader) => reader.load_rva_range(rva, len),
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn import_map(&mut self) -> Result<ImportMap, FileError> {
+		match self {
+			#[cfg(feature = "pe32")]
+			Self::Pe32(reader) => reader.import_map(),
+			#[cfg(feature = "pe64")]
+			Self::Pe64(reader) => reader.import_map(),
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn analyze(&self) -> Report {
+		match self {
+			#[cfg(feature = "pe32")]
+			Self::Pe32(reader) => reader.analyze(),
+			#[cfg(feature = "pe64")]
+			Self::Pe64(reader) => reader.analyze(),
+		}
+	}
+}
+
+unsafe fn parse_image<Nt: NtHeaders>(image: &[u8], options: ParseOptions) -> Result<PeHeaders<Nt>> {
+	unsafe { PeHeaders::parse_nt_with_size(image.as_ptr(), image.len(), options) }
+}
+
+/// Reads until `buf` is full or the reader is exhausted, returning how much was read.
+fn read_up_to(
+	reader: &mut (impl Read + Seek),
+	offset: u64,
+	buf: &mut [u8],
+) -> std::io::Result<usize> {
+	reader.seek(SeekFrom::Start(offset))?;
+	let mut filled = 0;
+	while filled < buf.len() {
+		match reader.read(&mut buf[filled..]) {
+			Ok(0) => break,
+			Ok(read) => filled += read,
+			Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+			Err(err) => return Err(err),
+		}
+	}
+	Ok(filled)
+}
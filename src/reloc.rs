@@ -0,0 +1,326 @@
+use crate::{
+	check_range,
+	error::{Context, Error, Result, ResultExt},
+	nt::NtHeaders,
+	offsets::FieldSpan,
+	PeHeaders,
+};
+use core::{mem::size_of, slice};
+use object::{
+	pe::{
+		ImageBaseRelocation, IMAGE_DIRECTORY_ENTRY_BASERELOC, IMAGE_FILE_MACHINE_ARM,
+		IMAGE_FILE_MACHINE_ARMNT, IMAGE_FILE_MACHINE_MIPS16, IMAGE_FILE_MACHINE_MIPSFPU,
+		IMAGE_FILE_MACHINE_MIPSFPU16, IMAGE_FILE_MACHINE_R10000, IMAGE_FILE_MACHINE_R3000,
+		IMAGE_FILE_MACHINE_R4000, IMAGE_FILE_MACHINE_RISCV128, IMAGE_FILE_MACHINE_RISCV32,
+		IMAGE_FILE_MACHINE_RISCV64, IMAGE_FILE_MACHINE_THUMB, IMAGE_FILE_MACHINE_WCEMIPSV2,
+		IMAGE_REL_BASED_ABSOLUTE, IMAGE_REL_BASED_DIR64, IMAGE_REL_BASED_HIGH,
+		IMAGE_REL_BASED_HIGHADJ, IMAGE_REL_BASED_HIGHLOW, IMAGE_REL_BASED_LOW,
+	},
+	read::pe::ImageOptionalHeader,
+	LittleEndian,
+};
+
+/// Type of a base relocation, with the machine specific types 5, 7, 8 and 9 resolved by the
+/// image's machine. ARM64 images only use [`RelocType::Dir64`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RelocType {
+	/// Padding to align a block, nothing to apply.
+	Absolute,
+	High,
+	Low,
+	HighLow,
+	/// Takes the low half of the adjustment from the following entry, which is skipped.
+	HighAdj,
+	ArmMov32,
+	ThumbMov32,
+	MipsJmpAddr,
+	MipsJmpAddr16,
+	RiscvHigh20,
+	RiscvLow12I,
+	RiscvLow12S,
+	Dir64,
+	Unknown(u8),
+}
+
+impl RelocType {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn from_raw(typ: u8, machine: u16) -> Self {
+		let arm = matches!(
+			machine,
+			IMAGE_FILE_MACHINE_ARM | IMAGE_FILE_MACHINE_THUMB | IMAGE_FILE_MACHINE_ARMNT
+		);
+		let mips = matches!(
+			machine,
+			IMAGE_FILE_MACHINE_R3000
+				| IMAGE_FILE_MACHINE_R4000
+				| IMAGE_FILE_MACHINE_R10000
+				| IMAGE_FILE_MACHINE_WCEMIPSV2
+				| IMAGE_FILE_MACHINE_MIPS16
+				| IMAGE_FILE_MACHINE_MIPSFPU
+				| IMAGE_FILE_MACHINE_MIPSFPU16
+		);
+		let riscv = matches!(
+			machine,
+			IMAGE_FILE_MACHINE_RISCV32 | IMAGE_FILE_MACHINE_RISCV64 | IMAGE_FILE_MACHINE_RISCV128
+		);
+		match typ as u16 {
+			IMAGE_REL_BASED_ABSOLUTE => Self::Absolute,
+			IMAGE_REL_BASED_HIGH => Self::High,
+			IMAGE_REL_BASED_LOW => Self::Low,
+			IMAGE_REL_BASED_HIGHLOW => Self::HighLow,
+			IMAGE_REL_BASED_HIGHADJ => Self::HighAdj,
+			5 if arm => Self::ArmMov32,
+			5 if mips => Self::MipsJmpAddr,
+			5 if riscv => Self::RiscvHigh20,
+			7 if arm => Self::ThumbMov32,
+			7 if riscv => Self::RiscvLow12I,
+			8 if riscv => Self::RiscvLow12S,
+			9 if mips => Self::MipsJmpAddr16,
+			IMAGE_REL_BASED_DIR64 => Self::Dir64,
+			_ => Self::Unknown(typ),
+		}
+	}
+
+	/// Bytes patched at the target, `None` for [`RelocType::Absolute`] and unknown types.
+	pub fn size(&self) -> Option<usize> {
+		match self {
+			Self::High | Self::Low | Self::HighAdj => Some(2),
+			Self::HighLow
+			| Self::ArmMov32
+			| Self::ThumbMov32
+			| Self::MipsJmpAddr
+			| Self::MipsJmpAddr16
+			| Self::RiscvHigh20
+			| Self::RiscvLow12I
+			| Self::RiscvLow12S => Some(4),
+			Self::Dir64 => Some(8),
+			Self::Absolute | Self::Unknown(_) => None,
+		}
+	}
+}
+
+pub struct RelocationTable {
+	pub data: &'static [u8],
+	pub machine: u16,
+}
+
+impl RelocationTable {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse(address: *const u8, size: usize, machine: u16) -> Self {
+		let data = unsafe { slice::from_raw_parts(address, size) };
+		Self { data, machine }
+	}
+
+	pub fn span(&self, image_base: *const u8) -> Option<FieldSpan> {
+		FieldSpan::of(image_base, self.data)
+	}
+
+	/// Blocks in directory order, stopping after the first malformed one.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn blocks(&self) -> RelocBlocks {
+		RelocBlocks {
+			data: self.data,
+			machine: self.machine,
+			failed: false,
+		}
+	}
+
+	/// All relocations in order, see [`RelocationTable::blocks`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn iter(&self) -> impl Iterator<Item = Result<(RelocType, u32)>> {
+		self.blocks().flat_map(|block| {
+			let (entries, error) = match block {
+				Ok(block) => (Some(block.entries()), None),
+				Err(err) => (None, Some(Err(err))),
+			};
+			entries.into_iter().flatten().map(Ok).chain(error)
+		})
+	}
+}
+
+/// Relocations for one 4 KiB page, as laid out in the directory.
+#[derive(Clone, Copy, Debug)]
+pub struct RelocBlock {
+	pub page_rva: u32,
+	/// Packed entries following the block header, including padding.
+	pub data: &'static [u8],
+	pub machine: u16,
+}
+
+impl RelocBlock {
+	/// `SizeOfBlock`, the header and the packed entries.
+	pub fn size_of_block(&self) -> usize {
+		size_of::<ImageBaseRelocation>() + self.data.len()
+	}
+
+	/// Number of packed entries, including padding and `HighAdj` parameters.
+	pub fn len(&self) -> usize {
+		self.data.len() / size_of::<u16>()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn entries(&self) -> RelocEntries {
+		RelocEntries {
+			page_rva: self.page_rva,
+			entries: self.data.chunks_exact(size_of::<u16>()),
+			machine: self.machine,
+		}
+	}
+}
+
+pub struct RelocBlocks {
+	data: &'static [u8],
+	machine: u16,
+	failed: bool,
+}
+
+impl Iterator for RelocBlocks {
+	type Item = Result<RelocBlock>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		const HEADER_SIZE: usize = size_of::<ImageBaseRelocation>();
+		if self.failed || self.data.len() < HEADER_SIZE {
+			return None;
+		}
+		let page_rva = u32::from_le_bytes(self.data[0..4].try_into().unwrap());
+		let size_of_block = u32::from_le_bytes(self.data[4..8].try_into().unwrap()) as usize;
+		// Trailing zeroes pad the directory to its declared size.
+		if size_of_block == 0 {
+			return None;
+		}
+		if size_of_block < HEADER_SIZE || size_of_block > self.data.len() {
+			self.failed = true;
+			return Some(Err(Error::RelocationTable));
+		}
+		let (block, rest) = self.data.split_at(size_of_block);
+		self.data = rest;
+		Some(Ok(RelocBlock {
+			page_rva,
+			data: &block[HEADER_SIZE..],
+			machine: self.machine,
+		}))
+	}
+}
+
+/// Relocations of one block, skipping [`RelocType::Absolute`] padding and the parameter slot
+/// of [`RelocType::HighAdj`].
+pub struct RelocEntries {
+	page_rva: u32,
+	entries: slice::ChunksExact<'static, u8>,
+	machine: u16,
+}
+
+impl Iterator for RelocEntries {
+	type Item = (RelocType, u32);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let entry = u16::from_le_bytes(self.entries.next()?.try_into().unwrap());
+			let typ = RelocType::from_raw((entry >> 12) as u8, self.machine);
+			match typ {
+				RelocType::Absolute => continue,
+				RelocType::HighAdj => {
+					self.entries.next();
+				}
+				_ => {}
+			}
+			return Some((typ, self.page_rva.wrapping_add((entry & 0xfff) as u32)));
+		}
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// The directory is found according to `options.layout`, like the other `*_mem` loaders.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn relocation_table_mem(
+		&self,
+		image_base: *const u8,
+	) -> Result<RelocationTable, Context> {
+		let reloc_data_dir = self
+			.data_directories
+			.get(IMAGE_DIRECTORY_ENTRY_BASERELOC)
+			.ok_or(Error::RelocationTable)
+			.context("relocation table")?;
+		let reloc_rva = reloc_data_dir.virtual_address.get(LittleEndian);
+		if reloc_rva == 0 {
+			return Err(Error::RelocationTable).context("relocation table");
+		}
+		let reloc_size = reloc_data_dir.size.get(LittleEndian);
+		trace_event!(
+			directory = "base relocation",
+			rva = reloc_rva,
+			size = reloc_size
+		);
+		let reloc_ptr = self
+			.rva_to_ptr(image_base, reloc_rva)
+			.context("relocation table")?;
+		unsafe { check_range(&self.options, reloc_ptr, reloc_size as _) }
+			.context("relocation table")?;
+		let machine = self.nt_header.file_header().machine.get(LittleEndian);
+		Ok(unsafe { RelocationTable::parse(reloc_ptr, reloc_size as _, machine) })
+	}
+
+	/// Adds `delta` to every relocated value of the image at `image_base`, in either layout,
+	/// returning how many were patched. Nothing is written if any relocation has a type other
+	/// than `HighLow`, `Dir64`, `High` or `Low`, or lies past `SizeOfImage`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn apply_relocations(&self, image_base: *mut u8, delta: u64) -> Result<usize> {
+		let relocation_table = unsafe { self.relocation_table_mem(image_base)? };
+		let size_of_image = self.nt_header.optional_header().size_of_image() as u64;
+		for relocation in relocation_table.iter() {
+			let (typ, rva) = relocation?;
+			let size = match typ {
+				RelocType::HighLow | RelocType::Dir64 | RelocType::High | RelocType::Low => {
+					typ.size().unwrap_or(0) as u64
+				}
+				_ => return Err(Error::RelocationTable),
+			};
+			if rva as u64 + size > size_of_image {
+				return Err(Error::RelocationTable);
+			}
+			// In file layout the value must not run past the raw data of its section.
+			let target = self.rva_to_ptr(image_base, rva)?;
+			let last = self.rva_to_ptr(image_base, rva + size as u32 - 1)?;
+			if last != target.wrapping_add(size as usize - 1) {
+				return Err(Error::RelocationTable);
+			}
+			unsafe { check_range(&self.options, target, size as usize)? };
+		}
+
+		let mut count = 0;
+		for relocation in relocation_table.iter() {
+			let (typ, rva) = relocation?;
+			let target = self.rva_to_ptr(image_base, rva)?.cast_mut();
+			unsafe {
+				match typ {
+					RelocType::HighLow => {
+						let target = target.cast::<u32>();
+						let value = u32::from_le(target.read_unaligned());
+						target.write_unaligned(value.wrapping_add(delta as u32).to_le());
+					}
+					RelocType::Dir64 => {
+						let target = target.cast::<u64>();
+						let value = u64::from_le(target.read_unaligned());
+						target.write_unaligned(value.wrapping_add(delta).to_le());
+					}
+					RelocType::High => {
+						let target = target.cast::<u16>();
+						let value = u16::from_le(target.read_unaligned());
+						target.write_unaligned(value.wrapping_add((delta >> 16) as u16).to_le());
+					}
+					RelocType::Low => {
+						let target = target.cast::<u16>();
+						let value = u16::from_le(target.read_unaligned());
+						target.write_unaligned(value.wrapping_add(delta as u16).to_le());
+					}
+					_ => unreachable!(),
+				}
+			}
+			count += 1;
+		}
+		Ok(count)
+	}
+}
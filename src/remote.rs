@@ -0,0 +1,150 @@
+use crate::{
+	error::{Error, Result},
+	hooks::ModuleRange,
+	source::MemorySource,
+	widestring,
+};
+use core::mem::{size_of, zeroed};
+use windows_sys::Win32::{
+	Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE},
+	System::Diagnostics::Debug::ReadProcessMemory,
+	System::Diagnostics::ToolHelp::{
+		CreateToolhelp32Snapshot, Module32FirstW, Module32NextW, MODULEENTRY32W, TH32CS_SNAPMODULE,
+		TH32CS_SNAPMODULE32,
+	},
+};
+
+/// A module of another process, `base` is only meaningful in that address space.
+#[derive(Clone, Copy)]
+pub struct RemoteModule {
+	pub process_id: u32,
+	pub base: usize,
+	pub size: u32,
+	name: [u16; 256],
+	path: [u16; 260],
+}
+
+impl RemoteModule {
+	pub fn name(&self) -> &[u16] {
+		until_nul(&self.name)
+	}
+
+	pub fn path(&self) -> &[u16] {
+		until_nul(&self.path)
+	}
+
+	pub fn contains(&self, address: usize) -> bool {
+		address.wrapping_sub(self.base) < self.size as usize
+	}
+}
+
+impl ModuleRange for RemoteModule {
+	fn base(&self) -> u64 {
+		self.base as u64
+	}
+
+	fn size(&self) -> u32 {
+		self.size
+	}
+
+	fn has_name(&self, name: &str) -> bool {
+		widestring::eq_str_ignore_ascii_case(self.name().iter().copied(), name)
+	}
+}
+
+fn until_nul(units: &[u16]) -> &[u16] {
+	let len = units
+		.iter()
+		.position(|&unit| unit == 0)
+		.unwrap_or(units.len());
+	&units[..len]
+}
+
+/// Walks a Toolhelp module snapshot, including the 32-bit modules of a WOW64 process.
+pub struct RemoteModules {
+	snapshot: HANDLE,
+	entry: MODULEENTRY32W,
+	started: bool,
+}
+
+impl Iterator for RemoteModules {
+	type Item = RemoteModule;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let found = if self.started {
+			unsafe { Module32NextW(self.snapshot, &mut self.entry) }
+		} else {
+			self.started = true;
+			unsafe { Module32FirstW(self.snapshot, &mut self.entry) }
+		};
+		if found == 0 {
+			return None;
+		}
+		Some(RemoteModule {
+			process_id: self.entry.th32ProcessID,
+			base: self.entry.modBaseAddr as usize,
+			size: self.entry.modBaseSize,
+			name: self.entry.szModule,
+			path: self.entry.szExePath,
+		})
+	}
+}
+
+impl Drop for RemoteModules {
+	fn drop(&mut self) {
+		unsafe { CloseHandle(self.snapshot) };
+	}
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub fn remote_modules(process_id: u32) -> Result<RemoteModules> {
+	let snapshot =
+		unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32, process_id) };
+	if snapshot == INVALID_HANDLE_VALUE {
+		return Err(Error::RemoteProcess);
+	}
+	let mut entry: MODULEENTRY32W = unsafe { zeroed() };
+	entry.dwSize = size_of::<MODULEENTRY32W>() as _;
+	Ok(RemoteModules {
+		snapshot,
+		entry,
+		started: false,
+	})
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub fn find_remote_module(process_id: u32, name: &str) -> Result<RemoteModule> {
+	remote_modules(process_id)?
+		.find(|module| widestring::eq_str_ignore_ascii_case(module.name().iter().copied(), name))
+		.ok_or(Error::ModuleNotFound)
+}
+
+/// A process handle opened with `PROCESS_VM_READ`.
+#[derive(Clone, Copy)]
+pub struct RemoteProcess(pub HANDLE);
+
+impl MemorySource for RemoteProcess {
+	fn read(&self, address: u64, buf: &mut [u8]) -> bool {
+		let mut read = 0;
+		let ok = unsafe {
+			ReadProcessMemory(
+				self.0,
+				address as usize as _,
+				buf.as_mut_ptr().cast(),
+				buf.len(),
+				&mut read,
+			)
+		};
+		ok != 0 && read == buf.len()
+	}
+}
+
+/// Copies the mapped image of `module` out of `process`, see [`crate::source::read_image`].
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "debug", inline(never))]
+pub fn read_remote_image(
+	process: RemoteProcess,
+	module: &RemoteModule,
+) -> Result<alloc::vec::Vec<u8>> {
+	crate::source::read_image(&process, module.base as _, module.size).ok_or(Error::RemoteProcess)
+}
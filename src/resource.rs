@@ -0,0 +1,315 @@
+use crate::{
+	check_range,
+	error::{Error, Result},
+	nt::NtHeaders,
+	options::DEFAULT_MAX_RESOURCE_NODES,
+	widestring, PeHeaders,
+};
+use core::{mem::size_of, slice};
+use object::{
+	pe::{
+		ImageResourceDataEntry, ImageResourceDirectory, ImageResourceDirectoryEntry,
+		IMAGE_RESOURCE_DATA_IS_DIRECTORY, IMAGE_RESOURCE_NAME_IS_STRING,
+	},
+	LittleEndian, U16Bytes,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourceId<'a> {
+	Id(u16),
+	Name(&'a str),
+}
+
+#[derive(Clone, Copy)]
+pub enum ResourceName {
+	Id(u16),
+	Name(&'static [U16Bytes<LittleEndian>]),
+}
+
+impl ResourceName {
+	/// Names compare case-insensitively, `FindResource` upper-cases them before looking up.
+	pub fn matches(&self, id: ResourceId) -> bool {
+		match (*self, id) {
+			(ResourceName::Id(a), ResourceId::Id(b)) => a == b,
+			(ResourceName::Name(a), ResourceId::Name(b)) => {
+				widestring::eq_str_ignore_case(widestring::units(a), b)
+			}
+			_ => false,
+		}
+	}
+
+	#[cfg(feature = "alloc")]
+	pub fn to_string_lossy(&self) -> alloc::string::String {
+		match *self {
+			ResourceName::Id(id) => alloc::format!("#{id}"),
+			ResourceName::Name(name) => widestring::to_string_lossy(widestring::units(name)),
+		}
+	}
+}
+
+pub enum ResourceEntryData {
+	Directory(ResourceDirectory),
+	Data(&'static ImageResourceDataEntry),
+}
+
+pub struct ResourceTable {
+	pub start_address: *const u8,
+	pub size: u32,
+	/// Limit of [`ResourceTable::walk`], [`crate::ParseOptions::max_resource_nodes`] when parsed
+	/// through [`PeHeaders`].
+	pub max_nodes: usize,
+}
+
+impl ResourceTable {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn parse(address: *const u8, size: u32) -> Self {
+		Self {
+			start_address: address,
+			size,
+			max_nodes: DEFAULT_MAX_RESOURCE_NODES,
+		}
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn root(&self) -> Result<ResourceDirectory> {
+		unsafe { ResourceDirectory::parse(self.start_address, self.size, 0) }
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn languages(
+		&self,
+		ty: ResourceId,
+		name: ResourceId,
+	) -> Option<impl Iterator<Item = ResourceLanguage> + '_> {
+		let ty_dir = unsafe { self.root().ok()?.find_directory(ty)? };
+		let name_dir = unsafe { ty_dir.find_directory(name)? };
+		Some(name_dir.entries.iter().filter_map(move |entry| {
+			match unsafe { name_dir.entry_data(entry) } {
+				Ok(ResourceEntryData::Data(data)) => Some(ResourceLanguage {
+					lang_id: entry.name_or_id.get(LittleEndian) as u16,
+					code_page: data.code_page.get(LittleEndian),
+					data,
+				}),
+				Ok(ResourceEntryData::Directory(_)) | Err(_) => None,
+			}
+		}))
+	}
+
+	/// The resource at `ty/name/lang`, or the first language if `lang` is `None`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn find(
+		&self,
+		ty: ResourceId,
+		name: ResourceId,
+		lang: Option<u16>,
+	) -> Option<ResourceLanguage> {
+		unsafe { self.languages(ty, name)? }
+			.find(|language| lang.is_none_or(|lang| language.lang_id == lang))
+	}
+
+	/// Calls `visit` with the depth of the containing directory, the root being 0, for every
+	/// data entry. Directories nested `max_depth` levels below the root fail with
+	/// [`Error::LimitExceeded`], which is where loops in crafted trees end up;
+	/// [`crate::options::DEFAULT_MAX_RESOURCE_DEPTH`] leaves room for any real tree. Visiting
+	/// more than [`ResourceTable::max_nodes`] directories and entries fails the same way.
+	/// A directory, entry or data entry outside the table fails with [`Error::ResourceTable`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn walk(
+		&self,
+		max_depth: usize,
+		visit: &mut impl FnMut(
+			usize,
+			&'static ImageResourceDirectoryEntry,
+			&'static ImageResourceDataEntry,
+		),
+	) -> Result<()> {
+		let mut nodes = self.max_nodes;
+		unsafe {
+			walk_directory(
+				self.root()?,
+				0,
+				max_depth,
+				(&mut nodes, self.max_nodes),
+				visit,
+			)
+		}
+	}
+}
+
+unsafe fn walk_directory(
+	directory: ResourceDirectory,
+	depth: usize,
+	max_depth: usize,
+	(nodes, max_nodes): (&mut usize, usize),
+	visit: &mut impl FnMut(usize, &'static ImageResourceDirectoryEntry, &'static ImageResourceDataEntry),
+) -> Result<()> {
+	*nodes = nodes
+		.checked_sub(1 + directory.entries.len())
+		.ok_or(Error::LimitExceeded { limit: max_nodes })?;
+	for entry in directory.entries {
+		match unsafe { directory.entry_data(entry)? } {
+			ResourceEntryData::Directory(_) if depth + 1 >= max_depth => {
+				return Err(Error::LimitExceeded { limit: max_depth })
+			}
+			ResourceEntryData::Directory(subdirectory) => unsafe {
+				walk_directory(
+					subdirectory,
+					depth + 1,
+					max_depth,
+					(nodes, max_nodes),
+					visit,
+				)?
+			},
+			ResourceEntryData::Data(data) => visit(depth, entry, data),
+		}
+	}
+	Ok(())
+}
+
+#[derive(Clone, Copy)]
+pub struct ResourceLanguage {
+	pub lang_id: u16,
+	pub code_page: u32,
+	pub data: &'static ImageResourceDataEntry,
+}
+
+#[derive(Clone, Copy)]
+pub struct ResourceDirectory {
+	pub directory: &'static ImageResourceDirectory,
+	pub entries: &'static [ImageResourceDirectoryEntry],
+	section_address: *const u8,
+	size: u32,
+}
+
+/// Checks that `len` bytes at `offset` lie in a resource table of `size` bytes, offsets in the
+/// table come from the file.
+fn check_in_table(size: u32, offset: usize, len: usize) -> Result<()> {
+	match offset.checked_add(len) {
+		Some(end) if end <= size as usize => Ok(()),
+		_ => Err(Error::ResourceTable),
+	}
+}
+
+impl ResourceDirectory {
+	/// The directory at `offset` in the resource table of `size` bytes at `section_address`,
+	/// which fails with [`Error::ResourceTable`] unless it and its entries lie in the table.
+	///
+	/// # Safety
+	/// `size` bytes at `section_address` have to be readable.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse(section_address: *const u8, size: u32, offset: u32) -> Result<Self> {
+		check_in_table(size, offset as _, size_of::<ImageResourceDirectory>())?;
+		let directory_ptr = section_address.wrapping_add(offset as _);
+		let directory = unsafe { &*directory_ptr.cast::<ImageResourceDirectory>() };
+		let entries_ptr = directory_ptr.wrapping_add(size_of::<ImageResourceDirectory>());
+		let entries_len = directory.number_of_named_entries.get(LittleEndian) as usize
+			+ directory.number_of_id_entries.get(LittleEndian) as usize;
+		check_in_table(
+			size,
+			offset as usize + size_of::<ImageResourceDirectory>(),
+			entries_len * size_of::<ImageResourceDirectoryEntry>(),
+		)?;
+		let entries = unsafe {
+			slice::from_raw_parts(
+				entries_ptr.cast::<ImageResourceDirectoryEntry>(),
+				entries_len,
+			)
+		};
+
+		Ok(Self {
+			directory,
+			entries,
+			section_address,
+			size,
+		})
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn entry_name(&self, entry: &ImageResourceDirectoryEntry) -> Result<ResourceName> {
+		let name_or_id = entry.name_or_id.get(LittleEndian);
+		if name_or_id & IMAGE_RESOURCE_NAME_IS_STRING == 0 {
+			return Ok(ResourceName::Id(name_or_id as u16));
+		}
+		let offset = (name_or_id & !IMAGE_RESOURCE_NAME_IS_STRING) as usize;
+		check_in_table(self.size, offset, size_of::<u16>())?;
+		let string_ptr = self.section_address.wrapping_add(offset);
+		let len = unsafe { (*string_ptr.cast::<U16Bytes<LittleEndian>>()).get(LittleEndian) };
+		check_in_table(
+			self.size,
+			offset + size_of::<u16>(),
+			len as usize * size_of::<u16>(),
+		)?;
+		let chars = unsafe {
+			slice::from_raw_parts(
+				string_ptr
+					.wrapping_add(size_of::<u16>())
+					.cast::<U16Bytes<LittleEndian>>(),
+				len as _,
+			)
+		};
+		Ok(ResourceName::Name(chars))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn entry_data(
+		&self,
+		entry: &ImageResourceDirectoryEntry,
+	) -> Result<ResourceEntryData> {
+		let offset = entry.offset_to_data_or_directory.get(LittleEndian);
+		if offset & IMAGE_RESOURCE_DATA_IS_DIRECTORY != 0 {
+			let offset = offset & !IMAGE_RESOURCE_DATA_IS_DIRECTORY;
+			return Ok(ResourceEntryData::Directory(unsafe {
+				ResourceDirectory::parse(self.section_address, self.size, offset)?
+			}));
+		}
+		check_in_table(self.size, offset as _, size_of::<ImageResourceDataEntry>())?;
+		let data_ptr = self.section_address.wrapping_add(offset as _);
+		Ok(ResourceEntryData::Data(unsafe {
+			&*data_ptr.cast::<ImageResourceDataEntry>()
+		}))
+	}
+
+	/// The first entry named `id`, entries whose name lies outside the table are skipped.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn find(&self, id: ResourceId) -> Option<&'static ImageResourceDirectoryEntry> {
+		self.entries
+			.iter()
+			.find(|entry| unsafe { self.entry_name(entry) }.is_ok_and(|name| name.matches(id)))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn find_directory(&self, id: ResourceId) -> Option<ResourceDirectory> {
+		let entry = unsafe { self.find(id)? };
+		match unsafe { self.entry_data(entry).ok()? } {
+			ResourceEntryData::Directory(directory) => Some(directory),
+			ResourceEntryData::Data(_) => None,
+		}
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// Data of the resource at `ty/name/lang`, e.g.
+	/// `resource(ResourceId::Id(RT_VERSION), ResourceId::Id(1), Some(0x0409))`.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn resource(
+		&self,
+		ty: ResourceId,
+		name: ResourceId,
+		lang: Option<u16>,
+	) -> Option<&'static [u8]> {
+		let resource_table = self.resource_table().ok()?;
+		let language = unsafe { resource_table.find(ty, name, lang)? };
+		self.resource_data(language.data)
+	}
+
+	/// Bytes of a data entry, `OffsetToData` is an RVA and translated according to the layout.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn resource_data(&self, data: &ImageResourceDataEntry) -> Option<&'static [u8]> {
+		let data_ptr = self
+			.rva_to_ptr(self.image_base, data.offset_to_data.get(LittleEndian))
+			.ok()?;
+		let size = data.size.get(LittleEndian) as usize;
+		unsafe { check_range(&self.options, data_ptr, size).ok()? };
+		Some(unsafe { slice::from_raw_parts(data_ptr, size) })
+	}
+}
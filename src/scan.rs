@@ -0,0 +1,70 @@
+use crate::{
+	error::{Error, Result},
+	nt::NativeNtHeaders,
+	HeadersOnly, ParseOptions, PeHeaders,
+};
+use object::read::pe::{ImageNtHeaders, ImageOptionalHeader};
+
+pub const PAGE_SIZE: usize = 0x1000;
+
+/// Walks backwards from `ptr` one page at a time, at most `max_scan` bytes, until a page
+/// starts with a valid MZ/PE pair whose `SizeOfImage` covers `ptr`.
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn find_module_base(ptr: *const u8, max_scan: usize) -> Option<*const u8> {
+	let options = ParseOptions::new();
+	let start = ptr.wrapping_sub(ptr as usize % PAGE_SIZE);
+	let mut scanned = 0;
+	while scanned <= max_scan {
+		let candidate = start.wrapping_sub(scanned);
+		if let Ok(headers) = unsafe { HeadersOnly::<NativeNtHeaders>::parse(candidate, &options) } {
+			let size_of_image = headers.nt_header.optional_header().size_of_image() as usize;
+			if (ptr as usize) - (candidate as usize) < size_of_image {
+				return Some(candidate);
+			}
+		}
+		if (candidate as usize) < PAGE_SIZE {
+			break;
+		}
+		scanned += PAGE_SIZE;
+	}
+	None
+}
+
+/// Upper bound for back-scans started from code addresses, larger than any sane image.
+pub const DEFAULT_MAX_SCAN: usize = 0x1000_0000;
+
+/// Returns the address the call to this function returns to, i.e. a code address in the caller.
+#[cfg(target_arch = "x86_64")]
+#[unsafe(naked)]
+pub extern "C" fn return_address() -> *const u8 {
+	core::arch::naked_asm!("mov rax, [rsp]", "ret")
+}
+
+/// Returns the address the call to this function returns to, i.e. a code address in the caller.
+#[cfg(target_arch = "x86")]
+#[unsafe(naked)]
+pub extern "C" fn return_address() -> *const u8 {
+	core::arch::naked_asm!("mov eax, [esp]", "ret")
+}
+
+#[cfg_attr(feature = "debug", inline(never))]
+pub unsafe fn module_from_address(
+	address: *const u8,
+	max_scan: usize,
+) -> Result<(*const u8, PeHeaders)> {
+	let base = unsafe { find_module_base(address, max_scan) }.ok_or(Error::ModuleNotFound)?;
+	Ok((base, unsafe { PeHeaders::parse(base)? }))
+}
+
+/// Resolves the base and headers of the module whose code expands this macro.
+///
+/// Expands to a call of an `unsafe fn`, so it must be used inside an `unsafe` block.
+#[macro_export]
+macro_rules! caller_module {
+	() => {
+		$crate::scan::module_from_address(
+			$crate::scan::return_address(),
+			$crate::scan::DEFAULT_MAX_SCAN,
+		)
+	};
+}
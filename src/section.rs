@@ -0,0 +1,242 @@
+use crate::error::{Error, Result};
+use object::{
+	pe::{
+		ImageSectionHeader, IMAGE_SCN_ALIGN_MASK, IMAGE_SCN_CNT_CODE,
+		IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_CNT_UNINITIALIZED_DATA, IMAGE_SCN_GPREL,
+		IMAGE_SCN_LNK_COMDAT, IMAGE_SCN_LNK_INFO, IMAGE_SCN_LNK_NRELOC_OVFL, IMAGE_SCN_LNK_OTHER,
+		IMAGE_SCN_LNK_REMOVE, IMAGE_SCN_MEM_DISCARDABLE, IMAGE_SCN_MEM_EXECUTE,
+		IMAGE_SCN_MEM_LOCKED, IMAGE_SCN_MEM_NOT_CACHED, IMAGE_SCN_MEM_NOT_PAGED,
+		IMAGE_SCN_MEM_PRELOAD, IMAGE_SCN_MEM_PURGEABLE, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_SHARED,
+		IMAGE_SCN_MEM_WRITE, IMAGE_SCN_NO_DEFER_SPEC_EXC, IMAGE_SCN_TYPE_NO_PAD,
+	},
+	LittleEndian,
+};
+
+pub fn section_name_bytes(section: &ImageSectionHeader) -> &[u8] {
+	let name = &section.name[..];
+	let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+	&name[..len]
+}
+
+pub fn section_name(section: &ImageSectionHeader) -> Result<&str> {
+	core::str::from_utf8(section_name_bytes(section)).map_err(|_| Error::SectionName)
+}
+
+pub fn section_contains_rva(section: &ImageSectionHeader, rva: u32) -> bool {
+	let start = section.virtual_address.get(LittleEndian);
+	rva >= start && rva - start < section_virtual_size(section)
+}
+
+pub fn section_is_executable(section: &ImageSectionHeader) -> bool {
+	section.characteristics.get(LittleEndian) & (IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_CNT_CODE) != 0
+}
+
+pub fn section_is_readable(section: &ImageSectionHeader) -> bool {
+	section.characteristics.get(LittleEndian) & IMAGE_SCN_MEM_READ != 0
+}
+
+pub fn section_is_writable(section: &ImageSectionHeader) -> bool {
+	section.characteristics.get(LittleEndian) & IMAGE_SCN_MEM_WRITE != 0
+}
+
+/// Discardable sections, e.g. a driver's `INIT`, are freed after initialization and may no
+/// longer be backed in an image taken from memory.
+pub fn section_is_discardable(section: &ImageSectionHeader) -> bool {
+	section.characteristics.get(LittleEndian) & IMAGE_SCN_MEM_DISCARDABLE != 0
+}
+
+pub fn section_virtual_size(section: &ImageSectionHeader) -> u32 {
+	match section.virtual_size.get(LittleEndian) {
+		0 => section.size_of_raw_data.get(LittleEndian),
+		size => size,
+	}
+}
+
+/// Bytes the loader copies from the file, the rest of the virtual size is zero-filled and
+/// raw data past it is never mapped.
+pub fn section_file_size(section: &ImageSectionHeader) -> u32 {
+	let raw_size = section.size_of_raw_data.get(LittleEndian);
+	match section.virtual_size.get(LittleEndian) {
+		0 => raw_size,
+		virtual_size => raw_size.min(virtual_size),
+	}
+}
+
+pub fn section_zero_fill(section: &ImageSectionHeader) -> u32 {
+	section_virtual_size(section).saturating_sub(section.size_of_raw_data.get(LittleEndian))
+}
+
+pub fn section_slack(section: &ImageSectionHeader) -> u32 {
+	section
+		.size_of_raw_data
+		.get(LittleEndian)
+		.saturating_sub(section_virtual_size(section))
+}
+
+/// Virtual size this many times the raw size counts as [`SectionSizeAnomaly::ZeroFilled`].
+pub const ZERO_FILL_RATIO: u32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionSizeAnomaly {
+	/// Mostly zero-filled in memory, typical for the target of an unpacking stub.
+	ZeroFilled { zero_fill: u32 },
+	/// Raw data past the virtual size rounded to the file alignment, which the loader never maps.
+	Slack { slack: u32 },
+}
+
+/// Plain `.bss` style sections without raw data are not anomalies.
+pub fn section_size_anomaly(
+	section: &ImageSectionHeader,
+	file_alignment: u32,
+) -> Option<SectionSizeAnomaly> {
+	let raw_size = section.size_of_raw_data.get(LittleEndian);
+	let virtual_size = section.virtual_size.get(LittleEndian);
+	if raw_size != 0 && virtual_size / raw_size >= ZERO_FILL_RATIO {
+		return Some(SectionSizeAnomaly::ZeroFilled {
+			zero_fill: section_zero_fill(section),
+		});
+	}
+	let aligned_virtual_size = match file_alignment {
+		0 => virtual_size,
+		alignment => virtual_size.checked_next_multiple_of(alignment)?,
+	};
+	if virtual_size != 0 && raw_size > aligned_virtual_size {
+		return Some(SectionSizeAnomaly::Slack {
+			slack: raw_size - virtual_size,
+		});
+	}
+	None
+}
+
+/// Unused space after a section's data, see [`crate::PeHeaders::section_slack_spaces`].
+#[derive(Clone, Copy, Debug)]
+pub struct SectionSlack {
+	pub section: &'static ImageSectionHeader,
+	/// File offset right after the bytes the loader maps.
+	pub file_offset: u32,
+	/// Bytes up to the aligned raw size or the next section's raw data, whichever comes first.
+	pub file_slack: u32,
+	/// RVA right after the virtual size.
+	pub rva: u32,
+	/// Bytes up to the aligned virtual size or the next section, whichever comes first.
+	pub memory_slack: u32,
+}
+
+pub const PAGE_NOACCESS: u32 = 0x01;
+pub const PAGE_READONLY: u32 = 0x02;
+pub const PAGE_READWRITE: u32 = 0x04;
+pub const PAGE_EXECUTE: u32 = 0x10;
+pub const PAGE_EXECUTE_READ: u32 = 0x20;
+pub const PAGE_EXECUTE_READWRITE: u32 = 0x40;
+pub const PAGE_NOCACHE: u32 = 0x200;
+
+/// Translates section characteristics into the `PAGE_*` protection a mapper should apply.
+///
+/// Write access without read access is still mapped readable, the CPU cannot express it.
+pub fn section_protection(section: &ImageSectionHeader) -> u32 {
+	let characteristics = section.characteristics.get(LittleEndian);
+	let execute = characteristics & IMAGE_SCN_MEM_EXECUTE != 0;
+	let read = characteristics & IMAGE_SCN_MEM_READ != 0;
+	let write = characteristics & IMAGE_SCN_MEM_WRITE != 0;
+	let protection = match (execute, read, write) {
+		(false, false, false) => PAGE_NOACCESS,
+		(false, true, false) => PAGE_READONLY,
+		(false, _, true) => PAGE_READWRITE,
+		(true, false, false) => PAGE_EXECUTE,
+		(true, true, false) => PAGE_EXECUTE_READ,
+		(true, _, true) => PAGE_EXECUTE_READWRITE,
+	};
+	if characteristics & IMAGE_SCN_MEM_NOT_CACHED != 0 {
+		protection | PAGE_NOCACHE
+	} else {
+		protection
+	}
+}
+
+/// Section characteristics, displayed as the `IMAGE_SCN_` names without prefix joined by `|`,
+/// e.g. `CODE|EXECUTE|READ`. Unknown bits are appended in hex, no flags at all print `0`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SectionFlags(pub u32);
+
+const SECTION_FLAG_NAMES: &[(u32, &str)] = &[
+	(IMAGE_SCN_TYPE_NO_PAD, "NO_PAD"),
+	(IMAGE_SCN_CNT_CODE, "CODE"),
+	(IMAGE_SCN_CNT_INITIALIZED_DATA, "INITIALIZED_DATA"),
+	(IMAGE_SCN_CNT_UNINITIALIZED_DATA, "UNINITIALIZED_DATA"),
+	(IMAGE_SCN_LNK_OTHER, "LNK_OTHER"),
+	(IMAGE_SCN_LNK_INFO, "LNK_INFO"),
+	(IMAGE_SCN_LNK_REMOVE, "LNK_REMOVE"),
+	(IMAGE_SCN_LNK_COMDAT, "LNK_COMDAT"),
+	(IMAGE_SCN_NO_DEFER_SPEC_EXC, "NO_DEFER_SPEC_EXC"),
+	(IMAGE_SCN_GPREL, "GPREL"),
+	(IMAGE_SCN_MEM_PURGEABLE, "PURGEABLE"),
+	(IMAGE_SCN_MEM_LOCKED, "LOCKED"),
+	(IMAGE_SCN_MEM_PRELOAD, "PRELOAD"),
+	(IMAGE_SCN_LNK_NRELOC_OVFL, "LNK_NRELOC_OVFL"),
+	(IMAGE_SCN_MEM_DISCARDABLE, "DISCARDABLE"),
+	(IMAGE_SCN_MEM_NOT_CACHED, "NOT_CACHED"),
+	(IMAGE_SCN_MEM_NOT_PAGED, "NOT_PAGED"),
+	(IMAGE_SCN_MEM_SHARED, "SHARED"),
+	(IMAGE_SCN_MEM_EXECUTE, "EXECUTE"),
+	(IMAGE_SCN_MEM_READ, "READ"),
+	(IMAGE_SCN_MEM_WRITE, "WRITE"),
+];
+
+impl SectionFlags {
+	pub fn of(section: &ImageSectionHeader) -> Self {
+		Self(section.characteristics.get(LittleEndian))
+	}
+
+	/// Alignment in bytes from the `IMAGE_SCN_ALIGN_*` field, only meaningful in objects.
+	pub fn alignment(&self) -> Option<u32> {
+		match (self.0 & IMAGE_SCN_ALIGN_MASK) >> 20 {
+			0 => None,
+			exponent => Some(1 << (exponent - 1)),
+		}
+	}
+}
+
+impl core::fmt::Display for SectionFlags {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		let mut rest = self.0;
+		let mut separator = "";
+		for &(flag, name) in SECTION_FLAG_NAMES {
+			if rest & flag != 0 {
+				write!(f, "{separator}{name}")?;
+				separator = "|";
+				rest &= !flag;
+			}
+		}
+		if let Some(alignment) = self.alignment() {
+			write!(f, "{separator}ALIGN_{alignment}BYTES")?;
+			separator = "|";
+			rest &= !IMAGE_SCN_ALIGN_MASK;
+		}
+		match (rest, separator) {
+			(0, "") => f.write_str("0"),
+			(0, _) => Ok(()),
+			(rest, separator) => write!(f, "{separator}{rest:#x}"),
+		}
+	}
+}
+
+/// Position of an address in a mapped module, formatted as `.text+0x1a2b`.
+#[derive(Clone, Copy, Debug)]
+pub struct Location {
+	/// `None` for the headers and gaps between sections.
+	pub section: Option<&'static ImageSectionHeader>,
+	pub rva: u32,
+	/// Offset from the start of `section`, the RVA if there is none.
+	pub section_offset: u32,
+}
+
+impl core::fmt::Display for Location {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		if let Some(section) = self.section {
+			for chunk in section_name_bytes(section).utf8_chunks() {
+				f.write_str(chunk.valid())?;
+			}
+		}
+		write!(f, "+{:#x}", self.section_offset)
+	}
+}
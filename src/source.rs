@@ -0,0 +1,26 @@
+#[cfg(feature = "alloc")]
+use crate::scan::PAGE_SIZE;
+
+/// An address space other than our own, e.g. a minidump or another process.
+pub trait MemorySource {
+	/// Copies `address..address + buf.len()` into `buf`, returning whether every byte was available.
+	fn read(&self, address: u64, buf: &mut [u8]) -> bool;
+}
+
+/// Copies the image at `base` page by page, zero-filling pages `source` lacks so the copy parses in
+/// mapped layout. `None` when not a single page was available.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "debug", inline(never))]
+pub fn read_image(source: &impl MemorySource, base: u64, size: u32) -> Option<alloc::vec::Vec<u8>> {
+	let mut image = alloc::vec![0u8; size as usize];
+	let mut any_read = false;
+	for (index, page) in image.chunks_mut(PAGE_SIZE).enumerate() {
+		let address = base.checked_add((index * PAGE_SIZE) as u64)?;
+		if source.read(address, page) {
+			any_read = true;
+		} else {
+			page.fill(0);
+		}
+	}
+	any_read.then_some(image)
+}
@@ -0,0 +1,131 @@
+use crate::{
+	check_range,
+	error::{Error, Result},
+	rva_ptr, section, ParseOptions,
+};
+use core::{mem::size_of, slice};
+use object::{
+	pe::{ImageDataDirectory, ImageSectionHeader},
+	LittleEndian, U16, U32, U64,
+};
+
+/// `"VZ"`
+pub const TE_SIGNATURE: u16 = 0x5a56;
+
+pub const TE_DIRECTORY_ENTRY_BASERELOC: usize = 0;
+pub const TE_DIRECTORY_ENTRY_DEBUG: usize = 1;
+
+/// `EFI_TE_IMAGE_HEADER` from the UEFI PI specification.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct TeHeader {
+	pub signature: U16<LittleEndian>,
+	pub machine: U16<LittleEndian>,
+	pub number_of_sections: u8,
+	pub subsystem: u8,
+	/// Bytes of DOS and NT headers removed from the front of the original PE.
+	pub stripped_size: U16<LittleEndian>,
+	pub address_of_entry_point: U32<LittleEndian>,
+	pub base_of_code: U32<LittleEndian>,
+	pub image_base: U64<LittleEndian>,
+	pub data_directories: [ImageDataDirectory; 2],
+}
+
+/// A Terse Executable. RVAs still refer to the original PE, whose headers were replaced by the
+/// smaller TE header.
+pub struct TeImage {
+	pub header: &'static TeHeader,
+	pub section_headers: &'static [ImageSectionHeader],
+	pub address: *const u8,
+}
+
+impl TeImage {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn parse(address: *const u8, options: &ParseOptions) -> Result<Self> {
+		unsafe { check_range(options, address, size_of::<TeHeader>())? };
+		let header = unsafe { &*address.cast::<TeHeader>() };
+		if header.signature.get(LittleEndian) != TE_SIGNATURE {
+			return Err(Error::TeHeader);
+		}
+		if (header.stripped_size.get(LittleEndian) as usize) < size_of::<TeHeader>() {
+			return Err(Error::TeHeader);
+		}
+		let section_headers_ptr = address.wrapping_add(size_of::<TeHeader>());
+		let num_section_headers = options
+			.limit(header.number_of_sections as _, options.max_sections)
+			.ok_or(Error::TeHeader)?;
+		unsafe {
+			check_range(
+				options,
+				section_headers_ptr,
+				num_section_headers * size_of::<ImageSectionHeader>(),
+			)?
+		};
+		let section_headers = unsafe {
+			slice::from_raw_parts(
+				section_headers_ptr.cast::<ImageSectionHeader>(),
+				num_section_headers,
+			)
+		};
+
+		Ok(Self {
+			header,
+			section_headers,
+			address,
+		})
+	}
+
+	/// Distance between an RVA and its offset from the TE header.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn rva_adjustment(&self) -> u32 {
+		self.header.stripped_size.get(LittleEndian) as u32 - size_of::<TeHeader>() as u32
+	}
+
+	/// The base the TE header itself is loaded at, `ImageBase` still describes the original PE.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn adjusted_image_base(&self) -> u64 {
+		self.header
+			.image_base
+			.get(LittleEndian)
+			.wrapping_add(self.rva_adjustment() as u64)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn rva_to_ptr(&self, rva: u32) -> Result<*const u8> {
+		let offset = rva
+			.checked_sub(self.rva_adjustment())
+			.ok_or(Error::RvaOverflow)?;
+		rva_ptr(self.address, offset as _)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn data_directory(&self, index: usize) -> Option<&'static ImageDataDirectory> {
+		self.header
+			.data_directories
+			.get(index)
+			.filter(|data_dir| data_dir.virtual_address.get(LittleEndian) != 0)
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn section_for_rva(&self, rva: u32) -> Option<&'static ImageSectionHeader> {
+		self.section_headers
+			.iter()
+			.find(|section| section::section_contains_rva(section, rva))
+	}
+
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn entry_point(&self) -> Result<Option<*const u8>> {
+		match self.header.address_of_entry_point.get(LittleEndian) {
+			0 => Ok(None),
+			rva => self.rva_to_ptr(rva).map(Some),
+		}
+	}
+
+	/// File data of `section`, `PointerToRawData` is adjusted like an RVA.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn section_data(&self, section: &ImageSectionHeader) -> Result<&'static [u8]> {
+		let ptr = self.rva_to_ptr(section.pointer_to_raw_data.get(LittleEndian))?;
+		let size = section::section_file_size(section);
+		Ok(unsafe { slice::from_raw_parts(ptr, size as _) })
+	}
+}
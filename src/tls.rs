@@ -0,0 +1,274 @@
+use crate::{
+	error::{Error, Result},
+	nt::{self, NativeTlsDirectory, NtHeaders, TlsDirectory},
+	options::DEFAULT_MAX_TLS_CALLBACKS,
+	section, PeHeaders, TlsDir,
+};
+use object::LittleEndian;
+
+/// The null-terminated callback array of a mapped image, for editing it in place.
+///
+/// With the `patch` feature on Windows, every write makes the slots it touches (and the TLS
+/// directory in [`TlsCallbackArray::relocate`]) writable meanwhile; without it they have to be
+/// writable already. Callbacks added after a module was loaded only run for later thread and
+/// process events.
+pub struct TlsCallbackArray<T: TlsDirectory = NativeTlsDirectory> {
+	tls_dir: *mut T,
+	array: *mut u8,
+	len: usize,
+	/// Slots including the terminator that may be written.
+	capacity: usize,
+	/// Zero slots from the terminator up to the next non-zero one or the end of the section.
+	zeroed: usize,
+}
+
+/// Makes `len` bytes at `address` writable until dropped.
+#[cfg(all(windows, feature = "patch"))]
+unsafe fn writable(address: *const u8, len: usize) -> Result<impl Sized> {
+	unsafe { crate::patch::unprotect(address, len) }
+}
+
+/// Without the `patch` feature the caller makes the memory writable.
+#[cfg(not(all(windows, feature = "patch")))]
+unsafe fn writable(_address: *const u8, _len: usize) -> Result<impl Sized> {
+	Ok(())
+}
+
+impl<T: TlsDirectory> TlsCallbackArray<T> {
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Slots available for callbacks without relocating, one less than the slots including the
+	/// terminator. The array is full until [`TlsCallbackArray::reserve`] or
+	/// [`TlsCallbackArray::relocate`].
+	pub fn capacity(&self) -> usize {
+		self.capacity.saturating_sub(1)
+	}
+
+	/// Lets [`TlsCallbackArray::push`] use `spare` zero slots after the terminator, padding the
+	/// caller knows to be unused, e.g. reserved after `.CRT$XL*` when building the image.
+	///
+	/// Fails if those slots are not all zero or run past the end of the section.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn reserve(&mut self, spare: usize) -> Result<()> {
+		let capacity = self.len + 1 + spare;
+		if capacity > self.zeroed + self.len {
+			return Err(Error::TlsTable);
+		}
+		self.capacity = self.capacity.max(capacity);
+		Ok(())
+	}
+
+	/// Start of the array, null when the image has no callbacks.
+	pub fn as_ptr(&self) -> *const u8 {
+		self.array
+	}
+
+	fn slot(&self, index: usize) -> *mut u8 {
+		self.array.wrapping_add(index * T::POINTER_SIZE)
+	}
+
+	pub fn get(&self, index: usize) -> Option<u64> {
+		(index < self.len).then(|| unsafe { nt::read_va::<T>(self.slot(index)) })
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+		(0..self.len).map(|index| unsafe { nt::read_va::<T>(self.slot(index)) })
+	}
+
+	/// Replaces a callback, returning the old VA.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn replace(&mut self, index: usize, va: u64) -> Result<u64> {
+		let old = self.get(index).ok_or(Error::TlsTable)?;
+		let _guard = unsafe { writable(self.slot(index), T::POINTER_SIZE)? };
+		unsafe { nt::write_va::<T>(self.slot(index), va) };
+		Ok(old)
+	}
+
+	/// Removes a callback and moves the following ones down, returning the removed VA.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn remove(&mut self, index: usize) -> Result<u64> {
+		let old = self.get(index).ok_or(Error::TlsTable)?;
+		let _guard = unsafe { writable(self.slot(index), (self.len - index) * T::POINTER_SIZE)? };
+		for next in index + 1..self.len {
+			let va = unsafe { nt::read_va::<T>(self.slot(next)) };
+			unsafe { nt::write_va::<T>(self.slot(next - 1), va) };
+		}
+		self.len -= 1;
+		unsafe { nt::write_va::<T>(self.slot(self.len), 0) };
+		self.zeroed += 1;
+		Ok(old)
+	}
+
+	/// Appends a callback in place, failing once [`TlsCallbackArray::capacity`] is used up.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn push(&mut self, va: u64) -> Result<()> {
+		if self.len >= self.capacity() || va == 0 {
+			return Err(Error::TlsTable);
+		}
+		let _guard = unsafe { writable(self.slot(self.len), 2 * T::POINTER_SIZE)? };
+		unsafe { nt::write_va::<T>(self.slot(self.len), va) };
+		self.len += 1;
+		unsafe { nt::write_va::<T>(self.slot(self.len), 0) };
+		self.zeroed -= 1;
+		Ok(())
+	}
+
+	/// Copies the callbacks into `storage`, zeroes the rest of it and points `AddressOfCallBacks`
+	/// at `storage_va`, the address of `storage` as the loaded image sees it.
+	///
+	/// `storage` has to outlive the module and hold the callbacks plus the terminator.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn relocate(&mut self, storage: &'static mut [u8], storage_va: u64) -> Result<()> {
+		let capacity = storage.len() / T::POINTER_SIZE;
+		if capacity <= self.len {
+			return Err(Error::TlsTable);
+		}
+		let array = storage.as_mut_ptr();
+		for (index, va) in self.iter().enumerate() {
+			unsafe { nt::write_va::<T>(array.wrapping_add(index * T::POINTER_SIZE), va) };
+		}
+		storage[self.len * T::POINTER_SIZE..].fill(0);
+		let _guard = unsafe { writable(self.tls_dir.cast(), size_of::<T>())? };
+		unsafe { (*self.tls_dir).set_address_of_call_backs(storage_va) };
+		self.array = array;
+		self.capacity = capacity;
+		self.zeroed = capacity - self.len;
+		Ok(())
+	}
+}
+
+impl<T: TlsDirectory> TlsDir<T> {
+	/// Copies the template `StartAddressOfRawData..EndAddressOfRawData` of the image mapped at
+	/// `image_base`, whose VAs are relative to `loaded_base`, to `dest` and zeroes
+	/// `SizeOfZeroFill` bytes after it, returning the size of the whole TLS block.
+	///
+	/// `dest` has to hold [`TlsDir::block_size`] bytes.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn copy_template(
+		&self,
+		image_base: *const u8,
+		loaded_base: u64,
+		dest: *mut u8,
+	) -> Result<usize> {
+		let template_size = self.template_size()?;
+		let zero_fill = self.tls_dir.size_of_zero_fill() as usize;
+		if template_size != 0 {
+			let offset = self
+				.tls_dir
+				.start_address_of_raw_data()
+				.wrapping_sub(loaded_base);
+			let template = image_base.wrapping_add(offset as usize);
+			unsafe { core::ptr::copy_nonoverlapping(template, dest, template_size) };
+		}
+		unsafe { dest.add(template_size).write_bytes(0, zero_fill) };
+		Ok(template_size + zero_fill)
+	}
+
+	/// Writes the TLS slot allocated for the module to the `u32` at `AddressOfIndex`.
+	///
+	/// The store is volatile; the page has to be writable, see `patch::unprotect` on a
+	/// module whose protection was already applied.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn set_index(
+		&self,
+		image_base: *mut u8,
+		loaded_base: u64,
+		index: u32,
+	) -> Result<()> {
+		let va = self.tls_dir.address_of_index();
+		if va == 0 {
+			return Err(Error::TlsTable);
+		}
+		let slot = image_base.wrapping_add(va.wrapping_sub(loaded_base) as usize);
+		unsafe { slot.cast::<u32>().write_volatile(index.to_le()) };
+		Ok(())
+	}
+
+	/// Bytes of the TLS block a thread gets, the template followed by the zero fill.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn block_size(&self) -> Result<usize> {
+		self.template_size()?
+			.checked_add(self.tls_dir.size_of_zero_fill() as usize)
+			.ok_or(Error::TlsTable)
+	}
+
+	fn template_size(&self) -> Result<usize> {
+		let size = self
+			.tls_dir
+			.end_address_of_raw_data()
+			.checked_sub(self.tls_dir.start_address_of_raw_data())
+			.ok_or(Error::TlsTable)?;
+		usize::try_from(size).map_err(|_| Error::TlsTable)
+	}
+}
+
+impl<Nt: NtHeaders> PeHeaders<Nt> {
+	/// The callback array of the image mapped at `image_base`, whose VAs are relative to
+	/// `loaded_base`. `None` without a TLS directory, an error if the array lies outside the
+	/// sections, e.g. after [`TlsCallbackArray::relocate`].
+	///
+	/// Zero slots after the terminator may be linker padding or zeroed data of the section, so
+	/// none of them are used until reserved with [`TlsCallbackArray::reserve`].
+	///
+	/// Arrays of more than [`DEFAULT_MAX_TLS_CALLBACKS`] callbacks fail with
+	/// [`Error::LimitExceeded`].
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub unsafe fn tls_callback_array(
+		&self,
+		image_base: *mut u8,
+		loaded_base: u64,
+	) -> Result<Option<TlsCallbackArray<Nt::TlsDirectory>>> {
+		let Some(tls_dir) = (unsafe { self.tls_table_mem(image_base)? }) else {
+			return Ok(None);
+		};
+		let tls_dir = (tls_dir.tls_dir as *const Nt::TlsDirectory).cast_mut();
+		let pointer_size = <Nt::TlsDirectory as TlsDirectory>::POINTER_SIZE;
+		let callbacks_va = unsafe { (*tls_dir).address_of_call_backs() };
+		if callbacks_va == 0 {
+			return Ok(Some(TlsCallbackArray {
+				tls_dir,
+				array: core::ptr::null_mut(),
+				len: 0,
+				capacity: 0,
+				zeroed: 0,
+			}));
+		}
+
+		let rva =
+			u32::try_from(callbacks_va.wrapping_sub(loaded_base)).map_err(|_| Error::TlsTable)?;
+		let section = self.section_for_rva(rva).ok_or(Error::TlsTable)?;
+		let section_end = section.virtual_address.get(LittleEndian) as usize
+			+ section::section_virtual_size(section) as usize;
+		let slots = (section_end - rva as usize) / pointer_size;
+		let array = image_base.wrapping_add(rva as usize);
+		let read = |index: usize| unsafe {
+			nt::read_va::<Nt::TlsDirectory>(array.wrapping_add(index * pointer_size))
+		};
+		let len = (0..slots.min(DEFAULT_MAX_TLS_CALLBACKS + 1))
+			.position(|index| read(index) == 0)
+			.ok_or(if slots > DEFAULT_MAX_TLS_CALLBACKS {
+				Error::LimitExceeded {
+					limit: DEFAULT_MAX_TLS_CALLBACKS,
+				}
+			} else {
+				Error::TlsTable
+			})?;
+		let zeroed = (len..slots)
+			.position(|index| read(index) != 0)
+			.unwrap_or(slots - len);
+
+		Ok(Some(TlsCallbackArray {
+			tls_dir,
+			array,
+			len,
+			capacity: len + 1,
+			zeroed,
+		}))
+	}
+}
@@ -0,0 +1,82 @@
+use crate::error::{Error, Result};
+use core::char::{decode_utf16, REPLACEMENT_CHARACTER};
+use object::{LittleEndian, U16Bytes};
+
+pub fn units(string: &[U16Bytes<LittleEndian>]) -> impl Iterator<Item = u16> + '_ {
+	string.iter().map(|unit| unit.get(LittleEndian))
+}
+
+/// Iterates UTF-16LE code units stored as raw bytes, ignoring a trailing odd byte.
+pub fn units_from_bytes(bytes: &[u8]) -> impl Iterator<Item = u16> + '_ {
+	bytes
+		.chunks_exact(2)
+		.map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+}
+
+pub fn chars_lossy(units: impl IntoIterator<Item = u16>) -> impl Iterator<Item = char> {
+	decode_utf16(units).map(|c| c.unwrap_or(REPLACEMENT_CHARACTER))
+}
+
+pub fn eq_str(units: impl IntoIterator<Item = u16>, string: &str) -> bool {
+	units.into_iter().eq(string.encode_utf16())
+}
+
+/// Case-insensitive comparison for ASCII letters, like module names in the loader.
+pub fn eq_str_ignore_ascii_case(units: impl IntoIterator<Item = u16>, string: &str) -> bool {
+	let fold = |unit: u16| match unit {
+		0x41..=0x5A => unit + 0x20,
+		_ => unit,
+	};
+	units
+		.into_iter()
+		.map(fold)
+		.eq(string.encode_utf16().map(fold))
+}
+
+/// Uppercase of a UTF-16 code unit when it is a single code unit, like `RtlUpcaseUnicodeChar`.
+/// Surrogates and characters whose uppercase takes several units are left as they are.
+pub fn upcase(unit: u16) -> u16 {
+	let Some(c) = char::from_u32(unit as u32) else {
+		return unit;
+	};
+	let mut upper = c.to_uppercase();
+	match (upper.next(), upper.next()) {
+		(Some(upper), None) => u16::try_from(upper as u32).unwrap_or(unit),
+		_ => unit,
+	}
+}
+
+/// Case-insensitive comparison of every code unit through [`upcase`], like resource names in
+/// `FindResource`.
+pub fn eq_str_ignore_case(units: impl IntoIterator<Item = u16>, string: &str) -> bool {
+	units
+		.into_iter()
+		.map(upcase)
+		.eq(string.encode_utf16().map(upcase))
+}
+
+#[cfg(feature = "alloc")]
+pub fn to_string(units: impl IntoIterator<Item = u16>) -> Result<alloc::string::String> {
+	decode_utf16(units)
+		.collect::<core::result::Result<alloc::string::String, _>>()
+		.map_err(|_| Error::Utf16)
+}
+
+#[cfg(feature = "alloc")]
+pub fn to_string_lossy(units: impl IntoIterator<Item = u16>) -> alloc::string::String {
+	chars_lossy(units).collect()
+}
+
+#[cfg(all(feature = "std", windows))]
+pub fn to_os_string(units: impl IntoIterator<Item = u16>) -> std::ffi::OsString {
+	use std::os::windows::ffi::OsStringExt;
+	let units: Vec<u16> = units.into_iter().collect();
+	std::ffi::OsString::from_wide(&units)
+}
+
+pub fn validate(units: impl IntoIterator<Item = u16>) -> Result<()> {
+	match decode_utf16(units).all(|c| c.is_ok()) {
+		true => Ok(()),
+		false => Err(Error::Utf16),
+	}
+}
@@ -0,0 +1,463 @@
+use crate::{
+	error::{Error, Result},
+	is_import_terminator,
+	nt::{NativeNtHeaders, NtHeaders},
+	offsets::{self, HeaderField},
+	ParseOptions, PeHeaders,
+};
+use alloc::{vec, vec::Vec};
+use core::{
+	marker::PhantomData,
+	mem::{offset_of, size_of},
+};
+use object::{
+	bytes_of,
+	pe::{
+		ImageDebugDirectory, ImageDosHeader, ImageImportDescriptor, ImageSectionHeader,
+		IMAGE_DEBUG_TYPE_CODEVIEW, IMAGE_DIRECTORY_ENTRY_BOUND_IMPORT, IMAGE_DIRECTORY_ENTRY_DEBUG,
+		IMAGE_DIRECTORY_ENTRY_IMPORT, IMAGE_DIRECTORY_ENTRY_SECURITY,
+		IMAGE_SCN_CNT_INITIALIZED_DATA, IMAGE_SCN_MEM_READ, IMAGE_SCN_MEM_WRITE,
+	},
+	pod::{from_bytes, slice_from_bytes},
+	read::pe::{ImageNtHeaders, ImageOptionalHeader},
+	LittleEndian, U32Bytes, U16, U32,
+};
+
+/// Name of the section [`PeWriter::inject_import`] adds.
+pub const IMPORT_SECTION_NAME: &[u8] = b".idata2";
+
+/// Edits an owned PE file in file layout, for changes that move or grow its contents.
+///
+/// The Authenticode signature is dropped by edits that invalidate it, `CheckSum` is left alone.
+pub struct PeWriter<Nt: NtHeaders = NativeNtHeaders> {
+	data: Vec<u8>,
+	nt: PhantomData<Nt>,
+}
+
+impl<Nt: NtHeaders> PeWriter<Nt> {
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn new(data: Vec<u8>) -> Result<Self> {
+		let writer = Self {
+			data,
+			nt: PhantomData,
+		};
+		writer.headers()?;
+		Ok(writer)
+	}
+
+	pub fn data(&self) -> &[u8] {
+		&self.data
+	}
+
+	pub fn into_inner(self) -> Vec<u8> {
+		self.data
+	}
+
+	/// Headers parsed from the current contents, they must not outlive the next edit.
+	fn headers(&self) -> Result<PeHeaders<Nt>> {
+		unsafe {
+			PeHeaders::parse_file_raw(self.data.as_ptr(), self.data.len(), ParseOptions::new())
+		}
+	}
+
+	fn write(&mut self, offset: usize, bytes: &[u8]) -> Result<()> {
+		self.data
+			.get_mut(offset..offset + bytes.len())
+			.ok_or(Error::PeHeaders)?
+			.copy_from_slice(bytes);
+		Ok(())
+	}
+
+	fn write_field(
+		&mut self,
+		headers: &PeHeaders<Nt>,
+		field: HeaderField,
+		value: u64,
+	) -> Result<()> {
+		let span = headers.field_span(field).ok_or(Error::PeHeaders)?;
+		self.write(span.offset, &value.to_le_bytes()[..span.len])
+	}
+
+	fn write_data_directory(
+		&mut self,
+		headers: &PeHeaders<Nt>,
+		index: usize,
+		virtual_address: u32,
+		size: u32,
+	) -> Result<()> {
+		if index >= headers.data_directories.len() {
+			return Ok(());
+		}
+		let span = headers
+			.field_span(HeaderField::DataDirectory(index))
+			.ok_or(Error::PeHeaders)?;
+		self.write(span.offset, &virtual_address.to_le_bytes())?;
+		self.write(span.offset + size_of::<u32>(), &size.to_le_bytes())
+	}
+
+	/// Clears the security directory and removes the certificate table it points to when that
+	/// ends the file, past the raw data of every section.
+	fn drop_signature(&mut self, headers: &PeHeaders<Nt>) -> Result<()> {
+		let certificates = headers
+			.data_directory(IMAGE_DIRECTORY_ENTRY_SECURITY)
+			.map(|data_dir| {
+				let offset = data_dir.virtual_address.get(LittleEndian) as usize;
+				(
+					offset,
+					offset.saturating_add(data_dir.size.get(LittleEndian) as usize),
+				)
+			});
+		self.write_data_directory(headers, IMAGE_DIRECTORY_ENTRY_SECURITY, 0, 0)?;
+		let Some((offset, end)) = certificates else {
+			return Ok(());
+		};
+		let raw_end = headers
+			.section_headers
+			.iter()
+			.map(|section| {
+				section.pointer_to_raw_data.get(LittleEndian) as usize
+					+ section.size_of_raw_data.get(LittleEndian) as usize
+			})
+			.fold(
+				headers.nt_header.optional_header().size_of_headers() as usize,
+				usize::max,
+			);
+		// Entries are padded to 8 bytes, the last one possibly not.
+		if offset >= raw_end && end <= self.data.len() && end.next_multiple_of(8) >= self.data.len()
+		{
+			self.data.truncate(offset);
+		}
+		Ok(())
+	}
+
+	/// Appends a section holding `contents` after the last one and returns its RVA.
+	///
+	/// The section header has to fit between the section table and `SizeOfHeaders` without
+	/// overlapping raw data. Data past the last section is kept after the new section, except
+	/// for the certificate table, which is dropped with the security directory.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn add_section(
+		&mut self,
+		name: &[u8],
+		characteristics: u32,
+		contents: &[u8],
+	) -> Result<u32> {
+		if name.len() > 8 {
+			return Err(Error::SectionName);
+		}
+		let headers = self.headers()?;
+		let optional_header = headers.nt_header.optional_header();
+		let file_alignment = optional_header.file_alignment().max(1);
+		let section_alignment = optional_header.section_alignment().max(1);
+		let number_of_sections = headers.section_headers.len();
+
+		let header_offset = offsets::section_table_entry_offset(
+			offsets::nt_headers_offset(headers.dos_header.e_lfanew.get(LittleEndian)),
+			headers
+				.nt_header
+				.file_header()
+				.size_of_optional_header
+				.get(LittleEndian) as _,
+			number_of_sections,
+		);
+		let first_raw_data = headers
+			.section_headers
+			.iter()
+			.filter(|section| section.size_of_raw_data.get(LittleEndian) != 0)
+			.map(|section| section.pointer_to_raw_data.get(LittleEndian) as usize)
+			.fold(optional_header.size_of_headers() as usize, usize::min);
+		if header_offset + size_of::<ImageSectionHeader>() > first_raw_data {
+			return Err(Error::NoHeaderSpace);
+		}
+
+		let virtual_address = headers.compute_size_of_image().ok_or(Error::RvaOverflow)?;
+		let virtual_size = u32::try_from(contents.len()).map_err(|_| Error::RvaOverflow)?;
+		let size_of_image = virtual_address
+			.checked_add(virtual_size)
+			.and_then(|end| end.checked_next_multiple_of(section_alignment))
+			.ok_or(Error::RvaOverflow)?;
+		let size_of_raw_data = virtual_size
+			.checked_next_multiple_of(file_alignment)
+			.ok_or(Error::RvaOverflow)?;
+		let raw_end = headers
+			.section_headers
+			.iter()
+			.map(|section| {
+				section.pointer_to_raw_data.get(LittleEndian) as usize
+					+ section.size_of_raw_data.get(LittleEndian) as usize
+			})
+			.fold(optional_header.size_of_headers() as usize, usize::max)
+			.min(self.data.len());
+		let pointer_to_raw_data = raw_end
+			.checked_next_multiple_of(file_alignment as usize)
+			.and_then(|offset| u32::try_from(offset).ok())
+			.ok_or(Error::RvaOverflow)?;
+
+		self.write_field(
+			&headers,
+			HeaderField::NumberOfSections,
+			number_of_sections as u64 + 1,
+		)?;
+		self.write_field(&headers, HeaderField::SizeOfImage, size_of_image.into())?;
+		self.drop_signature(&headers)?;
+
+		let mut section_name = [0u8; 8];
+		section_name[..name.len()].copy_from_slice(name);
+		let header = ImageSectionHeader {
+			name: section_name,
+			virtual_size: U32::new(LittleEndian, virtual_size),
+			virtual_address: U32::new(LittleEndian, virtual_address),
+			size_of_raw_data: U32::new(LittleEndian, size_of_raw_data),
+			pointer_to_raw_data: U32::new(LittleEndian, pointer_to_raw_data),
+			pointer_to_relocations: U32::default(),
+			pointer_to_linenumbers: U32::default(),
+			number_of_relocations: U16::default(),
+			number_of_linenumbers: U16::default(),
+			characteristics: U32::new(LittleEndian, characteristics),
+		};
+		self.write(header_offset, bytes_of(&header))?;
+
+		let mut raw_data = vec![0u8; size_of_raw_data as usize];
+		raw_data[..contents.len()].copy_from_slice(contents);
+		let pointer_to_raw_data = pointer_to_raw_data as usize;
+		let overlay = self.data.split_off(raw_end);
+		self.data.resize(pointer_to_raw_data, 0);
+		self.data.extend_from_slice(&raw_data);
+		self.data.extend_from_slice(&overlay);
+		Ok(virtual_address)
+	}
+
+	/// Makes the image import `function` from `dll` by copying the import descriptors to a new
+	/// section, followed by a descriptor for `dll` with its own thunks and names.
+	///
+	/// The existing thunks stay where they are, so RVAs into the IAT remain valid. Bound imports
+	/// are cleared since the loader would trust stale bindings.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn inject_import(&mut self, dll: &str, function: &str) -> Result<()> {
+		const DESCRIPTOR_SIZE: usize = size_of::<ImageImportDescriptor>();
+		let thunk_size = size_of::<<Nt as ImageNtHeaders>::ImageThunkData>();
+
+		let headers = self.headers()?;
+		let mut descriptors = Vec::new();
+		if let Some(data_dir) = headers.data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT) {
+			let image_base = self.data.as_ptr();
+			let rva = data_dir.virtual_address.get(LittleEndian);
+			let offset = headers.rva_to_ptr(image_base, rva)? as usize - image_base as usize;
+			let table = self.data.get(offset..).ok_or(Error::ImportTable)?;
+			let mut chunks = table.chunks_exact(DESCRIPTOR_SIZE);
+			loop {
+				let bytes = chunks.next().ok_or(Error::ImportTable)?;
+				let (descriptor, _) =
+					from_bytes::<ImageImportDescriptor>(bytes).map_err(|_| Error::ImportTable)?;
+				if is_import_terminator(descriptor) {
+					break;
+				}
+				descriptors.extend_from_slice(bytes);
+			}
+		}
+		let descriptors_size = descriptors.len() + 2 * DESCRIPTOR_SIZE;
+
+		// Descriptors, then the lookup and address tables, then the names.
+		let lookup_offset = descriptors_size.next_multiple_of(thunk_size);
+		let iat_offset = lookup_offset + 2 * thunk_size;
+		let hint_name_offset = iat_offset + 2 * thunk_size;
+		let dll_offset =
+			(hint_name_offset + size_of::<u16>() + function.len() + 1).next_multiple_of(2);
+		let mut contents = vec![0u8; dll_offset + dll.len() + 1];
+		contents[..descriptors.len()].copy_from_slice(&descriptors);
+		contents[hint_name_offset + size_of::<u16>()..][..function.len()]
+			.copy_from_slice(function.as_bytes());
+		contents[dll_offset..][..dll.len()].copy_from_slice(dll.as_bytes());
+
+		// The section RVA is only known once added, so fill in the RVAs afterwards.
+		let characteristics =
+			IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE;
+		let section_rva = self.add_section(IMPORT_SECTION_NAME, characteristics, &contents)?;
+		let rva = |offset: usize| section_rva + offset as u32;
+		let descriptor = ImageImportDescriptor {
+			original_first_thunk: U32Bytes::new(LittleEndian, rva(lookup_offset)),
+			time_date_stamp: U32Bytes::default(),
+			forwarder_chain: U32Bytes::default(),
+			name: U32Bytes::new(LittleEndian, rva(dll_offset)),
+			first_thunk: U32Bytes::new(LittleEndian, rva(iat_offset)),
+		};
+		let hint_name = u64::from(rva(hint_name_offset)).to_le_bytes();
+		contents[descriptors.len()..][..DESCRIPTOR_SIZE].copy_from_slice(bytes_of(&descriptor));
+		contents[lookup_offset..][..thunk_size].copy_from_slice(&hint_name[..thunk_size]);
+		contents[iat_offset..][..thunk_size].copy_from_slice(&hint_name[..thunk_size]);
+
+		let headers = self.headers()?;
+		let section = headers.section_headers.last().ok_or(Error::PeHeaders)?;
+		let pointer_to_raw_data = section.pointer_to_raw_data.get(LittleEndian) as usize;
+		self.write(pointer_to_raw_data, &contents)?;
+		self.write_data_directory(
+			&headers,
+			IMAGE_DIRECTORY_ENTRY_IMPORT,
+			section_rva,
+			descriptors_size as u32,
+		)?;
+		self.write_data_directory(&headers, IMAGE_DIRECTORY_ENTRY_BOUND_IMPORT, 0, 0)
+	}
+
+	/// Zeroes the debug directory entries and clears the debug data directory, returning how many
+	/// entries were removed. With `codeview_data`, the CodeView records holding the PDB path are
+	/// zeroed as well; other referenced data is left in place.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn strip_debug(&mut self, codeview_data: bool) -> Result<usize> {
+		let headers = self.headers()?;
+		let Some(data_dir) = headers.data_directory(IMAGE_DIRECTORY_ENTRY_DEBUG) else {
+			return Ok(0);
+		};
+		let image_base = self.data.as_ptr();
+		let rva = data_dir.virtual_address.get(LittleEndian);
+		let offset = headers.rva_to_ptr(image_base, rva)? as usize - image_base as usize;
+		let count = data_dir.size.get(LittleEndian) as usize / size_of::<ImageDebugDirectory>();
+		let table = self.data.get(offset..).ok_or(Error::DebugTable)?;
+		let (entries, _) =
+			slice_from_bytes::<ImageDebugDirectory>(table, count).map_err(|_| Error::DebugTable)?;
+		let codeview: Vec<(usize, usize)> = entries
+			.iter()
+			.filter(|entry| {
+				codeview_data && entry.typ.get(LittleEndian) == IMAGE_DEBUG_TYPE_CODEVIEW
+			})
+			.map(|entry| {
+				(
+					entry.pointer_to_raw_data.get(LittleEndian) as usize,
+					entry.size_of_data.get(LittleEndian) as usize,
+				)
+			})
+			.collect();
+
+		for (start, len) in codeview {
+			let end = start.saturating_add(len).min(self.data.len());
+			if let Some(data) = self.data.get_mut(start..end) {
+				data.fill(0);
+			}
+		}
+		self.data[offset..offset + count * size_of::<ImageDebugDirectory>()].fill(0);
+		self.write_data_directory(&headers, IMAGE_DIRECTORY_ENTRY_DEBUG, 0, 0)?;
+		self.drop_signature(&headers)?;
+		Ok(count)
+	}
+
+	/// Replaces the DOS stub program between the DOS header and the Rich header, or the NT
+	/// headers if there is none, moving the NT headers and section table back if the new stub
+	/// does not fit in front of them. The Rich
+	/// header is kept with its checksum updated for the new stub, or removed with `strip_rich`.
+	///
+	/// The headers have to fit in `SizeOfHeaders`, the sections and anything stored after the
+	/// section table are not moved.
+	#[cfg_attr(feature = "debug", inline(never))]
+	pub fn replace_dos_stub(&mut self, stub: &[u8], strip_rich: bool) -> Result<()> {
+		let headers = self.headers()?;
+		let optional_header = headers.nt_header.optional_header();
+		let nt_headers_offset =
+			offsets::nt_headers_offset(headers.dos_header.e_lfanew.get(LittleEndian));
+		let nt_headers_end = offsets::section_table_entry_offset(
+			nt_headers_offset,
+			headers
+				.nt_header
+				.file_header()
+				.size_of_optional_header
+				.get(LittleEndian) as _,
+			headers.section_headers.len(),
+		);
+		let size_of_headers = headers
+			.section_headers
+			.iter()
+			.filter(|section| section.size_of_raw_data.get(LittleEndian) != 0)
+			.map(|section| section.pointer_to_raw_data.get(LittleEndian) as usize)
+			.fold(optional_header.size_of_headers() as usize, usize::min)
+			.min(self.data.len());
+		let rich = match strip_rich {
+			true => None,
+			false => rich_header(&self.data[..nt_headers_offset]),
+		};
+
+		let mut prefix = self.data[..size_of::<ImageDosHeader>()].to_vec();
+		prefix.extend_from_slice(stub);
+		if let Some((start, end)) = rich {
+			// The Rich header is found by scanning for it, keep it on a 16 byte boundary.
+			prefix.resize(prefix.len().next_multiple_of(16), 0);
+			let key = rich_key(&self.data[..end]);
+			let entries: Vec<(u32, u32)> = self.data[start + 16..end - 8]
+				.chunks_exact(8)
+				.map(|entry| {
+					let id = u32::from_le_bytes(entry[..4].try_into().unwrap()) ^ key;
+					let count = u32::from_le_bytes(entry[4..].try_into().unwrap()) ^ key;
+					(id, count)
+				})
+				.collect();
+			let dans_offset = prefix.len();
+			let new_key = rich_checksum(&prefix, &entries);
+			let mut push = |value: u32| prefix.extend_from_slice(&(value ^ new_key).to_le_bytes());
+			push(RICH_DANS);
+			(0..3).for_each(|_| push(0));
+			for (id, count) in entries {
+				push(id);
+				push(count);
+			}
+			prefix.extend_from_slice(b"Rich");
+			prefix.extend_from_slice(&new_key.to_le_bytes());
+			debug_assert_eq!(prefix.len() - dans_offset, end - start);
+		}
+		let new_nt_headers_offset = match prefix.len() <= nt_headers_offset {
+			true => nt_headers_offset,
+			false => prefix.len().next_multiple_of(8),
+		};
+		let nt_headers_len = nt_headers_end - nt_headers_offset;
+		if new_nt_headers_offset + nt_headers_len > size_of_headers {
+			return Err(Error::NoHeaderSpace);
+		}
+
+		// Whatever follows the section table in `SizeOfHeaders` stays where it is.
+		let region_len = new_nt_headers_offset + nt_headers_len;
+		let mut region = vec![0u8; region_len];
+		region[..prefix.len()].copy_from_slice(&prefix);
+		region[new_nt_headers_offset..]
+			.copy_from_slice(&self.data[nt_headers_offset..nt_headers_end]);
+		region[offset_of!(ImageDosHeader, e_lfanew)..][..size_of::<u32>()]
+			.copy_from_slice(&(new_nt_headers_offset as u32).to_le_bytes());
+		self.data[..region_len].copy_from_slice(&region);
+
+		let headers = self.headers()?;
+		self.drop_signature(&headers)
+	}
+}
+
+/// `"DanS"` XORed with the key starts the Rich header.
+const RICH_DANS: u32 = u32::from_le_bytes(*b"DanS");
+
+/// Start and end of the Rich header in the bytes before the NT headers.
+fn rich_header(data: &[u8]) -> Option<(usize, usize)> {
+	let rich = (size_of::<ImageDosHeader>()..data.len().saturating_sub(8))
+		.step_by(4)
+		.find(|&offset| &data[offset..offset + 4] == b"Rich")?;
+	let key = rich_key(&data[..rich + 8]);
+	let start = (size_of::<ImageDosHeader>()..rich)
+		.step_by(4)
+		.rev()
+		.find(|&offset| {
+			u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) ^ key == RICH_DANS
+		})?;
+	(start + 16 <= rich && (rich - start).is_multiple_of(8)).then_some((start, rich + 8))
+}
+
+fn rich_key(rich_header: &[u8]) -> u32 {
+	u32::from_le_bytes(rich_header[rich_header.len() - 4..].try_into().unwrap())
+}
+
+/// The Rich header's key, a checksum of everything before it and of its entries.
+fn rich_checksum(prefix: &[u8], entries: &[(u32, u32)]) -> u32 {
+	let e_lfanew = offset_of!(ImageDosHeader, e_lfanew);
+	let checksum = prefix
+		.iter()
+		.enumerate()
+		.filter(|(index, _)| !(e_lfanew..e_lfanew + size_of::<u32>()).contains(index))
+		.fold(prefix.len() as u32, |checksum, (index, &byte)| {
+			checksum.wrapping_add((byte as u32).rotate_left(index as u32))
+		});
+	entries.iter().fold(checksum, |checksum, &(id, count)| {
+		checksum.wrapping_add(id.rotate_left(count))
+	})
+}
@@ -0,0 +1,91 @@
+//! Parsing borrows from the image and never allocates, whichever features are enabled.
+
+mod common;
+
+use common::{Layout, LAYOUTS};
+use object::pe;
+use objparse::{nt::NtHeaders, options::DEFAULT_MAX_RESOURCE_DEPTH};
+use std::{
+	alloc::{GlobalAlloc, Layout as AllocLayout, System},
+	cell::Cell,
+};
+
+/// Counts the allocations of the current thread, the test harness allocates on its own.
+struct CountingAllocator;
+
+thread_local! {
+	static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: AllocLayout) -> *mut u8 {
+		let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+		unsafe { System.alloc(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: AllocLayout) {
+		unsafe { System.dealloc(ptr, layout) }
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+	let before = ALLOCATIONS.with(Cell::get);
+	let value = f();
+	(value, ALLOCATIONS.with(Cell::get) - before)
+}
+
+/// Parses the headers and walks every directory of the sample, returning what it counted.
+fn parse<Nt: NtHeaders>(data: &'static [u8], layout: Layout, image_base: u64) -> usize {
+	let headers = common::parse::<Nt>(data, layout);
+	let base = data.as_ptr();
+	let mut count = headers.section_headers.len();
+
+	let export_table = unsafe { headers.export_table_mem(base) }.unwrap();
+	for (name_rva, _) in export_table.iter_name_index() {
+		count += unsafe { headers.export_name(base, name_rva) }
+			.unwrap()
+			.count_bytes();
+	}
+	let import_table = unsafe { headers.import_table_mem(base) }.unwrap();
+	for descriptor in import_table.import_descriptors {
+		count += unsafe { import_table.dll_name_with(descriptor, &headers, base) }
+			.unwrap()
+			.count_bytes();
+	}
+	let debug_table = unsafe { headers.debug_table_mem(base) }.unwrap();
+	count += unsafe { debug_table.iter_typed_with(&headers, base) }
+		.map(Result::unwrap)
+		.count();
+	let tls_table = unsafe { headers.tls_table_mem(base) }.unwrap().unwrap();
+	count += unsafe { tls_table.callback_addresses_with(&headers, base, image_base) }
+		.map(Result::unwrap)
+		.count();
+	let relocation_table = unsafe { headers.relocation_table_mem(base) }.unwrap();
+	count += relocation_table.iter().map(Result::unwrap).count();
+	let resource_table = unsafe { headers.resource_table_mem(base) }.unwrap();
+	unsafe { resource_table.walk(DEFAULT_MAX_RESOURCE_DEPTH, &mut |_, _, _| count += 1) }.unwrap();
+	count
+}
+
+fn check_parse<Nt: NtHeaders>() {
+	let pe = common::sample(size_of::<Nt>() == size_of::<pe::ImageNtHeaders64>());
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let (count, allocations) = allocations(|| parse::<Nt>(data, layout, pe.image_base));
+		assert!(count > 0);
+		assert_eq!(allocations, 0, "{layout:?}");
+	}
+}
+
+#[test]
+fn parsing_does_not_allocate() {
+	// The counter sees allocations on this thread.
+	let (_, allocations) = allocations(|| Vec::<u8>::with_capacity(1));
+	assert_eq!(allocations, 1);
+
+	check_parse::<pe::ImageNtHeaders64>();
+	check_parse::<pe::ImageNtHeaders32>();
+}
@@ -0,0 +1,166 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::{ExportFn, Layout, PeBuilder, CODE, LAYOUTS};
+use object::{bytes_of_slice, pe};
+use objparse::{
+	archive::{Archive, ImportType},
+	coff::CoffFile,
+	error::{Error, Result},
+	nt::NtHeaders,
+};
+
+/// Import library for the exports of `pe` in `layout`.
+fn to_import_library<Nt: NtHeaders>(pe: &PeBuilder, layout: Layout) -> Result<Vec<u8>> {
+	let data = pe.leak(layout);
+	let base = data.as_ptr();
+	let headers = common::parse::<Nt>(data, layout);
+	let export_table = unsafe { headers.export_table_mem(base) }.unwrap();
+	unsafe { export_table.to_import_library(&headers, base, "sample.dll") }
+}
+
+/// Import library for the exports of the sample, the same in both layouts.
+fn import_library(is_64: bool) -> &'static [u8] {
+	let pe = common::sample(is_64);
+	let [file, mapped] = LAYOUTS.map(|layout| match is_64 {
+		true => to_import_library::<pe::ImageNtHeaders64>(&pe, layout),
+		false => to_import_library::<pe::ImageNtHeaders32>(&pe, layout),
+	});
+	assert_eq!(file, mapped);
+	common::leak(&file.unwrap())
+}
+
+#[test]
+fn short_imports_round_trip() {
+	for (is_64, symbols) in [
+		(true, ["Alpha", "Beta", "Ordinal4", "Forward"]),
+		(false, ["_Alpha", "_Beta", "_Ordinal4", "_Forward"]),
+	] {
+		let archive = Archive::parse(import_library(is_64)).unwrap();
+		let mut unaligned = 0;
+		let mut imports = Vec::new();
+		for member in archive.objects() {
+			let member = member.unwrap();
+			if !member.is_short_import() {
+				continue;
+			}
+			// Members are only 2-byte aligned.
+			unaligned += !(member.data.as_ptr() as usize).is_multiple_of(4) as usize;
+			let import = member.short_import().unwrap();
+			assert_eq!(import.dll, c"sample.dll");
+			imports.push((
+				import.symbol.to_str().unwrap(),
+				import.import_name(),
+				import.ordinal(),
+				import.import_type(),
+				import.hint(),
+			));
+		}
+		assert_ne!(unaligned, 0);
+		let symbol_names: Vec<_> = imports.iter().map(|import| import.0).collect();
+		assert_eq!(symbol_names, symbols);
+		assert_eq!(imports[0].1, Some(&b"Alpha"[..]));
+		assert_eq!(imports[0].2, None);
+		assert_eq!(imports[2].1, None);
+		assert_eq!(imports[2].2, Some(4));
+		assert_eq!(imports[1].4, Some(1));
+		assert!(imports.iter().all(|import| import.3 == ImportType::Code));
+	}
+}
+
+/// An archive of `members`, each a name and its data.
+fn archive(members: &[(&str, &[u8])]) -> &'static [u8] {
+	let mut archive = b"!<arch>\n".to_vec();
+	for (name, data) in members {
+		archive.extend_from_slice(
+			format!(
+				"{name:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+				0,
+				0,
+				0,
+				644,
+				data.len()
+			)
+			.as_bytes(),
+		);
+		archive.extend_from_slice(data);
+		if !archive.len().is_multiple_of(2) {
+			archive.push(b'\n');
+		}
+	}
+	common::leak(&archive)
+}
+
+#[test]
+fn objects_at_any_even_offset() {
+	let library = Archive::parse(import_library(true)).unwrap();
+	// The import descriptor, with relocations to the null descriptor and the dll name.
+	let descriptor = library
+		.objects()
+		.map(Result::unwrap)
+		.find(|member| !member.is_short_import())
+		.unwrap();
+	let expected = descriptor.coff().unwrap();
+	let names = |coff: &CoffFile| {
+		let sections: Vec<_> = coff
+			.section_headers
+			.iter()
+			.map(|section| coff.section_name(section).unwrap())
+			.collect();
+		let symbols: Vec<_> = coff
+			.symbols()
+			.map(|symbol| coff.symbol_name(symbol.symbol).unwrap())
+			.collect();
+		(sections, symbols)
+	};
+
+	// Data at 2 mod 4 behind a member of one byte and its padding.
+	let archive = Archive::parse(archive(&[
+		("pad.obj/", b"\0"),
+		("descriptor.obj/", descriptor.data),
+	]))
+	.unwrap();
+	let member = archive.objects().nth(1).unwrap().unwrap();
+	assert_eq!(member.data.as_ptr() as usize % 4, 2);
+	let coff = member.coff().unwrap();
+	assert_eq!(names(&coff), names(&expected));
+	for (section, expected_section) in coff.section_headers.iter().zip(expected.section_headers) {
+		assert_eq!(
+			bytes_of_slice(coff.relocations(section).unwrap()),
+			bytes_of_slice(expected.relocations(expected_section).unwrap())
+		);
+	}
+	assert!(!coff
+		.relocations(&coff.section_headers[0])
+		.unwrap()
+		.is_empty());
+}
+
+/// An image exporting `count` functions by ordinal only.
+fn ordinal_exports(count: usize) -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	let mut text = pe.blob();
+	text.zeroes(0x10);
+	let functions = vec![ExportFn::Rva(text.here() - 0x10); count];
+	let export_directory = common::exports(&mut text, "sample.dll", 1, &functions, &[]);
+	pe.section(".text", CODE, text);
+	pe.directory(common::IMAGE_DIRECTORY_ENTRY_EXPORT, export_directory);
+	pe
+}
+
+#[test]
+fn members_past_the_16_bit_index_are_an_error() {
+	// The three members of the descriptor and terminators come first.
+	let pe = ordinal_exports(u16::MAX as usize - 3);
+	let library = to_import_library::<pe::ImageNtHeaders64>(&pe, Layout::Mapped).unwrap();
+	let archive = Archive::parse(common::leak(&library)).unwrap();
+	assert_eq!(archive.objects().count(), u16::MAX as usize);
+	let pe = ordinal_exports(u16::MAX as usize - 2);
+	assert_eq!(
+		to_import_library::<pe::ImageNtHeaders64>(&pe, Layout::Mapped),
+		Err(Error::LimitExceeded {
+			limit: u16::MAX as usize
+		})
+	);
+}
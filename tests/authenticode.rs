@@ -0,0 +1,503 @@
+mod common;
+
+use common::Layout;
+use object::pe;
+use objparse::{
+	authenticode::{
+		DigestAlgorithm, Signature, SignedData, SignerInfo, Timestamp, WinCertificate,
+		OID_COUNTER_SIGNATURE, OID_NESTED_SIGNATURE, OID_RFC3161_COUNTER_SIGNATURE,
+		OID_SIGNED_DATA, OID_SIGNING_TIME, OID_TST_INFO, WIN_CERT_TYPE_PKCS_SIGNED_DATA,
+	},
+	error::Error,
+	PeHeaders,
+};
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_CONTEXT_0: u8 = 0xA0;
+const TAG_CONTEXT_1: u8 = 0xA1;
+
+/// 2.16.840.1.101.3.4.2.1
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+/// 1.3.14.3.2.26
+const OID_SHA1: &[u8] = &[0x2B, 0x0E, 0x03, 0x02, 0x1A];
+/// 1.3.6.1.4.1.311.2.1.4, `SPC_INDIRECT_DATA_OBJID`.
+const OID_SPC_INDIRECT_DATA: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x01, 0x04];
+
+/// 2024-03-22 17:26:26 UTC.
+const TIME: i64 = 1711128386;
+
+/// A DER element, with a long-form length past 127 bytes.
+fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+	let mut der = vec![tag];
+	match contents.len() {
+		len @ 0..=0x7f => der.push(len as u8),
+		len => {
+			let bytes = len.to_be_bytes();
+			let skip = bytes.iter().take_while(|&&byte| byte == 0).count();
+			der.push(0x80 | (bytes.len() - skip) as u8);
+			der.extend_from_slice(&bytes[skip..]);
+		}
+	}
+	der.extend_from_slice(contents);
+	der
+}
+
+fn constructed(tag: u8, elements: &[Vec<u8>]) -> Vec<u8> {
+	tlv(tag, &elements.concat())
+}
+
+fn algorithm(oid: &[u8]) -> Vec<u8> {
+	constructed(TAG_SEQUENCE, &[tlv(TAG_OID, oid), tlv(TAG_NULL, &[])])
+}
+
+fn attribute(oid: &[u8], values: &[Vec<u8>]) -> Vec<u8> {
+	constructed(
+		TAG_SEQUENCE,
+		&[tlv(TAG_OID, oid), constructed(TAG_SET, values)],
+	)
+}
+
+/// A `SignerInfo` with `authenticated` and `unauthenticated` attributes, omitted when empty.
+fn signer_info(digest: &[u8], authenticated: &[Vec<u8>], unauthenticated: &[Vec<u8>]) -> Vec<u8> {
+	let issuer = constructed(
+		TAG_SEQUENCE,
+		&[constructed(TAG_SET, &[attribute(&[0x55, 0x04, 0x03], &[])])],
+	);
+	let mut elements = vec![
+		tlv(TAG_INTEGER, &[1]),
+		constructed(TAG_SEQUENCE, &[issuer, tlv(TAG_INTEGER, &[0x12, 0x34])]),
+		algorithm(digest),
+	];
+	if !authenticated.is_empty() {
+		elements.push(constructed(TAG_CONTEXT_0, authenticated));
+	}
+	elements.push(algorithm(&[
+		0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01,
+	]));
+	elements.push(tlv(TAG_OCTET_STRING, &[0x55; 0x10]));
+	if !unauthenticated.is_empty() {
+		elements.push(constructed(TAG_CONTEXT_1, unauthenticated));
+	}
+	constructed(TAG_SEQUENCE, &elements)
+}
+
+/// A `ContentInfo` holding `SignedData` of `content` with `signer_infos`.
+fn signed_data(content_type: &[u8], content: &[u8], signer_infos: &[Vec<u8>]) -> Vec<u8> {
+	let signed_data = constructed(
+		TAG_SEQUENCE,
+		&[
+			tlv(TAG_INTEGER, &[1]),
+			constructed(TAG_SET, &[algorithm(OID_SHA256)]),
+			constructed(
+				TAG_SEQUENCE,
+				&[tlv(TAG_OID, content_type), tlv(TAG_CONTEXT_0, content)],
+			),
+			constructed(TAG_SET, signer_infos),
+		],
+	);
+	constructed(
+		TAG_SEQUENCE,
+		&[
+			tlv(TAG_OID, OID_SIGNED_DATA),
+			constructed(TAG_CONTEXT_0, &[signed_data]),
+		],
+	)
+}
+
+/// An RFC 3161 time-stamp token generated at `gen_time`.
+fn time_stamp_token(gen_time: Vec<u8>) -> Vec<u8> {
+	let tst_info = constructed(
+		TAG_SEQUENCE,
+		&[
+			tlv(TAG_INTEGER, &[1]),
+			tlv(TAG_OID, &[0x2A, 0x03, 0x04]),
+			constructed(
+				TAG_SEQUENCE,
+				&[algorithm(OID_SHA256), tlv(TAG_OCTET_STRING, &[0x66; 0x20])],
+			),
+			tlv(TAG_INTEGER, &[0x42]),
+			gen_time,
+		],
+	);
+	signed_data(
+		OID_TST_INFO,
+		&tlv(TAG_OCTET_STRING, &tst_info),
+		&[signer_info(OID_SHA256, &[], &[])],
+	)
+}
+
+fn signing_time(time: Vec<u8>) -> Vec<u8> {
+	attribute(OID_SIGNING_TIME, &[time])
+}
+
+fn parse(content_info: &[u8]) -> Result<SignedData, Error> {
+	SignedData::parse(common::leak(content_info))
+}
+
+/// The only signer of `content_info`.
+fn signer(content_info: &[u8]) -> SignerInfo {
+	let signed_data = parse(content_info).unwrap();
+	let signers: Vec<_> = signed_data.signer_infos().collect();
+	assert_eq!(signers.len(), 1);
+	signers[0]
+}
+
+/// The sample with a certificate table of `certificates`, each a type and its data.
+fn with_certificates(certificates: &[(u16, &[u8])]) -> Vec<u8> {
+	let mut table = Vec::new();
+	for &(certificate_type, data) in certificates {
+		table.extend_from_slice(&(8 + data.len() as u32).to_le_bytes());
+		table.extend_from_slice(&0x0200u16.to_le_bytes());
+		table.extend_from_slice(&certificate_type.to_le_bytes());
+		table.extend_from_slice(data);
+		table.resize(table.len().next_multiple_of(8), 0);
+	}
+	let mut pe = common::sample(true);
+	let offset = pe.file().len();
+	pe.directory(
+		pe::IMAGE_DIRECTORY_ENTRY_SECURITY,
+		(offset as u32, table.len() as u32),
+	);
+	let mut file = pe.file();
+	file.extend_from_slice(&table);
+	file
+}
+
+fn certificates(file: &[u8]) -> Result<Vec<WinCertificate>, Error> {
+	let data = common::leak(file);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::File);
+	unsafe { headers.certificates(data.as_ptr()) }.map(Iterator::collect)
+}
+
+#[test]
+fn signing_time_in_both_encodings() {
+	for (time, expected) in [
+		(tlv(TAG_UTC_TIME, b"240322172626Z"), TIME),
+		(tlv(TAG_GENERALIZED_TIME, b"20240322172626Z"), TIME),
+		// Two-digit years from 50 are in the 20th century.
+		(tlv(TAG_UTC_TIME, b"500101000000Z"), -631152000),
+		(tlv(TAG_UTC_TIME, b"491231235959Z"), 2524607999),
+		(tlv(TAG_GENERALIZED_TIME, b"19700101000000Z"), 0),
+		(tlv(TAG_GENERALIZED_TIME, b"20000229120000Z"), 951825600),
+	] {
+		let content_info = signed_data(
+			OID_SPC_INDIRECT_DATA,
+			&[],
+			&[signer_info(OID_SHA256, &[signing_time(time)], &[])],
+		);
+		assert_eq!(signer(&content_info).signing_time(), Some(expected));
+	}
+}
+
+#[test]
+fn malformed_signing_times_are_skipped() {
+	for time in [
+		tlv(TAG_UTC_TIME, b"241322172626Z"),
+		tlv(TAG_UTC_TIME, b"240300172626Z"),
+		tlv(TAG_UTC_TIME, b"2403221726"),
+		tlv(TAG_UTC_TIME, b"24032217x626Z"),
+		tlv(TAG_GENERALIZED_TIME, b"240322172626Z"),
+		tlv(TAG_OCTET_STRING, b"240322172626Z"),
+		tlv(TAG_UTC_TIME, b""),
+	] {
+		let content_info = signed_data(
+			OID_SPC_INDIRECT_DATA,
+			&[],
+			&[signer_info(OID_SHA256, &[signing_time(time)], &[])],
+		);
+		assert_eq!(signer(&content_info).signing_time(), None);
+	}
+}
+
+#[test]
+fn counter_signature_timestamps() {
+	let rfc3161 = attribute(
+		OID_RFC3161_COUNTER_SIGNATURE,
+		&[time_stamp_token(tlv(
+			TAG_GENERALIZED_TIME,
+			b"20240322172626Z",
+		))],
+	);
+	let legacy = attribute(
+		OID_COUNTER_SIGNATURE,
+		&[signer_info(
+			OID_SHA1,
+			&[signing_time(tlv(TAG_UTC_TIME, b"240322172627Z"))],
+			&[],
+		)],
+	);
+	let content_info = signed_data(
+		OID_SPC_INDIRECT_DATA,
+		&[],
+		&[signer_info(
+			OID_SHA256,
+			&[signing_time(tlv(TAG_UTC_TIME, b"240101000000Z"))],
+			&[rfc3161, legacy],
+		)],
+	);
+	let signer = signer(&content_info);
+	let timestamps: Vec<_> = signer.timestamps().collect();
+	assert_eq!(
+		timestamps,
+		[Timestamp::Rfc3161(TIME), Timestamp::Legacy(TIME + 1)]
+	);
+	assert_eq!(timestamps[1].time(), TIME + 1);
+	// The claimed signing time is separate from the trusted ones.
+	assert_eq!(signer.signing_time(), Some(1704067200));
+	assert_eq!(signer.digest_algorithm(), Some(DigestAlgorithm::Sha256));
+	assert_eq!(signer.serial_number(), Some(&[0x12, 0x34][..]));
+}
+
+#[test]
+fn malformed_counter_signatures_are_skipped() {
+	let tst_info_time = |gen_time| {
+		let token = time_stamp_token(gen_time);
+		parse(&token).unwrap().tst_info_time()
+	};
+	assert_eq!(
+		tst_info_time(tlv(TAG_GENERALIZED_TIME, b"20240322172626Z")),
+		Some(TIME)
+	);
+	assert_eq!(tst_info_time(tlv(TAG_GENERALIZED_TIME, b"2024")), None);
+	// Only a `TSTInfo` has a `genTime`.
+	let content_info = signed_data(OID_SPC_INDIRECT_DATA, &[], &[]);
+	assert_eq!(parse(&content_info).unwrap().tst_info_time(), None);
+
+	let mut truncated = time_stamp_token(tlv(TAG_GENERALIZED_TIME, b"20240322172626Z"));
+	truncated.truncate(truncated.len() / 2);
+	let unauthenticated = [
+		attribute(OID_RFC3161_COUNTER_SIGNATURE, &[truncated]),
+		attribute(OID_RFC3161_COUNTER_SIGNATURE, &[tlv(TAG_OCTET_STRING, &[])]),
+		attribute(OID_COUNTER_SIGNATURE, &[tlv(TAG_SET, &[])]),
+		attribute(OID_COUNTER_SIGNATURE, &[signer_info(OID_SHA1, &[], &[])]),
+	];
+	let content_info = signed_data(
+		OID_SPC_INDIRECT_DATA,
+		&[],
+		&[signer_info(OID_SHA256, &[], &unauthenticated)],
+	);
+	assert_eq!(signer(&content_info).timestamps().count(), 0);
+}
+
+#[test]
+fn malformed_signed_data_is_rejected() {
+	let valid = signed_data(
+		OID_SPC_INDIRECT_DATA,
+		&[],
+		&[signer_info(OID_SHA256, &[], &[])],
+	);
+	assert!(parse(&valid).is_ok());
+
+	let mut wrong_oid = valid.clone();
+	let oid = wrong_oid
+		.windows(OID_SIGNED_DATA.len())
+		.position(|window| window == OID_SIGNED_DATA)
+		.unwrap();
+	wrong_oid[oid + OID_SIGNED_DATA.len() - 1] = 0x01;
+	let mut wrong_tag = valid.clone();
+	wrong_tag[0] = TAG_SET;
+	// A long-form length wider than `usize`, and one past the end.
+	let mut too_wide = vec![TAG_SEQUENCE, 0x89];
+	too_wide.extend_from_slice(&[0xff; 9]);
+	let past_the_end = [TAG_SEQUENCE, 0x84, 0x7f, 0xff, 0xff, 0xff, TAG_OID];
+	for content_info in [
+		&valid[..valid.len() - 1],
+		&valid[..2],
+		&[TAG_SEQUENCE][..],
+		&[][..],
+		&[TAG_SEQUENCE, 0x80][..],
+		&too_wide[..],
+		&past_the_end[..],
+		&wrong_oid[..],
+		&wrong_tag[..],
+	] {
+		assert_eq!(parse(content_info).unwrap_err(), Error::Authenticode);
+	}
+}
+
+#[test]
+fn certificate_table() {
+	let content_info = signed_data(
+		OID_SPC_INDIRECT_DATA,
+		&[],
+		&[signer_info(OID_SHA256, &[], &[])],
+	);
+	let entries = certificates(&with_certificates(&[(
+		WIN_CERT_TYPE_PKCS_SIGNED_DATA,
+		&content_info,
+	)]))
+	.unwrap();
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].revision, 0x0200);
+	assert_eq!(entries[0].data, content_info);
+	assert!(entries[0].signed_data().is_ok());
+
+	// X.509 certificates are not signed data.
+	let entries = certificates(&with_certificates(&[(0x0001, &content_info)])).unwrap();
+	assert_eq!(entries[0].signed_data().unwrap_err(), Error::Authenticode);
+
+	// The data directory holds a file offset.
+	let pe = common::sample(true);
+	let data = pe.leak(Layout::Mapped);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::Mapped);
+	assert!(matches!(
+		unsafe { headers.certificates(data.as_ptr()) },
+		Err(Error::Authenticode)
+	));
+	// Without a certificate table.
+	assert_eq!(certificates(&pe.file()).unwrap().len(), 0);
+}
+
+#[test]
+fn malformed_certificate_tables() {
+	let content_info = signed_data(OID_SPC_INDIRECT_DATA, &[], &[]);
+	let file = with_certificates(&[(WIN_CERT_TYPE_PKCS_SIGNED_DATA, &content_info)]);
+	let table = file.len() - (content_info.len() + 8).next_multiple_of(8);
+	for length in [0u32, 7, 0x1000] {
+		let mut file = file.clone();
+		file[table..table + 4].copy_from_slice(&length.to_le_bytes());
+		assert_eq!(certificates(&file).unwrap().len(), 0);
+	}
+
+	// A table past the end of the file.
+	let mut file = file.clone();
+	file.truncate(file.len() - 8);
+	let data = common::leak(&file);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::File);
+	assert!(unsafe { headers.certificates(data.as_ptr()) }.is_err());
+}
+
+fn signatures(file: &[u8]) -> Vec<Signature> {
+	let data = common::leak(file);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::File);
+	unsafe { headers.signatures(data.as_ptr()) }
+		.unwrap()
+		.collect()
+}
+
+/// Digest algorithms of the signers of `signature`.
+fn digests(signature: &Signature) -> Vec<DigestAlgorithm> {
+	signature
+		.signed_data
+		.signer_infos()
+		.map(|signer| signer.digest_algorithm().unwrap())
+		.collect()
+}
+
+#[test]
+fn nested_signatures() {
+	// SHA-1 signed, with a SHA-256 signature nested in it as dual-signed binaries are.
+	let innermost = signed_data(
+		OID_SPC_INDIRECT_DATA,
+		&[],
+		&[signer_info(OID_SHA256, &[], &[])],
+	);
+	let nested = signed_data(
+		OID_SPC_INDIRECT_DATA,
+		&[],
+		&[signer_info(
+			OID_SHA256,
+			&[],
+			&[attribute(OID_NESTED_SIGNATURE, &[innermost])],
+		)],
+	);
+	let dual = signed_data(
+		OID_SPC_INDIRECT_DATA,
+		&[],
+		&[signer_info(
+			OID_SHA1,
+			&[],
+			&[attribute(
+				OID_NESTED_SIGNATURE,
+				&[nested, tlv(TAG_SEQUENCE, &[])],
+			)],
+		)],
+	);
+	let second = signed_data(
+		OID_SPC_INDIRECT_DATA,
+		&[],
+		&[
+			signer_info(OID_SHA256, &[], &[]),
+			signer_info(&[0x2A, 0x03], &[], &[]),
+		],
+	);
+	let file = with_certificates(&[
+		(WIN_CERT_TYPE_PKCS_SIGNED_DATA, &dual),
+		(0x0001, &[0x30, 0x00]),
+		(WIN_CERT_TYPE_PKCS_SIGNED_DATA, &second),
+	]);
+
+	// Each certificate table entry, then the signatures nested one level deep in it.
+	let signatures = signatures(&file);
+	let summary: Vec<_> = signatures
+		.iter()
+		.map(|signature| (signature.certificate, signature.nested, digests(signature)))
+		.collect();
+	assert_eq!(
+		summary,
+		[
+			(0, false, vec![DigestAlgorithm::Sha1]),
+			(0, true, vec![DigestAlgorithm::Sha256]),
+			(
+				2,
+				false,
+				vec![
+					DigestAlgorithm::Sha256,
+					DigestAlgorithm::Other(&[0x2A, 0x03])
+				]
+			),
+		]
+	);
+}
+
+/// `cli-64.exe` from conda, signed with SHA-256 and time-stamped with RFC 3161.
+const SIGNED: &[u8] = include_bytes!("data/signed.exe");
+
+#[test]
+fn signed_binary() {
+	let entries = certificates(SIGNED).unwrap();
+	assert_eq!(entries.len(), 1);
+	assert_eq!(entries[0].revision, 0x0200);
+	assert_eq!(entries[0].certificate_type, WIN_CERT_TYPE_PKCS_SIGNED_DATA);
+
+	let signatures = signatures(SIGNED);
+	assert_eq!(signatures.len(), 1);
+	assert!(!signatures[0].nested);
+	let signed_data = signatures[0].signed_data;
+	assert_eq!(signed_data.content().unwrap().0, OID_SPC_INDIRECT_DATA);
+	assert_eq!(signed_data.tst_info_time(), None);
+
+	let signers: Vec<_> = signed_data.signer_infos().collect();
+	assert_eq!(signers.len(), 1);
+	let signer = signers[0];
+	assert_eq!(signer.digest_algorithm(), Some(DigestAlgorithm::Sha256));
+	assert_eq!(
+		signer.serial_number(),
+		Some(
+			&[
+				0x03, 0x9F, 0x1B, 0x2D, 0x37, 0x21, 0x02, 0xFF, 0x95, 0x2D, 0xED, 0x24, 0x47, 0xD2,
+				0xAF, 0x5D
+			][..]
+		)
+	);
+	let issuer = signer.issuer().unwrap();
+	assert_eq!(issuer[0], TAG_SEQUENCE);
+	let common_name = b"DigiCert Trusted G4 Code Signing RSA4096 SHA384 2021 CA1";
+	assert!(issuer
+		.windows(common_name.len())
+		.any(|window| window == common_name));
+	assert_eq!(signer.nested_signatures().count(), 0);
+	assert_eq!(signer.signing_time(), None);
+	// 2024-03-22 17:26:26 UTC.
+	assert_eq!(
+		signer.timestamps().collect::<Vec<_>>(),
+		[Timestamp::Rfc3161(TIME)]
+	);
+}
@@ -0,0 +1,32 @@
+#![cfg(all(feature = "rayon", any(feature = "pe32", feature = "pe64")))]
+
+mod common;
+
+use objparse::{batch, ParseOptions};
+use std::{fs, sync::Mutex};
+
+#[test]
+fn both_widths_are_analyzed() {
+	let dir = std::env::temp_dir().join(format!("objparse-batch-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+	fs::write(dir.join("pe32.dll"), common::sample(false).file()).unwrap();
+	fs::write(dir.join("pe64.dll"), common::sample(true).file()).unwrap();
+	fs::write(dir.join("notes.txt"), b"not an image").unwrap();
+
+	let reports = Mutex::new(Vec::new());
+	batch::analyze_dir(&dir, ParseOptions::new(), |path, report| {
+		let name = path.file_name().unwrap().to_string_lossy().into_owned();
+		reports.lock().unwrap().push((name, report.is_ok()));
+	})
+	.unwrap();
+	let mut reports = reports.into_inner().unwrap();
+	reports.sort();
+	assert_eq!(
+		reports,
+		[
+			("pe32.dll".to_string(), true),
+			("pe64.dll".to_string(), true)
+		]
+	);
+	fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use common::Blob;
+use objparse::{
+	bundle::{Bundle, BundleFileType, BUNDLE_SIGNATURE},
+	error::Error,
+};
+
+const ASSEMBLY: &[u8] = b"MZ assembly";
+const RUNTIME_CONFIG: &[u8] = b"{}";
+const SYMBOLS: &[u8] = b"deflated";
+
+fn string(blob: &mut Blob, value: &str) {
+	blob.u8(value.len() as u8).bytes(value.as_bytes());
+}
+
+/// An apphost with three files bundled by `major_version`, the last one compressed when it is
+/// 6 or later.
+fn apphost(major_version: u32) -> Vec<u8> {
+	let mut data = Blob {
+		rva: 0,
+		file_offset: 0,
+		data: Vec::new(),
+	};
+	data.bytes(b"MZ apphost").zeroes(6);
+	let header_offset = data.here() as usize;
+	data.u64(0).bytes(&BUNDLE_SIGNATURE);
+	let assembly = data.here() as u64;
+	data.bytes(ASSEMBLY);
+	let runtime_config = data.here() as u64;
+	data.bytes(RUNTIME_CONFIG);
+	let symbols = data.here() as u64;
+	data.bytes(SYMBOLS);
+
+	let header = data.here();
+	data.data[header_offset..header_offset + 8].copy_from_slice(&(header as u64).to_le_bytes());
+	data.u32(major_version).u32(0).u32(3);
+	string(&mut data, "bundle-id");
+	if major_version >= 2 {
+		data.zeroes(16)
+			.u64(runtime_config)
+			.u64(RUNTIME_CONFIG.len() as u64)
+			.u64(1);
+	}
+	let compressed = major_version >= 6;
+	for (offset, size, compressed_size, file_type, path) in [
+		(assembly, ASSEMBLY.len(), 0, 1, "app.dll"),
+		(
+			runtime_config,
+			RUNTIME_CONFIG.len(),
+			0,
+			4,
+			"app.runtimeconfig.json",
+		),
+		(symbols, 0x20, SYMBOLS.len(), 5, "app.pdb"),
+	] {
+		data.u64(offset).u64(size as u64);
+		if compressed {
+			data.u64(compressed_size as u64);
+		}
+		data.u8(file_type);
+		string(&mut data, path);
+	}
+	data.data
+}
+
+#[test]
+fn single_file_bundles() {
+	let data = common::leak(&apphost(6));
+	let bundle = Bundle::find(data).unwrap().unwrap();
+	assert_eq!((bundle.major_version, bundle.minor_version), (6, 0));
+	assert_eq!(bundle.bundle_id, "bundle-id");
+	assert_eq!(bundle.flags, 1);
+	let entries: Vec<_> = bundle.entries().map(Result::unwrap).collect();
+	let files: Vec<_> = entries
+		.iter()
+		.map(|entry| {
+			(
+				entry.path,
+				entry.file_type,
+				bundle.entry_data(entry).unwrap(),
+			)
+		})
+		.collect();
+	assert_eq!(
+		files,
+		[
+			("app.dll", BundleFileType::Assembly, ASSEMBLY),
+			(
+				"app.runtimeconfig.json",
+				BundleFileType::RuntimeConfigJson,
+				RUNTIME_CONFIG
+			),
+			("app.pdb", BundleFileType::Symbols, SYMBOLS),
+		]
+	);
+	assert_eq!((entries[2].size, entries[2].compressed_size), (0x20, 8));
+
+	// Version 1 has no flags and nothing compressed.
+	let bundle = Bundle::find(common::leak(&apphost(1))).unwrap().unwrap();
+	assert_eq!(bundle.flags, 0);
+	let entries: Vec<_> = bundle.entries().map(Result::unwrap).collect();
+	assert_eq!(entries.len(), 3);
+	assert_eq!(entries[0].compressed_size, 0);
+	assert_eq!(entries[1].path, "app.runtimeconfig.json");
+}
+
+#[test]
+fn malformed_bundles() {
+	let data = apphost(6);
+	// No signature, and an apphost that was never bundled.
+	assert!(Bundle::find(common::leak(&data[..0x20])).is_none());
+	let mut unbundled = data.clone();
+	unbundled[0x10..0x18].fill(0);
+	assert!(Bundle::find(common::leak(&unbundled)).is_none());
+
+	// The header past the end, or cut short, and a length prefix longer than 5 bytes.
+	let header = u64::from_le_bytes(data[0x10..0x18].try_into().unwrap());
+	let data = common::leak(&data);
+	for header_offset in [data.len() as u64, u64::MAX] {
+		assert_eq!(
+			Bundle::parse(data, header_offset).err(),
+			Some(Error::Bundle)
+		);
+	}
+	let cut = common::leak(&data[..header as usize + 16]);
+	assert_eq!(Bundle::parse(cut, header).err(), Some(Error::Bundle));
+	let mut overlong = data[..header as usize + 12].to_vec();
+	overlong.extend_from_slice(&[0x80; 5]);
+	assert_eq!(
+		Bundle::parse(common::leak(&overlong), header).err(),
+		Some(Error::Bundle)
+	);
+
+	// A manifest cut short in its last entry, whose data is then out of range.
+	let truncated = common::leak(&data[..data.len() - 3]);
+	let bundle = Bundle::find(truncated).unwrap().unwrap();
+	let entries: Vec<_> = bundle.entries().collect();
+	assert!(entries[1].is_ok());
+	assert_eq!(entries[2].err(), Some(Error::Bundle));
+	let mut entry = entries[0].unwrap();
+	entry.offset = u64::MAX;
+	assert_eq!(bundle.entry_data(&entry), None);
+}
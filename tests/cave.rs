@@ -0,0 +1,61 @@
+mod common;
+
+use common::{PeBuilder, CODE, DATA, LAYOUTS, NT_HEADERS_OFFSET, TEXT_RVA};
+use object::pe::ImageNtHeaders64;
+use objparse::cave::CodeCave;
+
+/// `.text` with padding between and after its functions, and `.data` of padding alone.
+fn padded() -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	let mut text = pe.blob();
+	text.bytes(&[0x55, 0x48])
+		.bytes(&[0xcc; 0x10])
+		.u8(0xc3)
+		.zeroes(8)
+		.bytes(&[0x90; 3])
+		.bytes(&[0xcc; 3]);
+	pe.section_with_size(".text", CODE, text, 0x40);
+	let mut data = pe.blob();
+	data.bytes(&[0xcc; 0x20]);
+	pe.section(".data", DATA, data);
+	pe
+}
+
+fn caves(data: &'static [u8], layout: common::Layout, min_size: usize) -> Vec<(u32, u32, u8)> {
+	let headers = common::parse::<ImageNtHeaders64>(data, layout);
+	unsafe { headers.find_code_caves(data.as_ptr(), min_size) }
+		.map(|cave: CodeCave| {
+			assert_eq!(&cave.section.name[..5], b".text");
+			(cave.rva, cave.size, cave.fill)
+		})
+		.collect()
+}
+
+#[test]
+fn code_caves() {
+	let pe = padded();
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		// The zero-filled tail of the virtual size is a cave in either layout.
+		assert_eq!(
+			caves(data, layout, 4),
+			[
+				(TEXT_RVA + 2, 0x10, 0xcc),
+				(TEXT_RVA + 0x13, 8, 0),
+				(TEXT_RVA + 0x21, 0x1f, 0),
+			],
+			"{layout:?}"
+		);
+		assert_eq!(caves(data, layout, 0).len(), 4, "{layout:?}");
+		assert!(caves(data, layout, 0x20).is_empty(), "{layout:?}");
+	}
+}
+
+#[test]
+fn sections_past_the_end() {
+	// Raw data past the end of the file leaves the section out.
+	let mut data = padded().file();
+	let pointer_to_raw_data = NT_HEADERS_OFFSET as usize + 4 + 20 + 0xf0 + 20;
+	data[pointer_to_raw_data..pointer_to_raw_data + 4].copy_from_slice(&0x10_0000u32.to_le_bytes());
+	assert!(caves(common::leak(&data), common::Layout::File, 1).is_empty());
+}
@@ -0,0 +1,172 @@
+//! Hybrid metadata is read at `image_base + rva`, so only the mapped layout is parsed.
+
+mod common;
+
+use common::{Blob, Layout, PeBuilder, RDATA};
+use core::mem::offset_of;
+use object::{pe, LittleEndian};
+use objparse::{
+	chpe::{CodeKind, CodeRange, HybridMetadata},
+	error::Error,
+	nt::NtHeaders,
+	PeHeaders,
+};
+
+/// Writes a table of `entries` of `u32` fields and returns its RVA.
+fn table<const N: usize>(blob: &mut Blob, entries: &[[u32; N]]) -> u32 {
+	let rva = blob.here();
+	for &value in entries.iter().flatten() {
+		blob.u32(value);
+	}
+	rva
+}
+
+/// An image whose load config, `load_config_size` bytes long, points to the metadata that
+/// `metadata` writes, by the VA `chpe_va` maps from its RVA.
+fn hybrid(
+	mut pe: PeBuilder,
+	load_config_size: usize,
+	chpe_va: impl FnOnce(u64, u32) -> u64,
+	metadata: impl FnOnce(&mut Blob) -> u32,
+) -> PeBuilder {
+	let mut rdata = pe.blob();
+	let metadata = metadata(&mut rdata);
+	rdata.align(8);
+	let load_config = rdata.here();
+	let (offset, width) = match pe.is_64 {
+		true => (
+			offset_of!(pe::ImageLoadConfigDirectory64, chpe_metadata_pointer),
+			8,
+		),
+		false => (
+			offset_of!(pe::ImageLoadConfigDirectory32, chpe_metadata_pointer),
+			4,
+		),
+	};
+	rdata.u32(load_config_size as u32).zeroes(offset - 4);
+	rdata.ptr(pe.is_64, chpe_va(pe.image_base, metadata));
+	rdata.zeroes(load_config_size.saturating_sub(offset + width));
+	pe.section(".rdata", RDATA, rdata);
+	pe.directory(
+		pe::IMAGE_DIRECTORY_ENTRY_LOAD_CONFIG,
+		(load_config, load_config_size as u32),
+	);
+	pe
+}
+
+/// ARM64EC metadata with a code map of ARM64, ARM64EC and x64 code.
+fn arm64ec(rdata: &mut Blob) -> u32 {
+	let code_map = table(rdata, &[[0x1000, 0x100], [0x1101, 0x100], [0x1202, 0x80]]);
+	let redirections = table(rdata, &[[0x1100, 0x1300]]);
+	let entry_points = table(rdata, &[[0x1100, 0x1200, 0x1180]]);
+	let mut metadata = [0; 20];
+	metadata[..4].copy_from_slice(&[1, code_map, 3, entry_points]);
+	metadata[4] = redirections;
+	metadata[12..14].copy_from_slice(&[1, 1]);
+	table(rdata, &[metadata])
+}
+
+/// CHPE metadata of an x86 image with x86 and ARM64 code.
+fn chpe_x86(rdata: &mut Blob) -> u32 {
+	let code_map = table(rdata, &[[0x1000, 0x100], [0x1101, 0x40]]);
+	table(rdata, &[[1, code_map, 2, 0, 0, 0, 0, 0, 0, 0]])
+}
+
+/// A load config large enough for the CHPE metadata pointer.
+const LOAD_CONFIG_SIZE: usize = 0x140;
+
+fn hybrid_metadata<Nt: NtHeaders>(pe: &PeBuilder) -> Result<Option<HybridMetadata>, Error> {
+	let data = pe.leak(Layout::Mapped);
+	let headers: PeHeaders<Nt> = common::parse(data, Layout::Mapped);
+	unsafe { headers.hybrid_metadata(data.as_ptr()) }
+}
+
+fn va(image_base: u64, rva: u32) -> u64 {
+	image_base + rva as u64
+}
+
+#[test]
+fn arm64ec_metadata() {
+	let pe = hybrid(PeBuilder::new64(), LOAD_CONFIG_SIZE, va, arm64ec);
+	let Some(HybridMetadata::Arm64Ec(metadata)) =
+		hybrid_metadata::<pe::ImageNtHeaders64>(&pe).unwrap()
+	else {
+		panic!("no ARM64EC metadata");
+	};
+	let ranges: Vec<_> = metadata.code_ranges().collect();
+	assert_eq!(
+		ranges,
+		[
+			CodeRange {
+				start: 0x1000,
+				length: 0x100,
+				kind: CodeKind::Arm64
+			},
+			CodeRange {
+				start: 0x1100,
+				length: 0x100,
+				kind: CodeKind::Arm64Ec
+			},
+			CodeRange {
+				start: 0x1200,
+				length: 0x80,
+				kind: CodeKind::Amd64
+			},
+		]
+	);
+	assert_eq!(metadata.redirect(0x1100), Some(0x1300));
+	assert_eq!(metadata.redirect(0x1000), None);
+	assert_eq!(metadata.entry_points.len(), 1);
+	assert_eq!(
+		metadata.entry_points[0].entry_point.get(LittleEndian),
+		0x1180
+	);
+
+	let hybrid = HybridMetadata::Arm64Ec(metadata);
+	assert_eq!(hybrid.code_kind(0x1150), Some(CodeKind::Arm64Ec));
+	assert_eq!(hybrid.code_kind(0x127f), Some(CodeKind::Amd64));
+	assert_eq!(hybrid.code_kind(0x1280), None);
+}
+
+#[test]
+fn chpe_x86_metadata() {
+	let pe = hybrid(PeBuilder::new32(), LOAD_CONFIG_SIZE, va, chpe_x86);
+	let hybrid = hybrid_metadata::<pe::ImageNtHeaders32>(&pe)
+		.unwrap()
+		.unwrap();
+	assert!(matches!(hybrid, HybridMetadata::X86(_)));
+	assert_eq!(hybrid.code_ranges().count(), 2);
+	assert_eq!(hybrid.code_kind(0x1000), Some(CodeKind::X86));
+	assert_eq!(hybrid.code_kind(0x1120), Some(CodeKind::Arm64));
+	assert_eq!(hybrid.code_kind(0x1140), None);
+}
+
+#[test]
+fn missing_and_malformed_metadata() {
+	// No load config, one too old for the CHPE pointer, and a null pointer.
+	let native = common::sample(true);
+	assert!(hybrid_metadata::<pe::ImageNtHeaders64>(&native)
+		.unwrap()
+		.is_none());
+	let offset = offset_of!(pe::ImageLoadConfigDirectory64, chpe_metadata_pointer);
+	let old = hybrid(PeBuilder::new64(), offset + 7, va, arm64ec);
+	assert!(hybrid_metadata::<pe::ImageNtHeaders64>(&old)
+		.unwrap()
+		.is_none());
+	let null = hybrid(PeBuilder::new64(), LOAD_CONFIG_SIZE, |_, _| 0, arm64ec);
+	assert!(hybrid_metadata::<pe::ImageNtHeaders64>(&null)
+		.unwrap()
+		.is_none());
+
+	// A pointer below the image base.
+	let below = hybrid(
+		PeBuilder::new64(),
+		LOAD_CONFIG_SIZE,
+		|image_base, _| image_base - 0x1000,
+		arm64ec,
+	);
+	assert!(matches!(
+		hybrid_metadata::<pe::ImageNtHeaders64>(&below),
+		Err(Error::ChpeMetadata)
+	));
+}
@@ -0,0 +1,583 @@
+//! Managed images built around a CLR header. Its directories are read at `image_base + rva`,
+//! so only the mapped layout is parsed.
+
+mod common;
+
+use common::{Blob, Layout, PeBuilder, CODE};
+use object::{pe, LittleEndian};
+use objparse::{
+	clr::{
+		ClrHeader, ClrVersion, READYTORUN_SECTION_COMPILER_IDENTIFIER,
+		READYTORUN_SECTION_RUNTIME_FUNCTIONS, READYTORUN_SIGNATURE,
+	},
+	error::Error,
+	metadata::{
+		decompress_u32, Metadata, TablesStream, METADATA_SIGNATURE, TABLE_MANIFEST_RESOURCE,
+		TABLE_MODULE,
+	},
+	PeHeaders,
+};
+
+const SIZE_OF_COR20_HEADER: u32 = 72;
+// Offsets of the directories in the CLR header.
+const META_DATA: u32 = 8;
+const RESOURCES: u32 = 24;
+const STRONG_NAME_SIGNATURE: u32 = 32;
+const MANAGED_NATIVE_HEADER: u32 = 64;
+
+/// A managed image whose only section starts with the CLR header. `contents` writes the
+/// directories after it with [`directory`].
+fn managed(flags: u32, contents: impl FnOnce(&mut Blob)) -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	let mut text = pe.blob();
+	text.u32(SIZE_OF_COR20_HEADER)
+		.u16(2)
+		.u16(5)
+		.zeroes(8)
+		.u32(flags);
+	text.zeroes(SIZE_OF_COR20_HEADER as usize - 20);
+	contents(&mut text);
+	let rva = pe.section(".text", CODE, text);
+	pe.directory(
+		pe::IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR,
+		(rva, SIZE_OF_COR20_HEADER),
+	);
+	pe
+}
+
+/// Writes `data` and points the directory at `offset` in the CLR header to it.
+fn directory(text: &mut Blob, offset: u32, data: &[u8]) {
+	let rva = text.here();
+	text.bytes(data).align(4);
+	text.patch_u32(text.rva + offset, rva);
+	text.patch_u32(text.rva + offset + 4, data.len() as u32);
+}
+
+/// Calls `check` with the CLR header of `pe` mapped at `image_base`.
+fn with_clr_header(pe: &PeBuilder, check: impl FnOnce(ClrHeader, *const u8)) {
+	let data = pe.leak(Layout::Mapped);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::Mapped);
+	let clr_header = unsafe { headers.clr_header_mem(data.as_ptr()) }
+		.unwrap()
+		.unwrap();
+	check(clr_header, data.as_ptr());
+}
+
+/// A ReadyToRun header with a compiler identifier and two runtime functions, its signature
+/// replaced by `signature`.
+fn ready_to_run(text: &mut Blob, signature: u32, compiler_identifier: &[u8]) {
+	let compiler = text.here();
+	text.bytes(compiler_identifier).align(4);
+	let functions = text.here();
+	text.u32(0x1000).u32(0x1010).u32(0x2000);
+	text.u32(0x1010).u32(0x1020).u32(0x2010);
+	let mut header = Vec::new();
+	for value in [
+		signature,
+		9 | 2 << 16,
+		0,
+		2,
+		READYTORUN_SECTION_COMPILER_IDENTIFIER,
+		compiler,
+		compiler_identifier.len() as u32,
+		READYTORUN_SECTION_RUNTIME_FUNCTIONS,
+		functions,
+		24,
+	] {
+		header.extend_from_slice(&value.to_le_bytes());
+	}
+	directory(text, MANAGED_NATIVE_HEADER, &header);
+}
+
+#[test]
+fn ready_to_run_header() {
+	let pe = managed(0, |text| {
+		ready_to_run(text, READYTORUN_SIGNATURE, b"Crossgen2 1.0\0")
+	});
+	with_clr_header(&pe, |clr_header, base| {
+		assert_eq!(
+			clr_header.cor20_header.cb.get(LittleEndian),
+			SIZE_OF_COR20_HEADER
+		);
+		let header = unsafe { clr_header.ready_to_run(base) }.unwrap().unwrap();
+		assert_eq!(header.header.major_version.get(LittleEndian), 9);
+		assert_eq!(header.header.minor_version.get(LittleEndian), 2);
+		assert_eq!(header.sections.len(), 2);
+		assert_eq!(
+			unsafe { header.compiler_identifier(base) },
+			Ok(Some(c"Crossgen2 1.0"))
+		);
+		let functions = unsafe { header.runtime_functions(base) }.unwrap();
+		let functions: Vec<_> = functions
+			.iter()
+			.map(|function| {
+				(
+					function.begin_address.get(LittleEndian),
+					function.end_address.get(LittleEndian),
+				)
+			})
+			.collect();
+		assert_eq!(functions, [(0x1000, 0x1010), (0x1010, 0x1020)]);
+		assert!(header
+			.section(READYTORUN_SECTION_COMPILER_IDENTIFIER + 100)
+			.is_none());
+	});
+}
+
+#[test]
+fn malformed_ready_to_run_headers() {
+	// No ReadyToRun header at all, and one with the wrong signature.
+	with_clr_header(&managed(0, |_| {}), |clr_header, base| {
+		assert!(unsafe { clr_header.ready_to_run(base) }.unwrap().is_none());
+	});
+	let pe = managed(0, |text| {
+		ready_to_run(text, 0x0052_5453, b"Crossgen2 1.0\0")
+	});
+	with_clr_header(&pe, |clr_header, base| {
+		assert!(unsafe { clr_header.ready_to_run(base) }.unwrap().is_none());
+	});
+
+	// A compiler identifier without its terminator.
+	let pe = managed(0, |text| {
+		ready_to_run(text, READYTORUN_SIGNATURE, b"Crossgen2 1.0")
+	});
+	with_clr_header(&pe, |clr_header, base| {
+		let header = unsafe { clr_header.ready_to_run(base) }.unwrap().unwrap();
+		assert_eq!(
+			unsafe { header.compiler_identifier(base) },
+			Err(Error::ClrHeader)
+		);
+	});
+}
+
+#[test]
+fn strong_name_signatures() {
+	for (flags, signature, signed, valid_length, delay_signed) in [
+		(
+			pe::COMIMAGE_FLAGS_STRONGNAMESIGNED,
+			&[0x5a; 256][..],
+			true,
+			true,
+			false,
+		),
+		// Space reserved for delay signing.
+		(0, &[0; 128][..], false, true, true),
+		(
+			pe::COMIMAGE_FLAGS_STRONGNAMESIGNED,
+			&[0x5a; 100][..],
+			true,
+			false,
+			false,
+		),
+	] {
+		let pe = managed(flags, |text| {
+			directory(text, STRONG_NAME_SIGNATURE, signature)
+		});
+		with_clr_header(&pe, |clr_header, base| {
+			let strong_name = unsafe { clr_header.strong_name_signature(base) }
+				.unwrap()
+				.unwrap();
+			assert_eq!(strong_name.data, signature);
+			assert_eq!(strong_name.signed, signed);
+			assert_eq!(strong_name.is_valid_length(), valid_length);
+			assert_eq!(strong_name.is_delay_signed(), delay_signed);
+		});
+	}
+
+	with_clr_header(
+		&managed(pe::COMIMAGE_FLAGS_STRONGNAMESIGNED, |_| {}),
+		|clr_header, base| {
+			assert!(unsafe { clr_header.strong_name_signature(base) }
+				.unwrap()
+				.is_none());
+		},
+	);
+}
+
+/// A metadata root of `version` with `streams`, each a name and its data.
+fn metadata_root(version: &str, streams: &[(&str, &[u8])]) -> Vec<u8> {
+	// Offsets in the root are relative to its start.
+	let mut root = Blob {
+		rva: 0,
+		file_offset: 0,
+		data: Vec::new(),
+	};
+	let version_len = (version.len() + 1).next_multiple_of(4);
+	root.u32(METADATA_SIGNATURE).u16(1).u16(1).u32(0);
+	root.u32(version_len as u32).cstr(version);
+	root.align(4).u16(0).u16(streams.len() as u16);
+	let headers_len: usize = streams
+		.iter()
+		.map(|(name, _)| 8 + (name.len() + 1).next_multiple_of(4))
+		.sum();
+	let mut offset = root.data.len() + headers_len;
+	for (name, data) in streams {
+		root.u32(offset as u32).u32(data.len() as u32);
+		root.cstr(name);
+		root.align(4);
+		offset += data.len().next_multiple_of(4);
+	}
+	for (_, data) in streams {
+		root.bytes(data).align(4);
+	}
+	root.data
+}
+
+const STRINGS: &[u8] = b"\0Sample\0config\0payload\0remote\0";
+// "Hi" in UTF-16 and the flag byte.
+const USER_STRINGS: &[u8] = b"\0\x05H\0i\0\0";
+const GUIDS: &[u8] = &[0x11; 16];
+
+fn blobs() -> Vec<u8> {
+	let mut blobs = b"\0\x03abc\x80\x80".to_vec();
+	blobs.extend_from_slice(&[0xbb; 0x80]);
+	blobs
+}
+
+/// A metadata root with the heaps and `streams`.
+fn metadata(streams: &[(&str, &[u8])]) -> Vec<u8> {
+	let blobs = blobs();
+	let heaps = [
+		("#Strings", STRINGS),
+		("#US", USER_STRINGS),
+		("#GUID", GUIDS),
+		("#Blob", &blobs[..]),
+	];
+	metadata_root("v4.0.30319", &[&heaps[..], streams].concat())
+}
+
+/// Calls `check` with the metadata of an image with the heaps and `streams`.
+fn with_metadata(streams: &[(&str, &[u8])], check: impl FnOnce(Metadata)) {
+	let root = metadata(streams);
+	let pe = managed(0, |text| directory(text, META_DATA, &root));
+	with_clr_header(&pe, |clr_header, base| {
+		check(unsafe { clr_header.metadata(base) }.unwrap().unwrap())
+	});
+}
+
+#[test]
+fn metadata_root_and_heaps() {
+	with_metadata(&[], |metadata| {
+		assert_eq!(metadata.version, b"v4.0.30319");
+		assert_eq!((metadata.major_version, metadata.minor_version), (1, 1));
+		assert_eq!(metadata.number_of_streams, 4);
+		let names: Vec<_> = metadata.streams().map(|stream| stream.name).collect();
+		assert_eq!(names, [c"#Strings", c"#US", c"#GUID", c"#Blob"]);
+		assert_eq!(metadata.stream("#GUID"), Some(GUIDS));
+		assert_eq!(metadata.stream("#~"), None);
+
+		let strings = metadata.strings().unwrap();
+		assert_eq!(strings.get(1), Some(c"Sample"));
+		assert_eq!(strings.get(3), Some(c"mple"));
+		assert_eq!(strings.get(STRINGS.len() as u32), None);
+		let user_strings = metadata.user_strings().unwrap();
+		assert_eq!(user_strings.get(1), Some(&b"H\0i\0"[..]));
+		assert_eq!(user_strings.get(0), Some(&[][..]));
+		let blobs = metadata.blobs().unwrap();
+		assert_eq!(blobs.get(1), Some(&b"abc"[..]));
+		assert_eq!(blobs.get(5), Some(&[0xbb; 0x80][..]));
+		assert_eq!(blobs.get(6), None);
+		let guids = metadata.guids().unwrap();
+		assert_eq!(guids.get(1), Some(&[0x11; 16]));
+		for index in [0, 2, u32::MAX] {
+			assert_eq!(guids.get(index), None);
+		}
+	});
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn user_strings_as_text() {
+	with_metadata(&[], |metadata| {
+		let user_strings = metadata.user_strings().unwrap();
+		assert_eq!(user_strings.get_string(1), Some(Ok("Hi".into())));
+		assert_eq!(user_strings.get_string(100), None);
+	});
+}
+
+#[test]
+fn compressed_integers() {
+	for (data, expected) in [
+		(&[0x03][..], Some((3, 1))),
+		(&[0x7f], Some((0x7f, 1))),
+		(&[0x80, 0x80], Some((0x80, 2))),
+		(&[0xbf, 0xff], Some((0x3fff, 2))),
+		(&[0xc0, 0x00, 0x40, 0x00], Some((0x4000, 4))),
+		(&[0xdf, 0xff, 0xff, 0xff], Some((0x1fff_ffff, 4))),
+		(&[0x80], None),
+		(&[0xc0, 0x00, 0x40], None),
+		(&[0xe0, 0, 0, 0], None),
+		(&[], None),
+	] {
+		assert_eq!(decompress_u32(data), expected);
+	}
+}
+
+#[test]
+fn malformed_metadata_roots() {
+	let parse = |root: &[u8]| Metadata::parse(common::leak(root));
+	let root = metadata_root("v4.0.30319", &[("#GUID", GUIDS)]);
+	assert!(parse(&root).is_ok());
+
+	let mut signature = root.clone();
+	signature[0] = b'X';
+	let mut version_len = root.clone();
+	version_len[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+	for root in [&root[..0x10], &root[..0x1c], &signature, &version_len] {
+		assert!(matches!(parse(root), Err(Error::ClrMetadata)));
+	}
+
+	// Streams past the end of the metadata, or beyond the stream headers, are dropped.
+	let mut stream_offset = root.clone();
+	stream_offset[0x20..0x24].copy_from_slice(&0x1000u32.to_le_bytes());
+	let mut number_of_streams = root.clone();
+	number_of_streams[0x1e..0x20].copy_from_slice(&2u16.to_le_bytes());
+	for (root, count) in [(stream_offset, 0), (number_of_streams, 1)] {
+		assert_eq!(parse(&root).unwrap().streams().count(), count);
+	}
+
+	// No metadata directory at all.
+	with_clr_header(&managed(0, |_| {}), |clr_header, base| {
+		assert!(unsafe { clr_header.metadata(base) }.unwrap().is_none());
+	});
+}
+
+/// A `#~` stream of `tables`, each an index, a row count and the rows, in the order of their
+/// indices.
+fn tables_stream(heap_sizes: u8, tables: &[(usize, u32, &[u8])]) -> Vec<u8> {
+	let valid = tables
+		.iter()
+		.fold(0u64, |valid, &(table, ..)| valid | 1 << table);
+	let mut stream = Blob {
+		rva: 0,
+		file_offset: 0,
+		data: Vec::new(),
+	};
+	stream
+		.u32(0)
+		.u8(2)
+		.u8(0)
+		.u8(heap_sizes)
+		.u8(1)
+		.u64(valid)
+		.u64(0);
+	for &(_, row_count, _) in tables {
+		stream.u32(row_count);
+	}
+	for &(.., rows) in tables {
+		stream.bytes(rows);
+	}
+	stream.data
+}
+
+const MODULE: &[u8] = &[0, 0, 1, 0, 1, 0, 0, 0, 0, 0];
+const MANIFEST_RESOURCE_PUBLIC: u32 = 0x0001;
+const MANIFEST_RESOURCE_PRIVATE: u32 = 0x0002;
+
+/// `ManifestResource` rows, each an offset, flags, a name and an `Implementation`.
+fn manifest_resources(rows: &[(u32, u32, u16, u16)]) -> Vec<u8> {
+	let mut table = Vec::new();
+	for &(offset, flags, name, implementation) in rows {
+		table.extend_from_slice(&offset.to_le_bytes());
+		table.extend_from_slice(&flags.to_le_bytes());
+		table.extend_from_slice(&name.to_le_bytes());
+		table.extend_from_slice(&implementation.to_le_bytes());
+	}
+	table
+}
+
+/// Calls `check` with an image holding `resources` and a `#~` stream of the module and
+/// `manifest_resources`.
+fn with_resources(
+	manifest_resources: &[(u32, u32, u16, u16)],
+	resources: &[u8],
+	check: impl FnOnce(ClrHeader, *const u8, Metadata),
+) {
+	let rows = self::manifest_resources(manifest_resources);
+	let tables = tables_stream(
+		0,
+		&[
+			(TABLE_MODULE, 1, MODULE),
+			(
+				TABLE_MANIFEST_RESOURCE,
+				manifest_resources.len() as u32,
+				&rows,
+			),
+		],
+	);
+	let root = metadata(&[("#~", &tables)]);
+	let pe = managed(0, |text| {
+		directory(text, META_DATA, &root);
+		directory(text, RESOURCES, resources);
+	});
+	with_clr_header(&pe, |clr_header, base| {
+		let metadata = unsafe { clr_header.metadata(base) }.unwrap().unwrap();
+		check(clr_header, base, metadata)
+	});
+}
+
+// Indices of the resource names in the `#Strings` heap.
+const CONFIG: u16 = 8;
+const PAYLOAD: u16 = 15;
+const REMOTE: u16 = 23;
+const RESOURCE_DATA: &[u8] = b"\x03\0\0\0abc\0\x05\0\0\0hello";
+
+#[test]
+fn tables_stream_rows() {
+	with_resources(
+		&[(0, MANIFEST_RESOURCE_PUBLIC, CONFIG, 0)],
+		&[],
+		|_, _, metadata| {
+			let tables = metadata.tables().unwrap();
+			assert_eq!(tables.row_count(TABLE_MODULE), 1);
+			assert_eq!(tables.row_count(TABLE_MANIFEST_RESOURCE), 1);
+			assert_eq!(tables.row_size(TABLE_MODULE), 10);
+			assert_eq!(tables.row_size(TABLE_MANIFEST_RESOURCE), 12);
+			assert_eq!(tables.get(TABLE_MODULE, 1, 1), Some(1));
+			assert_eq!(tables.get(TABLE_MODULE, 1, 2), Some(1));
+			assert_eq!(
+				tables.get(TABLE_MANIFEST_RESOURCE, 1, 2),
+				Some(CONFIG as u32)
+			);
+			// Rows are 1-based.
+			assert_eq!(tables.get(TABLE_MODULE, 0, 1), None);
+			assert_eq!(tables.get(TABLE_MODULE, 2, 1), None);
+			assert_eq!(tables.get(TABLE_MODULE, 1, 5), None);
+			assert_eq!(tables.get(64, 1, 0), None);
+		},
+	);
+
+	// Wide string indices widen every string column.
+	let stream = tables_stream(0x01, &[(TABLE_MODULE, 1, &[0; 12])]);
+	let tables = TablesStream::parse(common::leak(&stream)).unwrap();
+	assert_eq!(tables.row_size(TABLE_MODULE), 12);
+}
+
+#[test]
+fn managed_resources() {
+	let rows = [
+		(0, MANIFEST_RESOURCE_PUBLIC, CONFIG, 0),
+		(8, MANIFEST_RESOURCE_PRIVATE, PAYLOAD, 0),
+		// In another assembly, an `AssemblyRef` in `Implementation`.
+		(0, MANIFEST_RESOURCE_PUBLIC, REMOTE, 1 << 2 | 1),
+	];
+	with_resources(&rows, RESOURCE_DATA, |clr_header, base, metadata| {
+		let resources: Vec<_> = unsafe { clr_header.managed_resources(base, &metadata) }
+			.unwrap()
+			.map(|resource| (resource.name, resource.data, resource.public))
+			.collect();
+		assert_eq!(
+			resources,
+			[
+				(c"config", &b"abc"[..], true),
+				(c"payload", &b"hello"[..], false)
+			]
+		);
+		let payload = unsafe { clr_header.managed_resource(base, &metadata, "payload") };
+		assert_eq!(payload.unwrap().unwrap().data, b"hello");
+		let remote = unsafe { clr_header.managed_resource(base, &metadata, "remote") };
+		assert!(remote.unwrap().is_none());
+	});
+}
+
+#[test]
+fn malformed_tables_and_resources() {
+	// Offsets and lengths past the resources are skipped.
+	let rows = [
+		(0x100, MANIFEST_RESOURCE_PUBLIC, CONFIG, 0),
+		(u32::MAX, MANIFEST_RESOURCE_PUBLIC, CONFIG, 0),
+		(8, MANIFEST_RESOURCE_PUBLIC, PAYLOAD, 0),
+		(0, MANIFEST_RESOURCE_PUBLIC, 0x1000, 0),
+	];
+	let mut resources = RESOURCE_DATA.to_vec();
+	resources[8] = 0x06;
+	with_resources(&rows, &resources, |clr_header, base, metadata| {
+		let resources = unsafe { clr_header.managed_resources(base, &metadata) }.unwrap();
+		assert_eq!(resources.count(), 0);
+	});
+
+	// More rows than the stream holds, and no stream at all.
+	let stream = tables_stream(0, &[(TABLE_MODULE, 2, MODULE)]);
+	assert!(matches!(
+		TablesStream::parse(common::leak(&stream)),
+		Err(Error::ClrMetadata)
+	));
+	let stream = tables_stream(0, &[(TABLE_MODULE, u32::MAX, MODULE)]);
+	assert!(matches!(
+		TablesStream::parse(common::leak(&stream)),
+		Err(Error::ClrMetadata)
+	));
+	assert!(matches!(
+		TablesStream::parse(common::leak(&stream[..20])),
+		Err(Error::ClrMetadata)
+	));
+	with_metadata(&[], |metadata| {
+		assert!(matches!(metadata.tables(), Err(Error::ClrMetadata)));
+	});
+}
+
+/// The CLR version of `pe` mapped.
+fn clr_version(pe: &PeBuilder) -> Option<ClrVersion> {
+	let data = pe.leak(Layout::Mapped);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::Mapped);
+	unsafe { headers.clr_version(data.as_ptr()) }.unwrap()
+}
+
+#[test]
+fn clr_versions() {
+	let root = metadata(&[]);
+	for (flags, il_only, requires_32bit, prefers_32bit) in [
+		(pe::COMIMAGE_FLAGS_ILONLY, true, false, false),
+		(
+			pe::COMIMAGE_FLAGS_ILONLY | pe::COMIMAGE_FLAGS_32BITREQUIRED,
+			true,
+			true,
+			false,
+		),
+		(
+			pe::COMIMAGE_FLAGS_32BITREQUIRED | pe::COMIMAGE_FLAGS_32BITPREFERRED,
+			false,
+			false,
+			true,
+		),
+	] {
+		let pe = managed(flags, |text| directory(text, META_DATA, &root));
+		assert_eq!(
+			clr_version(&pe),
+			Some(ClrVersion {
+				runtime_version: (2, 5),
+				metadata_version: Some(b"v4.0.30319"),
+				il_only,
+				requires_32bit,
+				prefers_32bit,
+			})
+		);
+	}
+}
+
+#[test]
+fn native_and_malformed_clr_headers() {
+	let pe = common::sample(true);
+	let headers: PeHeaders<pe::ImageNtHeaders64> =
+		common::parse(pe.leak(Layout::Mapped), Layout::Mapped);
+	assert!(!headers.is_dotnet());
+	assert_eq!(clr_version(&pe), None);
+
+	// A COM descriptor too small for the CLR header.
+	let mut pe = managed(pe::COMIMAGE_FLAGS_ILONLY, |_| {});
+	pe.directory(
+		pe::IMAGE_DIRECTORY_ENTRY_COM_DESCRIPTOR,
+		(common::TEXT_RVA, SIZE_OF_COR20_HEADER - 1),
+	);
+	assert_eq!(clr_version(&pe), None);
+
+	// Metadata that does not parse only loses its version.
+	let mut root = metadata(&[]);
+	root[0] = b'X';
+	let pe = managed(pe::COMIMAGE_FLAGS_ILONLY, |text| {
+		directory(text, META_DATA, &root)
+	});
+	let version = clr_version(&pe).unwrap();
+	assert_eq!(version.metadata_version, None);
+	assert!(version.il_only);
+}
@@ -0,0 +1,312 @@
+mod common;
+
+use object::{
+	pe::{
+		IMAGE_COMDAT_SELECT_ANY, IMAGE_COMDAT_SELECT_ASSOCIATIVE, IMAGE_REL_AMD64_ADDR64,
+		IMAGE_REL_AMD64_REL32, IMAGE_SCN_CNT_UNINITIALIZED_DATA, IMAGE_SCN_LNK_COMDAT,
+		IMAGE_SCN_LNK_NRELOC_OVFL, IMAGE_SYM_CLASS_EXTERNAL, IMAGE_SYM_CLASS_STATIC,
+	},
+	LittleEndian,
+};
+use objparse::{
+	coff::{CoffFile, Comdat},
+	error::Error,
+};
+
+/// A section of an object, with relocations each at a virtual address against a symbol index
+/// with a type.
+struct Section {
+	name: &'static [u8],
+	characteristics: u32,
+	data: &'static [u8],
+	relocations: &'static [(u32, u32, u16)],
+}
+
+const EMPTY: Section = Section {
+	name: b".s",
+	characteristics: 0,
+	data: &[],
+	relocations: &[],
+};
+
+fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+	data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+/// An x64 object of `sections`, followed by its symbol table and string table.
+fn object(sections: &[Section], symbols: &[[u8; 18]], strings: &[u8]) -> &'static [u8] {
+	let mut data = vec![0; 20 + 40 * sections.len()];
+	put(&mut data, 0, &0x8664u16.to_le_bytes());
+	put(&mut data, 2, &(sections.len() as u16).to_le_bytes());
+	for (index, section) in sections.iter().enumerate() {
+		let header = 20 + 40 * index;
+		put(&mut data, header, section.name);
+		put(
+			&mut data,
+			header + 16,
+			&(section.data.len() as u32).to_le_bytes(),
+		);
+		if !section.data.is_empty() {
+			let offset = data.len() as u32;
+			put(&mut data, header + 20, &offset.to_le_bytes());
+			data.extend_from_slice(section.data);
+		}
+		let offset = data.len() as u32;
+		put(&mut data, header + 24, &offset.to_le_bytes());
+		for &(virtual_address, symbol, typ) in section.relocations {
+			data.extend_from_slice(&virtual_address.to_le_bytes());
+			data.extend_from_slice(&symbol.to_le_bytes());
+			data.extend_from_slice(&typ.to_le_bytes());
+		}
+		let number_of_relocations = section.relocations.len() as u16;
+		put(&mut data, header + 32, &number_of_relocations.to_le_bytes());
+		put(
+			&mut data,
+			header + 36,
+			&section.characteristics.to_le_bytes(),
+		);
+	}
+	let offset = data.len() as u32;
+	put(&mut data, 8, &offset.to_le_bytes());
+	put(&mut data, 12, &(symbols.len() as u32).to_le_bytes());
+	data.extend_from_slice(&symbols.concat());
+	data.extend_from_slice(&(4 + strings.len() as u32).to_le_bytes());
+	data.extend_from_slice(strings);
+	common::leak(&data)
+}
+
+fn short_name(name: &[u8]) -> [u8; 8] {
+	let mut short_name = [0; 8];
+	short_name[..name.len()].copy_from_slice(name);
+	short_name
+}
+
+/// The name at `offset` in the string table, including its size field.
+fn long_name(offset: u32) -> [u8; 8] {
+	let mut long_name = [0; 8];
+	long_name[4..].copy_from_slice(&offset.to_le_bytes());
+	long_name
+}
+
+fn symbol(
+	name: [u8; 8],
+	section_number: u16,
+	storage_class: u8,
+	number_of_aux_symbols: u8,
+) -> [u8; 18] {
+	let mut symbol = [0; 18];
+	put(&mut symbol, 0, &name);
+	put(&mut symbol, 12, &section_number.to_le_bytes());
+	symbol[16] = storage_class;
+	symbol[17] = number_of_aux_symbols;
+	symbol
+}
+
+/// The auxiliary record of a section symbol.
+fn section_aux(length: u32, associated_section: u16, selection: u8) -> [u8; 18] {
+	let mut aux = [0; 18];
+	put(&mut aux, 0, &length.to_le_bytes());
+	put(&mut aux, 12, &associated_section.to_le_bytes());
+	aux[14] = selection;
+	aux
+}
+
+const TEXT: u32 = 0x6050_1020;
+const RDATA: u32 = 0x4030_0040;
+const CODE: &[u8] = &[0xe8, 0, 0, 0, 0, 0xc3];
+const STRINGS: &[u8] = b".rdata$averylongname\0averylongsymbolname\0";
+
+/// A function and read-only data associated with it, both COMDATs, and `.bss`.
+fn sample() -> &'static [u8] {
+	let sections = [
+		Section {
+			name: b".text$mn",
+			characteristics: TEXT | IMAGE_SCN_LNK_COMDAT,
+			data: CODE,
+			relocations: &[(1, 5, IMAGE_REL_AMD64_REL32)],
+		},
+		Section {
+			name: b"/4",
+			characteristics: RDATA | IMAGE_SCN_LNK_COMDAT,
+			data: &[0x11; 8],
+			relocations: &[],
+		},
+		Section {
+			name: b".bss",
+			characteristics: IMAGE_SCN_CNT_UNINITIALIZED_DATA,
+			..EMPTY
+		},
+	];
+	let symbols = [
+		symbol(short_name(b".text$mn"), 1, IMAGE_SYM_CLASS_STATIC, 1),
+		section_aux(CODE.len() as u32, 0, IMAGE_COMDAT_SELECT_ANY),
+		symbol(short_name(b"func"), 1, IMAGE_SYM_CLASS_EXTERNAL, 0),
+		symbol(long_name(4), 2, IMAGE_SYM_CLASS_STATIC, 1),
+		section_aux(8, 1, IMAGE_COMDAT_SELECT_ASSOCIATIVE),
+		symbol(long_name(25), 0, IMAGE_SYM_CLASS_EXTERNAL, 0),
+	];
+	object(&sections, &symbols, STRINGS)
+}
+
+#[test]
+fn sections_and_relocations() {
+	let coff = CoffFile::parse(sample()).unwrap();
+	let names: Vec<_> = coff
+		.section_headers
+		.iter()
+		.map(|section| coff.section_name(section).unwrap())
+		.collect();
+	assert_eq!(names, [&b".text$mn"[..], b".rdata$averylongname", b".bss"]);
+	let [text, rdata, bss] = coff.section_headers else {
+		unreachable!()
+	};
+	assert_eq!(coff.section_data(text), Some(CODE));
+	assert_eq!(coff.section_data(rdata), Some(&[0x11; 8][..]));
+	assert_eq!(coff.section_data(bss), None);
+
+	let relocations = coff.relocations(text).unwrap();
+	assert_eq!(relocations.len(), 1);
+	assert_eq!(relocations[0].virtual_address.get(LittleEndian), 1);
+	assert_eq!(relocations[0].symbol_table_index.get(LittleEndian), 5);
+	assert_eq!(relocations[0].typ.get(LittleEndian), IMAGE_REL_AMD64_REL32);
+	assert!(coff.relocations(bss).unwrap().is_empty());
+}
+
+#[test]
+fn symbols_and_comdats() {
+	let coff = CoffFile::parse(sample()).unwrap();
+	let symbols: Vec<_> = coff
+		.symbols()
+		.map(|symbol| {
+			(
+				symbol.index,
+				coff.symbol_name(symbol.symbol).unwrap(),
+				symbol.section_number(),
+				symbol.aux.len(),
+			)
+		})
+		.collect();
+	assert_eq!(
+		symbols,
+		[
+			(0, &b".text$mn"[..], 1, 18),
+			(2, b"func", 1, 0),
+			(3, b".rdata$averylongname", 2, 18),
+			(5, b"averylongsymbolname", 0, 0),
+		]
+	);
+	assert_eq!(coff.symbol(2).unwrap().index, 2);
+	assert!(coff.symbol(6).is_none());
+
+	assert_eq!(
+		coff.comdat(0),
+		Some(Comdat {
+			selection: IMAGE_COMDAT_SELECT_ANY,
+			associated_section: 0,
+			symbol: Some(2),
+		})
+	);
+	assert_eq!(
+		coff.comdat(1),
+		Some(Comdat {
+			selection: IMAGE_COMDAT_SELECT_ASSOCIATIVE,
+			associated_section: 1,
+			symbol: None,
+		})
+	);
+	assert_eq!(coff.comdat(2), None);
+	assert_eq!(coff.comdat(3), None);
+}
+
+#[test]
+fn comdats_past_0x7fff_sections() {
+	// Section numbers from 0x8000 are negative as `i16`.
+	let mut sections: Vec<_> = (0..0x8000).map(|_| EMPTY).collect();
+	sections[0x7fff].characteristics = TEXT | IMAGE_SCN_LNK_COMDAT;
+	let symbols = [
+		symbol(short_name(b".text"), 0x8000, IMAGE_SYM_CLASS_STATIC, 1),
+		section_aux(0, 0, IMAGE_COMDAT_SELECT_ANY),
+		symbol(short_name(b"func"), 0x8000, IMAGE_SYM_CLASS_EXTERNAL, 0),
+	];
+	let coff = CoffFile::parse(object(&sections, &symbols, &[])).unwrap();
+	assert_eq!(coff.section_headers.len(), 0x8000);
+	assert_eq!(
+		coff.comdat(0x7fff),
+		Some(Comdat {
+			selection: IMAGE_COMDAT_SELECT_ANY,
+			associated_section: 0,
+			symbol: Some(2),
+		})
+	);
+}
+
+#[test]
+fn relocation_count_overflow() {
+	// The first entry holds the count, including itself.
+	let sections = [Section {
+		name: b".data",
+		characteristics: RDATA | IMAGE_SCN_LNK_NRELOC_OVFL,
+		data: &[0; 0x10],
+		relocations: &[
+			(3, 0, 0),
+			(0, 0, IMAGE_REL_AMD64_ADDR64),
+			(8, 0, IMAGE_REL_AMD64_ADDR64),
+		],
+	}];
+	let mut data = object(&sections, &[], &[]).to_vec();
+	put(&mut data, 20 + 32, &0xFFFFu16.to_le_bytes());
+	let coff = CoffFile::parse(common::leak(&data)).unwrap();
+	let relocations = coff.relocations(&coff.section_headers[0]).unwrap();
+	let offsets: Vec<_> = relocations
+		.iter()
+		.map(|relocation| relocation.virtual_address.get(LittleEndian))
+		.collect();
+	assert_eq!(offsets, [0, 8]);
+
+	// A count past the end of the file.
+	let relocations = 20 + 40 + 0x10;
+	put(&mut data, relocations, &0x1000u32.to_le_bytes());
+	let coff = CoffFile::parse(common::leak(&data)).unwrap();
+	assert_eq!(
+		coff.relocations(&coff.section_headers[0]).unwrap_err(),
+		Error::Coff
+	);
+}
+
+#[test]
+fn malformed_objects() {
+	let sample = sample();
+	let patched = |offset: usize, bytes: &[u8]| -> &'static [u8] {
+		let mut data = sample.to_vec();
+		put(&mut data, offset, bytes);
+		common::leak(&data)
+	};
+	let symbol_table = u32::from_le_bytes(sample[8..12].try_into().unwrap()) as usize;
+	for data in [
+		// An image, with an optional header.
+		patched(16, &0xf0u16.to_le_bytes()),
+		// An anonymous or bigobj object.
+		patched(0, &[0, 0, 0xff, 0xff]),
+		&sample[..20 + 40 * 3 - 1],
+		// Symbols or strings past the end.
+		patched(12, &0x1000u32.to_le_bytes()),
+		patched(symbol_table + 6 * 18, &0x1000u32.to_le_bytes()),
+		&sample[..symbol_table + 6 * 18 + 4],
+	] {
+		assert!(matches!(CoffFile::parse(data), Err(Error::Coff)));
+	}
+
+	// Names past the string table, and relocations past the end of the file.
+	let data = patched(symbol_table + 5 * 18 + 4, &0x1000u32.to_le_bytes());
+	let coff = CoffFile::parse(data).unwrap();
+	assert_eq!(coff.symbol_name(coff.symbol(5).unwrap().symbol), None);
+	for name in [&b"/1000\0\0\0"[..], b"/x\0\0\0\0\0\0"] {
+		let coff = CoffFile::parse(patched(20 + 40, name)).unwrap();
+		assert_eq!(coff.section_name(&coff.section_headers[1]), None);
+	}
+	let coff = CoffFile::parse(patched(20 + 24, &0x1_0000u32.to_le_bytes())).unwrap();
+	assert_eq!(
+		coff.relocations(&coff.section_headers[0]).unwrap_err(),
+		Error::Coff
+	);
+}
@@ -0,0 +1,758 @@
+//! Builds small PE images in memory, in file and in mapped layout, so the tests do not depend
+//! on binaries of the host.
+#![allow(dead_code)]
+
+pub const SECTION_ALIGNMENT: u32 = 0x1000;
+pub const FILE_ALIGNMENT: u32 = 0x200;
+pub const SIZE_OF_HEADERS: u32 = 0x400;
+pub const NT_HEADERS_OFFSET: u32 = 0x80;
+pub const IMAGE_BASE_64: u64 = 0x1_8000_0000;
+pub const IMAGE_BASE_32: u64 = 0x1000_0000;
+/// Whether the host's `PeHeaders` parse PE32+.
+pub const NATIVE_IS_64: bool = cfg!(target_pointer_width = "64");
+
+pub const IMAGE_SCN_CNT_CODE: u32 = 0x20;
+pub const IMAGE_SCN_CNT_INITIALIZED_DATA: u32 = 0x40;
+pub const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+pub const IMAGE_SCN_MEM_READ: u32 = 0x4000_0000;
+pub const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+pub const CODE: u32 = IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ;
+pub const RDATA: u32 = IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ;
+pub const DATA: u32 = IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+	File,
+	Mapped,
+}
+
+pub const LAYOUTS: [Layout; 2] = [Layout::File, Layout::Mapped];
+
+impl Layout {
+	pub fn options(self) -> objparse::ParseOptions {
+		let layout = match self {
+			Layout::File => objparse::Layout::File,
+			Layout::Mapped => objparse::Layout::Mapped,
+		};
+		objparse::ParseOptions::new().layout(layout)
+	}
+}
+
+/// Contents of a section under construction, addressed by RVA.
+pub struct Blob {
+	pub rva: u32,
+	pub file_offset: u32,
+	pub data: Vec<u8>,
+}
+
+impl Blob {
+	/// RVA of the next byte written.
+	pub fn here(&self) -> u32 {
+		self.rva + self.data.len() as u32
+	}
+
+	/// File offset of `rva`, which must lie in this blob.
+	pub fn file_offset_of(&self, rva: u32) -> u32 {
+		rva - self.rva + self.file_offset
+	}
+
+	pub fn u8(&mut self, value: u8) -> &mut Self {
+		self.data.push(value);
+		self
+	}
+
+	pub fn u16(&mut self, value: u16) -> &mut Self {
+		self.bytes(&value.to_le_bytes())
+	}
+
+	pub fn u32(&mut self, value: u32) -> &mut Self {
+		self.bytes(&value.to_le_bytes())
+	}
+
+	pub fn u64(&mut self, value: u64) -> &mut Self {
+		self.bytes(&value.to_le_bytes())
+	}
+
+	/// A pointer-sized value of the image's width.
+	pub fn ptr(&mut self, is_64: bool, value: u64) -> &mut Self {
+		match is_64 {
+			true => self.u64(value),
+			false => self.u32(value as u32),
+		}
+	}
+
+	pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+		self.data.extend_from_slice(bytes);
+		self
+	}
+
+	pub fn zeroes(&mut self, len: usize) -> &mut Self {
+		self.data.resize(self.data.len() + len, 0);
+		self
+	}
+
+	pub fn align(&mut self, alignment: usize) -> &mut Self {
+		self.data
+			.resize(self.data.len().next_multiple_of(alignment), 0);
+		self
+	}
+
+	/// Writes a nul-terminated string and returns its RVA.
+	pub fn cstr(&mut self, string: &str) -> u32 {
+		let rva = self.here();
+		self.bytes(string.as_bytes()).u8(0);
+		rva
+	}
+
+	pub fn patch_u16(&mut self, rva: u32, value: u16) {
+		let offset = (rva - self.rva) as usize;
+		self.data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+	}
+
+	pub fn patch_u32(&mut self, rva: u32, value: u32) {
+		let offset = (rva - self.rva) as usize;
+		self.data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+	}
+}
+
+pub struct Section {
+	pub name: &'static str,
+	pub rva: u32,
+	pub virtual_size: u32,
+	pub file_offset: u32,
+	pub characteristics: u32,
+	pub data: Vec<u8>,
+}
+
+impl Section {
+	pub fn raw_size(&self) -> u32 {
+		(self.data.len() as u32).next_multiple_of(FILE_ALIGNMENT)
+	}
+}
+
+pub struct PeBuilder {
+	pub is_64: bool,
+	pub machine: u16,
+	pub image_base: u64,
+	pub characteristics: u16,
+	pub time_date_stamp: u32,
+	pub entry_point: u32,
+	pub dll_characteristics: u16,
+	pub subsystem: u16,
+	pub directories: [(u32, u32); 16],
+	pub sections: Vec<Section>,
+}
+
+impl PeBuilder {
+	/// An x64 dll.
+	pub fn new64() -> Self {
+		Self {
+			is_64: true,
+			machine: 0x8664,
+			image_base: IMAGE_BASE_64,
+			characteristics: 0x2022,
+			time_date_stamp: 0x6000_0000,
+			entry_point: 0,
+			dll_characteristics: 0x0160,
+			subsystem: 2,
+			directories: [(0, 0); 16],
+			sections: Vec::new(),
+		}
+	}
+
+	/// An x86 dll.
+	pub fn new32() -> Self {
+		Self {
+			is_64: false,
+			machine: 0x14c,
+			image_base: IMAGE_BASE_32,
+			characteristics: 0x2102,
+			..Self::new64()
+		}
+	}
+
+	/// An empty blob at the RVA and file offset the next section gets.
+	pub fn blob(&self) -> Blob {
+		let (rva, file_offset) = match self.sections.last() {
+			Some(last) => (
+				(last.rva + last.virtual_size.max(1)).next_multiple_of(SECTION_ALIGNMENT),
+				last.file_offset + last.raw_size(),
+			),
+			None => (SECTION_ALIGNMENT, SIZE_OF_HEADERS),
+		};
+		Blob {
+			rva,
+			file_offset,
+			data: Vec::new(),
+		}
+	}
+
+	/// Adds `blob` as a section as large as its contents, returning the section's RVA.
+	pub fn section(&mut self, name: &'static str, characteristics: u32, blob: Blob) -> u32 {
+		let virtual_size = blob.data.len() as u32;
+		self.section_with_size(name, characteristics, blob, virtual_size)
+	}
+
+	/// Adds `blob` as a section of `virtual_size`, larger than the contents for zero-fill.
+	pub fn section_with_size(
+		&mut self,
+		name: &'static str,
+		characteristics: u32,
+		blob: Blob,
+		virtual_size: u32,
+	) -> u32 {
+		assert_eq!((blob.rva, blob.file_offset), {
+			let next = self.blob();
+			(next.rva, next.file_offset)
+		});
+		self.sections.push(Section {
+			name,
+			rva: blob.rva,
+			virtual_size,
+			file_offset: blob.file_offset,
+			characteristics,
+			data: blob.data,
+		});
+		blob.rva
+	}
+
+	pub fn directory(&mut self, index: usize, (rva, size): (u32, u32)) -> &mut Self {
+		self.directories[index] = (rva, size);
+		self
+	}
+
+	pub fn size_of_image(&self) -> u32 {
+		self.sections
+			.last()
+			.map_or(SECTION_ALIGNMENT, |last| {
+				last.rva + last.virtual_size.max(1)
+			})
+			.next_multiple_of(SECTION_ALIGNMENT)
+	}
+
+	pub fn size_of_optional_header(&self) -> u16 {
+		match self.is_64 {
+			true => 0xf0,
+			false => 0xe0,
+		}
+	}
+
+	pub fn headers(&self) -> Vec<u8> {
+		let mut blob = Blob {
+			rva: 0,
+			file_offset: 0,
+			data: Vec::new(),
+		};
+		blob.u16(0x5a4d).zeroes(0x3a).u32(NT_HEADERS_OFFSET);
+		blob.zeroes(NT_HEADERS_OFFSET as usize - blob.data.len());
+		blob.u32(0x4550)
+			.u16(self.machine)
+			.u16(self.sections.len() as u16)
+			.u32(self.time_date_stamp)
+			.u32(0)
+			.u32(0)
+			.u16(self.size_of_optional_header())
+			.u16(self.characteristics);
+		blob.u16(if self.is_64 { 0x20b } else { 0x10b })
+			.u16(0x0e)
+			.u32(0)
+			.u32(0)
+			.u32(0)
+			.u32(self.entry_point)
+			.u32(SECTION_ALIGNMENT);
+		if !self.is_64 {
+			blob.u32(0);
+		}
+		blob.ptr(self.is_64, self.image_base)
+			.u32(SECTION_ALIGNMENT)
+			.u32(FILE_ALIGNMENT)
+			.u16(6)
+			.u16(0)
+			.u16(0)
+			.u16(0)
+			.u16(6)
+			.u16(0)
+			.u32(0)
+			.u32(self.size_of_image())
+			.u32(SIZE_OF_HEADERS)
+			.u32(0)
+			.u16(self.subsystem)
+			.u16(self.dll_characteristics);
+		for value in [0x10_0000, 0x1000, 0x10_0000, 0x1000] {
+			blob.ptr(self.is_64, value);
+		}
+		blob.u32(0).u32(16);
+		for (rva, size) in self.directories {
+			blob.u32(rva).u32(size);
+		}
+		for section in &self.sections {
+			let mut name = [0; 8];
+			name[..section.name.len()].copy_from_slice(section.name.as_bytes());
+			blob.bytes(&name)
+				.u32(section.virtual_size)
+				.u32(section.rva)
+				.u32(section.raw_size())
+				.u32(section.file_offset)
+				.u32(0)
+				.u32(0)
+				.u16(0)
+				.u16(0)
+				.u32(section.characteristics);
+		}
+		assert!(blob.data.len() <= SIZE_OF_HEADERS as usize);
+		blob.data.resize(SIZE_OF_HEADERS as usize, 0);
+		blob.data
+	}
+
+	/// The image as stored on disk.
+	pub fn file(&self) -> Vec<u8> {
+		let mut data = self.headers();
+		for section in &self.sections {
+			data.resize(section.file_offset as usize, 0);
+			data.extend_from_slice(&section.data);
+			data.resize((section.file_offset + section.raw_size()) as usize, 0);
+		}
+		data
+	}
+
+	/// The image as the loader maps it, without relocating it or binding imports.
+	pub fn mapped(&self) -> Vec<u8> {
+		let mut data = self.headers();
+		data.resize(self.size_of_image() as usize, 0);
+		for section in &self.sections {
+			let start = section.rva as usize;
+			let len = section.data.len().min(section.virtual_size as usize);
+			data[start..start + len].copy_from_slice(&section.data[..len]);
+		}
+		data
+	}
+
+	pub fn build(&self, layout: Layout) -> Vec<u8> {
+		match layout {
+			Layout::File => self.file(),
+			Layout::Mapped => self.mapped(),
+		}
+	}
+
+	/// [`PeBuilder::build`] leaked into 8-byte aligned memory, which the crate's `'static`
+	/// tables borrow from.
+	pub fn leak(&self, layout: Layout) -> &'static mut [u8] {
+		leak(&self.build(layout))
+	}
+}
+
+pub fn leak(data: &[u8]) -> &'static mut [u8] {
+	let words = vec![0u64; data.len().div_ceil(8)].leak();
+	let bytes =
+		unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr().cast::<u8>(), data.len()) };
+	bytes.copy_from_slice(data);
+	bytes
+}
+
+#[derive(Clone, Copy)]
+pub enum ExportFn<'a> {
+	Rva(u32),
+	Forwarder(&'a str),
+	Unused,
+}
+
+/// Writes an export directory with `functions` in ordinal order from `base`. `names` pairs a
+/// name with an index into `functions` and must be sorted. Returns the directory entry.
+pub fn exports(
+	blob: &mut Blob,
+	dll: &str,
+	base: u32,
+	functions: &[ExportFn],
+	names: &[(&str, u16)],
+) -> (u32, u32) {
+	let directory = blob.here();
+	blob.zeroes(40);
+	let address_table = blob.here();
+	blob.zeroes(functions.len() * 4);
+	let name_table = blob.here();
+	blob.zeroes(names.len() * 4);
+	let ordinal_table = blob.here();
+	for &(_, index) in names {
+		blob.u16(index);
+	}
+	let dll_name = blob.cstr(dll);
+	for (i, &(name, _)) in names.iter().enumerate() {
+		let name_rva = blob.cstr(name);
+		blob.patch_u32(name_table + i as u32 * 4, name_rva);
+	}
+	for (i, function) in functions.iter().enumerate() {
+		let rva = match function {
+			ExportFn::Rva(rva) => *rva,
+			ExportFn::Forwarder(forwarder) => blob.cstr(forwarder),
+			ExportFn::Unused => 0,
+		};
+		blob.patch_u32(address_table + i as u32 * 4, rva);
+	}
+	blob.align(4);
+	let size = blob.here() - directory;
+	blob.patch_u32(directory + 12, dll_name);
+	blob.patch_u32(directory + 16, base);
+	blob.patch_u32(directory + 20, functions.len() as u32);
+	blob.patch_u32(directory + 24, names.len() as u32);
+	blob.patch_u32(directory + 28, address_table);
+	blob.patch_u32(directory + 32, name_table);
+	blob.patch_u32(directory + 36, ordinal_table);
+	(directory, size)
+}
+
+#[derive(Clone, Copy)]
+pub enum ImportFn<'a> {
+	Name(u16, &'a str),
+	Ordinal(u16),
+}
+
+/// Writes an import directory importing `functions` from each dll, with separate name and
+/// address tables. Returns the directory entry, covering the descriptors and the terminator.
+pub fn imports(blob: &mut Blob, is_64: bool, dlls: &[(&str, &[ImportFn])]) -> (u32, u32) {
+	let directory = blob.here();
+	let size = (dlls.len() as u32 + 1) * 20;
+	blob.zeroes(size as usize);
+	for (i, &(dll, functions)) in dlls.iter().enumerate() {
+		let descriptor = directory + i as u32 * 20;
+		let thunk_size = if is_64 { 8 } else { 4 };
+		blob.align(8);
+		let name_table = blob.here();
+		blob.zeroes((functions.len() + 1) * thunk_size);
+		let address_table = blob.here();
+		blob.zeroes((functions.len() + 1) * thunk_size);
+		for (j, function) in functions.iter().enumerate() {
+			let thunk = match *function {
+				ImportFn::Name(hint, name) => {
+					blob.align(2);
+					let rva = blob.here();
+					blob.u16(hint).cstr(name);
+					rva as u64
+				}
+				ImportFn::Ordinal(ordinal) => match is_64 {
+					true => 1 << 63 | ordinal as u64,
+					false => 1 << 31 | ordinal as u64,
+				},
+			};
+			for table in [name_table, address_table] {
+				let slot = table + (j * thunk_size) as u32;
+				blob.patch_u32(slot, thunk as u32);
+				if is_64 {
+					blob.patch_u32(slot + 4, (thunk >> 32) as u32);
+				}
+			}
+		}
+		let name = blob.cstr(dll);
+		blob.patch_u32(descriptor, name_table);
+		blob.patch_u32(descriptor + 12, name);
+		blob.patch_u32(descriptor + 16, address_table);
+	}
+	blob.align(8);
+	(directory, size)
+}
+
+/// Writes a debug directory with one entry per `(type, data)`, followed by the data. Returns
+/// the directory entry.
+pub fn debug(blob: &mut Blob, entries: &[(u32, &[u8])]) -> (u32, u32) {
+	let directory = blob.here();
+	let size = entries.len() as u32 * 28;
+	blob.zeroes(size as usize);
+	for (i, &(typ, data)) in entries.iter().enumerate() {
+		blob.align(4);
+		let data_rva = blob.here();
+		blob.bytes(data);
+		let entry = directory + i as u32 * 28;
+		blob.patch_u32(entry + 4, 0x6000_0000);
+		blob.patch_u32(entry + 12, typ);
+		blob.patch_u32(entry + 16, data.len() as u32);
+		blob.patch_u32(entry + 20, data_rva);
+		blob.patch_u32(entry + 24, blob.file_offset_of(data_rva));
+	}
+	blob.align(4);
+	(directory, size)
+}
+
+/// An RSDS CodeView record.
+pub fn codeview(guid: [u8; 16], age: u32, pdb_path: &str) -> Vec<u8> {
+	let mut data = b"RSDS".to_vec();
+	data.extend_from_slice(&guid);
+	data.extend_from_slice(&age.to_le_bytes());
+	data.extend_from_slice(pdb_path.as_bytes());
+	data.push(0);
+	data
+}
+
+/// Writes a TLS directory whose callback array holds `callbacks` as VAs, and a 4-byte
+/// template. Returns the directory entry.
+pub fn tls(blob: &mut Blob, is_64: bool, image_base: u64, callbacks: &[u32]) -> (u32, u32) {
+	blob.align(8);
+	let template = blob.here();
+	blob.u32(0x1234_5678);
+	let index = blob.here();
+	blob.u32(0);
+	blob.align(8);
+	let callback_array = blob.here();
+	for &callback in callbacks {
+		blob.ptr(is_64, image_base + callback as u64);
+	}
+	blob.ptr(is_64, 0);
+	let directory = blob.here();
+	blob.ptr(is_64, image_base + template as u64)
+		.ptr(is_64, image_base + template as u64 + 4)
+		.ptr(is_64, image_base + index as u64)
+		.ptr(is_64, image_base + callback_array as u64)
+		.u32(0)
+		.u32(0);
+	(directory, blob.here() - directory)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKey<'a> {
+	Id(u16),
+	Name(&'a str),
+}
+
+/// `items` split into runs with the same `key`.
+fn runs<T, K: PartialEq>(items: &[T], key: impl Fn(&T) -> K) -> Vec<&[T]> {
+	let mut runs: Vec<&[T]> = Vec::new();
+	let mut start = 0;
+	for end in 1..=items.len() {
+		if end == items.len() || key(&items[end]) != key(&items[start]) {
+			runs.push(&items[start..end]);
+			start = end;
+		}
+	}
+	runs
+}
+
+/// Writes a three level resource tree with a data entry per `(type, name, language, data)`.
+/// Entries must be grouped by type and name, named keys first. Returns the directory entry.
+pub fn resources(
+	blob: &mut Blob,
+	entries: &[(ResourceKey, ResourceKey, u16, &[u8])],
+) -> (u32, u32) {
+	let root = blob.here();
+	// Writes a directory of `keys`, returning the RVA of its first entry.
+	let directory = |blob: &mut Blob, keys: &[ResourceKey]| {
+		let named = keys
+			.iter()
+			.filter(|key| matches!(key, ResourceKey::Name(_)))
+			.count();
+		blob.zeroes(12)
+			.u16(named as u16)
+			.u16((keys.len() - named) as u16);
+		let first_entry = blob.here();
+		blob.zeroes(keys.len() * 8);
+		first_entry
+	};
+	// Entries with their key, patched once names and children are written. A child of `None`
+	// is the next data entry.
+	let mut slots = Vec::new();
+	let types = runs(entries, |entry| entry.0);
+	let type_entries = directory(blob, &types.iter().map(|ty| ty[0].0).collect::<Vec<_>>());
+	for (i, ty) in types.iter().enumerate() {
+		let names = runs(ty, |entry| entry.1);
+		slots.push((
+			type_entries + i as u32 * 8,
+			ty[0].0,
+			Some(blob.here() - root),
+		));
+		let name_entries = directory(
+			blob,
+			&names.iter().map(|name| name[0].1).collect::<Vec<_>>(),
+		);
+		for (j, name) in names.iter().enumerate() {
+			slots.push((
+				name_entries + j as u32 * 8,
+				name[0].1,
+				Some(blob.here() - root),
+			));
+			let langs: Vec<_> = name.iter().map(|entry| ResourceKey::Id(entry.2)).collect();
+			let lang_entries = directory(blob, &langs);
+			for (k, &lang) in langs.iter().enumerate() {
+				slots.push((lang_entries + k as u32 * 8, lang, None));
+			}
+		}
+	}
+	let data_entries: Vec<u32> = entries
+		.iter()
+		.map(|_| {
+			let data_entry = blob.here();
+			blob.zeroes(16);
+			data_entry
+		})
+		.collect();
+	let mut next_data_entry = data_entries.iter();
+	for (slot, key, child) in slots {
+		let name_or_id = match key {
+			ResourceKey::Id(id) => id as u32,
+			ResourceKey::Name(name) => {
+				blob.align(2);
+				let offset = blob.here() - root;
+				let units: Vec<u16> = name.encode_utf16().collect();
+				blob.u16(units.len() as u16);
+				units.into_iter().for_each(|unit| {
+					blob.u16(unit);
+				});
+				offset | 0x8000_0000
+			}
+		};
+		let child = match child {
+			Some(offset) => offset | 0x8000_0000,
+			None => next_data_entry.next().unwrap() - root,
+		};
+		blob.patch_u32(slot, name_or_id);
+		blob.patch_u32(slot + 4, child);
+	}
+	for (&data_entry, &(_, _, _, data)) in data_entries.iter().zip(entries) {
+		blob.align(4);
+		let data_rva = blob.here();
+		blob.bytes(data);
+		blob.patch_u32(data_entry, data_rva);
+		blob.patch_u32(data_entry + 4, data.len() as u32);
+		blob.patch_u32(data_entry + 8, 1252);
+	}
+	blob.align(4);
+	(root, blob.here() - root)
+}
+
+/// Writes a relocation directory with a block per `(page RVA, entries)`, each entry being the
+/// type in the top 4 bits and the page offset below. Returns the directory entry.
+pub fn relocs(blob: &mut Blob, blocks: &[(u32, &[u16])]) -> (u32, u32) {
+	let directory = blob.here();
+	for &(page_rva, entries) in blocks {
+		let padded = entries.len().next_multiple_of(2);
+		blob.u32(page_rva).u32(8 + padded as u32 * 2);
+		for &entry in entries {
+			blob.u16(entry);
+		}
+		blob.zeroes((padded - entries.len()) * 2);
+	}
+	(directory, blob.here() - directory)
+}
+
+pub const IMAGE_DIRECTORY_ENTRY_EXPORT: usize = 0;
+pub const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
+pub const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+pub const IMAGE_DIRECTORY_ENTRY_BASERELOC: usize = 5;
+pub const IMAGE_DIRECTORY_ENTRY_DEBUG: usize = 6;
+pub const IMAGE_DIRECTORY_ENTRY_TLS: usize = 9;
+
+pub const TEXT_RVA: u32 = 0x1000;
+pub const ALPHA_RVA: u32 = TEXT_RVA + 0x10;
+pub const BETA_RVA: u32 = TEXT_RVA + 0x20;
+pub const GAMMA_RVA: u32 = TEXT_RVA + 0x30;
+pub const TLS_CALLBACK_RVA: u32 = TEXT_RVA + 0x40;
+pub const DATA_RVA: u32 = 0x3000;
+pub const CODEVIEW_GUID: [u8; 16] = *b"0123456789abcdef";
+pub const VERSION_DATA: &[u8] = b"version resource";
+pub const CONFIG_DATA: &[u8] = b"config resource";
+
+/// A dll with a directory of every kind the tests read:
+/// - `.text` with `Alpha`, `Beta` and `Gamma`, the last exported by ordinal only, and a TLS callback
+/// - `.rdata` with exports including `Forward` to `other.Target`, imports from kernel32 and
+///   user32, and CodeView and POGO debug entries
+/// - `.data` with the TLS directory and a pointer to `Alpha` in its first slot
+/// - `.rsrc` with a version and a named resource
+/// - `.reloc` relocating the pointer in `.data`
+pub fn sample(is_64: bool) -> PeBuilder {
+	let mut pe = match is_64 {
+		true => PeBuilder::new64(),
+		false => PeBuilder::new32(),
+	};
+
+	let mut text = pe.blob();
+	text.bytes(&[0xc3; 0x50]);
+	pe.section(".text", CODE, text);
+
+	let mut rdata = pe.blob();
+	let export_directory = exports(
+		&mut rdata,
+		"sample.dll",
+		1,
+		&[
+			ExportFn::Rva(ALPHA_RVA),
+			ExportFn::Rva(BETA_RVA),
+			ExportFn::Unused,
+			ExportFn::Rva(GAMMA_RVA),
+			ExportFn::Forwarder("other.Target"),
+		],
+		&[("Alpha", 0), ("Beta", 1), ("Forward", 4)],
+	);
+	let import_directory = imports(
+		&mut rdata,
+		is_64,
+		&[
+			(
+				"KERNEL32.dll",
+				&[
+					ImportFn::Name(0x2b5, "GetProcAddress"),
+					ImportFn::Name(0x3c2, "LoadLibraryA"),
+				],
+			),
+			("USER32.dll", &[ImportFn::Ordinal(7)]),
+		],
+	);
+	let debug_directory = debug(
+		&mut rdata,
+		&[
+			(2, &codeview(CODEVIEW_GUID, 3, r"C:\build\sample.pdb")),
+			(13, b"PGU\0\0\0\0\0"),
+		],
+	);
+	pe.section(".rdata", RDATA, rdata);
+
+	let mut data = pe.blob();
+	data.ptr(is_64, pe.image_base + ALPHA_RVA as u64);
+	let tls_directory = tls(&mut data, is_64, pe.image_base, &[TLS_CALLBACK_RVA]);
+	pe.section(".data", DATA, data);
+
+	let mut rsrc = pe.blob();
+	let resource_directory = resources(
+		&mut rsrc,
+		&[
+			(
+				ResourceKey::Name("MYTYPE"),
+				ResourceKey::Name("CONFIG"),
+				0,
+				CONFIG_DATA,
+			),
+			(ResourceKey::Id(16), ResourceKey::Id(1), 0x409, VERSION_DATA),
+		],
+	);
+	pe.section(".rsrc", RDATA, rsrc);
+
+	let mut reloc = pe.blob();
+	let reloc_type: u16 = if is_64 { 0xa } else { 0x3 };
+	let reloc_directory = relocs(&mut reloc, &[(DATA_RVA, &[reloc_type << 12])]);
+	pe.section(".reloc", RDATA, reloc);
+
+	pe.directory(IMAGE_DIRECTORY_ENTRY_EXPORT, export_directory)
+		.directory(IMAGE_DIRECTORY_ENTRY_IMPORT, import_directory)
+		.directory(IMAGE_DIRECTORY_ENTRY_RESOURCE, resource_directory)
+		.directory(IMAGE_DIRECTORY_ENTRY_BASERELOC, reloc_directory)
+		.directory(IMAGE_DIRECTORY_ENTRY_DEBUG, debug_directory)
+		.directory(IMAGE_DIRECTORY_ENTRY_TLS, tls_directory);
+	pe
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> u32 {
+	u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> u64 {
+	u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// Parses `data` laid out as `layout`, bounded to `data` either way.
+pub fn parse<Nt: objparse::nt::NtHeaders>(
+	data: &'static [u8],
+	layout: Layout,
+) -> objparse::PeHeaders<Nt> {
+	match layout {
+		Layout::File => objparse::PeHeaders::parse_file_nt(data, layout.options()).unwrap(),
+		Layout::Mapped => unsafe {
+			objparse::PeHeaders::parse_nt_with_size(data.as_ptr(), data.len(), layout.options())
+		}
+		.unwrap(),
+	}
+}
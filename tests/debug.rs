@@ -0,0 +1,191 @@
+mod common;
+
+use common::{PeBuilder, CODEVIEW_GUID, IMAGE_DIRECTORY_ENTRY_DEBUG, LAYOUTS, RDATA};
+use object::{pe, LittleEndian};
+use objparse::{debug::CodeView, error::Error, ParseOptions, PeHeaders64};
+
+const ENTRIES: [(u32, &[u8]); 4] = [
+	(pe::IMAGE_DEBUG_TYPE_POGO, b"PGU\0"),
+	(pe::IMAGE_DEBUG_TYPE_VC_FEATURE, &[0; 20]),
+	(pe::IMAGE_DEBUG_TYPE_REPRO, &[0; 4]),
+	(20, &[1, 0, 0, 0]),
+];
+
+/// An image with the CodeView record followed by `extra` more entries, the directory size being
+/// adjusted by `size_delta`.
+fn image(extra: usize, size_delta: i32) -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	let mut rdata = pe.blob();
+	let codeview = common::codeview(CODEVIEW_GUID, 1, "a.pdb");
+	let mut entries = vec![(pe::IMAGE_DEBUG_TYPE_CODEVIEW, &codeview[..])];
+	entries.extend_from_slice(&ENTRIES[..extra]);
+	let (rva, size) = common::debug(&mut rdata, &entries);
+	pe.section(".rdata", RDATA, rdata);
+	pe.directory(
+		IMAGE_DIRECTORY_ENTRY_DEBUG,
+		(rva, size.wrapping_add_signed(size_delta)),
+	);
+	pe
+}
+
+#[test]
+fn one_to_four_entries() {
+	for count in 1..=4 {
+		let pe = image(count - 1, 0);
+		for layout in LAYOUTS {
+			let headers = common::parse::<pe::ImageNtHeaders64>(pe.leak(layout), layout);
+			let debug_table = headers.debug_table().unwrap();
+			let types: Vec<_> = debug_table
+				.debug_descriptors
+				.iter()
+				.map(|descriptor| descriptor.typ.get(LittleEndian))
+				.collect();
+			let mut expected = vec![pe::IMAGE_DEBUG_TYPE_CODEVIEW];
+			expected.extend(ENTRIES[..count - 1].iter().map(|&(typ, _)| typ));
+			assert_eq!(types, expected);
+		}
+	}
+}
+
+#[test]
+fn undersized_table_is_rejected() {
+	let pe = image(0, -1);
+	for layout in LAYOUTS {
+		let headers = common::parse::<pe::ImageNtHeaders64>(pe.leak(layout), layout);
+		let Err(err) = headers.debug_table() else {
+			panic!("table smaller than an entry");
+		};
+		assert_eq!(err.error, Error::DebugTable);
+	}
+}
+
+#[test]
+fn partial_entry_only_in_lenient_mode() {
+	let pe = image(1, 10);
+	let data = pe.leak(common::Layout::File);
+	let headers = PeHeaders64::parse_file_nt(data, ParseOptions::new()).unwrap();
+	assert!(headers.debug_table().is_err());
+	let headers = PeHeaders64::parse_file_nt(data, ParseOptions::lenient()).unwrap();
+	assert_eq!(headers.debug_table().unwrap().debug_descriptors.len(), 2);
+}
+
+/// [`CODEVIEW_GUID`] as a symbol server writes it, without the age.
+#[cfg(feature = "alloc")]
+const GUID_KEY: &str = "33323130353437363839616263646566";
+
+fn codeview(data: &[u8]) -> Result<CodeView, Error> {
+	CodeView::parse(common::leak(data))
+}
+
+/// An NB10 record, the time stamp taking the place of the GUID.
+fn nb10(signature: u32, age: u32, pdb_path: &str) -> Vec<u8> {
+	let mut data = b"NB10\0\0\0\0".to_vec();
+	data.extend_from_slice(&signature.to_le_bytes());
+	data.extend_from_slice(&age.to_le_bytes());
+	data.extend_from_slice(pdb_path.as_bytes());
+	data.push(0);
+	data
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn symbol_server_keys() {
+	let key = format!("{GUID_KEY}2A");
+	let rsds = codeview(&common::codeview(
+		CODEVIEW_GUID,
+		0x2a,
+		r"C:\build\sample.pdb",
+	))
+	.unwrap();
+	assert_eq!(rsds.symsrv_key(), key);
+	for base_url in [
+		"https://msdl.microsoft.com/download/symbols",
+		"https://msdl.microsoft.com/download/symbols/",
+	] {
+		assert_eq!(
+			rsds.symsrv_url(base_url).unwrap(),
+			format!("https://msdl.microsoft.com/download/symbols/sample.pdb/{key}/sample.pdb")
+		);
+	}
+	let nb10 = codeview(&nb10(0x1234_abcd, 1, "old.pdb")).unwrap();
+	assert_eq!(nb10.symsrv_key(), "1234ABCD1");
+	assert_eq!(
+		nb10.symsrv_url("srv").unwrap(),
+		"srv/old.pdb/1234ABCD1/old.pdb"
+	);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn symbol_server_urls_of_unusable_names() {
+	for path in ["", r"C:\build\", r"..\..", "C:sample.pdb", "sample.pdb\x7f"] {
+		let cv = codeview(&common::codeview(CODEVIEW_GUID, 1, path)).unwrap();
+		assert_eq!(cv.symsrv_url("srv"), None, "{path:?}");
+		// The key does not depend on the path.
+		assert_eq!(
+			cv.symsrv_key(),
+			"33323130353437363839616263646566".to_owned() + "1"
+		);
+	}
+}
+
+#[test]
+fn malformed_codeview_records() {
+	let rsds = common::codeview(CODEVIEW_GUID, 1, "a.pdb");
+	let nb10 = nb10(0x1234_abcd, 1, "a.pdb");
+	for data in [&b"RSDT"[..], &rsds[..23], &nb10[..15], &rsds[..3]] {
+		assert_eq!(codeview(data).err(), Some(Error::DebugTable));
+	}
+	// A path without its terminator runs to the end of the record.
+	let cv = codeview(&rsds[..rsds.len() - 1]).unwrap();
+	assert_eq!(cv.pdb_path, b"a.pdb");
+	assert_eq!(codeview(&nb10[..16]).unwrap().pdb_path, b"");
+}
+
+#[test]
+fn pdb_names_and_guids() {
+	let cv = codeview(&common::codeview(
+		CODEVIEW_GUID,
+		1,
+		r"C:\build/out\sample.pdb",
+	))
+	.unwrap();
+	assert_eq!(cv.pdb_path, br"C:\build/out\sample.pdb");
+	assert_eq!(cv.pdb_file_name(), b"sample.pdb");
+	assert_eq!(cv.pdb_name(), Some("sample.pdb"));
+	let guid = cv.guid().unwrap();
+	assert_eq!(
+		(guid.data1(), guid.data2(), guid.data3()),
+		(0x3332_3130, 0x3534, 0x3736)
+	);
+	assert_eq!(guid.data4(), *b"89abcdef");
+	assert_eq!(guid.to_string(), "33323130-3534-3736-3839-616263646566");
+
+	let cv = codeview(&nb10(0x1234_abcd, 1, "old.pdb")).unwrap();
+	assert_eq!(cv.guid(), None);
+	assert_eq!(cv.pdb_name(), Some("old.pdb"));
+}
+
+#[test]
+fn unusable_pdb_names() {
+	for path in [
+		"",
+		r"C:\build\",
+		"..",
+		r"C:\build\..",
+		".",
+		"C:sample.pdb",
+		"sample.pdb:stream",
+		"sample?.pdb",
+		"sample.pdb\t",
+	] {
+		let cv = codeview(&common::codeview(CODEVIEW_GUID, 1, path)).unwrap();
+		assert_eq!(cv.pdb_name(), None, "{path:?}");
+	}
+	// Not UTF-8, though the raw path and file name are still there.
+	let mut data = common::codeview(CODEVIEW_GUID, 1, r"C:\");
+	data.splice(data.len() - 1.., *b"\xffsample.pdb\0");
+	let cv = codeview(&data).unwrap();
+	assert_eq!(cv.pdb_file_name(), b"\xffsample.pdb");
+	assert_eq!(cv.pdb_name(), None);
+}
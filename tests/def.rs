@@ -0,0 +1,24 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::LAYOUTS;
+use object::pe;
+
+#[test]
+fn to_def_in_both_layouts() {
+	for layout in LAYOUTS {
+		let data = common::sample(true).leak(layout);
+		let headers = common::parse::<pe::ImageNtHeaders64>(data, layout);
+		let export_table = unsafe { headers.export_table_mem(data.as_ptr()) }.unwrap();
+		assert_eq!(
+			unsafe { export_table.to_def(&headers, data.as_ptr(), "sample.dll") },
+			"LIBRARY \"sample.dll\"\n\
+			 EXPORTS\n\
+			 \tAlpha @1\n\
+			 \tBeta @2\n\
+			 \tOrdinal4 @4 NONAME\n\
+			 \tForward = other.Target @5\n"
+		);
+	}
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::{Layout, PeBuilder, LAYOUTS, RDATA};
+use object::pe;
+use objparse::{
+	diff::{DebugChange, ExportChange, PeDiff},
+	PeHeaders,
+};
+
+const REPRO: u32 = 16;
+const CODEVIEW: u32 = 2;
+
+fn image(entries: &[(u32, &[u8])]) -> PeHeaders<pe::ImageNtHeaders64> {
+	let mut pe = PeBuilder::new64();
+	let mut rdata = pe.blob();
+	let directory = common::debug(&mut rdata, entries);
+	pe.section(".rdata", RDATA, rdata);
+	pe.directory(common::IMAGE_DIRECTORY_ENTRY_DEBUG, directory);
+	common::parse(pe.leak(Layout::Mapped), Layout::Mapped)
+}
+
+fn debug_changes(old: &[(u32, &[u8])], new: &[(u32, &[u8])]) -> Vec<(&'static str, Vec<u8>)> {
+	let diff = unsafe { PeDiff::compare(&image(old), &image(new)) };
+	diff.debug
+		.iter()
+		.map(|change| match change {
+			DebugChange::Added(entry) => ("added", entry.data.to_vec()),
+			DebugChange::Removed(entry) => ("removed", entry.data.to_vec()),
+			DebugChange::Modified { new, .. } => ("modified", new.data.to_vec()),
+		})
+		.collect()
+}
+
+#[test]
+fn debug_entries_match_by_position_within_their_type() {
+	let old: &[(u32, &[u8])] = &[(CODEVIEW, b"first"), (CODEVIEW, b"second")];
+	assert_eq!(debug_changes(old, old), []);
+	let new: &[(u32, &[u8])] = &[
+		(REPRO, b"repro"),
+		(CODEVIEW, b"first"),
+		(CODEVIEW, b"other"),
+	];
+	assert_eq!(
+		debug_changes(old, new),
+		[
+			("modified", b"other".to_vec()),
+			("added", b"repro".to_vec())
+		]
+	);
+	assert_eq!(
+		debug_changes(old, &old[..1]),
+		[("removed", b"second".to_vec())]
+	);
+}
+
+#[test]
+fn exports_in_both_layouts() {
+	for layout in LAYOUTS {
+		let sample: PeHeaders<pe::ImageNtHeaders64> =
+			common::parse(common::sample(true).leak(layout), layout);
+		let empty = common::parse(PeBuilder::new64().leak(layout), layout);
+		assert!(unsafe { PeDiff::compare(&sample, &sample) }
+			.exports
+			.is_empty());
+		let diff = unsafe { PeDiff::compare(&empty, &sample) };
+		let added: Vec<_> = diff
+			.exports
+			.changes
+			.iter()
+			.map(|change| match change {
+				ExportChange::Added(entry) => (entry.name, entry.ordinal),
+				change => panic!("{change:?}"),
+			})
+			.collect();
+		assert_eq!(
+			added,
+			[
+				(Some(&b"Alpha"[..]), 1),
+				(Some(&b"Beta"[..]), 2),
+				(None, 4),
+				(Some(&b"Forward"[..]), 5)
+			]
+		);
+	}
+}
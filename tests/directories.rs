@@ -0,0 +1,238 @@
+mod common;
+
+use common::{Layout, ALPHA_RVA, BETA_RVA, GAMMA_RVA, LAYOUTS, TLS_CALLBACK_RVA};
+use object::{pe, LittleEndian};
+use objparse::{debug::DebugData, error::Error, nt::NtHeaders, nt::TlsDirectory, PeHeaders};
+
+fn check_directories<Nt: NtHeaders>(layout: Layout) {
+	let is_64 = size_of::<Nt>() == size_of::<pe::ImageNtHeaders64>();
+	let pe = common::sample(is_64);
+	let data = pe.leak(layout);
+	let headers = common::parse::<Nt>(data, layout);
+	let base = data.as_ptr();
+
+	let export_table = unsafe { headers.export_table_mem(base) }.unwrap();
+	assert_eq!(export_table.address_table.len(), 5);
+	assert_eq!(export_table.rva_by_ordinal(1), Some(ALPHA_RVA));
+	assert_eq!(export_table.rva_by_ordinal(2), Some(BETA_RVA));
+	assert_eq!(export_table.rva_by_ordinal(4), Some(GAMMA_RVA));
+	let names: Vec<_> = export_table
+		.iter_name_index()
+		.map(|(name_rva, _)| unsafe { headers.export_name(base, name_rva) }.unwrap())
+		.collect();
+	assert_eq!(names, [c"Alpha", c"Beta", c"Forward"]);
+
+	let import_table = unsafe { headers.import_table_mem(base) }.unwrap();
+	let dlls: Vec<_> = import_table
+		.import_descriptors
+		.iter()
+		.map(|descriptor| {
+			unsafe { import_table.dll_name_with(descriptor, &headers, base) }.unwrap()
+		})
+		.collect();
+	assert_eq!(dlls, [c"KERNEL32.dll", c"USER32.dll"]);
+
+	let debug_table = unsafe { headers.debug_table_mem(base) }.unwrap();
+	let types: Vec<_> = debug_table
+		.debug_descriptors
+		.iter()
+		.map(|descriptor| descriptor.typ.get(LittleEndian))
+		.collect();
+	assert_eq!(
+		types,
+		[pe::IMAGE_DEBUG_TYPE_CODEVIEW, pe::IMAGE_DEBUG_TYPE_POGO]
+	);
+
+	let tls_table = unsafe { headers.tls_table_mem(base) }.unwrap().unwrap();
+	let template = tls_table.tls_dir.start_address_of_raw_data() - pe.image_base;
+	let template_ptr = headers.rva_to_ptr(base, template as u32).unwrap();
+	assert_eq!(
+		unsafe { template_ptr.cast::<u32>().read_unaligned() },
+		0x1234_5678
+	);
+	let callbacks: Vec<_> =
+		unsafe { tls_table.callback_addresses_with(&headers, base, pe.image_base) }
+			.map(Result::unwrap)
+			.collect();
+	assert_eq!(callbacks, [pe.image_base + TLS_CALLBACK_RVA as u64]);
+
+	let pdb_paths: Vec<_> = unsafe { debug_table.iter_typed_with(&headers, base) }
+		.filter_map(|entry| match entry.unwrap().data {
+			DebugData::CodeView(codeview) => Some(codeview.pdb_path),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(pdb_paths, [b"C:\\build\\sample.pdb"]);
+
+	assert!(unsafe { headers.clr_header_mem(base) }.unwrap().is_none());
+}
+
+#[test]
+fn directories_in_both_layouts() {
+	for layout in LAYOUTS {
+		check_directories::<pe::ImageNtHeaders64>(layout);
+		check_directories::<pe::ImageNtHeaders32>(layout);
+	}
+}
+
+#[test]
+fn export_directory_past_the_region() {
+	let mut pe = common::sample(true);
+	let size_of_image = pe.size_of_image();
+	pe.directory(
+		common::IMAGE_DIRECTORY_ENTRY_EXPORT,
+		(size_of_image - 0x10, 0x10),
+	);
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let headers = common::parse::<pe::ImageNtHeaders64>(data, layout);
+		assert!(unsafe { headers.export_table_mem(data.as_ptr()) }.is_err());
+	}
+}
+
+#[test]
+fn lazy_accessors_use_the_layout() {
+	let data = common::sample(true).leak(Layout::File);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::File);
+	assert_eq!(headers.export_table().unwrap().name_table.len(), 3);
+	assert_eq!(headers.import_table().unwrap().import_descriptors.len(), 2);
+	assert_eq!(headers.debug_table().unwrap().debug_descriptors.len(), 2);
+	assert!(headers.tls_table().unwrap().is_some());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn import_map_in_both_layouts() {
+	for layout in LAYOUTS {
+		let data = common::sample(true).leak(layout);
+		let headers = common::parse::<pe::ImageNtHeaders64>(data, layout);
+		let import_map = unsafe { headers.import_map(data.as_ptr().cast_mut()) }.unwrap();
+		let dlls: Vec<_> = import_map.dlls.keys().map(String::as_str).collect();
+		assert_eq!(dlls, ["KERNEL32.dll", "USER32.dll"]);
+		assert_eq!(import_map.iter().count(), 3);
+	}
+}
+
+#[test]
+fn debug_data_past_the_region() {
+	let file = common::sample(true).file();
+	let data = common::leak(&file);
+	let headers = common::parse::<pe::ImageNtHeaders64>(data, Layout::File);
+	let debug_table = unsafe { headers.debug_table_mem(data.as_ptr()) }.unwrap();
+	// Cut the file right after the directory, the data of its records follows it.
+	let end = debug_table.debug_descriptors.as_ptr_range().end as usize - data.as_ptr() as usize;
+	let data = common::leak(&file[..end]);
+	let headers = common::parse::<pe::ImageNtHeaders64>(data, Layout::File);
+	let debug_table = unsafe { headers.debug_table_mem(data.as_ptr()) }.unwrap();
+	assert!(
+		unsafe { debug_table.iter_typed_with(&headers, data.as_ptr()) }
+			.all(|entry| matches!(entry, Err(Error::OutOfRegion { .. })))
+	);
+}
+
+#[test]
+fn tls_callbacks_past_the_region() {
+	let pe = common::sample(true);
+	let tls_rva = pe.directories[common::IMAGE_DIRECTORY_ENTRY_TLS].0;
+	let data = pe.leak(Layout::Mapped);
+	// Point `AddressOfCallBacks` at the last bytes of the image.
+	let callbacks_va = pe.image_base + data.len() as u64 - 4;
+	let offset = tls_rva as usize + 24;
+	data[offset..offset + 8].copy_from_slice(&callbacks_va.to_le_bytes());
+	let headers = common::parse::<pe::ImageNtHeaders64>(data, Layout::Mapped);
+	let tls_table = unsafe { headers.tls_table_mem(data.as_ptr()) }
+		.unwrap()
+		.unwrap();
+	let callbacks: Vec<_> =
+		unsafe { tls_table.callback_addresses_with(&headers, data.as_ptr(), pe.image_base) }
+			.collect();
+	assert!(matches!(callbacks[..], [Err(Error::OutOfRegion { .. })]));
+}
+
+#[test]
+fn symbolize_stays_in_the_section() {
+	let data = common::sample(true).leak(Layout::Mapped);
+	let headers = common::parse::<pe::ImageNtHeaders64>(data, Layout::Mapped);
+	let base = data.as_ptr();
+	let export_table = unsafe { headers.export_table_mem(base) }.unwrap();
+	let symbolize =
+		|rva: usize| unsafe { export_table.symbolize(&headers, base, base.wrapping_add(rva)) };
+	assert_eq!(symbolize(ALPHA_RVA as usize), Some((c"Alpha", 0)));
+	assert_eq!(symbolize(BETA_RVA as usize + 5), Some((c"Beta", 5)));
+	// `Gamma` is exported by ordinal only.
+	assert_eq!(symbolize(GAMMA_RVA as usize + 8), Some((c"Beta", 0x18)));
+	assert_eq!(symbolize(ALPHA_RVA as usize - 1), None);
+	// Past the code, in `.rdata` and past the image.
+	assert_eq!(symbolize(0x1050), None);
+	assert_eq!(symbolize(0x2000), None);
+	assert_eq!(symbolize(data.len() + 0x100), None);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn exports_read_names_through_the_sections() {
+	let data = common::sample(true).leak(Layout::Mapped);
+	let base = data.as_mut_ptr();
+	let headers = common::parse::<pe::ImageNtHeaders64>(
+		unsafe { core::slice::from_raw_parts(base, data.len()) },
+		Layout::Mapped,
+	);
+	let export_table = unsafe { headers.export_table_mem(base) }.unwrap();
+	let find = |name: &[u8]| {
+		unsafe { export_table.find_by_name(&headers, base, name) }
+			.map(|address| address as usize - base as usize)
+	};
+	assert_eq!(find(b"Beta"), Some(BETA_RVA as usize));
+	assert_eq!(find(b"Missing"), None);
+	let exports: Vec<_> = unsafe { export_table.exports(&headers, base) }
+		.map(|export| (export.name, export.ordinal))
+		.collect();
+	assert_eq!(
+		exports,
+		[
+			(Some(c"Alpha"), 1),
+			(Some(c"Beta"), 2),
+			(None, 4),
+			(Some(c"Forward"), 5)
+		]
+	);
+
+	// Point the name of `Alpha` past the image.
+	let name_table = export_table.name_table.as_ptr().cast_mut();
+	unsafe { name_table.cast::<u32>().write_unaligned(0x10_0000) };
+	assert_eq!(find(b"Beta"), Some(BETA_RVA as usize));
+	let names: Vec<_> = unsafe { export_table.exports(&headers, base) }
+		.map(|export| export.name)
+		.collect();
+	assert_eq!(names, [None, Some(c"Beta"), None, Some(c"Forward")]);
+}
+
+#[test]
+fn export_names_with_ordinals_past_the_address_table() {
+	let data = common::sample(true).leak(Layout::Mapped);
+	let base = data.as_mut_ptr();
+	let headers = common::parse::<pe::ImageNtHeaders64>(
+		unsafe { core::slice::from_raw_parts(base, data.len()) },
+		Layout::Mapped,
+	);
+	let export_table = headers.export_table().unwrap();
+	let names = || {
+		unsafe { export_table.iter_string_addr(base) }
+			.map(|(name, address)| (name, address as usize - base as usize))
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(
+		names(),
+		[
+			(c"Alpha", ALPHA_RVA as usize),
+			(c"Beta", BETA_RVA as usize),
+			(c"Forward", export_table.rva_by_index(4).unwrap() as usize)
+		]
+	);
+
+	// Give `Beta` an ordinal past the five functions.
+	let ordinal_table = export_table.ordinal_table.as_ptr().cast_mut();
+	unsafe { ordinal_table.cast::<u16>().add(1).write_unaligned(0xffff) };
+	let names: Vec<_> = names().into_iter().map(|(name, _)| name).collect();
+	assert_eq!(names, [c"Alpha", c"Forward"]);
+}
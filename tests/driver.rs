@@ -0,0 +1,94 @@
+mod common;
+
+use common::{ImportFn, PeBuilder, CODE, IMAGE_DIRECTORY_ENTRY_IMPORT, LAYOUTS, RDATA};
+use object::{pe, LittleEndian};
+use objparse::{section, PeHeaders};
+
+const IMAGE_SUBSYSTEM_NATIVE: u16 = 1;
+const IMAGE_DLLCHARACTERISTICS_WDM_DRIVER: u16 = 0x2000;
+const IMAGE_SCN_MEM_DISCARDABLE: u32 = 0x0200_0000;
+
+/// A native image with an `INIT` section, importing from `dlls`.
+fn native(dlls: &[&str]) -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	pe.subsystem = IMAGE_SUBSYSTEM_NATIVE;
+	let mut text = pe.blob();
+	text.zeroes(0x10);
+	pe.section(".text", CODE, text);
+	let mut init = pe.blob();
+	init.zeroes(0x10);
+	pe.section("INIT", CODE | IMAGE_SCN_MEM_DISCARDABLE, init);
+	let mut idata = pe.blob();
+	let functions = [ImportFn::Name(0, "Function")];
+	let dlls: Vec<_> = dlls.iter().map(|&dll| (dll, &functions[..])).collect();
+	let import_directory = common::imports(&mut idata, true, &dlls);
+	pe.section(".idata", RDATA, idata);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_IMPORT, import_directory);
+	pe
+}
+
+fn section_names(
+	sections: impl Iterator<Item = &'static pe::ImageSectionHeader>,
+) -> Vec<&'static [u8]> {
+	sections.map(section::section_name_bytes).collect()
+}
+
+#[test]
+fn driver() {
+	let mut pe = native(&["NTOSKRNL.EXE", "msvcrt.dll", "hal.dll"]);
+	pe.dll_characteristics |= IMAGE_DLLCHARACTERISTICS_WDM_DRIVER;
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, layout);
+		assert!(headers.is_native());
+		assert!(headers.is_wdm_driver());
+		let kernel_imports: Vec<_> = unsafe { headers.kernel_imports(data.as_ptr()) }.collect();
+		assert_eq!(kernel_imports, [c"NTOSKRNL.EXE", c"hal.dll"]);
+		assert!(unsafe { headers.is_driver(data.as_ptr()) });
+		let init = headers.init_section().unwrap();
+		assert_eq!(init.virtual_address.get(LittleEndian), pe.sections[1].rva);
+		assert_eq!(
+			section_names(headers.resident_sections()),
+			[&b".text"[..], b".idata"]
+		);
+	}
+}
+
+#[test]
+fn native_program_is_not_a_driver() {
+	let pe = native(&["ntdll.dll"]);
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, layout);
+		assert!(headers.is_native());
+		assert!(!headers.is_wdm_driver());
+		assert_eq!(unsafe { headers.kernel_imports(data.as_ptr()) }.count(), 0);
+		assert!(!unsafe { headers.is_driver(data.as_ptr()) });
+	}
+}
+
+#[test]
+fn user_mode_dll_is_not_a_driver() {
+	let pe = common::sample(true);
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, layout);
+		assert!(!headers.is_native());
+		assert!(!unsafe { headers.is_driver(data.as_ptr()) });
+		assert!(headers.init_section().is_none());
+		assert_eq!(headers.resident_sections().count(), pe.sections.len());
+	}
+}
+
+#[test]
+fn unreadable_imports_are_not_kernel_imports() {
+	let mut pe = native(&["ntoskrnl.exe"]);
+	// The import directory past the end of the image.
+	pe.directory(IMAGE_DIRECTORY_ENTRY_IMPORT, (0x10_0000, 40));
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, layout);
+		assert_eq!(unsafe { headers.kernel_imports(data.as_ptr()) }.count(), 0);
+		assert!(!unsafe { headers.is_driver(data.as_ptr()) });
+	}
+}
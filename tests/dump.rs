@@ -0,0 +1,40 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::LAYOUTS;
+use object::pe;
+use objparse::dump::Value;
+
+#[test]
+fn exports_in_both_layouts() {
+	for layout in LAYOUTS {
+		let data = common::sample(true).leak(layout);
+		let headers = common::parse::<pe::ImageNtHeaders64>(data, layout);
+		let records = unsafe { headers.dump_records(data.as_ptr()) };
+		let exports: Vec<_> = records
+			.iter()
+			.filter(|record| record.kind == "export")
+			.map(|record| {
+				let field = |name| {
+					record
+						.fields
+						.iter()
+						.find(|field| field.0 == name)
+						.map(|field| field.1.clone())
+				};
+				(field("ordinal"), field("name"), field("forwarder"))
+			})
+			.collect();
+		let str = |value: &str| Some(Value::Str(value.into()));
+		assert_eq!(
+			exports,
+			[
+				(Some(Value::Int(1)), str("Alpha"), None),
+				(Some(Value::Int(2)), str("Beta"), None),
+				(Some(Value::Int(4)), None, None),
+				(Some(Value::Int(5)), str("Forward"), str("other.Target")),
+			]
+		);
+	}
+}
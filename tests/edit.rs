@@ -0,0 +1,61 @@
+mod common;
+
+use common::{Layout, PeBuilder, ALPHA_RVA, LAYOUTS, TEXT_RVA};
+use object::{pe, read::pe::ImageOptionalHeader};
+use objparse::{error::Error, nt::NtHeaders, PeHeaders};
+
+/// Parses a copy of `pe`, returning the headers and the base to write through.
+fn parse_mut<Nt: NtHeaders>(pe: &PeBuilder, layout: Layout) -> (PeHeaders<Nt>, *mut u8) {
+	let data = pe.leak(layout);
+	let base = data.as_mut_ptr();
+	let data = unsafe { core::slice::from_raw_parts(base, data.len()) };
+	(common::parse(data, layout), base)
+}
+
+fn entry_point<Nt: NtHeaders>(pe: &PeBuilder, base: *mut u8, layout: Layout) -> u32 {
+	let data = unsafe { core::slice::from_raw_parts(base, pe.build(layout).len()) };
+	let headers = common::parse::<Nt>(data, layout);
+	headers.nt_header.optional_header().address_of_entry_point()
+}
+
+fn check_set_entry_point<Nt: NtHeaders>(is_64: bool) {
+	let mut pe = common::sample(is_64);
+	pe.entry_point = TEXT_RVA;
+	for layout in LAYOUTS {
+		let (headers, base) = parse_mut::<Nt>(&pe, layout);
+		assert_eq!(
+			unsafe { headers.set_entry_point(base, ALPHA_RVA) },
+			Ok(TEXT_RVA)
+		);
+		assert_eq!(entry_point::<Nt>(&pe, base, layout), ALPHA_RVA);
+		// Zero clears the entry point, as of a DLL without `DllMain`.
+		assert_eq!(unsafe { headers.set_entry_point(base, 0) }, Ok(ALPHA_RVA));
+		assert_eq!(entry_point::<Nt>(&pe, base, layout), 0);
+	}
+}
+
+#[test]
+fn set_entry_point_in_both_layouts() {
+	check_set_entry_point::<pe::ImageNtHeaders64>(true);
+	check_set_entry_point::<pe::ImageNtHeaders32>(false);
+}
+
+#[test]
+fn entry_point_past_the_image() {
+	let mut pe = common::sample(true);
+	pe.entry_point = TEXT_RVA;
+	let size_of_image = pe.size_of_image();
+	for layout in LAYOUTS {
+		let (headers, base) = parse_mut::<pe::ImageNtHeaders64>(&pe, layout);
+		for rva in [size_of_image, u32::MAX] {
+			assert_eq!(
+				unsafe { headers.set_entry_point(base, rva) },
+				Err(Error::RvaOutOfBounds { rva, size_of_image })
+			);
+		}
+		assert_eq!(
+			entry_point::<pe::ImageNtHeaders64>(&pe, base, layout),
+			TEXT_RVA
+		);
+	}
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::{Layout, PeBuilder, CODE, LAYOUTS, TEXT_RVA};
+use object::pe;
+use objparse::{
+	efi::EfiSubsystem,
+	error::Error,
+	offsets::{HeaderField, SectionField},
+	PeHeaders,
+};
+
+/// An EFI application of one section, with its entry point at the start of `.text`.
+fn application() -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	pe.subsystem = pe::IMAGE_SUBSYSTEM_EFI_APPLICATION;
+	pe.entry_point = TEXT_RVA;
+	let mut text = pe.blob();
+	text.bytes(&[0xc3; 0x10]);
+	pe.section(".text", CODE, text);
+	pe
+}
+
+#[test]
+fn subsystems() {
+	for (subsystem, expected) in [
+		(
+			pe::IMAGE_SUBSYSTEM_EFI_APPLICATION,
+			Some(EfiSubsystem::Application),
+		),
+		(
+			pe::IMAGE_SUBSYSTEM_EFI_BOOT_SERVICE_DRIVER,
+			Some(EfiSubsystem::BootServiceDriver),
+		),
+		(
+			pe::IMAGE_SUBSYSTEM_EFI_RUNTIME_DRIVER,
+			Some(EfiSubsystem::RuntimeDriver),
+		),
+		(pe::IMAGE_SUBSYSTEM_EFI_ROM, Some(EfiSubsystem::Rom)),
+		(pe::IMAGE_SUBSYSTEM_WINDOWS_GUI, None),
+		(pe::IMAGE_SUBSYSTEM_NATIVE, None),
+	] {
+		let mut pe = application();
+		pe.subsystem = subsystem;
+		let headers: PeHeaders<pe::ImageNtHeaders64> =
+			common::parse(pe.leak(Layout::Mapped), Layout::Mapped);
+		assert_eq!(headers.efi_subsystem(), expected);
+		assert_eq!(headers.is_efi(), expected.is_some());
+	}
+}
+
+#[test]
+fn entry_point() {
+	let pe = application();
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, layout);
+		let entry_point = headers.entry_point(data.as_ptr()).unwrap().unwrap();
+		assert_eq!(unsafe { *entry_point }, 0xc3);
+		// EFI applications have no exports or imports to read.
+		assert_eq!(
+			headers.export_table().err().unwrap().error,
+			Error::ExportTable
+		);
+		assert_eq!(
+			headers.import_table().err().unwrap().error,
+			Error::ImportTable
+		);
+	}
+
+	let mut pe = application();
+	pe.entry_point = 0;
+	let headers: PeHeaders<pe::ImageNtHeaders64> =
+		common::parse(pe.leak(Layout::Mapped), Layout::Mapped);
+	assert_eq!(headers.entry_point(headers.image_base), Ok(None));
+
+	pe.entry_point = 0x10_0000;
+	let data = pe.leak(Layout::Mapped);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::Mapped);
+	assert!(headers.entry_point(data.as_ptr()).is_err());
+}
+
+#[test]
+fn flat_images() {
+	let pe = application();
+	let mut file = pe.file();
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(common::leak(&file), Layout::File);
+	assert!(!headers.is_flat());
+
+	// Sections aligned like the file, with `.text` right after the headers.
+	for (field, value) in [
+		(HeaderField::SectionAlignment, 0x200u32),
+		(HeaderField::Section(0, SectionField::VirtualAddress), 0x400),
+		(HeaderField::AddressOfEntryPoint, 0x400),
+	] {
+		let span = headers.field_span(field).unwrap();
+		file[span.offset..span.end()].copy_from_slice(&value.to_le_bytes());
+	}
+	let data = common::leak(&file);
+	let flat: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, Layout::File);
+	assert!(flat.is_flat());
+	// The file reads the same in either layout.
+	for layout in LAYOUTS {
+		let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(data, layout);
+		let entry_point = headers.entry_point(data.as_ptr()).unwrap().unwrap();
+		assert_eq!(unsafe { *entry_point }, 0xc3);
+	}
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{Layout, PeBuilder, CODE, LAYOUTS};
+use object::pe;
+use objparse::{error::Error, nt::NtHeaders, ParseOptions, PeHeaders};
+use std::error::Error as _;
+
+#[test]
+fn loader_errors_name_the_directory() {
+	let file = common::sample(common::NATIVE_IS_64).file();
+	let data = common::leak(&file[..0x610]);
+	let headers = PeHeaders::parse_file(data, Layout::File.options()).unwrap();
+	let Err(err) = headers.import_table() else {
+		panic!("import table past the data");
+	};
+	assert_eq!(err.to_string(), "import table");
+	let source = err.source().unwrap().to_string();
+	assert!(source.contains("region size 0x610"), "{source}");
+}
+
+#[test]
+fn rva_errors_carry_the_rva() {
+	let data = common::sample(common::NATIVE_IS_64).leak(Layout::Mapped);
+	let headers = unsafe { PeHeaders::parse_with_size(data.as_ptr(), data.len()) }.unwrap();
+	let size_of_image = data.len() as u32;
+	assert_eq!(
+		headers.rva_to_ptr(data.as_ptr(), size_of_image),
+		Err(Error::RvaOutOfBounds {
+			rva: size_of_image,
+			size_of_image
+		})
+	);
+}
+
+/// Checks the tables of an image whose export, import and debug directories are `(0, size)`.
+fn check_absent_directories<Nt: NtHeaders>(size: u32) {
+	let mut pe = match size_of::<Nt>() == size_of::<pe::ImageNtHeaders64>() {
+		true => PeBuilder::new64(),
+		false => PeBuilder::new32(),
+	};
+	let mut text = pe.blob();
+	text.bytes(&[0xc3; 0x10]);
+	pe.section(".text", CODE, text);
+	for index in [
+		common::IMAGE_DIRECTORY_ENTRY_EXPORT,
+		common::IMAGE_DIRECTORY_ENTRY_IMPORT,
+		common::IMAGE_DIRECTORY_ENTRY_DEBUG,
+	] {
+		pe.directory(index, (0, size));
+	}
+	for layout in LAYOUTS {
+		let headers = common::parse::<Nt>(pe.leak(layout), layout);
+		let Err(err) = headers.export_table() else {
+			panic!("export table at RVA 0");
+		};
+		assert_eq!(
+			(err.context, err.error),
+			("export table", Error::ExportTable)
+		);
+		let Err(err) = headers.import_table() else {
+			panic!("import table at RVA 0");
+		};
+		assert_eq!(
+			(err.context, err.error),
+			("import table", Error::ImportTable)
+		);
+		let Err(err) = headers.debug_table() else {
+			panic!("debug table at RVA 0");
+		};
+		assert_eq!((err.context, err.error), ("debug table", Error::DebugTable));
+	}
+}
+
+#[test]
+fn absent_directories_are_not_read_at_rva_0() {
+	for size in [0, 0x28, 0x1000] {
+		check_absent_directories::<pe::ImageNtHeaders64>(size);
+		check_absent_directories::<pe::ImageNtHeaders32>(size);
+	}
+}
+
+#[test]
+fn default_options_limit_data_directories() {
+	assert_eq!(ParseOptions::new(), ParseOptions::strict());
+	let sample = common::sample(true);
+	let names: Vec<_> = sample.sections.iter().map(|section| section.name).collect();
+	// The section table follows `SizeOfOptionalHeader`, not the declared directories.
+	for declared in [0, 17, u32::MAX] {
+		let data = sample.leak(Layout::Mapped);
+		// `NumberOfRvaAndSizes` of the PE32+ optional header.
+		let offset = common::NT_HEADERS_OFFSET as usize + 4 + 20 + 108;
+		data[offset..offset + 4].copy_from_slice(&declared.to_le_bytes());
+		let parse = |options| unsafe {
+			PeHeaders::<pe::ImageNtHeaders64>::parse_nt_with_size(
+				data.as_ptr(),
+				data.len(),
+				options,
+			)
+		};
+		let headers = match declared {
+			0 => parse(ParseOptions::new()).unwrap(),
+			_ => {
+				assert_eq!(parse(ParseOptions::new()).err(), Some(Error::PeHeaders));
+				parse(ParseOptions::lenient()).unwrap()
+			}
+		};
+		assert_eq!(headers.data_directories.len(), declared.min(16) as usize);
+		let parsed: Vec<_> = headers
+			.section_headers
+			.iter()
+			.map(|section| {
+				std::str::from_utf8(objparse::section::section_name_bytes(section)).unwrap()
+			})
+			.collect();
+		assert_eq!(parsed, names);
+	}
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "std")]
+//! Export names are read at `image_base + rva`, so only the mapped layout is parsed.
+
+mod common;
+
+use common::{
+	ExportFn, Layout, PeBuilder, ALPHA_RVA, BETA_RVA, IMAGE_DIRECTORY_ENTRY_EXPORT, RDATA,
+};
+use object::pe::ImageNtHeaders64;
+use objparse::export_index::ExportIndex;
+
+fn export_index(pe: &PeBuilder) -> (ExportIndex, *const u8) {
+	let data = pe.leak(Layout::Mapped);
+	let headers = common::parse::<ImageNtHeaders64>(data, Layout::Mapped);
+	let base = data.as_ptr();
+	let index = unsafe { ExportIndex::build(headers.export_table().unwrap(), base) };
+	(index, base)
+}
+
+#[test]
+fn lookups_by_name() {
+	let (index, base) = export_index(&common::sample(true));
+	assert_eq!(index.len(), 3);
+	assert_eq!(index.rva(b"Alpha"), Some(ALPHA_RVA));
+	assert_eq!(index.rva(b"Beta"), Some(BETA_RVA));
+	assert_eq!(
+		index.address(base, b"Alpha"),
+		Some(base.wrapping_add(ALPHA_RVA as usize))
+	);
+	// Forwarders map to their forwarder string, ordinal-only exports have no name.
+	assert!(index.rva(b"Forward").is_some());
+	assert_eq!(index.rva(b"alpha"), None);
+	assert_eq!(index.rva(b"Alpha\0"), None);
+	assert_eq!(index.address(base, b"Gamma"), None);
+}
+
+#[test]
+fn names_of_missing_functions() {
+	// A name ordinal past the address table is left out of the index.
+	let mut pe = PeBuilder::new64();
+	let mut rdata = pe.blob();
+	let exports = common::exports(
+		&mut rdata,
+		"sample.dll",
+		1,
+		&[ExportFn::Rva(ALPHA_RVA)],
+		&[("Alpha", 0), ("Missing", 9)],
+	);
+	pe.section(".rdata", RDATA, rdata);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_EXPORT, exports);
+	let (index, _) = export_index(&pe);
+	assert_eq!(index.len(), 1);
+	assert_eq!(index.rva(b"Alpha"), Some(ALPHA_RVA));
+	assert_eq!(index.rva(b"Missing"), None);
+
+	let mut pe = PeBuilder::new64();
+	let mut rdata = pe.blob();
+	let exports = common::exports(&mut rdata, "sample.dll", 1, &[], &[]);
+	pe.section(".rdata", RDATA, rdata);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_EXPORT, exports);
+	assert!(export_index(&pe).0.is_empty());
+}
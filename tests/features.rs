@@ -0,0 +1,42 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::LAYOUTS;
+use object::pe;
+use objparse::{
+	features::{FEATURE_COUNT, FEATURE_NAMES},
+	nt::NtHeaders,
+};
+
+fn check_features<Nt: NtHeaders>() {
+	let mut pe = common::sample(size_of::<Nt>() == size_of::<pe::ImageNtHeaders64>());
+	// Any range will do, only the presence of the directories is recorded.
+	let (import_rva, _) = pe.directories[common::IMAGE_DIRECTORY_ENTRY_IMPORT];
+	pe.directory(pe::IMAGE_DIRECTORY_ENTRY_IAT, (import_rva, 8));
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let headers = common::parse::<Nt>(data, layout);
+		let features = unsafe { headers.features(data.as_ptr()) };
+		assert_eq!(features.number_of_sections, 5);
+		assert_eq!(features.import_dlls, 2);
+		assert_eq!(features.import_functions, 3);
+		assert_eq!(features.export_functions, 5);
+		assert_eq!(features.export_names, 3);
+		assert!(features.section_entropy_max > 0.0);
+
+		let vector = features.to_vector();
+		let feature = |name: &str| vector[FEATURE_NAMES.iter().position(|&n| n == name).unwrap()];
+		assert_eq!(feature("dir_import"), 1.0);
+		assert_eq!(feature("dir_iat"), 1.0);
+		assert_eq!(feature("dir_delay_import"), 0.0);
+		assert_eq!(feature("dir_exception"), 0.0);
+	}
+}
+
+#[test]
+fn features_in_both_widths_and_layouts() {
+	assert_eq!(FEATURE_NAMES.len(), FEATURE_COUNT);
+	check_features::<pe::ImageNtHeaders64>();
+	check_features::<pe::ImageNtHeaders32>();
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "ffi")]
+
+mod common;
+
+use common::{Layout, ALPHA_RVA};
+use core::{
+	ffi::{c_void, CStr},
+	ptr,
+};
+use objparse::{error::Error, ffi::*};
+
+/// Exports of the native sample through the C API, `find` returning the status and address.
+fn with_sample(find: impl FnOnce(*const ObjparseImage, *const u8)) {
+	let data = common::sample(common::NATIVE_IS_64).leak(Layout::Mapped);
+	let mut image = ptr::null_mut();
+	assert_eq!(
+		unsafe { objparse_parse(data.as_ptr(), &mut image) },
+		OBJPARSE_OK
+	);
+	find(image, data.as_ptr());
+	unsafe { objparse_free(image) };
+}
+
+#[test]
+fn find_export() {
+	with_sample(|image, base| {
+		let mut address = ptr::null_mut::<c_void>();
+		let status = unsafe { objparse_find_export(image, c"Alpha".as_ptr(), &mut address) };
+		assert_eq!(status, OBJPARSE_OK);
+		assert_eq!(address as usize - base as usize, ALPHA_RVA as usize);
+		let status = unsafe { objparse_find_export(image, c"Missing".as_ptr(), &mut address) };
+		assert_eq!(status, OBJPARSE_ERROR_NOT_FOUND);
+	});
+}
+
+#[test]
+fn find_export_reports_forwarders() {
+	with_sample(|image, _| {
+		let mut address = ptr::null_mut::<c_void>();
+		let status = unsafe { objparse_find_export(image, c"Forward".as_ptr(), &mut address) };
+		assert_eq!(status, OBJPARSE_FORWARDED);
+		assert_eq!(unsafe { CStr::from_ptr(address.cast()) }, c"other.Target");
+
+		let mut address = ptr::null_mut::<c_void>();
+		let status = unsafe { objparse_find_export_by_ordinal(image, 5, &mut address) };
+		assert_eq!(status, OBJPARSE_FORWARDED);
+		assert_eq!(unsafe { CStr::from_ptr(address.cast()) }, c"other.Target");
+	});
+}
+
+#[test]
+fn find_export_by_ordinal_skips_unused_slots() {
+	with_sample(|image, base| {
+		let mut address = ptr::null_mut::<c_void>();
+		let status = unsafe { objparse_find_export_by_ordinal(image, 1, &mut address) };
+		assert_eq!(status, OBJPARSE_OK);
+		assert_eq!(address as usize - base as usize, ALPHA_RVA as usize);
+		for ordinal in [3, 6] {
+			let status = unsafe { objparse_find_export_by_ordinal(image, ordinal, &mut address) };
+			assert_eq!(status, OBJPARSE_ERROR_NOT_FOUND);
+		}
+	});
+}
+
+#[test]
+fn header_lists_every_error() {
+	let header = include_str!("../include/objparse.h");
+	let errors = [
+		("PE_HEADERS", Error::PeHeaders),
+		("EXPORT_TABLE", Error::ExportTable),
+		("IMPORT_TABLE", Error::ImportTable),
+		("DEBUG_TABLE", Error::DebugTable),
+		("TLS_TABLE", Error::TlsTable),
+		("RESOURCE_TABLE", Error::ResourceTable),
+		("DELAY_IMPORT_TABLE", Error::DelayImportTable),
+		("IMPORT_RESOLUTION", Error::ImportResolution),
+		("CLR_HEADER", Error::ClrHeader),
+		("CLR_METADATA", Error::ClrMetadata),
+		("RVA_OVERFLOW", Error::RvaOverflow),
+		("RVA_OUTSIDE_SECTIONS", Error::RvaOutsideSections { rva: 0 }),
+		(
+			"INVALID_MEMORY",
+			Error::InvalidMemory { address: 0, len: 0 },
+		),
+		("SECTION_NAME", Error::SectionName),
+		("UTF16", Error::Utf16),
+		("MODULE_NOT_FOUND", Error::ModuleNotFound),
+		("REMOTE_PROCESS", Error::RemoteProcess),
+		("MINIDUMP", Error::Minidump),
+		("TE_HEADER", Error::TeHeader),
+		("CHPE_METADATA", Error::ChpeMetadata),
+		("BUNDLE", Error::Bundle),
+		("IO", Error::Io),
+		("NO_HEADER_SPACE", Error::NoHeaderSpace),
+		("RELOCATION_TABLE", Error::RelocationTable),
+		("AUTHENTICODE", Error::Authenticode),
+		("COFF", Error::Coff),
+		("ARCHIVE", Error::Archive),
+		("IMPORT_OBJECT", Error::ImportObject),
+		("LIMIT_EXCEEDED", Error::LimitExceeded { limit: 0 }),
+		(
+			"ARCH_MISMATCH",
+			Error::ArchMismatch {
+				expected: 0,
+				found: 0,
+			},
+		),
+		(
+			"RVA_OUT_OF_BOUNDS",
+			Error::RvaOutOfBounds {
+				rva: 0,
+				size_of_image: 0,
+			},
+		),
+		(
+			"OUT_OF_REGION",
+			Error::OutOfRegion {
+				offset: 0,
+				len: 0,
+				size: 0,
+			},
+		),
+	];
+	let listed = header
+		.lines()
+		.filter(|line| line.trim_start().starts_with("OBJPARSE_ERROR_"))
+		.count();
+	assert_eq!(listed, errors.len());
+	for (name, error) in errors {
+		let line = format!("OBJPARSE_ERROR_{name} = {},", error.code() + 1);
+		assert!(header.contains(&line), "{line}");
+	}
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::{Layout, ALPHA_RVA, BETA_RVA};
+use objparse::{error::Error, import::ImportName, PeHeaders};
+
+fn parse_file(data: &'static [u8]) -> PeHeaders {
+	PeHeaders::parse_file(data, Layout::File.options()).unwrap()
+}
+
+#[test]
+fn exports_of_a_file() {
+	let data = common::sample(common::NATIVE_IS_64).leak(Layout::File);
+	let headers = parse_file(data);
+	let export_table = headers.export_table().unwrap();
+	let base = data.as_ptr().cast_mut();
+	let exports: Vec<_> = unsafe { export_table.iter_string_addr_checked(&headers, base) }
+		.map(|export| {
+			let (name, address) = export.unwrap();
+			(name, address as usize - base as usize)
+		})
+		.collect();
+	assert_eq!(exports[0], (c"Alpha", ALPHA_RVA as usize));
+	assert_eq!(exports[1], (c"Beta", BETA_RVA as usize));
+	assert_eq!(exports[2].0, c"Forward");
+}
+
+#[test]
+fn imports_of_a_file() {
+	let data = common::sample(common::NATIVE_IS_64).leak(Layout::File);
+	let headers = parse_file(data);
+	let import_table = headers.import_table().unwrap();
+	let base = data.as_ptr().cast_mut();
+	let mut imports = Vec::new();
+	for descriptor in import_table.import_descriptors {
+		let dll = unsafe { import_table.dll_name_with(descriptor, &headers, base) }.unwrap();
+		for thunk in unsafe { headers.import_thunks(descriptor, base) }.unwrap() {
+			imports.push((dll, thunk.unwrap().name.unwrap()));
+		}
+	}
+	assert_eq!(
+		imports,
+		[
+			(
+				c"KERNEL32.dll",
+				ImportName::Name {
+					hint: 0x2b5,
+					name: c"GetProcAddress"
+				}
+			),
+			(
+				c"KERNEL32.dll",
+				ImportName::Name {
+					hint: 0x3c2,
+					name: c"LoadLibraryA"
+				}
+			),
+			(c"USER32.dll", ImportName::Ordinal(7)),
+		]
+	);
+}
+
+#[test]
+fn truncated_file_stays_inside_the_data() {
+	let file = common::sample(common::NATIVE_IS_64).file();
+	// Keeps the headers, `.text` and the first bytes of `.rdata`.
+	let data = common::leak(&file[..0x610]);
+	let headers = parse_file(data);
+	let Err(err) = headers.export_table() else {
+		panic!("export table past the data");
+	};
+	assert_eq!(err.context, "export table");
+	assert!(matches!(err.error, Error::OutOfRegion { size: 0x610, .. }));
+	assert!(headers.import_table().is_err());
+	assert!(headers.debug_table().is_err());
+}
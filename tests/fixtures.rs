@@ -0,0 +1,201 @@
+//! Every directory of the sample image, in both widths and layouts.
+
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::{
+	PeBuilder, ALPHA_RVA, BETA_RVA, CODEVIEW_GUID, CONFIG_DATA, DATA_RVA, GAMMA_RVA, LAYOUTS,
+	TLS_CALLBACK_RVA, VERSION_DATA,
+};
+use object::pe;
+use objparse::{
+	debug::{DebugData, PdbId},
+	import_map::ImportEntryName,
+	nt::NtHeaders,
+	reloc::RelocType,
+	resource::ResourceId,
+	PeHeaders,
+};
+
+/// Calls `check` with the sample of `Nt`'s width parsed in each layout.
+fn for_each_layout<Nt: NtHeaders>(check: impl Fn(&PeBuilder, &PeHeaders<Nt>, *mut u8)) {
+	let pe = common::sample(size_of::<Nt>() == size_of::<pe::ImageNtHeaders64>());
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let base = data.as_mut_ptr();
+		let headers = common::parse::<Nt>(
+			unsafe { core::slice::from_raw_parts(base, data.len()) },
+			layout,
+		);
+		check(&pe, &headers, base);
+	}
+}
+
+fn check_parse<Nt: NtHeaders>() {
+	for_each_layout::<Nt>(|pe, headers, _| {
+		let info = headers.info();
+		assert_eq!(info.is_64, pe.is_64);
+		assert_eq!(info.machine, pe.machine);
+		assert_eq!(info.number_of_sections, 5);
+		assert_eq!(info.image_base, pe.image_base);
+		assert_eq!(info.size_of_image, pe.size_of_image());
+		assert_eq!(info.size_of_headers, common::SIZE_OF_HEADERS);
+		let names: Vec<_> = headers
+			.section_headers
+			.iter()
+			.map(objparse::section::section_name_bytes)
+			.collect();
+		assert_eq!(
+			names,
+			[&b".text"[..], b".rdata", b".data", b".rsrc", b".reloc"]
+		);
+	});
+}
+
+#[test]
+fn parse() {
+	check_parse::<pe::ImageNtHeaders64>();
+	check_parse::<pe::ImageNtHeaders32>();
+}
+
+fn check_exports<Nt: NtHeaders>() {
+	for_each_layout::<Nt>(|_, headers, base| {
+		let export_table = headers.export_table().unwrap();
+		let exports: Vec<_> = unsafe { export_table.iter_string_addr_checked(headers, base) }
+			.map(|export| {
+				let (name, address) = export.unwrap();
+				(name, address as usize - base as usize)
+			})
+			.collect();
+		assert_eq!(
+			exports[..2],
+			[(c"Alpha", ALPHA_RVA as usize), (c"Beta", BETA_RVA as usize)]
+		);
+		assert_eq!(exports[2].0, c"Forward");
+		assert_eq!(export_table.rva_by_ordinal(4), Some(GAMMA_RVA));
+		assert_eq!(export_table.rva_by_ordinal(6), None);
+	});
+}
+
+#[test]
+fn exports() {
+	check_exports::<pe::ImageNtHeaders64>();
+	check_exports::<pe::ImageNtHeaders32>();
+}
+
+fn check_imports<Nt: NtHeaders>() {
+	for_each_layout::<Nt>(|_, headers, base| {
+		let import_map = unsafe { headers.import_map(base) }.unwrap();
+		let imports: Vec<_> = import_map
+			.iter()
+			.map(|(dll, entry)| (dll, entry.name.clone().unwrap()))
+			.collect();
+		assert_eq!(
+			imports,
+			[
+				(
+					"KERNEL32.dll",
+					ImportEntryName::Name {
+						hint: 0x2b5,
+						name: "GetProcAddress".into()
+					}
+				),
+				(
+					"KERNEL32.dll",
+					ImportEntryName::Name {
+						hint: 0x3c2,
+						name: "LoadLibraryA".into()
+					}
+				),
+				("USER32.dll", ImportEntryName::Ordinal(7)),
+			]
+		);
+	});
+}
+
+#[test]
+fn imports() {
+	check_imports::<pe::ImageNtHeaders64>();
+	check_imports::<pe::ImageNtHeaders32>();
+}
+
+fn check_debug<Nt: NtHeaders>() {
+	for_each_layout::<Nt>(|_, headers, base| {
+		let debug_table = headers.debug_table().unwrap();
+		let entries: Vec<_> = unsafe { debug_table.iter_typed_with(headers, base) }
+			.map(|entry| entry.unwrap().data)
+			.collect();
+		let [DebugData::CodeView(codeview), DebugData::Pogo(pogo)] = entries[..] else {
+			panic!("{entries:?}");
+		};
+		assert!(matches!(codeview.id, PdbId::Guid(guid) if guid.0 == CODEVIEW_GUID));
+		assert_eq!(codeview.age, 3);
+		assert_eq!(codeview.pdb_path, br"C:\build\sample.pdb");
+		assert_eq!(pogo, b"PGU\0\0\0\0\0");
+	});
+}
+
+#[test]
+fn debug() {
+	check_debug::<pe::ImageNtHeaders64>();
+	check_debug::<pe::ImageNtHeaders32>();
+}
+
+fn check_tls<Nt: NtHeaders>() {
+	for_each_layout::<Nt>(|pe, headers, base| {
+		let tls_table = headers.tls_table().unwrap().unwrap();
+		let callbacks: Vec<_> =
+			unsafe { tls_table.callback_addresses_with(headers, base, pe.image_base) }
+				.map(Result::unwrap)
+				.collect();
+		assert_eq!(callbacks, [pe.image_base + TLS_CALLBACK_RVA as u64]);
+	});
+}
+
+#[test]
+fn tls() {
+	check_tls::<pe::ImageNtHeaders64>();
+	check_tls::<pe::ImageNtHeaders32>();
+}
+
+fn check_resources<Nt: NtHeaders>() {
+	for_each_layout::<Nt>(|_, headers, _| {
+		assert_eq!(
+			headers.resource(ResourceId::Name("MYTYPE"), ResourceId::Name("CONFIG"), None),
+			Some(CONFIG_DATA)
+		);
+		assert_eq!(
+			headers.resource(ResourceId::Id(16), ResourceId::Id(1), Some(0x409)),
+			Some(VERSION_DATA)
+		);
+		assert_eq!(
+			headers.resource(ResourceId::Id(16), ResourceId::Id(2), None),
+			None
+		);
+	});
+}
+
+#[test]
+fn resources() {
+	check_resources::<pe::ImageNtHeaders64>();
+	check_resources::<pe::ImageNtHeaders32>();
+}
+
+fn check_relocations<Nt: NtHeaders>() {
+	for_each_layout::<Nt>(|pe, headers, base| {
+		let relocation_table = unsafe { headers.relocation_table_mem(base) }.unwrap();
+		let relocations: Vec<_> = relocation_table.iter().map(Result::unwrap).collect();
+		let typ = match pe.is_64 {
+			true => RelocType::Dir64,
+			false => RelocType::HighLow,
+		};
+		assert_eq!(relocations, [(typ, DATA_RVA)]);
+	});
+}
+
+#[test]
+fn relocations() {
+	check_relocations::<pe::ImageNtHeaders64>();
+	check_relocations::<pe::ImageNtHeaders32>();
+}
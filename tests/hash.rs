@@ -0,0 +1,21 @@
+use objparse::hash::{ExportHasher, Fnv1a};
+
+fn hash_module_name(name: &str) -> u32 {
+	Fnv1a::hash_module_name(name.encode_utf16())
+}
+
+#[test]
+fn module_names_hash_as_lowercase_ascii() {
+	let expected = Fnv1a::hash_const(b"kernel32.dll");
+	assert_eq!(hash_module_name("kernel32.dll"), expected);
+	assert_eq!(hash_module_name("KERNEL32.DLL"), expected);
+	assert_eq!(hash_module_name("Kernel32.Dll"), expected);
+}
+
+#[test]
+fn module_names_fold_beyond_ascii() {
+	let expected = Fnv1a::hash_const("Ärger.dll".as_bytes());
+	assert_eq!(hash_module_name("ärger.dll"), expected);
+	assert_eq!(hash_module_name("ÄRGER.DLL"), expected);
+	assert_ne!(hash_module_name("arger.dll"), expected);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{Layout, NATIVE_IS_64, NT_HEADERS_OFFSET};
+use object::{read::pe::ImageOptionalHeader, LittleEndian};
+use objparse::{error::Error, offsets::HeaderField, PeHeaders};
+
+/// The native sample in file layout with `field` set to `value`.
+fn patched(field: HeaderField, value: u32) -> &'static [u8] {
+	let mut file = common::sample(NATIVE_IS_64).file();
+	let headers: PeHeaders = common::parse(common::leak(&file), Layout::File);
+	let span = headers.field_span(field).unwrap();
+	file[span.offset..span.end()].copy_from_slice(&value.to_le_bytes()[..span.len]);
+	common::leak(&file)
+}
+
+#[test]
+fn headers_only() {
+	// The data directories and section table are not looked at.
+	let data = patched(HeaderField::NumberOfRvaAndSizes, u32::MAX);
+	assert!(PeHeaders::parse_file(data, Layout::File.options()).is_err());
+	let headers = unsafe { PeHeaders::parse_headers_only(data.as_ptr()) }.unwrap();
+	assert_eq!(headers.nt_header_offset, NT_HEADERS_OFFSET as usize);
+	assert_eq!(
+		headers.dos_header.e_lfanew.get(LittleEndian),
+		NT_HEADERS_OFFSET
+	);
+	let file_header = &headers.nt_header.file_header;
+	assert_eq!(file_header.number_of_sections.get(LittleEndian), 5);
+	let optional_header = &headers.nt_header.optional_header;
+	assert_eq!(optional_header.number_of_rva_and_sizes(), u32::MAX);
+	assert_eq!(
+		optional_header.image_base(),
+		common::sample(NATIVE_IS_64).image_base
+	);
+}
+
+#[test]
+fn malformed_headers() {
+	let sample = common::sample(NATIVE_IS_64).file();
+	let patched = |offset: usize, bytes: &[u8]| {
+		let mut data = sample.clone();
+		data[offset..offset + bytes.len()].copy_from_slice(bytes);
+		common::leak(&data)
+	};
+	let nt_headers_offset = NT_HEADERS_OFFSET as usize;
+	for data in [
+		patched(0, b"ZM"),
+		patched(nt_headers_offset, b"PX\0\0"),
+		// Further than the default limit on `e_lfanew`.
+		patched(0x3c, &0x10_0000u32.to_le_bytes()),
+		// An optional header of the other bitness.
+		patched(nt_headers_offset + 24, &[0x0b, 2 - NATIVE_IS_64 as u8]),
+	] {
+		assert!(unsafe { PeHeaders::parse_headers_only(data.as_ptr()) }.is_err());
+	}
+	let data = common::sample(!NATIVE_IS_64).leak(Layout::File);
+	assert!(matches!(
+		unsafe { PeHeaders::parse_headers_only(data.as_ptr()) },
+		Err(Error::ArchMismatch { .. } | Error::PeHeaders)
+	));
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::Layout;
+use objparse::{
+	hooks::{HookKind, ModuleRange},
+	import::ImportName,
+	PeHeaders,
+};
+
+struct Module {
+	base: u64,
+	name: &'static str,
+	/// Where the module forwards `GetProcAddress`.
+	forwards_to: Option<u64>,
+}
+
+impl ModuleRange for Module {
+	fn base(&self) -> u64 {
+		self.base
+	}
+
+	fn size(&self) -> u32 {
+		0x1000
+	}
+
+	fn has_name(&self, name: &str) -> bool {
+		self.name.eq_ignore_ascii_case(name)
+	}
+
+	unsafe fn resolve_export(&self, import: ImportName) -> Option<u64> {
+		match import {
+			ImportName::Name { name, .. } if name == c"GetProcAddress" => self.forwards_to,
+			_ => None,
+		}
+	}
+}
+
+const KERNEL32: u64 = 0x1000_0000;
+const NTDLL: u64 = 0x2000_0000;
+
+/// Hooks of the native sample whose IAT holds `targets` in import order.
+fn hooks(modules: &[Module], targets: [u64; 3]) -> Vec<(u64, HookKind)> {
+	let data = common::sample(common::NATIVE_IS_64).leak(Layout::Mapped);
+	let base = data.as_mut_ptr();
+	let headers = unsafe { PeHeaders::parse_with_size(base, data.len()) }.unwrap();
+	let import_table = unsafe { headers.import_table_mem(base) }.unwrap();
+	let slots = import_table
+		.import_descriptors
+		.iter()
+		.flat_map(|descriptor| unsafe { headers.import_thunks(descriptor, base) }.unwrap())
+		.map(|thunk| thunk.unwrap().iat_slot);
+	for (slot, target) in slots.zip(targets) {
+		unsafe { slot.cast::<usize>().write_unaligned(target as usize) };
+	}
+	unsafe { headers.iat_hooks(base, modules) }
+		.map(|hook| (hook.target, hook.kind))
+		.collect()
+}
+
+#[test]
+fn forwarded_imports_are_not_hooks() {
+	let modules = [
+		Module {
+			base: KERNEL32,
+			name: "KERNEL32.dll",
+			forwards_to: Some(NTDLL + 0x10),
+		},
+		Module {
+			base: NTDLL,
+			name: "ntdll.dll",
+			forwards_to: None,
+		},
+	];
+	let targets = [NTDLL + 0x10, NTDLL + 0x20, 0x3000_0000];
+	assert_eq!(
+		hooks(&modules, targets),
+		[
+			(NTDLL + 0x20, HookKind::OtherModule(1)),
+			(0x3000_0000, HookKind::OutsideModules),
+		]
+	);
+}
+
+#[test]
+fn unresolved_forwarders_are_hooks() {
+	let modules = [
+		Module {
+			base: KERNEL32,
+			name: "KERNEL32.dll",
+			forwards_to: None,
+		},
+		Module {
+			base: NTDLL,
+			name: "ntdll.dll",
+			forwards_to: None,
+		},
+	];
+	let targets = [NTDLL + 0x10, KERNEL32 + 0x20, 0x3000_0000];
+	assert_eq!(
+		hooks(&modules, targets),
+		[
+			(NTDLL + 0x10, HookKind::OtherModule(1)),
+			(0x3000_0000, HookKind::OutsideModules),
+		]
+	);
+}
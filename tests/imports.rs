@@ -0,0 +1,90 @@
+//! Import names are read at `image_base + rva`, so only the mapped layout is parsed.
+
+mod common;
+
+use common::{ImportFn, Layout, PeBuilder, IMAGE_DIRECTORY_ENTRY_IMPORT, NATIVE_IS_64, RDATA};
+use objparse::{import::IatSlot, nt::NativeNtHeaders};
+
+/// Looks up `name` from `dll` in a mapped copy of `pe`, with the image base.
+fn find_import(pe: &PeBuilder, dll: &str, name: &str) -> (Option<IatSlot>, *mut u8) {
+	let data = pe.leak(Layout::Mapped);
+	let base = data.as_mut_ptr();
+	let headers = common::parse::<NativeNtHeaders>(
+		unsafe { core::slice::from_raw_parts(base, data.len()) },
+		Layout::Mapped,
+	);
+	let import_table = headers.import_table().unwrap();
+	let slot = unsafe { import_table.find_import(base, pe.size_of_image(), dll, name) };
+	assert_eq!(
+		unsafe { import_table.imports_function(base, pe.size_of_image(), dll, name) },
+		slot.is_some()
+	);
+	(slot, base)
+}
+
+/// RVA of the IAT slot of `function` imported from `dll`, by the descriptor's `FirstThunk`.
+fn iat_rva(pe: &PeBuilder, dll: usize, function: usize) -> u32 {
+	let data = pe.mapped();
+	let directory = pe.directories[IMAGE_DIRECTORY_ENTRY_IMPORT].0 as usize;
+	let first_thunk = common::read_u32(&data, directory + dll * 20 + 16);
+	first_thunk + function as u32 * if NATIVE_IS_64 { 8 } else { 4 }
+}
+
+#[test]
+fn imports_by_name() {
+	let pe = common::sample(NATIVE_IS_64);
+	// Both names compare ignoring ASCII case.
+	for (dll, name) in [
+		("KERNEL32.dll", "LoadLibraryA"),
+		("kernel32.DLL", "loadlibrarya"),
+	] {
+		let (slot, base) = find_import(&pe, dll, name);
+		let slot = slot.unwrap();
+		assert_eq!(slot.rva, iat_rva(&pe, 0, 1));
+		assert_eq!(slot.slot, base.wrapping_add(slot.rva as usize));
+		assert!(!slot.resolved);
+	}
+	let (slot, _) = find_import(&pe, "KERNEL32.dll", "GetProcAddress");
+	assert_eq!(slot.unwrap().rva, iat_rva(&pe, 0, 0));
+}
+
+#[test]
+fn imports_not_found() {
+	let pe = common::sample(NATIVE_IS_64);
+	for (dll, name) in [
+		("KERNEL32.dll", "CreateRemoteThread"),
+		("KERNEL32", "LoadLibraryA"),
+		("USER32.dll", "LoadLibraryA"),
+		// Imports by ordinal have no name to match.
+		("USER32.dll", "#7"),
+		("", ""),
+	] {
+		assert!(find_import(&pe, dll, name).0.is_none(), "{dll} {name}");
+	}
+
+	// A thunk whose name lies outside the image hides the imports after it.
+	let mut pe = match NATIVE_IS_64 {
+		true => PeBuilder::new64(),
+		false => PeBuilder::new32(),
+	};
+	let mut rdata = pe.blob();
+	let imports = common::imports(
+		&mut rdata,
+		NATIVE_IS_64,
+		&[(
+			"KERNEL32.dll",
+			&[
+				ImportFn::Name(0, "GetProcAddress"),
+				ImportFn::Name(0, "LoadLibraryA"),
+			],
+		)],
+	);
+	let original_first_thunk = common::read_u32(&rdata.data, (imports.0 - rdata.rva) as usize);
+	rdata.patch_u32(original_first_thunk, 0x10_0000);
+	pe.section(".rdata", RDATA, rdata);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_IMPORT, imports);
+	assert!(find_import(&pe, "KERNEL32.dll", "LoadLibraryA").0.is_none());
+	assert!(find_import(&pe, "KERNEL32.dll", "GetProcAddress")
+		.0
+		.is_none());
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::Layout;
+use object::pe;
+use objparse::{
+	loader::{LoaderRule, SizeOfImageMismatch, MAX_SIZE_OF_IMAGE},
+	offsets::{HeaderField, SectionField},
+	ParseOptions, PeHeaders,
+};
+
+/// The x64 sample in file layout with `fields` set, parsed leniently.
+fn patched(fields: &[(HeaderField, u64)]) -> PeHeaders<pe::ImageNtHeaders64> {
+	let mut file = common::sample(true).file();
+	// Room for the section table of more sections than the loader allows.
+	file.resize(file.len().max(0x2000), 0);
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(common::leak(&file), Layout::File);
+	for &(field, value) in fields {
+		let span = headers.field_span(field).unwrap();
+		file[span.offset..span.end()].copy_from_slice(&value.to_le_bytes()[..span.len]);
+	}
+	PeHeaders::parse_file_nt(common::leak(&file), ParseOptions::lenient()).unwrap()
+}
+
+fn check(fields: &[(HeaderField, u64)]) -> Result<(), LoaderRule> {
+	patched(fields).check_loader_rules()
+}
+
+#[test]
+fn sample_loads() {
+	assert_eq!(check(&[]), Ok(()));
+	// The section table follows `SizeOfOptionalHeader`, however many directories are declared.
+	assert_eq!(check(&[(HeaderField::NumberOfRvaAndSizes, 0x1000)]), Ok(()));
+}
+
+#[test]
+fn each_rule() {
+	use HeaderField::*;
+	let section = |index, field| HeaderField::Section(index, field);
+	let cases: [(&[(HeaderField, u64)], LoaderRule); 11] = [
+		(&[(Characteristics, 0x2020)], LoaderRule::NotExecutable),
+		(&[(NumberOfSections, 97)], LoaderRule::TooManySections),
+		(
+			&[(SizeOfOptionalHeader, 0x60)],
+			LoaderRule::SizeOfOptionalHeader,
+		),
+		(&[(FileAlignment, 0x300)], LoaderRule::FileAlignment),
+		(&[(SectionAlignment, 0x100)], LoaderRule::SectionAlignment),
+		(&[(ImageBase, 0x1_8000_1000)], LoaderRule::ImageBase),
+		(&[(SizeOfHeaders, 0x100)], LoaderRule::SizeOfHeaders),
+		(
+			&[(SizeOfImage, MAX_SIZE_OF_IMAGE as u64 + 0x1000)],
+			LoaderRule::SizeOfImage,
+		),
+		(
+			&[(section(1, SectionField::VirtualAddress), 0x3000)],
+			LoaderRule::SectionLayout,
+		),
+		// With low alignment, the first section at the end of the headers with its raw data
+		// elsewhere.
+		(
+			&[
+				(NumberOfSections, 1),
+				(SectionAlignment, 0x200),
+				(section(0, SectionField::VirtualAddress), 0x400),
+				(section(0, SectionField::PointerToRawData), 0x600),
+			],
+			LoaderRule::SectionRawData,
+		),
+		(&[(AddressOfEntryPoint, 0x10_0000)], LoaderRule::EntryPoint),
+	];
+	for (fields, rule) in cases {
+		assert_eq!(check(fields), Err(rule), "{fields:?}");
+	}
+	// Sections smaller than the image are fine, larger ones are not.
+	assert_eq!(
+		check(&[(SizeOfImage, 0x1000)]),
+		Err(LoaderRule::SizeOfImage)
+	);
+	assert_eq!(
+		check(&[
+			(NumberOfSections, 1),
+			(SectionAlignment, 0x200),
+			(section(0, SectionField::VirtualAddress), 0x400),
+		]),
+		Ok(())
+	);
+}
+
+#[test]
+fn compute_size_of_image() {
+	let sample = common::sample(true);
+	let headers = patched(&[]);
+	assert_eq!(
+		headers.compute_size_of_image(),
+		Some(sample.size_of_image())
+	);
+	assert_eq!(headers.size_of_image_mismatch(), None);
+
+	let declared = sample.size_of_image() as u64 + 0x1000;
+	let headers = patched(&[(HeaderField::SizeOfImage, declared)]);
+	assert_eq!(
+		headers.size_of_image_mismatch(),
+		Some(SizeOfImageMismatch {
+			declared: declared as u32,
+			computed: sample.size_of_image(),
+		})
+	);
+
+	// The end of the last section overflows.
+	let last = sample.sections.len() - 1;
+	let headers = patched(&[(
+		HeaderField::Section(last, SectionField::VirtualSize),
+		u32::MAX as u64,
+	)]);
+	assert_eq!(headers.compute_size_of_image(), None);
+	assert_eq!(
+		headers
+			.size_of_image_mismatch()
+			.map(|mismatch| mismatch.computed),
+		Some(u32::MAX)
+	);
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "alloc")]
+//! Exports are read at `image_base + rva`, so only the mapped layout is parsed.
+
+mod common;
+
+use common::{ExportFn, Layout, PeBuilder, CODE, IMAGE_DIRECTORY_ENTRY_EXPORT, RDATA};
+use object::pe::ImageNtHeaders64;
+
+const PUBLICS: &str = "\n  Address         Publics by Value              Rva+Base\n";
+
+fn map_report(pe: &PeBuilder) -> String {
+	let data = pe.leak(Layout::Mapped);
+	let headers = common::parse::<ImageNtHeaders64>(data, Layout::Mapped);
+	unsafe { headers.map_report(data.as_ptr(), "sample") }
+}
+
+#[test]
+fn sections_and_publics() {
+	let report = map_report(&common::sample(true));
+	let expected = [
+		" sample\n",
+		" Timestamp is 60000000\n",
+		" Preferred load address is 0000000180000000\n",
+		" Start         Length     Name                   Class",
+		" 0001:00000000 00000050H .text                  CODE",
+		" 0002:00000000 000001b4H .rdata                 DATA",
+		" 0003:00000000 00000048H .data                  DATA",
+		" 0004:00000000 000000dcH .rsrc                  DATA",
+		" 0005:00000000 0000000cH .reloc                 DATA",
+		"\n  Address         Publics by Value              Rva+Base",
+		// The forwarder is left out, the export without a name goes by its ordinal.
+		" 0001:00000010       Alpha                      0000000180001010",
+		" 0001:00000020       Beta                       0000000180001020",
+		" 0001:00000030       Ordinal4                   0000000180001030",
+		"",
+	];
+	assert_eq!(report, expected.join("\n"));
+}
+
+#[test]
+fn publics_outside_sections_and_malformed_exports() {
+	let mut pe = PeBuilder::new64();
+	let mut text = pe.blob();
+	text.zeroes(0x10);
+	pe.section(".text", CODE, text);
+	let mut rdata = pe.blob();
+	let exports = common::exports(
+		&mut rdata,
+		"sample.dll",
+		1,
+		&[
+			ExportFn::Rva(0x1008),
+			ExportFn::Rva(0x20),
+			ExportFn::Rva(0x1000),
+		],
+		&[("Late", 0), ("InHeaders", 1), ("Early", 2)],
+	);
+	pe.section(".rdata", RDATA, rdata);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_EXPORT, exports);
+	let report = map_report(&pe);
+	let publics = &report[report.find(PUBLICS).unwrap() + PUBLICS.len()..];
+	let lines: Vec<_> = publics.lines().collect();
+	assert_eq!(
+		lines,
+		[
+			" 0000:00000020       InHeaders                  0000000180000020",
+			" 0001:00000000       Early                      0000000180001000",
+			" 0001:00000008       Late                       0000000180001008",
+		]
+	);
+
+	// Without a readable export table the report ends at the publics heading.
+	pe.directory(IMAGE_DIRECTORY_ENTRY_EXPORT, (0x10_0000, 40));
+	assert!(map_report(&pe).ends_with(PUBLICS));
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use common::{Blob, PeBuilder};
+use objparse::{
+	error::Error,
+	minidump::{
+		Minidump, MEMORY64_LIST_STREAM, MEMORY_LIST_STREAM, MINIDUMP_SIGNATURE, MODULE_LIST_STREAM,
+	},
+	source::MemorySource,
+};
+
+const MODULE_PATH: &str = r"C:\Windows\System32\Sample.DLL";
+
+fn utf16(string: &str) -> Vec<u8> {
+	string.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+/// Points directory entry `index` at the stream from `start` to the end of `dump`.
+fn end_stream(dump: &mut Blob, index: u32, start: u32) {
+	let entry = 32 + 12 * index;
+	dump.patch_u32(entry + 4, dump.here() - start);
+	dump.patch_u32(entry + 8, start);
+}
+
+/// A dump of modules with their base, size and path, and the memory of `MemoryListStream` and
+/// `Memory64ListStream` by address.
+fn dump(
+	modules: &[(u64, u32, &str)],
+	memory: &[(u64, &[u8])],
+	memory64: &[(u64, &[u8])],
+) -> Vec<u8> {
+	let mut dump = Blob {
+		rva: 0,
+		file_offset: 0,
+		data: Vec::new(),
+	};
+	dump.u32(MINIDUMP_SIGNATURE)
+		.u32(0xa793)
+		.u32(3)
+		.u32(32)
+		.zeroes(16);
+	for stream_type in [MODULE_LIST_STREAM, MEMORY_LIST_STREAM, MEMORY64_LIST_STREAM] {
+		dump.u32(stream_type).u32(0).u32(0);
+	}
+
+	let start = dump.here();
+	dump.u32(modules.len() as u32);
+	let mut names = Vec::new();
+	for &(base, size, _) in modules {
+		names.push(dump.here() + 20);
+		dump.u64(base).u32(size).u32(0x1234).u32(0x5678).zeroes(88);
+	}
+	end_stream(&mut dump, 0, start);
+
+	let start = dump.here();
+	dump.u32(memory.len() as u32);
+	let mut locations = Vec::new();
+	for &(address, data) in memory {
+		locations.push(dump.here() + 12);
+		dump.u64(address).u32(data.len() as u32).u32(0);
+	}
+	end_stream(&mut dump, 1, start);
+
+	let start = dump.here();
+	dump.u64(memory64.len() as u64).u64(0);
+	for &(address, data) in memory64 {
+		dump.u64(address).u64(data.len() as u64);
+	}
+	end_stream(&mut dump, 2, start);
+
+	for (&(_, _, path), name) in modules.iter().zip(names) {
+		dump.patch_u32(name, dump.here());
+		let path = utf16(path);
+		dump.u32(path.len() as u32).bytes(&path).u16(0);
+	}
+	for (&(_, data), location) in memory.iter().zip(locations) {
+		dump.patch_u32(location, dump.here());
+		dump.bytes(data);
+	}
+	dump.patch_u32(start + 8, dump.here());
+	for &(_, data) in memory64 {
+		dump.bytes(data);
+	}
+	dump.data
+}
+
+fn parse(data: &[u8]) -> Result<Minidump, Error> {
+	Minidump::parse(common::leak(data))
+}
+
+#[test]
+fn modules_and_memory() {
+	let pe = common::sample(true);
+	let image = pe.mapped();
+	let base = pe.image_base;
+	let data = dump(
+		&[(base, image.len() as u32, MODULE_PATH)],
+		&[(base + 0x800, &image[0x800..0x1800])],
+		&[(base, &image[..0x1000]), (base + 0x1000, &image[0x1000..])],
+	);
+	let dump = parse(&data).unwrap();
+
+	let modules: Vec<_> = dump.modules().collect();
+	assert_eq!(modules.len(), 1);
+	let module = modules[0];
+	assert_eq!((module.base, module.size), (base, image.len() as u32));
+	assert_eq!((module.checksum, module.time_date_stamp), (0x1234, 0x5678));
+	assert_eq!(module.name, utf16(MODULE_PATH));
+	assert_eq!(module.file_name(), utf16("Sample.DLL"));
+	assert!(module.contains(base + image.len() as u64 - 1));
+	assert!(!module.contains(base + image.len() as u64));
+	assert_eq!(dump.find_module("sample.dll").unwrap().base, base);
+	assert!(dump.find_module("System32").is_none());
+
+	let ranges: Vec<_> = dump
+		.memory_ranges()
+		.map(|range| (range.address, range.data.len()))
+		.collect();
+	assert_eq!(
+		ranges,
+		[
+			(base + 0x800, 0x1000),
+			(base, 0x1000),
+			(base + 0x1000, image.len() - 0x1000)
+		]
+	);
+	let mut buf = vec![0; image.len()];
+	assert!(dump.read(base, &mut buf));
+	assert_eq!(buf, image);
+	assert!(!dump.read(base + 1, &mut buf));
+	assert!(!dump.read(u64::MAX, &mut [0; 2]));
+
+	#[cfg(feature = "alloc")]
+	{
+		let copy = dump.module_image(&module).unwrap();
+		assert_eq!(copy, image);
+		let headers: objparse::PeHeaders<object::pe::ImageNtHeaders64> =
+			common::parse(common::leak(&copy), common::Layout::Mapped);
+		assert_eq!(headers.export_table().unwrap().name_table.len(), 3);
+	}
+}
+
+#[test]
+fn overlapping_ranges() {
+	let data = dump(
+		&[],
+		&[(0x1000, &[1; 0x100]), (0x1080, &[2; 0x100])],
+		&[(0x1200, &[3; 0x80])],
+	);
+	let dump = parse(&data).unwrap();
+	let mut buf = [0; 0x180];
+	assert!(dump.read(0x1000, &mut buf));
+	assert_eq!(buf[..0x100], [1; 0x100]);
+	assert_eq!(buf[0x100..], [2; 0x80]);
+
+	// Overlapping bytes do not make up for the gap from 0x1180.
+	assert!(!dump.read(0x1000, &mut [0; 0x200]));
+	assert!(!dump.read(0x1100, &mut [0; 0x180]));
+	let mut buf = [0; 0x10];
+	assert!(dump.read(0x1270, &mut buf));
+	assert_eq!(buf, [3; 0x10]);
+}
+
+#[test]
+fn malformed_minidumps() {
+	let pe = PeBuilder::new64();
+	let data = dump(
+		&[(pe.image_base, 0x1000, MODULE_PATH)],
+		&[(0x1000, &[1; 0x10])],
+		&[(0x2000, &[2; 0x10])],
+	);
+	let patched = |offset: usize, value: u32| {
+		let mut data = data.clone();
+		data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+		data
+	};
+	let module_list = common::read_u32(&data, 32 + 8) as usize;
+	let memory_list = common::read_u32(&data, 32 + 12 + 8) as usize;
+	for data in [
+		patched(0, 0x1234),
+		data[..31].to_vec(),
+		// The directory, a stream, or a list past the end.
+		patched(8, 0x1000_0000),
+		patched(12, data.len() as u32),
+		patched(32 + 8, data.len() as u32),
+		patched(module_list, 2),
+		patched(memory_list, u32::MAX),
+	] {
+		assert_eq!(parse(&data).err(), Some(Error::Minidump));
+	}
+
+	// A module name and memory past the end are left out.
+	let dump = parse(&patched(module_list + 4 + 20, data.len() as u32)).unwrap();
+	assert_eq!(dump.modules().count(), 0);
+	let dump = parse(&patched(memory_list + 4 + 12, data.len() as u32)).unwrap();
+	let ranges: Vec<_> = dump.memory_ranges().map(|range| range.address).collect();
+	assert_eq!(ranges, [0x2000]);
+	assert!(!dump.read(0x1000, &mut [0; 1]));
+	let memory64_base = memory_list + 4 + 16 + 8;
+	let dump = parse(&patched(memory64_base, data.len() as u32 - 8)).unwrap();
+	assert_eq!(dump.memory_ranges().count(), 1);
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::{
+	Layout, PeBuilder, IMAGE_DIRECTORY_ENTRY_DEBUG, IMAGE_DIRECTORY_ENTRY_EXPORT,
+	IMAGE_DIRECTORY_ENTRY_IMPORT, IMAGE_DIRECTORY_ENTRY_TLS, LAYOUTS, NT_HEADERS_OFFSET,
+};
+use object::pe;
+use objparse::{
+	nt::NtHeaders,
+	offsets::{FieldSpan, HeaderField, SectionField},
+	ParseOptions, PeHeaders,
+};
+
+/// The little-endian value `span` covers in `data`.
+fn value(data: &[u8], span: FieldSpan) -> u64 {
+	let mut bytes = [0; 8];
+	bytes[..span.len].copy_from_slice(&data[span.offset..span.end()]);
+	u64::from_le_bytes(bytes)
+}
+
+fn check_field_spans<Nt: NtHeaders>(pe: &PeBuilder) {
+	let data = common::leak(&pe.file());
+	let headers: PeHeaders<Nt> = common::parse(data, Layout::File);
+	let span = |field| headers.field_span(field).unwrap();
+	let optional_header = NT_HEADERS_OFFSET as usize + 24;
+
+	assert_eq!(
+		span(HeaderField::Lfanew),
+		FieldSpan {
+			offset: 0x3c,
+			len: 4
+		}
+	);
+	assert_eq!(
+		span(HeaderField::AddressOfEntryPoint),
+		FieldSpan {
+			offset: optional_header + 16,
+			len: 4
+		}
+	);
+	let image_base = span(HeaderField::ImageBase);
+	assert_eq!(image_base.len, if pe.is_64 { 8 } else { 4 });
+	for (field, expected) in [
+		(HeaderField::Lfanew, NT_HEADERS_OFFSET as u64),
+		(HeaderField::Machine, pe.machine as u64),
+		(HeaderField::NumberOfSections, 5),
+		(
+			HeaderField::SizeOfOptionalHeader,
+			pe.size_of_optional_header() as u64,
+		),
+		(HeaderField::AddressOfEntryPoint, pe.entry_point as u64),
+		(HeaderField::ImageBase, pe.image_base),
+		(HeaderField::SectionAlignment, 0x1000),
+		(HeaderField::FileAlignment, 0x200),
+		(HeaderField::SizeOfImage, pe.size_of_image() as u64),
+		(HeaderField::SizeOfHeaders, 0x400),
+		(HeaderField::NumberOfRvaAndSizes, 16),
+	] {
+		assert_eq!(value(data, span(field)), expected, "{field:?}");
+	}
+
+	let (rva, size) = pe.directories[IMAGE_DIRECTORY_ENTRY_IMPORT];
+	let directory = span(HeaderField::DataDirectory(IMAGE_DIRECTORY_ENTRY_IMPORT));
+	assert_eq!(directory.len, 8);
+	assert_eq!(value(data, directory), ((size as u64) << 32) | rva as u64);
+	for (index, section) in headers.section_headers.iter().enumerate() {
+		let span = |field| span(HeaderField::Section(index, field));
+		assert_eq!(
+			data[span(SectionField::Name).offset..span(SectionField::Name).end()],
+			section.name
+		);
+		assert_eq!(
+			value(data, span(SectionField::VirtualAddress)),
+			pe.sections[index].rva as u64
+		);
+		assert_eq!(
+			value(data, span(SectionField::PointerToRawData)),
+			pe.sections[index].file_offset as u64
+		);
+	}
+	assert_eq!(headers.field_span(HeaderField::DataDirectory(16)), None);
+	assert_eq!(
+		headers.field_span(HeaderField::Section(5, SectionField::Name)),
+		None
+	);
+}
+
+#[test]
+fn field_spans() {
+	check_field_spans::<pe::ImageNtHeaders64>(&common::sample(true));
+	check_field_spans::<pe::ImageNtHeaders32>(&common::sample(false));
+}
+
+#[test]
+fn field_spans_of_truncated_directories() {
+	let mut file = common::sample(true).file();
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(common::leak(&file), Layout::File);
+	let sections = headers.field_span(HeaderField::Section(0, SectionField::Name));
+	let count = headers
+		.field_span(HeaderField::NumberOfRvaAndSizes)
+		.unwrap();
+	file[count.offset..count.end()].copy_from_slice(&2u32.to_le_bytes());
+
+	// The section table stays after the optional header whatever the directory count.
+	let headers = PeHeaders::<pe::ImageNtHeaders64>::parse_file_nt(
+		common::leak(&file),
+		ParseOptions::lenient(),
+	)
+	.unwrap();
+	assert!(headers.field_span(HeaderField::DataDirectory(1)).is_some());
+	assert_eq!(headers.field_span(HeaderField::DataDirectory(2)), None);
+	assert_eq!(
+		headers.field_span(HeaderField::Section(0, SectionField::Name)),
+		sections
+	);
+
+	let data = [0u8; 8];
+	assert_eq!(
+		FieldSpan::of(data.as_ptr(), &data[4..6]),
+		Some(FieldSpan { offset: 4, len: 2 })
+	);
+	assert_eq!(FieldSpan::of(data[1..].as_ptr(), &data[0]), None);
+}
+
+fn check_table_spans<Nt: NtHeaders>(pe: &PeBuilder) {
+	for layout in LAYOUTS {
+		let data = pe.leak(layout);
+		let base = data.as_ptr();
+		let headers: PeHeaders<Nt> = common::parse(data, layout);
+		let directory = |index: usize| {
+			let (rva, size) = pe.directories[index];
+			let offset = headers.rva_to_ptr(base, rva).unwrap() as usize - base as usize;
+			FieldSpan {
+				offset,
+				len: size as usize,
+			}
+		};
+
+		let exports = headers.export_table().unwrap();
+		let export_directory = directory(IMAGE_DIRECTORY_ENTRY_EXPORT);
+		assert_eq!(
+			exports.directory_span(base),
+			Some(FieldSpan {
+				len: size_of::<pe::ImageExportDirectory>(),
+				..export_directory
+			})
+		);
+		let lens = [
+			exports.address_table_span(base).unwrap().len,
+			exports.name_table_span(base).unwrap().len,
+			exports.ordinal_table_span(base).unwrap().len,
+		];
+		assert_eq!(lens, [5 * 4, 3 * 4, 3 * 2]);
+		let address_table = exports.address_table_span(base).unwrap();
+		assert!(address_table.offset >= export_directory.offset);
+		assert!(address_table.end() <= export_directory.end());
+
+		// The import descriptors include their terminator.
+		let imports = headers.import_table().unwrap();
+		assert_eq!(
+			imports.span(base),
+			Some(directory(IMAGE_DIRECTORY_ENTRY_IMPORT))
+		);
+		let debug = headers.debug_table().unwrap();
+		assert_eq!(
+			debug.span(base),
+			Some(directory(IMAGE_DIRECTORY_ENTRY_DEBUG))
+		);
+		let tls = headers.tls_table().unwrap().unwrap();
+		assert_eq!(
+			tls.span(base),
+			Some(FieldSpan {
+				len: size_of::<Nt::TlsDirectory>(),
+				..directory(IMAGE_DIRECTORY_ENTRY_TLS)
+			})
+		);
+
+		// Tables before the given base have no span.
+		let past_the_end = base.wrapping_add(data.len());
+		assert_eq!(exports.directory_span(past_the_end), None);
+		assert_eq!(exports.ordinal_table_span(past_the_end), None);
+		assert_eq!(imports.span(past_the_end), None);
+		assert_eq!(debug.span(past_the_end), None);
+		assert_eq!(tls.span(past_the_end), None);
+	}
+}
+
+#[test]
+fn table_spans() {
+	check_table_spans::<pe::ImageNtHeaders64>(&common::sample(true));
+	check_table_spans::<pe::ImageNtHeaders32>(&common::sample(false));
+}
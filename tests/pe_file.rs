@@ -0,0 +1,104 @@
+#![cfg(feature = "mmap")]
+
+mod common;
+
+use object::pe;
+use objparse::{
+	error::{Error, FileError},
+	file::PeFile,
+	import_map::{ImportEntryName, ImportMap},
+	nt::NtHeaders,
+	ParseOptions,
+};
+use std::{fs, io, path::PathBuf};
+
+fn write_temp(name: &str, data: &[u8]) -> PathBuf {
+	let path = std::env::temp_dir().join(format!("objparse-{}-{name}", std::process::id()));
+	fs::write(&path, data).unwrap();
+	path
+}
+
+fn import_names(import_map: &ImportMap, dll: &str) -> Vec<ImportEntryName> {
+	import_map.dlls[dll]
+		.iter()
+		.filter_map(|entry| entry.name.clone())
+		.collect()
+}
+
+#[test]
+fn imports_of_a_mapped_file() {
+	let path = write_temp("sample.dll", &common::sample(common::NATIVE_IS_64).file());
+	let file = PeFile::open(&path).unwrap();
+	assert_eq!(file.section_headers().len(), 5);
+	let import_map = file.import_map().unwrap();
+	assert_eq!(
+		import_names(&import_map, "KERNEL32.dll"),
+		[
+			ImportEntryName::Name {
+				hint: 0x2b5,
+				name: "GetProcAddress".into()
+			},
+			ImportEntryName::Name {
+				hint: 0x3c2,
+				name: "LoadLibraryA".into()
+			},
+		]
+	);
+	assert_eq!(
+		import_names(&import_map, "USER32.dll"),
+		[ImportEntryName::Ordinal(7)]
+	);
+	drop(file);
+	fs::remove_file(path).unwrap();
+}
+
+/// Opens the sample of `Nt`'s bitness, and fails on the other one.
+fn check_open_nt<Nt: NtHeaders>(is_64: bool) {
+	let path = write_temp(
+		&format!("sample{}.dll", if is_64 { 64 } else { 32 }),
+		&common::sample(is_64).file(),
+	);
+	let file = PeFile::<Nt>::open_nt(&path, ParseOptions::new()).unwrap();
+	assert_eq!(file.nt_header().is_type_64(), is_64);
+	assert_eq!(file.section_headers().len(), 5);
+	let import_map = file.import_map().unwrap();
+	assert_eq!(
+		import_names(&import_map, "USER32.dll"),
+		[ImportEntryName::Ordinal(7)]
+	);
+	drop(file);
+
+	let other = common::sample(!is_64).file();
+	fs::write(&path, other).unwrap();
+	match PeFile::<Nt>::open_nt(&path, ParseOptions::new()) {
+		Err(FileError::Parse(Error::ArchMismatch { .. })) => {}
+		Err(err) => panic!("{err}"),
+		Ok(_) => panic!("opened the other bitness"),
+	}
+	fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn files_of_either_bitness() {
+	check_open_nt::<pe::ImageNtHeaders64>(true);
+	check_open_nt::<pe::ImageNtHeaders32>(false);
+}
+
+#[test]
+fn truncated_file_stays_inside_the_mapping() {
+	let file = common::sample(common::NATIVE_IS_64).file();
+	let path = write_temp("truncated.dll", &file[..0x610]);
+	let file = PeFile::open(&path).unwrap();
+	assert!(file.import_map().is_err());
+	drop(file);
+	fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_file_keeps_the_io_error() {
+	match PeFile::open(std::env::temp_dir().join("objparse-does-not-exist.dll")) {
+		Err(FileError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+		Err(err) => panic!("{err}"),
+		Ok(_) => panic!("opened a missing file"),
+	}
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::{NT_HEADERS_OFFSET, SIZE_OF_HEADERS};
+use object::{pe, LittleEndian};
+use objparse::{
+	error::{Error, FileError},
+	import_map::ImportEntryName,
+	nt::NtHeaders,
+	reader::PeReader,
+	ParseOptions,
+};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+/// `SizeOfImage`, at the same offset in both optional headers.
+const SIZE_OF_IMAGE_OFFSET: usize = NT_HEADERS_OFFSET as usize + 24 + 56;
+
+fn check_imports<Nt: NtHeaders>(is_64: bool) {
+	let file = common::sample(is_64).file();
+	let mut reader = PeReader::<_, Nt>::open_nt(Cursor::new(file), ParseOptions::new()).unwrap();
+	assert_eq!(reader.image().len(), SIZE_OF_HEADERS as usize);
+	let import_map = reader.import_map().unwrap();
+	let names: Vec<_> = import_map
+		.iter()
+		.map(|(dll, entry)| (dll, entry.name.clone().unwrap()))
+		.collect();
+	assert_eq!(
+		names,
+		[
+			(
+				"KERNEL32.dll",
+				ImportEntryName::Name {
+					hint: 0x2b5,
+					name: "GetProcAddress".into()
+				}
+			),
+			(
+				"KERNEL32.dll",
+				ImportEntryName::Name {
+					hint: 0x3c2,
+					name: "LoadLibraryA".into()
+				}
+			),
+			("USER32.dll", ImportEntryName::Ordinal(7)),
+		]
+	);
+	// Only `.rdata` was needed, later sections are not in the buffer.
+	let rdata = reader.section_headers()[1];
+	let rdata_end = rdata.virtual_address.get(LittleEndian) + rdata.virtual_size.get(LittleEndian);
+	assert_eq!(reader.image().len(), rdata_end as usize);
+}
+
+#[test]
+fn imports_of_both_widths() {
+	check_imports::<pe::ImageNtHeaders64>(true);
+	check_imports::<pe::ImageNtHeaders32>(false);
+}
+
+#[test]
+fn huge_size_of_image_is_not_allocated() {
+	let mut file = common::sample(common::NATIVE_IS_64).file();
+	file[SIZE_OF_IMAGE_OFFSET..SIZE_OF_IMAGE_OFFSET + 4]
+		.copy_from_slice(&0xffff_f000u32.to_le_bytes());
+	let reader = PeReader::open(Cursor::new(file)).unwrap();
+	assert_eq!(reader.image().len(), SIZE_OF_HEADERS as usize);
+}
+
+#[test]
+fn buffer_stops_at_the_limit() {
+	let file = common::sample(common::NATIVE_IS_64).file();
+	let mut reader = PeReader::<_, pe::ImageNtHeaders64>::open_nt_with_limit(
+		Cursor::new(file),
+		ParseOptions::new(),
+		0x2000,
+	)
+	.unwrap();
+	let text = reader.section_headers()[0];
+	assert_eq!(reader.section_data(&text).unwrap(), [0xc3; 0x50]);
+	match reader.load_exports() {
+		Err(FileError::Parse(Error::LimitExceeded { limit: 0x2000 })) => {}
+		Err(err) => panic!("{err}"),
+		Ok(()) => panic!("read past the limit"),
+	}
+}
+
+/// Fails every read after the first `good` bytes.
+struct Failing {
+	inner: Cursor<Vec<u8>>,
+	good: u64,
+}
+
+impl Read for Failing {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		if self.inner.position() >= self.good {
+			return Err(io::Error::new(io::ErrorKind::TimedOut, "disk gone"));
+		}
+		self.inner.read(buf)
+	}
+}
+
+impl Seek for Failing {
+	fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+		self.inner.seek(pos)
+	}
+}
+
+#[test]
+fn reader_keeps_the_io_error() {
+	let file = common::sample(common::NATIVE_IS_64).file();
+	let reader = Failing {
+		inner: Cursor::new(file),
+		good: 0,
+	};
+	match PeReader::open(reader) {
+		Err(FileError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::TimedOut),
+		Err(err) => panic!("{err}"),
+		Ok(_) => panic!("read past a failing reader"),
+	}
+}
@@ -0,0 +1,196 @@
+mod common;
+
+use common::{Blob, Layout, PeBuilder, ALPHA_RVA, DATA, DATA_RVA, LAYOUTS};
+use object::pe;
+use objparse::{
+	error::Error,
+	nt::NtHeaders,
+	reloc::{RelocType, RelocationTable},
+	PeHeaders,
+};
+
+const DELTA: u64 = 0x1_0000;
+
+/// Parses a copy of `pe`, returning the headers and the base to write through.
+fn parse_mut<Nt: NtHeaders>(pe: &PeBuilder, layout: Layout) -> (PeHeaders<Nt>, *mut u8) {
+	let data = pe.leak(layout);
+	let base = data.as_mut_ptr();
+	let data = unsafe { core::slice::from_raw_parts(base, data.len()) };
+	(common::parse(data, layout), base)
+}
+
+unsafe fn read_pointer<Nt: NtHeaders>(headers: &PeHeaders<Nt>, base: *mut u8, rva: u32) -> u64 {
+	let ptr = headers.rva_to_ptr(base, rva).unwrap();
+	match headers.nt_header.is_type_64() {
+		true => unsafe { ptr.cast::<u64>().read_unaligned() },
+		false => unsafe { ptr.cast::<u32>().read_unaligned() as u64 },
+	}
+}
+
+fn check_rebase<Nt: NtHeaders>(is_64: bool) {
+	let pe = common::sample(is_64);
+	for layout in LAYOUTS {
+		let (headers, base) = parse_mut::<Nt>(&pe, layout);
+		let old = unsafe { headers.set_image_base(base, pe.image_base + DELTA, true) }.unwrap();
+		assert_eq!(old, pe.image_base);
+		assert_eq!(
+			unsafe { read_pointer(&headers, base, DATA_RVA) },
+			pe.image_base + DELTA + ALPHA_RVA as u64
+		);
+		let reparsed = common::parse::<Nt>(
+			unsafe { core::slice::from_raw_parts(base, pe.build(layout).len()) },
+			layout,
+		);
+		assert_eq!(
+			object::read::pe::ImageOptionalHeader::image_base(reparsed.nt_header.optional_header()),
+			pe.image_base + DELTA
+		);
+	}
+}
+
+#[test]
+fn rebase_in_both_layouts() {
+	check_rebase::<pe::ImageNtHeaders64>(true);
+	check_rebase::<pe::ImageNtHeaders32>(false);
+}
+
+#[test]
+fn rebase_without_relocations() {
+	let mut pe = common::sample(true);
+	pe.directory(common::IMAGE_DIRECTORY_ENTRY_BASERELOC, (0, 0));
+	for layout in LAYOUTS {
+		let (headers, base) = parse_mut::<pe::ImageNtHeaders64>(&pe, layout);
+		assert_eq!(
+			unsafe { headers.set_image_base(base, pe.image_base + DELTA, true) },
+			Err(Error::RelocationTable)
+		);
+		assert_eq!(
+			unsafe { read_pointer(&headers, base, DATA_RVA) },
+			pe.image_base + ALPHA_RVA as u64
+		);
+		// Nothing to relocate for the same base, or when the caller opts out.
+		assert!(unsafe { headers.set_image_base(base, pe.image_base, true) }.is_ok());
+		assert!(unsafe { headers.set_image_base(base, pe.image_base + DELTA, false) }.is_ok());
+	}
+}
+
+#[test]
+fn relocation_past_the_raw_data() {
+	let mut pe = PeBuilder::new64();
+	let mut data = pe.blob();
+	data.bytes(&[0xaa; 0x200]);
+	let data_rva = pe.section_with_size(".data", DATA, data, 0x1000);
+	let mut reloc = pe.blob();
+	// A `Dir64` value in the last 4 bytes of the raw data, the rest is zero-fill.
+	let reloc_directory = common::relocs(&mut reloc, &[(data_rva, &[0xa << 12 | 0x1fc])]);
+	pe.section(".reloc", common::RDATA, reloc);
+	pe.directory(common::IMAGE_DIRECTORY_ENTRY_BASERELOC, reloc_directory);
+
+	let (headers, base) = parse_mut::<pe::ImageNtHeaders64>(&pe, Layout::File);
+	let before = pe.file();
+	assert!(unsafe { headers.apply_relocations(base, DELTA) }.is_err());
+	let after = unsafe { core::slice::from_raw_parts(base, before.len()) };
+	assert_eq!(after, before);
+
+	let (headers, base) = parse_mut::<pe::ImageNtHeaders64>(&pe, Layout::Mapped);
+	assert_eq!(unsafe { headers.apply_relocations(base, DELTA) }, Ok(1));
+}
+
+const DIR64: u16 = 0xa << 12;
+const HIGHLOW: u16 = 0x3 << 12;
+
+/// A relocation directory of `blocks` followed by `trailer`, outside any image.
+fn relocation_table(blocks: &[(u32, &[u16])], trailer: &[u8], machine: u16) -> RelocationTable {
+	let mut blob = Blob {
+		rva: 0,
+		file_offset: 0,
+		data: Vec::new(),
+	};
+	common::relocs(&mut blob, blocks);
+	blob.bytes(trailer);
+	let data = common::leak(&blob.data);
+	unsafe { RelocationTable::parse(data.as_ptr(), data.len(), machine) }
+}
+
+#[test]
+fn relocation_blocks() {
+	let mut pe = PeBuilder::new64();
+	let mut data = pe.blob();
+	data.zeroes(0x20);
+	pe.section(".data", DATA, data);
+	let mut reloc = pe.blob();
+	let reloc_directory = common::relocs(
+		&mut reloc,
+		&[
+			(0x1000, &[DIR64 | 0x10, DIR64 | 0x18, HIGHLOW | 0x4]),
+			(0x2000, &[DIR64 | 0x8]),
+		],
+	);
+	pe.section(".reloc", common::RDATA, reloc);
+	pe.directory(common::IMAGE_DIRECTORY_ENTRY_BASERELOC, reloc_directory);
+	for layout in LAYOUTS {
+		let (headers, base) = parse_mut::<pe::ImageNtHeaders64>(&pe, layout);
+		let table = unsafe { headers.relocation_table_mem(base) }.unwrap();
+		let blocks: Vec<_> = table.blocks().map(Result::unwrap).collect();
+		let shapes: Vec<_> = blocks
+			.iter()
+			.map(|block| (block.page_rva, block.size_of_block(), block.len()))
+			.collect();
+		// Padding to a 4-byte boundary is part of the block.
+		assert_eq!(shapes, [(0x1000, 16, 4), (0x2000, 12, 2)]);
+		assert_eq!(
+			blocks[0].entries().collect::<Vec<_>>(),
+			[
+				(RelocType::Dir64, 0x1010),
+				(RelocType::Dir64, 0x1018),
+				(RelocType::HighLow, 0x1004)
+			]
+		);
+		assert_eq!(
+			blocks[1].entries().collect::<Vec<_>>(),
+			[(RelocType::Dir64, 0x2008)]
+		);
+		assert_eq!(table.iter().count(), 4);
+	}
+
+	// The parameter of `HighAdj` is not an entry of its own.
+	let table = relocation_table(
+		&[(0x1000, &[0x4 << 12 | 0x10, 0x1234, HIGHLOW | 0x20])],
+		&[],
+		pe::IMAGE_FILE_MACHINE_I386,
+	);
+	let block = table.blocks().next().unwrap().unwrap();
+	assert_eq!(block.len(), 4);
+	assert_eq!(
+		block.entries().collect::<Vec<_>>(),
+		[(RelocType::HighAdj, 0x1010), (RelocType::HighLow, 0x1020)]
+	);
+}
+
+#[test]
+fn malformed_relocation_blocks() {
+	let block: &[(u32, &[u16])] = &[(0x1000, &[DIR64 | 0x8])];
+	let machine = pe::IMAGE_FILE_MACHINE_AMD64;
+	// A block smaller than its header, or running past the directory, ends the iteration.
+	for size_of_block in [4u32, 0x100] {
+		let mut trailer = 0x2000u32.to_le_bytes().to_vec();
+		trailer.extend_from_slice(&size_of_block.to_le_bytes());
+		let table = relocation_table(block, &trailer, machine);
+		let blocks: Vec<_> = table
+			.blocks()
+			.map(|block| block.map(|block| block.page_rva))
+			.collect();
+		assert_eq!(blocks, [Ok(0x1000), Err(Error::RelocationTable)]);
+		let relocations: Vec<_> = table.iter().collect();
+		assert_eq!(
+			relocations,
+			[Ok((RelocType::Dir64, 0x1008)), Err(Error::RelocationTable)]
+		);
+	}
+	// Zero padding and a partial header after the last block are not blocks.
+	for trailer in [&[0; 8][..], &[0xff; 6]] {
+		let table = relocation_table(block, trailer, machine);
+		assert_eq!(table.blocks().count(), 1);
+		assert!(table.iter().all(|relocation| relocation.is_ok()));
+	}
+}
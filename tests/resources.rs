@@ -0,0 +1,184 @@
+mod common;
+
+use common::{
+	Layout, ResourceKey, CONFIG_DATA, IMAGE_DIRECTORY_ENTRY_RESOURCE, LAYOUTS, RDATA, VERSION_DATA,
+};
+use object::pe;
+use objparse::{
+	error::Error,
+	options::DEFAULT_MAX_RESOURCE_DEPTH,
+	resource::{ResourceEntryData, ResourceId},
+	widestring,
+};
+
+const GRUSS_DATA: &[u8] = b"named outside ASCII";
+
+/// The sample with a resource named outside ASCII, stored upper-cased as `rc` does.
+fn sample() -> common::PeBuilder {
+	let mut pe = common::sample(true);
+	let mut rsrc = pe.blob();
+	let resource_directory = common::resources(
+		&mut rsrc,
+		&[
+			(
+				ResourceKey::Name("MYTYPE"),
+				ResourceKey::Name("CONFIG"),
+				0,
+				CONFIG_DATA,
+			),
+			(
+				ResourceKey::Name("MYTYPE"),
+				ResourceKey::Name("ÄRGER"),
+				0,
+				GRUSS_DATA,
+			),
+			(ResourceKey::Id(16), ResourceKey::Id(1), 0x407, b"de"),
+			(ResourceKey::Id(16), ResourceKey::Id(1), 0x409, VERSION_DATA),
+		],
+	);
+	pe.section(".rsrc2", RDATA, rsrc);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_RESOURCE, resource_directory);
+	pe
+}
+
+#[test]
+fn lookup_in_both_layouts() {
+	let pe = sample();
+	for layout in LAYOUTS {
+		let headers = common::parse::<pe::ImageNtHeaders64>(pe.leak(layout), layout);
+		let version = |lang| headers.resource(ResourceId::Id(16), ResourceId::Id(1), lang);
+		assert_eq!(version(Some(0x409)), Some(VERSION_DATA));
+		assert_eq!(version(Some(0x407)), Some(&b"de"[..]));
+		// The first language without one.
+		assert_eq!(version(None), Some(&b"de"[..]));
+		assert_eq!(version(Some(0x40c)), None);
+		assert_eq!(
+			headers.resource(ResourceId::Id(16), ResourceId::Id(2), None),
+			None
+		);
+		assert_eq!(
+			headers.resource(ResourceId::Id(3), ResourceId::Id(1), None),
+			None
+		);
+	}
+}
+
+#[test]
+fn names_ignore_case() {
+	let pe = sample();
+	for layout in LAYOUTS {
+		let headers = common::parse::<pe::ImageNtHeaders64>(pe.leak(layout), layout);
+		for (ty, name) in [
+			("MYTYPE", "CONFIG"),
+			("mytype", "config"),
+			("MyType", "Config"),
+		] {
+			assert_eq!(
+				headers.resource(ResourceId::Name(ty), ResourceId::Name(name), None),
+				Some(CONFIG_DATA)
+			);
+		}
+		assert_eq!(
+			headers.resource(ResourceId::Name("mytype"), ResourceId::Name("ärger"), None),
+			Some(GRUSS_DATA)
+		);
+		assert_eq!(
+			headers.resource(
+				ResourceId::Name("mytype"),
+				ResourceId::Name("configs"),
+				None
+			),
+			None
+		);
+	}
+}
+
+/// The sample with a resource table of `entries` (name or ID, offset to data or directory)
+/// under a root directory, followed by `trailer` and `size` bytes long.
+fn malformed(entries: &[(u32, u32)], trailer: &[u8], size: u32) -> common::PeBuilder {
+	let mut pe = common::sample(true);
+	let mut rsrc = pe.blob();
+	let root = rsrc.here();
+	let named = entries
+		.iter()
+		.filter(|entry| entry.0 & 0x8000_0000 != 0)
+		.count();
+	rsrc.zeroes(12)
+		.u16(named as u16)
+		.u16((entries.len() - named) as u16);
+	for &(name_or_id, offset) in entries {
+		rsrc.u32(name_or_id).u32(offset);
+	}
+	rsrc.bytes(trailer);
+	pe.section(".rsrc2", RDATA, rsrc);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_RESOURCE, (root, size));
+	pe
+}
+
+#[test]
+fn offsets_outside_the_table_are_rejected() {
+	let directory = 0x8000_0000;
+	let name = 0x8000_0000;
+	let cases = [
+		// A subdirectory, a data entry and a name past the end of the table.
+		malformed(&[(16, directory | 0x1000)], &[], 0x18),
+		malformed(&[(16, 0x14)], &[], 0x18),
+		malformed(&[(name | 0x1000, directory | 0x1000)], &[], 0x18),
+		// A name whose length runs past the end.
+		malformed(
+			&[(name | 0x18, directory | 0x1000)],
+			&[0x40, 0, b'A', 0],
+			0x1c,
+		),
+		// Entries past the end, with the table ending in the root directory.
+		malformed(&[(16, directory)], &[], 0x14),
+		malformed(&[(16, directory)], &[], 0x0c),
+	];
+	for pe in cases {
+		for layout in LAYOUTS {
+			let headers = common::parse::<pe::ImageNtHeaders64>(pe.leak(layout), layout);
+			assert_eq!(
+				headers.resource(ResourceId::Id(16), ResourceId::Id(1), None),
+				None
+			);
+			assert_eq!(
+				headers.resource(ResourceId::Name("A"), ResourceId::Id(1), None),
+				None
+			);
+			let resource_table = headers.resource_table().unwrap();
+			let walk =
+				unsafe { resource_table.walk(DEFAULT_MAX_RESOURCE_DEPTH, &mut |_, _, _| ()) };
+			assert_eq!(walk, Err(Error::ResourceTable));
+		}
+	}
+}
+
+#[test]
+fn entries_inside_the_table_are_read() {
+	// A name and a data entry right after the root directory, ending the table.
+	let mut trailer = vec![1, 0, b'A', 0];
+	trailer.extend_from_slice(&[0; 16]);
+	let pe = malformed(&[(0x8000_0018, 0x1c)], &trailer, 0x2c);
+	let headers = common::parse::<pe::ImageNtHeaders64>(pe.leak(Layout::Mapped), Layout::Mapped);
+	let resource_table = headers.resource_table().unwrap();
+	let root = unsafe { resource_table.root() }.unwrap();
+	let entry = unsafe { root.find(ResourceId::Name("A")) }.unwrap();
+	assert!(matches!(
+		unsafe { root.entry_data(entry) },
+		Ok(ResourceEntryData::Data(_))
+	));
+}
+
+#[test]
+fn upcase_like_the_loader() {
+	assert_eq!(widestring::upcase('a' as u16), 'A' as u16);
+	assert_eq!(widestring::upcase('ä' as u16), 'Ä' as u16);
+	assert_eq!(widestring::upcase('я' as u16), 'Я' as u16);
+	// `ß` upper-cases to two characters and is left alone, as are surrogates.
+	assert_eq!(widestring::upcase('ß' as u16), 'ß' as u16);
+	assert_eq!(widestring::upcase(0xd800), 0xd800);
+	assert!(widestring::eq_str_ignore_case(
+		"ÄRGER".encode_utf16(),
+		"ärger"
+	));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use common::{Layout, PeBuilder, CODE, DATA, SIZE_OF_HEADERS};
+use objparse::{error::Error, PeHeaders64};
+
+/// A code section with 0x10 bytes of raw data and a data section zero-filled past 0x20 bytes.
+fn image() -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	let mut text = pe.blob();
+	text.bytes(&[0xcc; 0x10]);
+	pe.section(".text", CODE, text);
+	let mut data = pe.blob();
+	data.bytes(&[0xaa; 0x20]);
+	pe.section_with_size(".data", DATA, data, 0x3000);
+	pe
+}
+
+fn section_header_offset(index: usize) -> usize {
+	0x80 + 4 + 20 + 0xf0 + index * 40
+}
+
+#[test]
+fn file_rva_goes_through_section_headers() {
+	let data = image().leak(Layout::File);
+	let headers = PeHeaders64::parse_file_nt(data, Layout::File.options()).unwrap();
+	let base = data.as_ptr();
+	let ptr = headers.rva_to_ptr(base, 0x1008).unwrap();
+	assert_eq!(ptr as usize - base as usize, SIZE_OF_HEADERS as usize + 8);
+	let ptr = headers.rva_to_ptr(base, 0x201f).unwrap();
+	assert_eq!(ptr as usize - base as usize, 0x600 + 0x1f);
+	let ptr = headers.rva_to_ptr(base, 0x80).unwrap();
+	assert_eq!(ptr as usize - base as usize, 0x80);
+}
+
+#[test]
+fn file_rva_in_zero_fill_has_no_bytes() {
+	let data = image().leak(Layout::File);
+	let headers = PeHeaders64::parse_file_nt(data, Layout::File.options()).unwrap();
+	let base = data.as_ptr();
+	// The raw size is rounded up to the file alignment, but only the virtual size is mapped.
+	assert_eq!(
+		headers.rva_to_ptr(base, 0x1010),
+		Err(Error::RvaOutsideSections { rva: 0x1010 })
+	);
+	assert_eq!(
+		headers.rva_to_ptr(base, 0x2200),
+		Err(Error::RvaOutsideSections { rva: 0x2200 })
+	);
+	assert_eq!(
+		headers.rva_to_ptr(base, 0x4fff),
+		Err(Error::RvaOutsideSections { rva: 0x4fff })
+	);
+	assert_eq!(
+		headers.rva_to_ptr(base, 0x8000),
+		Err(Error::RvaOutsideSections { rva: 0x8000 })
+	);
+}
+
+#[test]
+fn file_rva_with_overflowing_raw_pointer() {
+	let data = image().leak(Layout::File);
+	let offset = section_header_offset(1) + 20;
+	data[offset..offset + 4].copy_from_slice(&0xffff_fff0u32.to_le_bytes());
+	let headers = PeHeaders64::parse_file_nt(data, Layout::File.options()).unwrap();
+	assert_eq!(
+		headers.rva_to_ptr(data.as_ptr(), 0x2018),
+		Err(Error::RvaOverflow)
+	);
+}
+
+#[test]
+fn mapped_rva_is_an_offset() {
+	let data = image().leak(Layout::Mapped);
+	let options = Layout::Mapped.options();
+	let headers =
+		unsafe { PeHeaders64::parse_nt_with_size(data.as_ptr(), data.len(), options) }.unwrap();
+	let base = data.as_ptr();
+	let ptr = headers.rva_to_ptr(base, 0x2200).unwrap();
+	assert_eq!(ptr as usize - base as usize, 0x2200);
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use common::{Layout, PeBuilder, CODE, DATA};
+use object::pe;
+use objparse::{
+	offsets::{HeaderField, SectionField},
+	section::SectionSizeAnomaly,
+	ParseOptions, PeHeaders,
+};
+
+/// `.text` of 0x50 bytes, `.data` of 0x300 and `.bss` of 0x100 bytes without raw data.
+fn sections() -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	let mut text = pe.blob();
+	text.bytes(&[0xc3; 0x50]);
+	pe.section(".text", CODE, text);
+	let mut data = pe.blob();
+	data.bytes(&[0xaa; 0x300]);
+	pe.section(".data", DATA, data);
+	let bss = pe.blob();
+	pe.section_with_size(".bss", DATA, bss, 0x100);
+	pe
+}
+
+/// `pe` in file layout with section header fields set, parsed leniently.
+fn patched(
+	pe: &PeBuilder,
+	fields: &[(usize, SectionField, u32)],
+) -> PeHeaders<pe::ImageNtHeaders64> {
+	let mut file = pe.file();
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(common::leak(&file), Layout::File);
+	for &(index, field, value) in fields {
+		let span = headers
+			.field_span(HeaderField::Section(index, field))
+			.unwrap();
+		file[span.offset..span.end()].copy_from_slice(&value.to_le_bytes());
+	}
+	PeHeaders::parse_file_nt(common::leak(&file), ParseOptions::lenient()).unwrap()
+}
+
+/// File offset, file slack, RVA and memory slack of each section.
+fn slack_spaces(headers: &PeHeaders<pe::ImageNtHeaders64>) -> Vec<(u32, u32, u32, u32)> {
+	headers
+		.section_slack_spaces()
+		.map(|slack| {
+			(
+				slack.file_offset,
+				slack.file_slack,
+				slack.rva,
+				slack.memory_slack,
+			)
+		})
+		.collect()
+}
+
+#[test]
+fn slack_spaces_on_disk_and_in_memory() {
+	let headers = patched(&sections(), &[]);
+	assert_eq!(
+		slack_spaces(&headers),
+		[
+			(0x450, 0x1b0, 0x1050, 0xfb0),
+			(0x900, 0x100, 0x2300, 0xd00),
+			// No raw data to have slack after.
+			(0xa00, 0, 0x3100, 0xf00),
+		]
+	);
+}
+
+#[test]
+fn slack_spaces_of_overlapping_sections() {
+	use SectionField::*;
+	let pe = sections();
+	// Raw data running into the next section's, and a virtual size past the end of the
+	// address space.
+	let headers = patched(
+		&pe,
+		&[
+			(0, SizeOfRawData, 0x1000),
+			(0, VirtualSize, 0),
+			(2, VirtualSize, u32::MAX),
+		],
+	);
+	assert_eq!(
+		slack_spaces(&headers),
+		[
+			(0x1400, 0, 0x2000, 0),
+			(0x900, 0x100, 0x2300, 0xd00),
+			(0xa00, 0, u32::MAX, 0),
+		]
+	);
+	// A section without a raw data pointer has no slack on disk.
+	let headers = patched(&pe, &[(1, PointerToRawData, 0)]);
+	assert_eq!(slack_spaces(&headers)[1], (0x300, 0, 0x2300, 0xd00));
+}
+
+/// `.text`, a packed section mostly zero-filled, one with raw data far past its virtual size
+/// and `.bss`.
+fn mismatched_sizes() -> PeBuilder {
+	let mut pe = PeBuilder::new64();
+	let mut text = pe.blob();
+	text.bytes(&[0xc3; 0x50]);
+	pe.section(".text", CODE, text);
+	let mut packed = pe.blob();
+	packed.bytes(&[0xaa; 0x200]);
+	pe.section_with_size("UPX0", DATA, packed, 0x2000);
+	let mut slack = pe.blob();
+	slack.bytes(&[0xbb; 0x400]);
+	pe.section_with_size(".slack", DATA, slack, 0x10);
+	let bss = pe.blob();
+	pe.section_with_size(".bss", DATA, bss, 0x100);
+	pe
+}
+
+fn anomalies(headers: &PeHeaders<pe::ImageNtHeaders64>) -> Vec<(usize, SectionSizeAnomaly)> {
+	headers
+		.section_size_anomalies()
+		.map(|(section, anomaly)| {
+			let index = headers
+				.section_headers
+				.iter()
+				.position(|other| core::ptr::eq(other, section))
+				.unwrap();
+			(index, anomaly)
+		})
+		.collect()
+}
+
+#[test]
+fn size_anomalies_and_section_data() {
+	let pe = mismatched_sizes();
+	assert_eq!(
+		anomalies(&patched(&pe, &[])),
+		[
+			(1, SectionSizeAnomaly::ZeroFilled { zero_fill: 0x1e00 }),
+			(2, SectionSizeAnomaly::Slack { slack: 0x3f0 }),
+		]
+	);
+	// Raw data within the file alignment of the virtual size is not slack.
+	let headers = patched(&pe, &[(2, SectionField::VirtualSize, 0x201)]);
+	assert_eq!(anomalies(&headers).len(), 1);
+
+	// Only the virtual size is mapped, and only the raw data within it read from the file.
+	for (layout, sizes) in [
+		(Layout::File, [0x50, 0x200, 0x10, 0]),
+		(Layout::Mapped, [0x50, 0x2000, 0x10, 0x100]),
+	] {
+		let data = pe.leak(layout);
+		let headers = common::parse::<pe::ImageNtHeaders64>(data, layout);
+		let lens: Vec<_> = headers
+			.section_headers
+			.iter()
+			.map(|section| {
+				unsafe { headers.section_data(data.as_ptr(), section) }
+					.unwrap()
+					.len()
+			})
+			.collect();
+		assert_eq!(lens, sizes, "{layout:?}");
+	}
+}
+
+#[test]
+fn size_anomalies_of_extreme_sizes() {
+	use SectionField::*;
+	let pe = mismatched_sizes();
+	for (fields, expected) in [
+		// A virtual size that overflows when aligned.
+		(
+			&[(2, VirtualSize, u32::MAX), (2, SizeOfRawData, 0x8000_0000)][..],
+			None,
+		),
+		// A zero raw size is `.bss`, however large the virtual size.
+		(&[(1, SizeOfRawData, 0)], None),
+		(
+			&[(1, SizeOfRawData, 1), (1, VirtualSize, u32::MAX)],
+			Some(SectionSizeAnomaly::ZeroFilled {
+				zero_fill: u32::MAX - 1,
+			}),
+		),
+	] {
+		let headers = patched(&pe, fields);
+		let index = fields[0].0;
+		let anomaly = anomalies(&headers)
+			.into_iter()
+			.find(|&(other, _)| other == index)
+			.map(|(_, anomaly)| anomaly);
+		assert_eq!(anomaly, expected, "{fields:?}");
+	}
+}
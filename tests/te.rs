@@ -0,0 +1,110 @@
+mod common;
+
+use common::Blob;
+use object::LittleEndian;
+use objparse::{
+	error::Error,
+	te::{TeImage, TE_DIRECTORY_ENTRY_BASERELOC, TE_DIRECTORY_ENTRY_DEBUG, TE_SIGNATURE},
+	ParseOptions,
+};
+
+const IMAGE_BASE: u64 = 0x1_0000;
+/// Offset of the section table in the original PE, which moves to just after the TE header.
+const STRIPPED_SIZE: u16 = 0x1a8;
+const ADJUSTMENT: u32 = STRIPPED_SIZE as u32 - 40;
+const TEXT_RVA: u32 = 0x200;
+const CODE: &[u8] = &[0xc3; 0x10];
+
+/// A TE image of one section whose file offset equals its RVA, as EFI images are laid out.
+fn te() -> Vec<u8> {
+	let mut te = Blob {
+		rva: 0,
+		file_offset: 0,
+		data: Vec::new(),
+	};
+	te.u16(TE_SIGNATURE)
+		.u16(0x8664)
+		.u8(1)
+		.u8(10)
+		.u16(STRIPPED_SIZE);
+	te.u32(TEXT_RVA).u32(TEXT_RVA).u64(IMAGE_BASE);
+	te.u32(0).u32(0).u32(TEXT_RVA + 8).u32(0x1c);
+	te.bytes(b".text\0\0\0")
+		.u32(CODE.len() as u32)
+		.u32(TEXT_RVA)
+		.u32(CODE.len() as u32)
+		.u32(TEXT_RVA)
+		.zeroes(12)
+		.u32(0x6000_0020);
+	te.zeroes((TEXT_RVA - ADJUSTMENT) as usize - te.data.len());
+	te.bytes(CODE);
+	te.data
+}
+
+unsafe fn parse(data: &[u8], options: ParseOptions) -> Result<TeImage, Error> {
+	let data = common::leak(data);
+	unsafe { TeImage::parse(data.as_ptr(), &options.region(data.as_ptr(), data.len())) }
+}
+
+#[test]
+fn te_image() {
+	let data = common::leak(&te());
+	let base = data.as_ptr();
+	let image = unsafe { TeImage::parse(base, &ParseOptions::new()) }.unwrap();
+	assert_eq!(image.header.machine.get(LittleEndian), 0x8664);
+	assert_eq!(image.header.subsystem, 10);
+	assert_eq!(image.rva_adjustment(), ADJUSTMENT);
+	assert_eq!(image.adjusted_image_base(), IMAGE_BASE + ADJUSTMENT as u64);
+
+	let text = image.section_for_rva(TEXT_RVA + 8).unwrap();
+	assert_eq!(&text.name, b".text\0\0\0");
+	assert!(image.section_for_rva(TEXT_RVA + 0x10).is_none());
+	assert_eq!(unsafe { image.section_data(text) }, Ok(CODE));
+	assert_eq!(
+		image.entry_point(),
+		Ok(Some(base.wrapping_add((TEXT_RVA - ADJUSTMENT) as usize)))
+	);
+	let debug = image.data_directory(TE_DIRECTORY_ENTRY_DEBUG).unwrap();
+	assert_eq!(debug.virtual_address.get(LittleEndian), TEXT_RVA + 8);
+	assert!(image.data_directory(TE_DIRECTORY_ENTRY_BASERELOC).is_none());
+	assert!(image.data_directory(2).is_none());
+}
+
+#[test]
+fn malformed_te_images() {
+	let te = te();
+	let patched = |offset: usize, bytes: &[u8]| {
+		let mut data = te.clone();
+		data[offset..offset + bytes.len()].copy_from_slice(bytes);
+		data
+	};
+	for (data, options, error) in [
+		(patched(0, b"MZ"), ParseOptions::new(), Error::TeHeader),
+		// Fewer bytes stripped than the TE header has.
+		(
+			patched(6, &39u16.to_le_bytes()),
+			ParseOptions::new(),
+			Error::TeHeader,
+		),
+		(
+			te.clone(),
+			ParseOptions::new().max_sections(0),
+			Error::TeHeader,
+		),
+	] {
+		assert_eq!(unsafe { parse(&data, options) }.err(), Some(error));
+	}
+	// Section headers or the header itself past the end.
+	for data in [patched(4, &[0x20]), te[..39].to_vec()] {
+		assert!(matches!(
+			unsafe { parse(&data, ParseOptions::new()) },
+			Err(Error::OutOfRegion { .. })
+		));
+	}
+
+	// RVAs in the stripped headers.
+	let image = unsafe { parse(&patched(8, &0x100u32.to_le_bytes()), ParseOptions::new()) };
+	let image = image.unwrap();
+	assert_eq!(image.rva_to_ptr(ADJUSTMENT - 1), Err(Error::RvaOverflow));
+	assert_eq!(image.entry_point(), Err(Error::RvaOverflow));
+}
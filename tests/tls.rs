@@ -0,0 +1,220 @@
+mod common;
+
+use common::{Blob, Layout, PeBuilder, CODE, DATA, IMAGE_DIRECTORY_ENTRY_TLS, TLS_CALLBACK_RVA};
+use object::pe;
+use objparse::{
+	error::Error,
+	nt::{NtHeaders, TlsDirectory},
+	tls::TlsCallbackArray,
+	PeHeaders, TlsDir,
+};
+
+/// A mapped image with one callback, `spare` zero slots after the terminator and a non-zero slot
+/// after them, or zeroed data up to the end of the section without `spare`.
+fn image<Nt: NtHeaders>(spare: Option<usize>) -> (u64, &'static mut [u8]) {
+	let is_64 = size_of::<Nt>() == size_of::<pe::ImageNtHeaders64>();
+	let mut pe = match is_64 {
+		true => PeBuilder::new64(),
+		false => PeBuilder::new32(),
+	};
+	let callback = pe.image_base + TLS_CALLBACK_RVA as u64;
+	let mut text = pe.blob();
+	text.bytes(&[0xc3; 0x50]);
+	pe.section(".text", CODE, text);
+
+	let mut data = pe.blob();
+	let index = data.here();
+	data.u32(0).align(8);
+	let directory = data.here();
+	let array = directory + if is_64 { 40 } else { 24 };
+	data.ptr(is_64, pe.image_base + index as u64)
+		.ptr(is_64, pe.image_base + index as u64)
+		.ptr(is_64, pe.image_base + index as u64)
+		.ptr(is_64, pe.image_base + array as u64)
+		.u32(0)
+		.u32(0);
+	assert_eq!(data.here(), array);
+	data.ptr(is_64, callback).ptr(is_64, 0);
+	if let Some(spare) = spare {
+		for _ in 0..spare {
+			data.ptr(is_64, 0);
+		}
+		data.ptr(is_64, callback);
+	}
+	pe.section_with_size(".data", DATA, data, 0x1000);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_TLS, (directory, array - directory));
+	(pe.image_base, pe.leak(Layout::Mapped))
+}
+
+fn callback_array<Nt: NtHeaders>(
+	image_base: u64,
+	data: &'static mut [u8],
+) -> TlsCallbackArray<Nt::TlsDirectory> {
+	let base = data.as_mut_ptr();
+	let headers = common::parse::<Nt>(
+		unsafe { core::slice::from_raw_parts(base, data.len()) },
+		Layout::Mapped,
+	);
+	unsafe { headers.tls_callback_array(base, image_base) }
+		.unwrap()
+		.unwrap()
+}
+
+fn check_zeroed_data_is_not_capacity<Nt: NtHeaders>() {
+	let (image_base, data) = image::<Nt>(None);
+	let mut array = callback_array::<Nt>(image_base, data);
+	assert_eq!(array.len(), 1);
+	assert_eq!(array.capacity(), 1);
+	assert!(unsafe { array.push(image_base + 0x1000) }.is_err());
+	// The section only has room for what its virtual size covers.
+	assert!(array.reserve(0x1000).is_err());
+	array.reserve(1).unwrap();
+	unsafe { array.push(image_base + 0x1000) }.unwrap();
+	assert_eq!(
+		array.iter().collect::<Vec<_>>(),
+		[image_base + TLS_CALLBACK_RVA as u64, image_base + 0x1000]
+	);
+}
+
+#[test]
+fn zeroed_data_is_not_capacity() {
+	check_zeroed_data_is_not_capacity::<pe::ImageNtHeaders64>();
+	check_zeroed_data_is_not_capacity::<pe::ImageNtHeaders32>();
+}
+
+fn check_reserve_stops_at_data<Nt: NtHeaders>() {
+	let (image_base, data) = image::<Nt>(Some(2));
+	let mut array = callback_array::<Nt>(image_base, data);
+	assert!(array.reserve(3).is_err());
+	array.reserve(2).unwrap();
+	assert_eq!(array.capacity(), 3);
+	unsafe { array.push(image_base + 0x1000) }.unwrap();
+	unsafe { array.push(image_base + 0x1010) }.unwrap();
+	assert!(unsafe { array.push(image_base + 0x1020) }.is_err());
+
+	assert_eq!(
+		unsafe { array.remove(0) },
+		Ok(image_base + TLS_CALLBACK_RVA as u64)
+	);
+	unsafe { array.push(image_base + 0x1020) }.unwrap();
+	assert_eq!(
+		array.iter().collect::<Vec<_>>(),
+		[
+			image_base + 0x1000,
+			image_base + 0x1010,
+			image_base + 0x1020
+		]
+	);
+}
+
+#[test]
+fn reserve_stops_at_data() {
+	check_reserve_stops_at_data::<pe::ImageNtHeaders64>();
+	check_reserve_stops_at_data::<pe::ImageNtHeaders32>();
+}
+
+/// A mapped image with the TLS directory of [`common::tls`], whose 4-byte template is followed
+/// by `zero_fill` bytes. `patch` edits the directory, given its RVA and the pointer size.
+fn tls_image<Nt: NtHeaders>(
+	zero_fill: u32,
+	patch: impl FnOnce(&mut Blob, u32, u32),
+) -> (u64, PeHeaders<Nt>, *mut u8) {
+	let is_64 = size_of::<Nt>() == size_of::<pe::ImageNtHeaders64>();
+	let mut pe = match is_64 {
+		true => PeBuilder::new64(),
+		false => PeBuilder::new32(),
+	};
+	let mut data = pe.blob();
+	let directory = common::tls(&mut data, is_64, pe.image_base, &[]);
+	let pointer_size = if is_64 { 8 } else { 4 };
+	data.patch_u32(directory.0 + 4 * pointer_size, zero_fill);
+	patch(&mut data, directory.0, pointer_size);
+	pe.section(".data", DATA, data);
+	pe.directory(IMAGE_DIRECTORY_ENTRY_TLS, directory);
+	let data = pe.leak(Layout::Mapped);
+	let base = data.as_mut_ptr();
+	let headers = common::parse::<Nt>(
+		unsafe { core::slice::from_raw_parts(base, data.len()) },
+		Layout::Mapped,
+	);
+	(pe.image_base, headers, base)
+}
+
+fn tls_dir<Nt: NtHeaders>(headers: &PeHeaders<Nt>) -> &TlsDir<Nt::TlsDirectory> {
+	headers.tls_table().unwrap().unwrap()
+}
+
+fn check_copy_template<Nt: NtHeaders>() {
+	let (image_base, headers, base) = tls_image::<Nt>(8, |_, _, _| {});
+	let tls_dir = tls_dir(&headers);
+	assert_eq!(tls_dir.block_size(), Ok(12));
+	let mut block = [0xaa; 16];
+	assert_eq!(
+		unsafe { tls_dir.copy_template(base, image_base, block.as_mut_ptr()) },
+		Ok(12)
+	);
+	assert_eq!(block[..4], 0x1234_5678u32.to_le_bytes());
+	assert_eq!(block[4..12], [0; 8]);
+	assert_eq!(block[12..], [0xaa; 4]);
+}
+
+#[test]
+fn copy_template() {
+	check_copy_template::<pe::ImageNtHeaders64>();
+	check_copy_template::<pe::ImageNtHeaders32>();
+}
+
+fn check_template_ending_before_its_start<Nt: NtHeaders>() {
+	// `EndAddressOfRawData` one byte before `StartAddressOfRawData`.
+	let (image_base, headers, base) = tls_image::<Nt>(8, |data, directory, pointer_size| {
+		let start = common::read_u32(&data.data, (directory - data.rva) as usize);
+		data.patch_u32(directory + pointer_size, start - 1);
+	});
+	let tls_dir = tls_dir(&headers);
+	assert_eq!(tls_dir.block_size(), Err(Error::TlsTable));
+	let mut block = [0xaa; 16];
+	assert_eq!(
+		unsafe { tls_dir.copy_template(base, image_base, block.as_mut_ptr()) },
+		Err(Error::TlsTable)
+	);
+	assert_eq!(block, [0xaa; 16]);
+}
+
+#[test]
+fn template_ending_before_its_start() {
+	check_template_ending_before_its_start::<pe::ImageNtHeaders64>();
+	check_template_ending_before_its_start::<pe::ImageNtHeaders32>();
+}
+
+fn check_set_index<Nt: NtHeaders>() {
+	let (image_base, headers, base) = tls_image::<Nt>(0, |_, _, _| {});
+	let tls_dir = tls_dir(&headers);
+	unsafe { tls_dir.set_index(base, image_base, 7) }.unwrap();
+	let index = tls_dir.tls_dir.address_of_index() - image_base;
+	let slot = unsafe { base.add(index as usize).cast::<u32>().read_unaligned() };
+	assert_eq!(u32::from_le(slot), 7);
+}
+
+#[test]
+fn set_index() {
+	check_set_index::<pe::ImageNtHeaders64>();
+	check_set_index::<pe::ImageNtHeaders32>();
+}
+
+fn check_set_index_without_address_of_index<Nt: NtHeaders>() {
+	let (image_base, headers, base) = tls_image::<Nt>(0, |data, directory, pointer_size| {
+		let address_of_index = directory + 2 * pointer_size;
+		data.patch_u32(address_of_index, 0);
+		data.patch_u32(address_of_index + pointer_size - 4, 0);
+	});
+	assert_eq!(
+		unsafe { tls_dir(&headers).set_index(base, image_base, 7) },
+		Err(Error::TlsTable)
+	);
+}
+
+#[test]
+fn set_index_without_address_of_index() {
+	check_set_index_without_address_of_index::<pe::ImageNtHeaders64>();
+	check_set_index_without_address_of_index::<pe::ImageNtHeaders32>();
+}
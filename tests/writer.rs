@@ -0,0 +1,250 @@
+#![cfg(feature = "alloc")]
+
+mod common;
+
+use common::{Layout, IMAGE_DIRECTORY_ENTRY_IMPORT};
+use object::{pe, LittleEndian};
+use objparse::{
+	import_map::{ImportEntryName, ImportMap},
+	nt::NtHeaders,
+	writer::{PeWriter, IMPORT_SECTION_NAME},
+	PeHeaders,
+};
+
+fn imports<Nt: NtHeaders>(data: &[u8]) -> ImportMap {
+	let base = common::leak(data).as_mut_ptr();
+	let data = unsafe { core::slice::from_raw_parts(base, data.len()) };
+	let headers = common::parse::<Nt>(data, Layout::File);
+	unsafe { headers.import_map(base) }.unwrap()
+}
+
+/// Injects an import into the sample, after giving its terminator a name when `named_terminator`.
+fn check_inject_import<Nt: NtHeaders>(named_terminator: bool) {
+	let pe = common::sample(size_of::<Nt>() == size_of::<pe::ImageNtHeaders64>());
+	let mut file = pe.file();
+	if named_terminator {
+		let (rva, size) = pe.directories[IMAGE_DIRECTORY_ENTRY_IMPORT];
+		let data = common::leak(&file);
+		let headers: PeHeaders<Nt> = common::parse(data, Layout::File);
+		let terminator = rva + size - 20;
+		let offset = headers.rva_to_ptr(data.as_ptr(), terminator).unwrap() as usize
+			- data.as_ptr() as usize;
+		// Only `FirstThunk` stays zero, which still ends the table.
+		file[offset + 12..offset + 16].copy_from_slice(&rva.to_le_bytes());
+	}
+
+	let mut writer = PeWriter::<Nt>::new(file).unwrap();
+	writer.inject_import("injected.dll", "Injected").unwrap();
+	let data = writer.into_inner();
+
+	let headers: PeHeaders<Nt> = common::parse(common::leak(&data), Layout::File);
+	let last = headers.section_headers.last().unwrap();
+	assert_eq!(
+		objparse::section::section_name_bytes(last),
+		IMPORT_SECTION_NAME
+	);
+
+	let import_map = imports::<Nt>(&data);
+	let dlls: Vec<_> = import_map.dlls.keys().map(String::as_str).collect();
+	assert_eq!(dlls, ["KERNEL32.dll", "USER32.dll", "injected.dll"]);
+	let injected = import_map.get("injected.dll").unwrap();
+	assert_eq!(injected.len(), 1);
+	assert_eq!(
+		injected[0].name,
+		Some(ImportEntryName::Name {
+			hint: 0,
+			name: "Injected".into()
+		})
+	);
+	// The original thunks are kept in place.
+	let original = imports::<Nt>(&pe.file());
+	assert_eq!(import_map.get("KERNEL32.dll"), original.get("KERNEL32.dll"));
+	assert_eq!(import_map.get("USER32.dll"), original.get("USER32.dll"));
+}
+
+#[test]
+fn inject_import_round_trip() {
+	check_inject_import::<pe::ImageNtHeaders64>(false);
+	check_inject_import::<pe::ImageNtHeaders32>(false);
+}
+
+#[test]
+fn inject_import_stops_at_a_terminator_with_a_name() {
+	check_inject_import::<pe::ImageNtHeaders64>(true);
+	check_inject_import::<pe::ImageNtHeaders32>(true);
+}
+
+const IMAGE_DIRECTORY_ENTRY_SECURITY: usize = 4;
+
+/// The sample with a certificate table appended, then `trailer`, and the table's file offset.
+fn signed(trailer: &[u8]) -> (Vec<u8>, usize) {
+	let mut pe = common::sample(true);
+	let offset = pe.file().len();
+	pe.directory(IMAGE_DIRECTORY_ENTRY_SECURITY, (offset as u32, 0x10));
+	let mut file = pe.file();
+	// WIN_CERTIFICATE of 0x10 bytes, PKCS#7 signed data.
+	file.extend_from_slice(&0x10u32.to_le_bytes());
+	file.extend_from_slice(&0x0200u16.to_le_bytes());
+	file.extend_from_slice(&0x0002u16.to_le_bytes());
+	file.extend_from_slice(&[0x30; 8]);
+	file.extend_from_slice(trailer);
+	(file, offset)
+}
+
+fn security_directory(data: &[u8]) -> (u32, u32) {
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(common::leak(data), Layout::File);
+	let data_dir = headers.data_directories[IMAGE_DIRECTORY_ENTRY_SECURITY];
+	(
+		data_dir.virtual_address.get(LittleEndian),
+		data_dir.size.get(LittleEndian),
+	)
+}
+
+#[test]
+fn edits_drop_the_certificate_table() {
+	let edits: [fn(&mut PeWriter<pe::ImageNtHeaders64>); 3] = [
+		|writer| assert_eq!(writer.strip_debug(true), Ok(2)),
+		|writer| writer.replace_dos_stub(&[0xcc; 0x10], false).unwrap(),
+		|writer| {
+			writer.add_section(b".new", common::DATA, b"new").unwrap();
+		},
+	];
+	for edit in edits {
+		let (file, offset) = signed(&[]);
+		let mut writer = PeWriter::new(file).unwrap();
+		edit(&mut writer);
+		let data = writer.into_inner();
+		assert_eq!(security_directory(&data), (0, 0));
+		// A new section takes the place of the signature.
+		assert!(data.len() == offset || !data[offset..].contains(&0x30));
+	}
+}
+
+#[test]
+fn strip_debug_keeps_data_after_the_certificate_table() {
+	let (file, _) = signed(&[0xee; 0x20]);
+	let len = file.len();
+	let mut writer = PeWriter::<pe::ImageNtHeaders64>::new(file).unwrap();
+	assert_eq!(writer.strip_debug(false), Ok(2));
+	let data = writer.into_inner();
+	assert_eq!(security_directory(&data), (0, 0));
+	assert_eq!(data.len(), len);
+}
+
+const RICH_ENTRIES: [(u32, u32); 2] = [(0x0104_7809, 3), (0x0103_5f7c, 12)];
+
+/// Offset of the marker stored after the section table, which edits to the headers keep.
+const HEADER_MARKER: usize = common::SIZE_OF_HEADERS as usize - 0x10;
+
+fn rich_checksum(prefix: &[u8], entries: &[(u32, u32)]) -> u32 {
+	let mut checksum = prefix.len() as u32;
+	for (index, &byte) in prefix.iter().enumerate() {
+		// Skips `e_lfanew`.
+		if !(0x3c..0x40).contains(&index) {
+			checksum = checksum.wrapping_add((byte as u32).rotate_left(index as u32));
+		}
+	}
+	for &(id, count) in entries {
+		checksum = checksum.wrapping_add(id.rotate_left(count));
+	}
+	checksum
+}
+
+/// The entries of the Rich header ending at `end` after checking its key.
+fn rich_entries(data: &[u8], start: usize, end: usize) -> Vec<(u32, u32)> {
+	let key = common::read_u32(data, end - 4);
+	assert_eq!(&data[end - 8..end - 4], b"Rich");
+	assert_eq!(
+		&(common::read_u32(data, start) ^ key).to_le_bytes(),
+		b"DanS"
+	);
+	let entries: Vec<_> = (start + 16..end - 8)
+		.step_by(8)
+		.map(|offset| {
+			(
+				common::read_u32(data, offset) ^ key,
+				common::read_u32(data, offset + 4) ^ key,
+			)
+		})
+		.collect();
+	assert_eq!(key, rich_checksum(&data[..start], &entries));
+	entries
+}
+
+/// The sample with a stub of 0x10 bytes followed by a Rich header up to 0x78, and the marker.
+fn with_rich_header() -> Vec<u8> {
+	let mut file = common::sample(true).file();
+	file[0x40..0x50].fill(0xb4);
+	let key = rich_checksum(&file[..0x50], &RICH_ENTRIES);
+	let mut rich = common::Blob {
+		rva: 0,
+		file_offset: 0,
+		data: Vec::new(),
+	};
+	rich.u32(u32::from_le_bytes(*b"DanS") ^ key)
+		.u32(key)
+		.u32(key)
+		.u32(key);
+	for (id, count) in RICH_ENTRIES {
+		rich.u32(id ^ key).u32(count ^ key);
+	}
+	rich.bytes(b"Rich").u32(key);
+	file[0x50..0x78].copy_from_slice(&rich.data);
+	file[HEADER_MARKER..][..0x10].fill(0xee);
+	file
+}
+
+fn nt_headers(data: &[u8]) -> &[u8] {
+	let headers: PeHeaders<pe::ImageNtHeaders64> = common::parse(common::leak(data), Layout::File);
+	let offset = common::read_u32(data, 0x3c) as usize;
+	let len = 4
+		+ size_of::<pe::ImageFileHeader>()
+		+ headers
+			.nt_header
+			.file_header
+			.size_of_optional_header
+			.get(LittleEndian) as usize
+		+ size_of_val(headers.section_headers);
+	&data[offset..offset + len]
+}
+
+#[test]
+fn replace_dos_stub_keeping_the_rich_header() {
+	let file = with_rich_header();
+	assert_eq!(rich_entries(&file, 0x50, 0x78), RICH_ENTRIES);
+	let mut writer = PeWriter::<pe::ImageNtHeaders64>::new(file.clone()).unwrap();
+	writer.replace_dos_stub(&[0xcc; 0x30], false).unwrap();
+	let data = writer.into_inner();
+
+	// The Rich header follows the stub with a new key, pushing the NT headers back.
+	assert_eq!(data[0x40..0x70], [0xcc; 0x30]);
+	assert_eq!(rich_entries(&data, 0x70, 0x98), RICH_ENTRIES);
+	assert_eq!(common::read_u32(&data, 0x3c), 0x98);
+	assert_eq!(nt_headers(&data), nt_headers(&file));
+	assert_eq!(data[HEADER_MARKER..][..0x10], [0xee; 0x10]);
+	assert_eq!(
+		data[common::SIZE_OF_HEADERS as usize..],
+		file[common::SIZE_OF_HEADERS as usize..]
+	);
+}
+
+#[test]
+fn replace_dos_stub_stripping_the_rich_header() {
+	let file = with_rich_header();
+	let mut writer = PeWriter::<pe::ImageNtHeaders64>::new(file.clone()).unwrap();
+	writer.replace_dos_stub(&[0xcc; 0x10], true).unwrap();
+	let data = writer.into_inner();
+
+	assert_eq!(data[0x40..0x50], [0xcc; 0x10]);
+	assert_eq!(data[0x50..0x80], [0; 0x30]);
+	assert_eq!(common::read_u32(&data, 0x3c), 0x80);
+	assert_eq!(nt_headers(&data), nt_headers(&file));
+	assert_eq!(data[HEADER_MARKER..][..0x10], [0xee; 0x10]);
+
+	// A stub that leaves no room for the headers.
+	let mut writer = PeWriter::<pe::ImageNtHeaders64>::new(file).unwrap();
+	assert_eq!(
+		writer.replace_dos_stub(&[0xcc; 0x300], true),
+		Err(objparse::error::Error::NoHeaderSpace)
+	);
+}